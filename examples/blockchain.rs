@@ -1,11 +1,8 @@
-use noxy::prelude::*;
 use noxy::blockchain::basic::{BasicBlock, BasicTransaction, BasicBlockchain};
 use noxy::blockchain::{Block, Transaction, Blockchain};
 use noxy::crypto;
-use noxy::types::PeerId;
 
 use std::error::Error;
-use tokio;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
@@ -49,8 +46,8 @@ async fn main() -> Result<(), Box<dyn Error>> {
     );
     tx1.sign(&alice_keypair)?;
     println!("Транзакция 1: {} -> {} (50 монет)", 
-        hex::encode(&alice_pubkey)[..8], 
-        hex::encode(&bob_pubkey)[..8]
+        &hex::encode(&alice_pubkey)[..8], 
+        &hex::encode(&bob_pubkey)[..8]
     );
     
     // Bob отправляет 20 монет Charlie
@@ -62,8 +59,8 @@ async fn main() -> Result<(), Box<dyn Error>> {
     );
     tx2.sign(&bob_keypair)?;
     println!("Транзакция 2: {} -> {} (20 монет)", 
-        hex::encode(&bob_pubkey)[..8], 
-        hex::encode(&charlie_pubkey)[..8]
+        &hex::encode(&bob_pubkey)[..8], 
+        &hex::encode(&charlie_pubkey)[..8]
     );
     
     // Charlie отправляет 5 монет Alice
@@ -75,8 +72,8 @@ async fn main() -> Result<(), Box<dyn Error>> {
     );
     tx3.sign(&charlie_keypair)?;
     println!("Транзакция 3: {} -> {} (5 монет)", 
-        hex::encode(&charlie_pubkey)[..8], 
-        hex::encode(&alice_pubkey)[..8]
+        &hex::encode(&charlie_pubkey)[..8], 
+        &hex::encode(&alice_pubkey)[..8]
     );
     
     // Добавляем транзакции в пул
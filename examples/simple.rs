@@ -1,11 +1,9 @@
 use noxy::prelude::*;
+use futures::StreamExt;
 use noxy::transport::tcp::TcpTransport;
-use noxy::discovery::mdns::MdnsDiscovery;
 use noxy::types::{PeerId, TransportType};
-use noxy::crypto;
 
 use std::error::Error;
-use tokio;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
@@ -15,10 +13,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
     println!("Запуск простого P2P узла на noxy v{}", noxy::VERSION);
     
     // Создаем случайный идентификатор для узла
-    let peer_id = PeerId::new(rand::random::<[u8; 32]>().to_vec());
-    
-    // Создаем ключи
-    let key_pair = crypto::generate_ed25519_keypair()?;
+    let peer_id = PeerId::try_new(rand::random::<[u8; 32]>().to_vec())?;
     
     // Создаем и настраиваем узел
     let mut node = NodeBuilder::new()
@@ -1,13 +1,87 @@
 use async_trait::async_trait;
 use serde::{Serialize, Deserialize};
-use std::collections::{HashMap, HashSet};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::sync::{Arc, Mutex};
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::sync::broadcast;
+use tokio_util::sync::CancellationToken;
 
 use crate::error::{Error, Result};
-use crate::crypto::{Signer, sha256};
+use crate::crypto::{HashAlgorithm, Signer, sha256};
+use crate::crypto::ed25519::{self, Ed25519KeyPair};
 use crate::storage::Storage;
+use crate::storage::memory::MemoryStorage;
+use crate::storage::migration::{Migration, MigrationRunner};
 use super::{Block, Transaction, Blockchain};
+use super::mempool::Mempool;
+use super::merkle;
+use super::spv::SpvProof;
+use super::state::{AccountProof, StateTree};
+use super::migrations::{default_migrations, BlockHashIndexMigration};
+use super::codec::{BincodeCodec, WireCodec};
+
+/// Количество попыток nonce между проверками отмены при асинхронном майнинге
+const MINING_BATCH_SIZE: u64 = 10_000;
+
+/// Количество последних блоков, по которым оценивается комиссия
+const FEE_HISTORY_BLOCKS: usize = 100;
+
+/// Количество последних блоков, включаемых в локатор подряд
+const LOCATOR_DENSE_BLOCKS: usize = 10;
+
+/// Целевой интервал между блоками по умолчанию
+pub const DEFAULT_TARGET_BLOCK_TIME: Duration = Duration::from_secs(60);
+
+/// Количество блоков между пересчетами сложности по умолчанию
+pub const DEFAULT_RETARGET_INTERVAL: u64 = 10;
+
+/// Максимальное изменение сложности за один пересчет, в битах
+///
+/// Одно изменение на бит вдвое меняет ожидаемое число попыток, поэтому
+/// за один пересчет сложность меняется не более чем в 4 раза.
+const MAX_RETARGET_STEP: i64 = 2;
+
+/// Минимальная сложность блока
+const MIN_DIFFICULTY: u32 = 1;
+
+/// Награда за блок по умолчанию
+pub const DEFAULT_BLOCK_REWARD: u64 = 50 * UNITS_PER_COIN;
+
+/// Максимальное количество транзакций в блоке по умолчанию (включая coinbase)
+pub const DEFAULT_MAX_BLOCK_TRANSACTIONS: usize = 1000;
+
+/// Максимальный размер закодированного блока в потоке экспорта
+const MAX_EXPORTED_BLOCK_SIZE: usize = 16 * 1024 * 1024;
+
+/// Ключ хранилища с высотой последнего обрезанного блока
+const PRUNED_HEIGHT_KEY: &[u8] = b"pruned_height";
+
+/// Ключ хранилища со снимком состояния на высоте последнего обрезанного блока
+const STATE_SNAPSHOT_KEY: &[u8] = b"state_snapshot";
+
+/// Подсчитать количество ведущих нулевых бит хеша
+pub fn leading_zero_bits(hash: &[u8]) -> u32 {
+    let mut count = 0;
+    for &byte in hash {
+        if byte == 0 {
+            count += 8;
+        } else {
+            count += byte.leading_zeros();
+            break;
+        }
+    }
+    count
+}
+
+/// Проверить, удовлетворяет ли хеш сложности
+///
+/// Сложность задает требуемое количество ведущих нулевых бит во всем хеше,
+/// поэтому может превышать 64. Сложность 0 удовлетворяется любым хешем.
+pub fn meets_difficulty(hash: &[u8], difficulty: u32) -> bool {
+    leading_zero_bits(hash) >= difficulty
+}
 
 /// Базовая реализация блока
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -24,26 +98,49 @@ pub struct BasicBlock {
     difficulty: u32,
     /// Nonce для proof-of-work
     nonce: u64,
+    /// Корень дерева Меркла транзакций
+    merkle_root: Vec<u8>,
+    /// Корень дерева состояния счетов после применения транзакций блока
+    state_root: Vec<u8>,
     /// Транзакции
     transactions: Vec<BasicTransaction>,
     /// Данные блока
     data: Vec<u8>,
+    /// Алгоритм хеширования блока
+    hash_algorithm: HashAlgorithm,
 }
 
 impl BasicBlock {
-    /// Создать новый блок
+    /// Создать новый блок с хешем SHA-256
     pub fn new(
         previous_hash: Vec<u8>,
         height: u64,
+        difficulty: u32,
         transactions: Vec<BasicTransaction>,
-        data: Vec<u8>,
+        data: impl Into<Vec<u8>>,
+    ) -> Self {
+        Self::with_hash_algorithm(previous_hash, height, difficulty, transactions, data, HashAlgorithm::Sha256)
+    }
+    
+    /// Создать новый блок, хешируемый заданным алгоритмом
+    ///
+    /// Алгоритм сохраняется в блоке, поэтому проверяющие узлы вычисляют
+    /// хеш так же, как майнер. Корень дерева Меркла всегда строится по SHA-256.
+    pub fn with_hash_algorithm(
+        previous_hash: Vec<u8>,
+        height: u64,
         difficulty: u32,
+        transactions: Vec<BasicTransaction>,
+        data: impl Into<Vec<u8>>,
+        hash_algorithm: HashAlgorithm,
     ) -> Self {
         let timestamp = SystemTime::now()
             .duration_since(UNIX_EPOCH)
             .expect("Время до начала эпохи")
             .as_secs();
         
+        let merkle_root = Self::compute_merkle_root(&transactions);
+        
         let mut block = Self {
             hash: Vec::new(),
             previous_hash,
@@ -51,8 +148,11 @@ impl BasicBlock {
             timestamp,
             difficulty,
             nonce: 0,
+            merkle_root,
+            state_root: Vec::new(),
             transactions,
-            data,
+            data: data.into(),
+            hash_algorithm,
         };
         
         // Вычисляем хеш блока
@@ -63,62 +163,274 @@ impl BasicBlock {
     
     /// Создать genesis блок
     pub fn genesis() -> Self {
-        Self::new(
+        Self::genesis_with_hash_algorithm(HashAlgorithm::Sha256)
+    }
+    
+    /// Создать genesis блок, хешируемый заданным алгоритмом
+    pub fn genesis_with_hash_algorithm(hash_algorithm: HashAlgorithm) -> Self {
+        Self::with_hash_algorithm(
             vec![0; 32],  // Хеш предыдущего блока (нули для генезис-блока)
             0,            // Высота
+            1,            // Сложность
             Vec::new(),   // Транзакции
             b"Genesis Block".to_vec(), // Данные
-            1,            // Сложность
+            hash_algorithm,
         )
     }
     
     /// Майнинг блока (proof-of-work)
+    ///
+    /// Использует все доступные ядра процессора.
     pub fn mine(&mut self) {
-        let target = 1u64 << (64 - self.difficulty as u64);
+        let threads = std::thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1);
+        
+        self.mine_parallel(threads);
+    }
+    
+    /// Параллельный майнинг блока в нескольких потоках
+    ///
+    /// Пространство nonce делится на непересекающиеся диапазоны между потоками.
+    /// Все потоки останавливаются, как только один из них находит подходящий хеш.
+    pub fn mine_parallel(&mut self, threads: usize) {
+        let threads = threads.max(1) as u64;
+        let found = AtomicBool::new(false);
+        let winner: Mutex<Option<(u64, Vec<u8>)>> = Mutex::new(None);
+        let range_size = u64::MAX / threads;
+        
+        std::thread::scope(|scope| {
+            for worker in 0..threads {
+                let mut candidate = self.clone();
+                let found = &found;
+                let winner = &winner;
+                
+                scope.spawn(move || {
+                    let start = worker * range_size;
+                    let end = if worker + 1 == threads { u64::MAX } else { start + range_size };
+                    
+                    for (attempt, nonce) in (start..end).enumerate() {
+                        // Периодически проверяем, не нашел ли решение другой поток
+                        if attempt % 1024 == 0 && found.load(Ordering::Relaxed) {
+                            return;
+                        }
+                        
+                        candidate.nonce = nonce;
+                        let hash = candidate.calculate_hash();
+                        
+                        if meets_difficulty(&hash, candidate.difficulty) {
+                            if found.compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst).is_ok() {
+                                *winner.lock().expect("Не удалось получить блокировку winner") = Some((nonce, hash));
+                            }
+                            return;
+                        }
+                    }
+                });
+            }
+        });
         
+        if let Some((nonce, hash)) = winner.into_inner().expect("Не удалось получить блокировку winner") {
+            self.nonce = nonce;
+            self.hash = hash;
+        }
+    }
+    
+    /// Асинхронный майнинг блока с возможностью отмены
+    ///
+    /// Периодически уступает управление планировщику и проверяет токен отмены.
+    /// Возвращает `Ok(false)`, если майнинг был отменен до нахождения подходящего nonce.
+    pub async fn mine_async(&mut self, cancel: CancellationToken) -> Result<bool> {
         loop {
-            self.hash = self.calculate_hash();
-            
-            // Проверяем, удовлетворяет ли хеш требованиям сложности
-            let hash_value = if self.hash.len() >= 8 {
-                let mut value = 0u64;
-                for i in 0..8 {
-                    value = (value << 8) | self.hash[i] as u64;
+            for _ in 0..MINING_BATCH_SIZE {
+                self.hash = self.calculate_hash();
+                
+                if meets_difficulty(&self.hash, self.difficulty) {
+                    return Ok(true);
                 }
-                value
-            } else {
-                0
-            };
+                
+                self.nonce = self.nonce.checked_add(1)
+                    .ok_or_else(|| Error::Blockchain("Пространство nonce исчерпано".to_string()))?;
+            }
             
-            if hash_value < target {
-                break;
+            if cancel.is_cancelled() {
+                return Ok(false);
             }
             
-            self.nonce += 1;
+            tokio::task::yield_now().await;
+        }
+    }
+    
+    /// Получить транзакции блока
+    pub fn transactions(&self) -> &[BasicTransaction] {
+        &self.transactions
+    }
+    
+    /// Получить сложность блока
+    pub fn difficulty(&self) -> u32 {
+        self.difficulty
+    }
+    
+    /// Получить nonce, найденный при майнинге блока
+    pub fn get_nonce(&self) -> u64 {
+        self.nonce
+    }
+    
+    /// Получить работу, подтверждаемую блоком
+    ///
+    /// Сложность задает число ведущих нулевых бит хеша, поэтому для ее
+    /// достижения требуется в среднем `2^difficulty` попыток.
+    pub fn work(&self) -> u128 {
+        1u128.checked_shl(self.difficulty).unwrap_or(u128::MAX)
+    }
+    
+    /// Получить корень дерева Меркла транзакций
+    pub fn merkle_root(&self) -> &[u8] {
+        &self.merkle_root
+    }
+    
+    /// Установить корень состояния счетов и заново найти nonce
+    ///
+    /// Корень для следующего блока вычисляет `BasicBlockchain::next_state_root`.
+    pub fn with_state_root(mut self, state_root: Vec<u8>) -> Self {
+        self.state_root = state_root;
+        self.mine();
+        self
+    }
+    
+    /// Получить корень состояния счетов после применения транзакций блока
+    ///
+    /// У генезис-блока корень пуст: начальные балансы задаются вне блоков.
+    pub fn state_root(&self) -> &[u8] {
+        &self.state_root
+    }
+    
+    /// Построить доказательство включения транзакции в блок
+    ///
+    /// Возвращает путь из хешей соседних узлов с признаком того, что сосед справа.
+    /// Для транзакции, отсутствующей в блоке, возвращается `None`.
+    pub fn merkle_proof(&self, tx_id: &[u8]) -> Option<Vec<(Vec<u8>, bool)>> {
+        let index = self.transactions.iter().position(|tx| tx.id() == tx_id)?;
+        let leaves: Vec<Vec<u8>> = self.transactions.iter().map(|tx| tx.id()).collect();
+        merkle::merkle_proof(&leaves, index)
+    }
+    
+    /// Вычислить корень дерева Меркла по идентификаторам транзакций
+    fn compute_merkle_root(transactions: &[BasicTransaction]) -> Vec<u8> {
+        let leaves: Vec<Vec<u8>> = transactions.iter().map(|tx| tx.id()).collect();
+        merkle::merkle_root(&leaves)
+    }
+    
+    /// Получить алгоритм хеширования блока
+    pub fn hash_algorithm(&self) -> HashAlgorithm {
+        self.hash_algorithm
+    }
+    
+    /// Получить заголовок блока
+    pub fn header(&self) -> BlockHeader {
+        BlockHeader {
+            previous_hash: self.previous_hash.clone(),
+            height: self.height,
+            timestamp: self.timestamp,
+            difficulty: self.difficulty,
+            nonce: self.nonce,
+            merkle_root: self.merkle_root.clone(),
+            state_root: self.state_root.clone(),
+            data: self.data.clone(),
+            hash_algorithm: self.hash_algorithm,
         }
     }
     
     /// Вычислить хеш блока
     fn calculate_hash(&self) -> Vec<u8> {
-        // Для вычисления хеша сериализуем все поля кроме самого хеша
-        let mut data = Vec::new();
-        data.extend_from_slice(&self.previous_hash);
-        data.extend_from_slice(&self.height.to_be_bytes());
-        data.extend_from_slice(&self.timestamp.to_be_bytes());
-        data.extend_from_slice(&self.difficulty.to_be_bytes());
-        data.extend_from_slice(&self.nonce.to_be_bytes());
-        
-        // Добавляем хеши всех транзакций
-        for tx in &self.transactions {
-            data.extend_from_slice(&tx.id());
-        }
-        
-        data.extend_from_slice(&self.data);
-        
-        sha256(&data)
+        self.hash_algorithm.hash(&header_preimage(
+            &self.previous_hash,
+            self.height,
+            self.timestamp,
+            self.difficulty,
+            self.nonce,
+            &self.merkle_root,
+            &self.state_root,
+            &self.data,
+        ))
+    }
+}
+
+/// Заголовок блока без транзакций
+///
+/// Содержит все поля, от которых зависит хеш блока, поэтому по нему можно
+/// проверить proof-of-work и включение транзакций без тела блока.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockHeader {
+    /// Хеш предыдущего блока
+    pub previous_hash: Vec<u8>,
+    /// Высота блока
+    pub height: u64,
+    /// Метка времени
+    pub timestamp: u64,
+    /// Сложность
+    pub difficulty: u32,
+    /// Nonce для proof-of-work
+    pub nonce: u64,
+    /// Корень дерева Меркла транзакций
+    pub merkle_root: Vec<u8>,
+    /// Корень дерева состояния счетов после применения транзакций блока
+    pub state_root: Vec<u8>,
+    /// Данные блока
+    pub data: Vec<u8>,
+    /// Алгоритм хеширования блока
+    pub hash_algorithm: HashAlgorithm,
+}
+
+impl BlockHeader {
+    /// Вычислить хеш блока по заголовку
+    pub fn hash(&self) -> Vec<u8> {
+        self.hash_algorithm.hash(&header_preimage(
+            &self.previous_hash,
+            self.height,
+            self.timestamp,
+            self.difficulty,
+            self.nonce,
+            &self.merkle_root,
+            &self.state_root,
+            &self.data,
+        ))
+    }
+    
+    /// Проверить, что хеш заголовка удовлетворяет его сложности
+    pub fn meets_difficulty(&self) -> bool {
+        meets_difficulty(&self.hash(), self.difficulty)
     }
 }
 
+/// Собрать данные заголовка, от которых вычисляется хеш блока
+#[allow(clippy::too_many_arguments)]
+fn header_preimage(
+    previous_hash: &[u8],
+    height: u64,
+    timestamp: u64,
+    difficulty: u32,
+    nonce: u64,
+    merkle_root: &[u8],
+    state_root: &[u8],
+    block_data: &[u8],
+) -> Vec<u8> {
+    // Для вычисления хеша сериализуем все поля кроме самого хеша
+    let mut data = Vec::new();
+    data.extend_from_slice(previous_hash);
+    data.extend_from_slice(&height.to_be_bytes());
+    data.extend_from_slice(&timestamp.to_be_bytes());
+    data.extend_from_slice(&difficulty.to_be_bytes());
+    data.extend_from_slice(&nonce.to_be_bytes());
+    
+    // Транзакции учитываются через корень дерева Меркла
+    data.extend_from_slice(merkle_root);
+    data.extend_from_slice(state_root);
+    
+    data.extend_from_slice(block_data);
+    
+    data
+}
+
 impl Block for BasicBlock {
     fn hash(&self) -> Vec<u8> {
         self.hash.clone()
@@ -144,29 +456,122 @@ impl Block for BasicBlock {
         }
         
         // Проверяем, что хеш удовлетворяет требованиям сложности
-        let target = 1u64 << (64 - self.difficulty as u64);
-        let hash_value = if self.hash.len() >= 8 {
-            let mut value = 0u64;
-            for i in 0..8 {
-                value = (value << 8) | self.hash[i] as u64;
-            }
-            value
-        } else {
-            0
-        };
+        if !meets_difficulty(&self.hash, self.difficulty) {
+            return false;
+        }
         
-        if hash_value >= target {
+        // Проверяем, что корень дерева Меркла соответствует транзакциям
+        if Self::compute_merkle_root(&self.transactions) != self.merkle_root {
             return false;
         }
         
-        // Проверяем все транзакции в блоке
+        // Проверяем транзакции, а их подписи — одной пачкой
+        let mut signed = Vec::with_capacity(self.transactions.len());
         for tx in &self.transactions {
-            if !tx.is_valid() {
+            if !tx.is_well_formed() {
                 return false;
             }
+            if tx.is_coinbase() {
+                continue;
+            }
+            
+            match &tx.signature {
+                Some(signature) => signed.push((tx.sender.as_slice(), tx.data_to_sign(), signature.as_slice())),
+                None => return false,
+            }
         }
         
-        true
+        let batch: Vec<(&[u8], &[u8], &[u8])> = signed.iter()
+            .map(|(sender, data, signature)| (*sender, data.as_slice(), *signature))
+            .collect();
+        matches!(ed25519::verify_batch(&batch), Ok(true))
+    }
+}
+
+impl From<Box<BasicBlock>> for BasicBlock {
+    fn from(block: Box<BasicBlock>) -> Self {
+        *block
+    }
+}
+
+/// Количество десятичных знаков в сумме транзакции
+pub const AMOUNT_DECIMALS: u32 = 8;
+
+/// Количество минимальных единиц в одной монете
+pub const UNITS_PER_COIN: u64 = 10u64.pow(AMOUNT_DECIMALS);
+
+/// Разобрать десятичную запись суммы (например, `"12.5"`) в минимальные единицы
+///
+/// Разбор выполняется без использования чисел с плавающей точкой,
+/// поэтому результат не зависит от ошибок округления.
+pub fn parse_amount(amount: &str) -> Result<u64> {
+    let invalid = || Error::Blockchain(format!("Некорректная сумма: {}", amount));
+    
+    let amount = amount.trim();
+    let (whole, fraction) = match amount.split_once('.') {
+        Some((whole, fraction)) => (whole, fraction),
+        None => (amount, ""),
+    };
+    
+    if whole.is_empty() && fraction.is_empty() {
+        return Err(invalid());
+    }
+    
+    if !whole.bytes().all(|b| b.is_ascii_digit()) || !fraction.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(invalid());
+    }
+    
+    if fraction.len() > AMOUNT_DECIMALS as usize {
+        return Err(Error::Blockchain(format!(
+            "Сумма {} содержит больше {} знаков после запятой", amount, AMOUNT_DECIMALS
+        )));
+    }
+    
+    let whole_units = if whole.is_empty() { 0 } else { whole.parse::<u64>().map_err(|_| invalid())? };
+    let fraction_units = if fraction.is_empty() {
+        0
+    } else {
+        fraction.parse::<u64>().map_err(|_| invalid())?
+            * 10u64.pow(AMOUNT_DECIMALS - fraction.len() as u32)
+    };
+    
+    whole_units.checked_mul(UNITS_PER_COIN)
+        .and_then(|units| units.checked_add(fraction_units))
+        .ok_or_else(|| Error::Blockchain(format!("Сумма слишком велика: {}", amount)))
+}
+
+/// Отформатировать сумму в минимальных единицах как десятичную строку
+pub fn format_amount(units: u64) -> String {
+    let whole = units / UNITS_PER_COIN;
+    let fraction = units % UNITS_PER_COIN;
+    
+    if fraction == 0 {
+        return whole.to_string();
+    }
+    
+    let fraction = format!("{:0width$}", fraction, width = AMOUNT_DECIMALS as usize);
+    format!("{}.{}", whole, fraction.trim_end_matches('0'))
+}
+
+/// Сумма, которую можно передать в [`BasicTransaction::new`]
+///
+/// Целое число задает сумму в минимальных единицах, число с плавающей
+/// точкой — в монетах с округлением до минимальной единицы.
+pub trait IntoAmount {
+    /// Получить сумму в минимальных единицах
+    fn into_amount(self) -> u64;
+}
+
+impl IntoAmount for u64 {
+    fn into_amount(self) -> u64 {
+        self
+    }
+}
+
+impl IntoAmount for f64 {
+    fn into_amount(self) -> u64 {
+        // Отрицательные значения и NaN дают ноль, слишком большие — u64::MAX
+        (self * UNITS_PER_COIN as f64).round() as u64
     }
 }
 
@@ -179,8 +584,12 @@ pub struct BasicTransaction {
     sender: Vec<u8>,
     /// Получатель
     receiver: Vec<u8>,
-    /// Сумма
+    /// Сумма в минимальных единицах (см. [`AMOUNT_DECIMALS`])
     amount: u64,
+    /// Комиссия в минимальных единицах, списываемая с отправителя сверх суммы
+    fee: u64,
+    /// Порядковый номер транзакции отправителя
+    nonce: u64,
     /// Метка времени
     timestamp: u64,
     /// Подпись
@@ -194,8 +603,8 @@ impl BasicTransaction {
     pub fn new(
         sender: Vec<u8>,
         receiver: Vec<u8>,
-        amount: u64,
-        data: Vec<u8>,
+        amount: impl IntoAmount,
+        data: impl Into<Vec<u8>>,
     ) -> Self {
         let timestamp = SystemTime::now()
             .duration_since(UNIX_EPOCH)
@@ -206,10 +615,12 @@ impl BasicTransaction {
             id: Vec::new(),
             sender,
             receiver,
-            amount,
+            amount: amount.into_amount(),
+            fee: 0,
+            nonce: 0,
             timestamp,
             signature: None,
-            data,
+            data: data.into(),
         };
         
         // Вычисляем ID транзакции
@@ -218,6 +629,99 @@ impl BasicTransaction {
         tx
     }
     
+    /// Создать новую транзакцию с суммой в десятичной записи (например, `"0.1"`)
+    pub fn with_decimal_amount(
+        sender: Vec<u8>,
+        receiver: Vec<u8>,
+        amount: &str,
+        data: impl Into<Vec<u8>>,
+    ) -> Result<Self> {
+        Ok(Self::new(sender, receiver, parse_amount(amount)?, data))
+    }
+    
+    /// Создать coinbase-транзакцию, начисляющую майнеру награду за блок
+    ///
+    /// У coinbase нет отправителя и подписи. Высота блока записывается
+    /// в nonce, чтобы coinbase разных блоков имели разные идентификаторы.
+    pub fn coinbase(miner: Vec<u8>, amount: u64, height: u64) -> Self {
+        Self::new(Vec::new(), miner, amount, Vec::new()).with_nonce(height)
+    }
+    
+    /// Установить порядковый номер транзакции отправителя
+    ///
+    /// Изменяет идентификатор транзакции, поэтому вызывается до подписи.
+    pub fn with_nonce(mut self, nonce: u64) -> Self {
+        self.nonce = nonce;
+        self.id = self.calculate_hash();
+        self.signature = None;
+        self
+    }
+    
+    /// Установить комиссию в минимальных единицах
+    ///
+    /// Изменяет идентификатор транзакции, поэтому вызывается до подписи.
+    pub fn with_fee(mut self, fee: u64) -> Self {
+        self.fee = fee;
+        self.id = self.calculate_hash();
+        self.signature = None;
+        self
+    }
+    
+    /// Получить отправителя
+    pub fn sender(&self) -> &[u8] {
+        &self.sender
+    }
+    
+    /// Получить получателя
+    pub fn receiver(&self) -> &[u8] {
+        &self.receiver
+    }
+    
+    /// Получить порядковый номер транзакции отправителя
+    pub fn nonce(&self) -> u64 {
+        self.nonce
+    }
+    
+    /// Получить сумму в минимальных единицах
+    pub fn amount(&self) -> u64 {
+        self.amount
+    }
+    
+    /// Получить комиссию в минимальных единицах
+    pub fn fee(&self) -> u64 {
+        self.fee
+    }
+    
+    /// Получить сумму, списываемую с отправителя (сумма и комиссия)
+    pub fn total_cost(&self) -> u64 {
+        self.amount.saturating_add(self.fee)
+    }
+    
+    /// Является ли транзакция coinbase (без отправителя)
+    pub fn is_coinbase(&self) -> bool {
+        self.sender.is_empty()
+    }
+    
+    /// Проверить все, кроме подписи
+    ///
+    /// Идентификатор должен соответствовать содержимому, а у coinbase не
+    /// должно быть комиссии.
+    fn is_well_formed(&self) -> bool {
+        // Проверяем, что идентификатор транзакции соответствует её содержимому
+        if self.calculate_hash() != self.id {
+            return false;
+        }
+        
+        !self.is_coinbase() || self.fee == 0
+    }
+    
+    /// Получить сумму в монетах
+    ///
+    /// Значение предназначено для отображения; для вычислений используйте [`Self::amount`].
+    pub fn amount_decimal(&self) -> f64 {
+        self.amount as f64 / UNITS_PER_COIN as f64
+    }
+    
     /// Вычислить хеш транзакции
     fn calculate_hash(&self) -> Vec<u8> {
         // Сериализуем все поля кроме идентификатора и подписи
@@ -225,6 +729,8 @@ impl BasicTransaction {
         data.extend_from_slice(&self.sender);
         data.extend_from_slice(&self.receiver);
         data.extend_from_slice(&self.amount.to_be_bytes());
+        data.extend_from_slice(&self.fee.to_be_bytes());
+        data.extend_from_slice(&self.nonce.to_be_bytes());
         data.extend_from_slice(&self.timestamp.to_be_bytes());
         data.extend_from_slice(&self.data);
         
@@ -257,110 +763,1364 @@ impl Transaction for BasicTransaction {
             None => return Ok(false),
         };
         
-        // В реальной реализации здесь будет проверка подписи
-        // через публичный ключ отправителя
-        // Для упрощения примера просто возвращаем true
-        Ok(true)
+        // Отправитель хранит публичный ключ, проверяем подпись через него
+        let public_key = Ed25519KeyPair::from_public_key(&self.sender)?;
+        public_key.verify(&self.data_to_sign(), signature)
     }
     
     fn is_valid(&self) -> bool {
-        // Проверяем, что идентификатор транзакции соответствует её содержимому
-        let calculated_id = self.calculate_hash();
-        if calculated_id != self.id {
+        if !self.is_well_formed() {
             return false;
         }
         
-        // Проверяем подпись
-        match self.verify_signature() {
-            Ok(valid) => valid,
-            Err(_) => false,
+        // У coinbase нет подписи, ее сумма проверяется вместе с блоком
+        if self.is_coinbase() {
+            return true;
         }
+        
+        // Проверяем подпись
+        self.verify_signature().unwrap_or(false)
+    }
+}
+
+impl From<Box<BasicTransaction>> for BasicTransaction {
+    fn from(tx: Box<BasicTransaction>) -> Self {
+        *tx
     }
 }
 
+/// Состояние счетов на высоте последнего обрезанного блока
+///
+/// Тела обрезанных блоков удалены, поэтому при инициализации балансы и
+/// nonce восстанавливаются из снимка, а не воспроизведением транзакций.
+#[derive(Debug, Serialize, Deserialize)]
+struct StateSnapshot {
+    /// Балансы адресов
+    balances: HashMap<Vec<u8>, u64>,
+    /// Следующие ожидаемые nonce отправителей
+    nonces: HashMap<Vec<u8>, u64>,
+    /// Суммарная работа блоков до снимка включительно
+    total_work: u128,
+}
+
+/// Сведения о реорганизации цепочки
+#[derive(Debug, Clone)]
+pub struct ChainReorg {
+    /// Высота общего предка старой и новой ветки
+    pub fork_height: u64,
+    /// Хеши блоков, отключенных от основной цепочки, от старых к новым
+    pub disconnected: Vec<Vec<u8>>,
+    /// Хеши блоков новой ветки, от старых к новым
+    pub connected: Vec<Vec<u8>>,
+    /// Транзакции отключенных блоков, возвращенные в пул
+    pub returned_transactions: Vec<BasicTransaction>,
+}
+
 /// Базовая реализация блокчейна
+///
+/// Блоки, продолжающие не вершину, а другой известный блок, буферизуются
+/// как побочная ветка. Когда побочная ветка набирает больше суммарной
+/// работы, чем основная, цепочка реорганизуется.
 pub struct BasicBlockchain {
     /// Хранилище блоков
     storage: Box<dyn Storage>,
     /// Последний блок
     last_block: Arc<Mutex<Option<BasicBlock>>>,
     /// Пул транзакций
-    transaction_pool: Arc<Mutex<HashSet<BasicTransaction>>>,
+    transaction_pool: Arc<Mutex<Mempool>>,
     /// Индекс блоков по высоте
     blocks_by_height: Arc<Mutex<HashMap<u64, Vec<u8>>>>,
-    /// Сложность
+    /// Индекс балансов по подтвержденным транзакциям
+    balances: Arc<Mutex<HashMap<Vec<u8>, u64>>>,
+    /// Следующий ожидаемый nonce отправителей по подтвержденным транзакциям
+    nonces: Arc<Mutex<HashMap<Vec<u8>, u64>>>,
+    /// Начальные балансы, существующие до первого блока
+    initial_balances: HashMap<Vec<u8>, u64>,
+    /// Миграции формата хранимых данных
+    migrations: MigrationRunner,
+    /// Кодек для хранения блоков
+    codec: Arc<dyn WireCodec<BasicBlock, BasicTransaction>>,
+    /// Минимальная комиссия для приема транзакции в пул
+    min_relay_fee: u64,
+    /// Максимальное количество транзакций в блоке, включая coinbase
+    max_block_transactions: usize,
+    /// Отправители, освобожденные от минимальной комиссии
+    fee_exempt_senders: HashSet<Vec<u8>>,
+    /// Комиссии транзакций последних блоков, от старых к новым
+    recent_fees: Arc<Mutex<VecDeque<Vec<u64>>>>,
+    /// Суммарная работа блоков основной цепочки
+    total_work: u128,
+    /// Блоки побочных веток по хешу
+    side_blocks: Arc<Mutex<HashMap<Vec<u8>, BasicBlock>>>,
+    /// Канал уведомлений о реорганизациях цепочки
+    reorg_tx: broadcast::Sender<ChainReorg>,
+    /// Награда за блок без учета комиссий
+    block_reward: u64,
+    /// Начальная сложность, действующая до первого пересчета
     difficulty: u32,
+    /// Сложность следующего блока основной цепочки
+    current_difficulty: u32,
+    /// Целевой интервал между блоками
+    target_block_time: Duration,
+    /// Количество блоков между пересчетами сложности
+    retarget_interval: u64,
+    /// Высота последнего блока, тело которого удалено при обрезке
+    pruned_height: Option<u64>,
+    /// Алгоритм хеширования блоков цепочки
+    hash_algorithm: HashAlgorithm,
 }
 
 impl BasicBlockchain {
-    /// Создать новый блокчейн
-    pub fn new(storage: Box<dyn Storage>, difficulty: u32) -> Self {
+    /// Создать и инициализировать блокчейн в памяти
+    ///
+    /// Удобно для примеров и экспериментов: блоки хранятся в `MemoryStorage`
+    /// и теряются при завершении процесса.
+    pub async fn new(difficulty: u32) -> Result<Self> {
+        let mut blockchain = Self::with_storage(Box::new(MemoryStorage::new("blockchain")), difficulty);
+        blockchain.initialize().await?;
+        
+        Ok(blockchain)
+    }
+    
+    /// Создать блокчейн поверх заданного хранилища
+    ///
+    /// Сложность пересчитывается с целевым интервалом `DEFAULT_TARGET_BLOCK_TIME`
+    /// каждые `DEFAULT_RETARGET_INTERVAL` блоков. Перед использованием
+    /// блокчейн нужно инициализировать вызовом `initialize`.
+    pub fn with_storage(storage: Box<dyn Storage>, difficulty: u32) -> Self {
+        Self::with_retargeting(storage, difficulty, DEFAULT_TARGET_BLOCK_TIME, DEFAULT_RETARGET_INTERVAL)
+    }
+    
+    /// Создать новый блокчейн с заданными параметрами пересчета сложности
+    ///
+    /// Каждые `retarget_interval` блоков сложность следующего блока
+    /// корректируется так, чтобы блоки появлялись в среднем раз в `target_block_time`.
+    pub fn with_retargeting(
+        storage: Box<dyn Storage>,
+        difficulty: u32,
+        target_block_time: Duration,
+        retarget_interval: u64,
+    ) -> Self {
         Self {
             storage,
             last_block: Arc::new(Mutex::new(None)),
-            transaction_pool: Arc::new(Mutex::new(HashSet::new())),
+            transaction_pool: Arc::new(Mutex::new(Mempool::new())),
             blocks_by_height: Arc::new(Mutex::new(HashMap::new())),
+            balances: Arc::new(Mutex::new(HashMap::new())),
+            nonces: Arc::new(Mutex::new(HashMap::new())),
+            initial_balances: HashMap::new(),
+            migrations: default_migrations(Arc::new(BincodeCodec)),
+            codec: Arc::new(BincodeCodec),
+            min_relay_fee: 0,
+            max_block_transactions: DEFAULT_MAX_BLOCK_TRANSACTIONS,
+            fee_exempt_senders: HashSet::new(),
+            recent_fees: Arc::new(Mutex::new(VecDeque::with_capacity(FEE_HISTORY_BLOCKS))),
+            total_work: 0,
+            side_blocks: Arc::new(Mutex::new(HashMap::new())),
+            reorg_tx: broadcast::channel(16).0,
+            block_reward: DEFAULT_BLOCK_REWARD,
             difficulty,
+            current_difficulty: difficulty,
+            target_block_time,
+            retarget_interval: retarget_interval.max(1),
+            pruned_height: None,
+            hash_algorithm: HashAlgorithm::Sha256,
         }
     }
     
-    /// Инициализировать блокчейн
-    pub async fn initialize(&mut self) -> Result<()> {
-        // Проверяем, есть ли уже блоки в хранилище
-        let genesis_key = b"block:0".to_vec();
+    /// Установить начальные балансы адресов
+    ///
+    /// Применяются при инициализации до воспроизведения подтвержденных транзакций.
+    pub fn with_initial_balances(mut self, balances: HashMap<Vec<u8>, u64>) -> Self {
+        self.initial_balances = balances;
+        self
+    }
+    
+    /// Установить алгоритм хеширования блоков
+    ///
+    /// Все блоки цепочки, включая генезис, должны использовать этот алгоритм.
+    /// Задается до инициализации.
+    pub fn with_hash_algorithm(mut self, hash_algorithm: HashAlgorithm) -> Self {
+        self.hash_algorithm = hash_algorithm;
+        self
+    }
+    
+    /// Алгоритм хеширования блоков
+    pub fn hash_algorithm(&self) -> HashAlgorithm {
+        self.hash_algorithm
+    }
+    
+    /// Установить награду за блок без учета комиссий
+    pub fn with_block_reward(mut self, reward: u64) -> Self {
+        self.block_reward = reward;
+        self
+    }
+    
+    /// Получить награду за блок без учета комиссий
+    pub fn block_reward(&self) -> u64 {
+        self.block_reward
+    }
+    
+    /// Установить максимальное количество транзакций в блоке, включая coinbase
+    ///
+    /// Значение меньше 2 не оставляет места для транзакций из пула, поэтому
+    /// поднимается до 2.
+    pub fn with_max_block_transactions(mut self, max: usize) -> Self {
+        self.max_block_transactions = max.max(2);
+        self
+    }
+    
+    /// Получить максимальное количество транзакций в блоке, включая coinbase
+    pub fn max_block_transactions(&self) -> usize {
+        self.max_block_transactions
+    }
+    
+    /// Установить минимальную комиссию для приема транзакций в пул
+    ///
+    /// По умолчанию 0, то есть транзакции без комиссии принимаются.
+    pub fn with_min_relay_fee(mut self, fee: u64) -> Self {
+        self.min_relay_fee = fee;
+        self
+    }
+    
+    /// Освободить отправителя от требования минимальной комиссии
+    pub fn with_fee_exempt_sender(mut self, sender: Vec<u8>) -> Self {
+        self.fee_exempt_senders.insert(sender);
+        self
+    }
+    
+    /// Установить кодек для хранения блоков
+    ///
+    /// Кодек должен совпадать с тем, которым были записаны уже сохраненные блоки.
+    /// Встроенные миграции переключаются на него же.
+    pub fn with_codec(mut self, codec: Arc<dyn WireCodec<BasicBlock, BasicTransaction>>) -> Self {
+        self.migrations.add_migration(Box::new(BlockHashIndexMigration::new(Arc::clone(&codec))));
+        self.codec = codec;
+        self
+    }
+    
+    /// Получить кодек блоков и транзакций
+    pub fn codec(&self) -> Arc<dyn WireCodec<BasicBlock, BasicTransaction>> {
+        Arc::clone(&self.codec)
+    }
+    
+    /// Добавить миграцию формата хранимых данных
+    ///
+    /// Миграции применяются при вызове `initialize`.
+    pub fn with_migration(mut self, migration: Box<dyn Migration>) -> Self {
+        self.migrations.add_migration(migration);
+        self
+    }
+    
+    /// Получить баланс адреса по подтвержденным транзакциям
+    pub fn get_balance(&self, address: &[u8]) -> Result<u64> {
+        let balances = self.balances.lock()
+            .map_err(|_| Error::Blockchain("Не удалось получить блокировку balances".to_string()))?;
         
-        if let Some(genesis_data) = self.storage.get(&genesis_key).await? {
-            // Загружаем генезис-блок
-            let genesis: BasicBlock = bincode::deserialize(&genesis_data)
-                .map_err(|e| Error::Serialization(format!("Не удалось десериализовать генезис-блок: {}", e)))?;
-            
-            // Загружаем последний блок
-            let last_height_data = self.storage.get(b"last_height").await?
-                .ok_or_else(|| Error::Blockchain("Не найдена высота последнего блока".to_string()))?;
-            
-            let last_height = bincode::deserialize::<u64>(&last_height_data)
-                .map_err(|e| Error::Serialization(format!("Не удалось десериализовать высоту последнего блока: {}", e)))?;
-            
-            let last_block_key = format!("block:{}", last_height).into_bytes();
-            let last_block_data = self.storage.get(&last_block_key).await?
-                .ok_or_else(|| Error::Blockchain("Не найден последний блок".to_string()))?;
-            
-            let last_block: BasicBlock = bincode::deserialize(&last_block_data)
-                .map_err(|e| Error::Serialization(format!("Не удалось десериализовать последний блок: {}", e)))?;
-            
-            // Загружаем индекс блоков по высоте
-            let mut blocks_by_height = self.blocks_by_height.lock()
-                .map_err(|_| Error::Blockchain("Не удалось получить блокировку blocks_by_height".to_string()))?;
+        Ok(balances.get(address).copied().unwrap_or(0))
+    }
+    
+    /// Получить nonce, который должна иметь следующая подтвержденная транзакция адреса
+    pub fn get_nonce(&self, address: &[u8]) -> Result<u64> {
+        let nonces = self.nonces.lock()
+            .map_err(|_| Error::Blockchain("Не удалось получить блокировку nonces".to_string()))?;
+        
+        Ok(nonces.get(address).copied().unwrap_or(0))
+    }
+    
+    /// Вычислить корень состояния счетов по подтвержденным транзакциям
+    ///
+    /// Корень фиксирует балансы всех счетов в разреженном дереве Меркла.
+    pub fn state_root(&self) -> Result<Vec<u8>> {
+        Ok(self.state_tree()?.root())
+    }
+    
+    /// Построить доказательство баланса счета относительно `state_root`
+    ///
+    /// Для отсутствующего счета возвращается доказательство отсутствия.
+    pub fn account_proof(&self, address: &[u8]) -> Result<AccountProof> {
+        Ok(self.state_tree()?.proof(address))
+    }
+    
+    /// Построить SPV-доказательство включения подтвержденной транзакции
+    ///
+    /// Блоки основной цепочки просматриваются от вершины к генезису;
+    /// обрезанные блоки не просматриваются.
+    /// Для транзакции, не найденной в цепочке, возвращается `None`.
+    pub async fn get_spv_proof(&self, tx_id: &[u8]) -> Result<Option<SpvProof>> {
+        let last_block = self.get_last_block().await?;
+        
+        let first_height = self.pruned_height.map(|height| height + 1).unwrap_or(0);
+        
+        for height in (first_height..=last_block.height()).rev() {
+            let block = match self.get_block_by_height(height).await? {
+                Some(block) => block,
+                None => continue,
+            };
             
-            for height in 0..=last_height {
-                let block_key = format!("block:{}", height).into_bytes();
-                if let Some(block_data) = self.storage.get(&block_key).await? {
-                    let block: BasicBlock = bincode::deserialize(&block_data)
-                        .map_err(|e| Error::Serialization(format!("Не удалось десериализовать блок: {}", e)))?;
-                    
-                    blocks_by_height.insert(height, block.hash());
-                }
+            if let Some(merkle_path) = block.merkle_proof(tx_id) {
+                return Ok(Some(SpvProof {
+                    header: block.header(),
+                    merkle_path,
+                }));
             }
-            
-            // Устанавливаем последний блок
-            let mut last_block_lock = self.last_block.lock()
-                .map_err(|_| Error::Blockchain("Не удалось получить блокировку last_block".to_string()))?;
+        }
+        
+        Ok(None)
+    }
+    
+    /// Оценить комиссию для включения транзакции в течение `target_blocks` блоков
+    ///
+    /// Оценка берется из распределения комиссий транзакций последних блоков:
+    /// для `target_blocks = 1` это медиана, для больших значений — все более
+    /// низкий квантиль (`1 / (target_blocks + 1)`). Результат не бывает ниже
+    /// `min_relay_fee`, и без истории равен ей.
+    pub fn estimate_fee(&self, target_blocks: u64) -> u64 {
+        let mut fees: Vec<u64> = match self.recent_fees.lock() {
+            Ok(recent) => recent.iter().flatten().copied().collect(),
+            Err(_) => return self.min_relay_fee,
+        };
+        
+        if fees.is_empty() {
+            return self.min_relay_fee;
+        }
+        
+        fees.sort_unstable();
+        let target = target_blocks.max(1) as usize;
+        let index = (fees.len() - 1) / (target + 1);
+        
+        fees[index].max(self.min_relay_fee)
+    }
+    
+    /// Построить локатор блоков для согласования синхронизации
+    ///
+    /// Локатор содержит хеши блоков от вершины цепочки к генезису: первые
+    /// `LOCATOR_DENSE_BLOCKS` подряд, далее с удваивающимся шагом. Хеш генезиса
+    /// включается всегда. До инициализации блокчейна локатор пуст.
+    pub fn block_locator(&self) -> Vec<Vec<u8>> {
+        let blocks_by_height = match self.blocks_by_height.lock() {
+            Ok(blocks_by_height) => blocks_by_height,
+            Err(_) => return Vec::new(),
+        };
+        
+        let tip = match blocks_by_height.keys().max() {
+            Some(&tip) => tip,
+            None => return Vec::new(),
+        };
+        
+        let mut locator = Vec::new();
+        let mut height = tip;
+        let mut step = 1;
+        
+        loop {
+            if let Some(hash) = blocks_by_height.get(&height) {
+                locator.push(hash.clone());
+            }
+            
+            if height == 0 {
+                break;
+            }
+            
+            if locator.len() >= LOCATOR_DENSE_BLOCKS {
+                step *= 2;
+            }
+            height = height.saturating_sub(step);
+        }
+        
+        locator
+    }
+    
+    /// Найти высоту лучшего общего блока по локатору пира
+    ///
+    /// Возвращает высоту первого блока из локатора, который есть в нашей
+    /// цепочке, — с нее можно начинать синхронизацию. `None` означает, что
+    /// общих блоков нет (цепочки с разными генезисами).
+    pub fn find_common_height(&self, locator: &[Vec<u8>]) -> Result<Option<u64>> {
+        let blocks_by_height = self.blocks_by_height.lock()
+            .map_err(|_| Error::Blockchain("Не удалось получить блокировку blocks_by_height".to_string()))?;
+        
+        let heights: HashMap<&[u8], u64> = blocks_by_height.iter()
+            .map(|(height, hash)| (hash.as_slice(), *height))
+            .collect();
+        
+        Ok(locator.iter().find_map(|hash| heights.get(hash.as_slice()).copied()))
+    }
+    
+    /// Учесть комиссии транзакций блока в истории для оценки комиссии
+    fn record_block_fees(&self, transactions: &[BasicTransaction]) -> Result<()> {
+        let mut recent = self.recent_fees.lock()
+            .map_err(|_| Error::Blockchain("Не удалось получить блокировку recent_fees".to_string()))?;
+        
+        if recent.len() == FEE_HISTORY_BLOCKS {
+            recent.pop_front();
+        }
+        recent.push_back(transactions.iter()
+            .filter(|tx| !tx.is_coinbase())
+            .map(|tx| tx.fee())
+            .collect());
+        
+        Ok(())
+    }
+    
+    /// Вычислить корень состояния после применения `transactions` к текущим балансам
+    ///
+    /// Это значение `add_block` ожидает в заголовке следующего блока.
+    pub fn next_state_root(&self, transactions: &[BasicTransaction]) -> Result<Vec<u8>> {
+        let mut balances = self.balances.lock()
+            .map_err(|_| Error::Blockchain("Не удалось получить блокировку balances".to_string()))?
+            .clone();
+        let mut nonces = self.nonces.lock()
+            .map_err(|_| Error::Blockchain("Не удалось получить блокировку nonces".to_string()))?
+            .clone();
+        Self::apply_transactions(&mut balances, &mut nonces, transactions)?;
+        
+        Ok(StateTree::from_balances(&balances).root())
+    }
+    
+    /// Вычислить корень состояния блока с `transactions`, продолжающего блок `parent_hash`
+    ///
+    /// Родитель может быть в основной цепочке или в побочной ветке: балансы
+    /// откатываются до точки ответвления и применяются блоки ветки.
+    pub async fn state_root_after(&self, parent_hash: &[u8], transactions: &[BasicTransaction]) -> Result<Vec<u8>> {
+        // Поднимаемся по побочной ветке до блока основной цепочки
+        let mut branch = Vec::new();
+        let mut fork_hash = parent_hash.to_vec();
+        loop {
+            let parent = self.side_blocks.lock()
+                .map_err(|_| Error::Blockchain("Не удалось получить блокировку side_blocks".to_string()))?
+                .get(&fork_hash)
+                .cloned();
+            match parent {
+                Some(block) => {
+                    fork_hash = block.previous_hash().to_vec();
+                    branch.push(block);
+                }
+                None => break,
+            }
+        }
+        
+        let fork_height = self.branch_parent(&fork_hash).await?
+            .ok_or_else(|| Error::Blockchain("Предыдущий блок не найден".to_string()))?
+            .height();
+        let last_height = self.get_last_block().await?.height();
+        
+        let mut balances = self.balances.lock()
+            .map_err(|_| Error::Blockchain("Не удалось получить блокировку balances".to_string()))?
+            .clone();
+        let mut nonces = self.nonces.lock()
+            .map_err(|_| Error::Blockchain("Не удалось получить блокировку nonces".to_string()))?
+            .clone();
+        for height in (fork_height + 1..=last_height).rev() {
+            let block = self.get_block_by_height(height).await?
+                .ok_or_else(|| Error::Blockchain(format!("Не найден блок на высоте {}", height)))?;
+            Self::revert_transactions(&mut balances, &mut nonces, block.transactions())?;
+        }
+        for block in branch.iter().rev() {
+            Self::apply_transactions(&mut balances, &mut nonces, block.transactions())?;
+        }
+        Self::apply_transactions(&mut balances, &mut nonces, transactions)?;
+        
+        Ok(StateTree::from_balances(&balances).root())
+    }
+    
+    /// Построить дерево состояния из текущих балансов
+    fn state_tree(&self) -> Result<StateTree> {
+        let balances = self.balances.lock()
+            .map_err(|_| Error::Blockchain("Не удалось получить блокировку balances".to_string()))?;
+        
+        Ok(StateTree::from_balances(&balances))
+    }
+    
+    /// Применить транзакции к индексам балансов и nonce
+    ///
+    /// Nonce каждой транзакции должен быть следующим по порядку для
+    /// отправителя, поэтому повторно включить транзакцию в цепочку нельзя.
+    /// При ошибке индексы могут остаться частично измененными, поэтому
+    /// вызывающая сторона применяет транзакции к копиям.
+    fn apply_transactions(
+        balances: &mut HashMap<Vec<u8>, u64>,
+        nonces: &mut HashMap<Vec<u8>, u64>,
+        transactions: &[BasicTransaction],
+    ) -> Result<()> {
+        for tx in transactions {
+            // Coinbase только начисляет награду получателю
+            if tx.is_coinbase() {
+                let receiver_balance = balances.entry(tx.receiver().to_vec()).or_insert(0);
+                *receiver_balance = receiver_balance.checked_add(tx.amount())
+                    .ok_or_else(|| Error::Blockchain("Переполнение баланса получателя".to_string()))?;
+                continue;
+            }
+            
+            let expected_nonce = nonces.get(tx.sender()).copied().unwrap_or(0);
+            if tx.nonce() != expected_nonce {
+                return Err(Error::Blockchain(format!(
+                    "Nonce транзакции {} не равен ожидаемому {}", tx.nonce(), expected_nonce
+                )));
+            }
+            nonces.insert(tx.sender().to_vec(), expected_nonce + 1);
+            
+            let sender_balance = balances.get(tx.sender()).copied().unwrap_or(0);
+            // Комиссия списывается с отправителя и достается майнеру через coinbase
+            let remaining = sender_balance.checked_sub(tx.total_cost()).ok_or_else(|| {
+                Error::Blockchain(format!(
+                    "Недостаточно средств у отправителя: баланс {}, требуется {}",
+                    format_amount(sender_balance), format_amount(tx.total_cost())
+                ))
+            })?;
+            balances.insert(tx.sender().to_vec(), remaining);
+            
+            let receiver_balance = balances.entry(tx.receiver().to_vec()).or_insert(0);
+            *receiver_balance = receiver_balance.checked_add(tx.amount())
+                .ok_or_else(|| Error::Blockchain("Переполнение баланса получателя".to_string()))?;
+        }
+        
+        Ok(())
+    }
+    
+    /// Проверить, что корень состояния в заголовке блока совпадает с балансами после него
+    fn check_state_root(balances: &HashMap<Vec<u8>, u64>, block: &BasicBlock) -> Result<()> {
+        if StateTree::from_balances(balances).root() != block.state_root() {
+            return Err(Error::Blockchain("Корень состояния блока не соответствует балансам".to_string()));
+        }
+        
+        Ok(())
+    }
+    
+    /// Сумма, которую должна начислять coinbase блока с заданными транзакциями
+    ///
+    /// Равна награде за блок плюс комиссиям остальных транзакций.
+    pub fn coinbase_amount(&self, transactions: &[BasicTransaction]) -> Result<u64> {
+        transactions.iter()
+            .filter(|tx| !tx.is_coinbase())
+            .try_fold(self.block_reward, |total, tx| total.checked_add(tx.fee()))
+            .ok_or_else(|| Error::Blockchain("Переполнение суммы награды за блок".to_string()))
+    }
+    
+    /// Проверить coinbase блока
+    ///
+    /// Первая транзакция блока должна быть единственной coinbase, а ее
+    /// сумма — равна награде за блок плюс комиссиям остальных транзакций.
+    fn validate_coinbase(&self, block: &BasicBlock) -> Result<()> {
+        let (coinbase, rest) = block.transactions().split_first()
+            .ok_or_else(|| Error::Blockchain("Блок не содержит coinbase-транзакцию".to_string()))?;
+        
+        if !coinbase.is_coinbase() {
+            return Err(Error::Blockchain("Первая транзакция блока не является coinbase".to_string()));
+        }
+        
+        if rest.iter().any(|tx| tx.is_coinbase()) {
+            return Err(Error::Blockchain("Блок содержит больше одной coinbase-транзакции".to_string()));
+        }
+        
+        let expected = self.coinbase_amount(rest)?;
+        if coinbase.amount() != expected {
+            return Err(Error::Blockchain(format!(
+                "Сумма coinbase {} не равна награде с комиссиями {}",
+                format_amount(coinbase.amount()), format_amount(expected)
+            )));
+        }
+        
+        Ok(())
+    }
+    
+    /// Собрать и добыть следующий блок основной цепочки
+    ///
+    /// В блок включаются до `limit` транзакций из пула и coinbase,
+    /// начисляющая `miner` награду за блок и комиссии. Вместе с coinbase
+    /// блок содержит не больше `max_block_transactions` транзакций.
+    pub async fn build_block(&self, miner: Vec<u8>, limit: usize) -> Result<BasicBlock> {
+        let last_block = self.get_last_block().await?;
+        let height = last_block.height() + 1;
+        let difficulty = self.next_difficulty().await?;
+        
+        let limit = limit.min(self.max_block_transactions - 1);
+        let selected = self.select_transactions_for_block(limit)?;
+        let coinbase = BasicTransaction::coinbase(miner, self.coinbase_amount(&selected)?, height);
+        
+        let mut transactions = Vec::with_capacity(selected.len() + 1);
+        transactions.push(coinbase);
+        transactions.extend(selected);
+        
+        let state_root = self.next_state_root(&transactions)?;
+        Ok(BasicBlock::with_hash_algorithm(
+            last_block.hash(), height, difficulty, transactions, Vec::new(), self.hash_algorithm,
+        ).with_state_root(state_root))
+    }
+    
+    /// Выбрать транзакции из пула для нового блока
+    ///
+    /// Транзакции выбираются по убыванию комиссии с соблюдением порядка nonce
+    /// каждого отправителя; транзакции без подтвержденного или выбранного
+    /// предшественника пропускаются.
+    pub fn select_transactions_for_block(&self, limit: usize) -> Result<Vec<BasicTransaction>> {
+        let pool = self.transaction_pool.lock()
+            .map_err(|_| Error::Blockchain("Не удалось получить блокировку пула транзакций".to_string()))?;
+        
+        Ok(pool.select_transactions_for_block(limit))
+    }
+    
+    /// Целевой интервал между блоками
+    pub fn target_block_time(&self) -> Duration {
+        self.target_block_time
+    }
+    
+    /// Количество блоков между пересчетами сложности
+    pub fn retarget_interval(&self) -> u64 {
+        self.retarget_interval
+    }
+    
+    /// Вычислить сложность, которую должен иметь следующий блок основной цепочки
+    ///
+    /// До первого пересчета действует начальная сложность. На высотах, кратных
+    /// `retarget_interval`, фактическое время появления последних
+    /// `retarget_interval` блоков сравнивается с целевым: сложность растет,
+    /// если блоки появлялись быстрее, и падает, если медленнее. Изменение
+    /// ограничено `MAX_RETARGET_STEP` битами, сложность не опускается ниже 1.
+    pub async fn next_difficulty(&self) -> Result<u32> {
+        let last_block = self.get_last_block().await?;
+        self.next_difficulty_after(&last_block).await
+    }
+    
+    /// Вычислить сложность, которую должен иметь потомок блока `parent`
+    ///
+    /// Правила те же, что у `next_difficulty`, но время появления блоков
+    /// берется из собственной ветки `parent`, которая может быть побочной.
+    async fn next_difficulty_after(&self, parent: &BasicBlock) -> Result<u32> {
+        let last_block = parent;
+        let next_height = last_block.height() + 1;
+        
+        if last_block.height() < self.retarget_interval {
+            return Ok(self.difficulty);
+        }
+        
+        if !next_height.is_multiple_of(self.retarget_interval) {
+            return Ok(last_block.difficulty());
+        }
+        
+        let first_height = last_block.height() - self.retarget_interval;
+        let first_timestamp = match self.side_ancestor_timestamp(last_block, first_height)? {
+            Some(timestamp) => timestamp,
+            None => self.get_header_by_height(first_height).await?
+                .ok_or_else(|| Error::Blockchain(format!("Не найден блок на высоте {}", first_height)))?
+                .timestamp,
+        };
+        
+        let actual = last_block.timestamp().saturating_sub(first_timestamp).max(1);
+        let expected = self.target_block_time.as_secs().max(1).saturating_mul(self.retarget_interval);
+        
+        // Сложность измеряется в битах, поэтому поправка — двоичный логарифм отношения
+        let step = (expected as f64 / actual as f64).log2().round() as i64;
+        let step = step.clamp(-MAX_RETARGET_STEP, MAX_RETARGET_STEP);
+        let difficulty = (last_block.difficulty() as i64 + step).max(MIN_DIFFICULTY as i64);
+        
+        Ok(difficulty.min(u32::MAX as i64) as u32)
+    }
+    
+    /// Получить сложность следующего блока основной цепочки
+    ///
+    /// Значение обновляется при каждом изменении вершины цепочки.
+    pub fn get_difficulty(&self) -> u32 {
+        self.current_difficulty
+    }
+    
+    /// Получить количество блоков основной цепочки, включая генезис
+    pub async fn get_chain_length(&self) -> u64 {
+        self.get_last_block().await
+            .map(|block| block.height() + 1)
+            .unwrap_or(0)
+    }
+    
+    /// Получить транзакции пула, которые войдут в следующий блок
+    ///
+    /// Транзакции отбираются так же, как в `build_block`: по убыванию
+    /// комиссии с соблюдением порядка nonce, с учетом места под coinbase.
+    pub async fn get_pending_transactions(&self) -> Vec<BasicTransaction> {
+        self.select_transactions_for_block(self.max_block_transactions - 1)
+            .unwrap_or_default()
+    }
+    
+    /// Получить суммарную работу блоков основной цепочки
+    pub fn get_total_work(&self) -> u128 {
+        self.total_work
+    }
+    
+    /// Высота последнего блока, тело которого удалено при обрезке
+    pub fn pruned_height(&self) -> Option<u64> {
+        self.pruned_height
+    }
+    
+    /// Получить заголовок блока основной цепочки по высоте
+    ///
+    /// В отличие от `get_block_by_height` работает и для обрезанных блоков.
+    pub async fn get_header_by_height(&self, height: u64) -> Result<Option<BlockHeader>> {
+        if self.pruned_height.is_some_and(|pruned| height <= pruned) {
+            let header_key = format!("header:{}", height).into_bytes();
+            
+            return match self.storage.get(&header_key).await? {
+                Some(header_data) => Ok(Some(bincode::deserialize(&header_data)
+                    .map_err(|e| Error::Serialization(format!("Не удалось десериализовать заголовок блока: {}", e)))?)),
+                None => Ok(None),
+            };
+        }
+        
+        Ok(self.get_block_by_height(height).await?.map(|block| block.header()))
+    }
+    
+    /// Удалить тела блоков старше `keep_last` последних блоков
+    ///
+    /// Обрезаются блоки на высотах ниже `tip_height - keep_last`. Их заголовки
+    /// сохраняются для проверки цепочки и SPV, а балансы и nonce на границе
+    /// обрезки записываются снимком, чтобы блокчейн можно было
+    /// инициализировать без удаленных тел. Реорганизация глубже границы
+    /// обрезки становится невозможной. Возвращает количество обрезанных блоков.
+    pub async fn prune(&mut self, keep_last: u64) -> Result<u64> {
+        let tip_height = self.get_last_block().await?.height();
+        if tip_height <= keep_last {
+            return Ok(0);
+        }
+        
+        let prune_to = tip_height - keep_last - 1;
+        let prune_from = self.pruned_height.map(|height| height + 1).unwrap_or(0);
+        if prune_from > prune_to {
+            return Ok(0);
+        }
+        
+        // Восстанавливаем состояние на границе обрезки, откатывая остающиеся блоки
+        let mut balances = self.balances.lock()
+            .map_err(|_| Error::Blockchain("Не удалось получить блокировку balances".to_string()))?
+            .clone();
+        let mut nonces = self.nonces.lock()
+            .map_err(|_| Error::Blockchain("Не удалось получить блокировку nonces".to_string()))?
+            .clone();
+        let mut total_work = self.total_work;
+        
+        for height in (prune_to + 1..=tip_height).rev() {
+            let block = self.get_block_by_height(height).await?
+                .ok_or_else(|| Error::Blockchain(format!("Не найден блок на высоте {}", height)))?;
+            
+            Self::revert_transactions(&mut balances, &mut nonces, block.transactions())?;
+            total_work = total_work.saturating_sub(block.work());
+        }
+        
+        // Сначала сохраняем заголовки и снимок, затем удаляем тела, чтобы
+        // прерванная обрезка не оставила блоки без заголовков
+        let mut entries = Vec::with_capacity((prune_to - prune_from + 1) as usize + 2);
+        let mut pruned_hashes = Vec::with_capacity(entries.capacity());
+        
+        for height in prune_from..=prune_to {
+            let block = self.get_block_by_height(height).await?
+                .ok_or_else(|| Error::Blockchain(format!("Не найден блок на высоте {}", height)))?;
+            
+            let header_data = bincode::serialize(&block.header())
+                .map_err(|e| Error::Serialization(format!("Не удалось сериализовать заголовок блока: {}", e)))?;
+            entries.push((format!("header:{}", height).into_bytes(), header_data));
+            pruned_hashes.push((height, block.hash()));
+        }
+        
+        let snapshot = StateSnapshot { balances, nonces, total_work };
+        let snapshot_data = bincode::serialize(&snapshot)
+            .map_err(|e| Error::Serialization(format!("Не удалось сериализовать снимок состояния: {}", e)))?;
+        entries.push((STATE_SNAPSHOT_KEY.to_vec(), snapshot_data));
+        
+        let pruned_height_data = bincode::serialize(&prune_to)
+            .map_err(|e| Error::Serialization(format!("Не удалось сериализовать высоту обрезки: {}", e)))?;
+        entries.push((PRUNED_HEIGHT_KEY.to_vec(), pruned_height_data));
+        
+        self.storage.put_batch(&entries).await?;
+        self.pruned_height = Some(prune_to);
+        
+        for (height, hash) in &pruned_hashes {
+            self.storage.delete(format!("block:{}", height).as_bytes()).await?;
+            self.storage.delete(format!("block_by_hash:{}", hex::encode(hash)).as_bytes()).await?;
+        }
+        
+        Ok(pruned_hashes.len() as u64)
+    }
+    
+    /// Выгрузить цепочку от генезиса до вершины в поток
+    ///
+    /// Каждый блок записывается как длина закодированного блока (`u32`,
+    /// big-endian) и сами байты. Обрезанную цепочку выгрузить нельзя.
+    /// Возвращает количество выгруженных блоков.
+    pub async fn export(&self, mut writer: impl AsyncWrite + Unpin) -> Result<u64> {
+        let tip_height = self.get_last_block().await?.height();
+        
+        for height in 0..=tip_height {
+            let block = self.get_block_by_height(height).await?
+                .ok_or_else(|| Error::Blockchain(format!("Не найден блок на высоте {}", height)))?;
+            let block_data = self.codec.encode_block(&block)?;
+            
+            writer.write_all(&(block_data.len() as u32).to_be_bytes()).await?;
+            writer.write_all(&block_data).await?;
+        }
+        
+        writer.flush().await?;
+        Ok(tip_height + 1)
+    }
+    
+    /// Загрузить цепочку из потока, созданного [`export`](Self::export)
+    ///
+    /// Каждый блок проверяется по мере чтения: он должен быть валидным и
+    /// ссылаться на предыдущий блок потока, а затем пройти все проверки
+    /// `add_block`. Если в цепочке есть только генезис, она переходит на
+    /// генезис потока; иначе генезисы должны совпадать, а уже известные
+    /// блоки основной цепочки пропускаются. Возвращает количество
+    /// добавленных блоков, не считая генезиса.
+    pub async fn import(&mut self, mut reader: impl AsyncRead + Unpin) -> Result<u64> {
+        let mut previous: Option<BasicBlock> = None;
+        let mut imported = 0;
+        
+        loop {
+            // Конец потока допустим только на границе блока
+            let mut len_buf = [0u8; 4];
+            let read = reader.read(&mut len_buf).await?;
+            if read == 0 {
+                break;
+            }
+            reader.read_exact(&mut len_buf[read..]).await?;
+            
+            let len = u32::from_be_bytes(len_buf) as usize;
+            if len > MAX_EXPORTED_BLOCK_SIZE {
+                return Err(Error::Blockchain(format!("Некорректная длина блока в потоке: {}", len)));
+            }
+            
+            let mut block_data = vec![0u8; len];
+            reader.read_exact(&mut block_data).await?;
+            let block = self.codec.decode_block(&block_data)?;
+            
+            // Проверяем хеш, proof-of-work и корень дерева Меркла
+            if !block.is_valid() {
+                return Err(Error::Blockchain(format!("Блок на высоте {} в потоке не валиден", block.height())));
+            }
+            
+            match &previous {
+                None => {
+                    if block.height() != 0 {
+                        return Err(Error::Blockchain("Поток должен начинаться с генезис-блока".to_string()));
+                    }
+                    if block.hash_algorithm() != self.hash_algorithm {
+                        return Err(Error::Blockchain("Алгоритм хеширования генезис-блока потока не совпадает с алгоритмом цепочки".to_string()));
+                    }
+                    self.adopt_genesis(&block).await?;
+                }
+                Some(parent) => {
+                    if block.previous_hash() != parent.hash() || block.height() != parent.height() + 1 {
+                        return Err(Error::Blockchain(format!(
+                            "Блок на высоте {} в потоке не связан с предыдущим", block.height()
+                        )));
+                    }
+                    
+                    let known = self.blocks_by_height.lock()
+                        .map_err(|_| Error::Blockchain("Не удалось получить блокировку blocks_by_height".to_string()))?
+                        .get(&block.height())
+                        .map(|hash| *hash == block.hash())
+                        .unwrap_or(false);
+                    if !known {
+                        self.add_block(block.clone()).await?;
+                        imported += 1;
+                    }
+                }
+            }
+            
+            previous = Some(block);
+        }
+        
+        if previous.is_none() {
+            return Err(Error::Blockchain("Поток не содержит блоков".to_string()));
+        }
+        
+        Ok(imported)
+    }
+    
+    /// Перейти на генезис-блок загружаемой цепочки
+    ///
+    /// Пустая цепочка или цепочка из одного генезиса заменяется новым
+    /// генезисом; у более длинной цепочки генезис должен совпадать.
+    async fn adopt_genesis(&mut self, genesis: &BasicBlock) -> Result<()> {
+        let current = self.last_block.lock()
+            .map_err(|_| Error::Blockchain("Не удалось получить блокировку last_block".to_string()))?
+            .clone();
+        
+        if let Some(current) = current {
+            let current_genesis = self.blocks_by_height.lock()
+                .map_err(|_| Error::Blockchain("Не удалось получить блокировку blocks_by_height".to_string()))?
+                .get(&0)
+                .cloned();
+            
+            if current_genesis.as_deref() == Some(genesis.hash().as_slice()) {
+                return Ok(());
+            }
+            if current.height() > 0 {
+                return Err(Error::Blockchain("Генезис-блок потока не совпадает с генезисом цепочки".to_string()));
+            }
+            if let Some(old_hash) = current_genesis {
+                self.storage.delete(format!("block_by_hash:{}", hex::encode(old_hash)).as_bytes()).await?;
+            }
+        }
+        
+        // Сохраняем новый генезис и высоту одной пачкой
+        let genesis_data = self.codec.encode_block(genesis)?;
+        let last_height_data = bincode::serialize(&0u64)
+            .map_err(|e| Error::Serialization(format!("Не удалось сериализовать высоту последнего блока: {}", e)))?;
+        self.storage.put_batch(&[
+            (b"block:0".to_vec(), genesis_data.clone()),
+            (format!("block_by_hash:{}", hex::encode(genesis.hash())).into_bytes(), genesis_data),
+            (b"last_height".to_vec(), last_height_data),
+        ]).await?;
+        
+        // Сбрасываем индексы к состоянию сразу после генезиса
+        *self.blocks_by_height.lock()
+            .map_err(|_| Error::Blockchain("Не удалось получить блокировку blocks_by_height".to_string()))? =
+            HashMap::from([(0, genesis.hash())]);
+        *self.balances.lock()
+            .map_err(|_| Error::Blockchain("Не удалось получить блокировку balances".to_string()))? = self.initial_balances.clone();
+        self.nonces.lock()
+            .map_err(|_| Error::Blockchain("Не удалось получить блокировку nonces".to_string()))?
+            .clear();
+        self.recent_fees.lock()
+            .map_err(|_| Error::Blockchain("Не удалось получить блокировку recent_fees".to_string()))?
+            .clear();
+        self.side_blocks.lock()
+            .map_err(|_| Error::Blockchain("Не удалось получить блокировку side_blocks".to_string()))?
+            .clear();
+        self.total_work = genesis.work();
+        *self.last_block.lock()
+            .map_err(|_| Error::Blockchain("Не удалось получить блокировку last_block".to_string()))? = Some(genesis.clone());
+        
+        self.current_difficulty = self.next_difficulty().await?;
+        
+        Ok(())
+    }
+    
+    /// Подписаться на уведомления о реорганизациях цепочки
+    pub fn reorgs(&self) -> broadcast::Receiver<ChainReorg> {
+        self.reorg_tx.subscribe()
+    }
+    
+    /// Откатить транзакции из индексов балансов и nonce
+    ///
+    /// Транзакции откатываются в обратном порядке. Как и при применении,
+    /// при ошибке индексы могут остаться частично измененными.
+    fn revert_transactions(
+        balances: &mut HashMap<Vec<u8>, u64>,
+        nonces: &mut HashMap<Vec<u8>, u64>,
+        transactions: &[BasicTransaction],
+    ) -> Result<()> {
+        for tx in transactions.iter().rev() {
+            let receiver_balance = balances.get(tx.receiver()).copied().unwrap_or(0);
+            let remaining = receiver_balance.checked_sub(tx.amount())
+                .ok_or_else(|| Error::Blockchain("Баланс получателя меньше суммы откатываемой транзакции".to_string()))?;
+            balances.insert(tx.receiver().to_vec(), remaining);
+            
+            if tx.is_coinbase() {
+                continue;
+            }
+            
+            let sender_balance = balances.entry(tx.sender().to_vec()).or_insert(0);
+            *sender_balance = sender_balance.checked_add(tx.total_cost())
+                .ok_or_else(|| Error::Blockchain("Переполнение баланса отправителя".to_string()))?;
+            
+            if tx.nonce() == 0 {
+                nonces.remove(tx.sender());
+            } else {
+                nonces.insert(tx.sender().to_vec(), tx.nonce());
+            }
+        }
+        
+        Ok(())
+    }
+    
+    /// Найти известный блок, от которого может продолжаться ветка
+    ///
+    /// Блок должен быть либо в основной цепочке, либо среди буферизованных
+    /// блоков побочных веток.
+    async fn branch_parent(&self, hash: &[u8]) -> Result<Option<BasicBlock>> {
+        if let Some(block) = self.side_blocks.lock()
+            .map_err(|_| Error::Blockchain("Не удалось получить блокировку side_blocks".to_string()))?
+            .get(hash)
+        {
+            return Ok(Some(block.clone()));
+        }
+        
+        let block = match self.get_block_by_hash(hash).await? {
+            Some(block) => block,
+            None => return Ok(None),
+        };
+        
+        let blocks_by_height = self.blocks_by_height.lock()
+            .map_err(|_| Error::Blockchain("Не удалось получить блокировку blocks_by_height".to_string()))?;
+        
+        if blocks_by_height.get(&block.height()).map(|main| main.as_slice()) == Some(hash) {
+            Ok(Some(block))
+        } else {
+            Ok(None)
+        }
+    }
+    
+    /// Найти время появления предка блока `tip` на высоте `height` среди побочных веток
+    ///
+    /// Возвращает `None`, если на этой высоте ветка `tip` уже совпадает
+    /// с основной цепочкой.
+    fn side_ancestor_timestamp(&self, tip: &BasicBlock, height: u64) -> Result<Option<u64>> {
+        let side_blocks = self.side_blocks.lock()
+            .map_err(|_| Error::Blockchain("Не удалось получить блокировку side_blocks".to_string()))?;
+        
+        let mut current = tip;
+        while current.height() > height {
+            match side_blocks.get(current.previous_hash()) {
+                Some(parent) => current = parent,
+                None => return Ok(None),
+            }
+        }
+        
+        Ok(Some(current.timestamp()))
+    }
+    
+    /// Добавить блок, продолжающий основную цепочку
+    async fn extend_chain(&mut self, block: BasicBlock) -> Result<()> {
+        // Проверяем, что отправителям хватает средств, а nonce идут по порядку
+        let mut balances = self.balances.lock()
+            .map_err(|_| Error::Blockchain("Не удалось получить блокировку balances".to_string()))?
+            .clone();
+        let mut nonces = self.nonces.lock()
+            .map_err(|_| Error::Blockchain("Не удалось получить блокировку nonces".to_string()))?
+            .clone();
+        Self::apply_transactions(&mut balances, &mut nonces, block.transactions())?;
+        
+        // Корень состояния в заголовке должен совпадать с балансами после блока
+        Self::check_state_root(&balances, &block)?;
+        
+        // Сериализуем блок
+        let block_data = self.codec.encode_block(&block)?;
+        
+        // Ключи блока по высоте и по хешу
+        let block_key = format!("block:{}", block.height()).into_bytes();
+        let block_hash_key = format!("block_by_hash:{}", hex::encode(block.hash())).into_bytes();
+        
+        // Новая высота последнего блока
+        let last_height_data = bincode::serialize(&block.height())
+            .map_err(|e| Error::Serialization(format!("Не удалось сериализовать высоту последнего блока: {}", e)))?;
+        
+        // Сохраняем блок и высоту одной пачкой, чтобы сбой между записями
+        // не оставил цепочку в несогласованном состоянии
+        self.storage.put_batch(&[
+            (block_key, block_data.clone()),
+            (block_hash_key, block_data),
+            (b"last_height".to_vec(), last_height_data),
+        ]).await?;
+        
+        // Обновляем индекс блоков по высоте
+        let mut blocks_by_height = self.blocks_by_height.lock()
+            .map_err(|_| Error::Blockchain("Не удалось получить блокировку blocks_by_height".to_string()))?;
+        
+        blocks_by_height.insert(block.height(), block.hash());
+        drop(blocks_by_height);
+        
+        self.total_work = self.total_work.saturating_add(block.work());
+        
+        // Обновляем индексы балансов и nonce
+        *self.balances.lock()
+            .map_err(|_| Error::Blockchain("Не удалось получить блокировку balances".to_string()))? = balances;
+        *self.nonces.lock()
+            .map_err(|_| Error::Blockchain("Не удалось получить блокировку nonces".to_string()))? = nonces;
+        
+        // Обновляем историю комиссий
+        self.record_block_fees(block.transactions())?;
+        
+        // Удаляем подтвержденные транзакции из пула
+        let mut pool = self.transaction_pool.lock()
+            .map_err(|_| Error::Blockchain("Не удалось получить блокировку пула транзакций".to_string()))?;
+        pool.remove_confirmed(block.transactions());
+        drop(pool);
+        
+        // Устанавливаем последний блок
+        let mut last_block_lock = self.last_block.lock()
+            .map_err(|_| Error::Blockchain("Не удалось получить блокировку last_block".to_string()))?;
+        *last_block_lock = Some(block);
+        
+        Ok(())
+    }
+    
+    /// Переключить основную цепочку на ветку, заканчивающуюся блоком `new_tip`,
+    /// если ветка набрала больше работы
+    ///
+    /// Работа ветки после общего предка сравнивается с работой блоков основной
+    /// цепочки после него; при равенстве остается основная цепочка. При
+    /// переключении индексы балансов и высот откатываются до общего предка, после чего
+    /// применяются блоки новой ветки. Транзакции отключенных блоков, не
+    /// вошедшие в новую ветку, возвращаются в пул. Если новая ветка содержит
+    /// недопустимые транзакции или неверный корень состояния, цепочка не
+    /// меняется, а такие блоки удаляются из буфера побочных веток.
+    async fn reorganize(&mut self, new_tip: BasicBlock) -> Result<()> {
+        // Собираем новую ветку от точки ответвления до новой вершины
+        let branch = {
+            let side_blocks = self.side_blocks.lock()
+                .map_err(|_| Error::Blockchain("Не удалось получить блокировку side_blocks".to_string()))?;
+            
+            let mut branch = vec![new_tip];
+            while let Some(parent) = side_blocks.get(branch[branch.len() - 1].previous_hash()) {
+                branch.push(parent.clone());
+            }
+            branch.reverse();
+            branch
+        };
+        
+        // Ветка должна начинаться от блока основной цепочки
+        let fork_height = branch[0].height() - 1;
+        let forks_from_main = self.blocks_by_height.lock()
+            .map_err(|_| Error::Blockchain("Не удалось получить блокировку blocks_by_height".to_string()))?
+            .get(&fork_height)
+            .map(|hash| hash.as_slice() == branch[0].previous_hash())
+            .unwrap_or(false);
+        if !forks_from_main {
+            return Err(Error::Blockchain("Ветка не связана с основной цепочкой".to_string()));
+        }
+        let last_height = self.get_last_block().await?.height();
+        
+        // Блоки основной цепочки после общего предка
+        let mut disconnected = Vec::new();
+        for height in fork_height + 1..=last_height {
+            disconnected.push(self.get_block_by_height(height).await?
+                .ok_or_else(|| Error::Blockchain(format!("Не найден блок на высоте {}", height)))?);
+        }
+        
+        let branch_work = branch.iter().fold(0u128, |work, block| work.saturating_add(block.work()));
+        let disconnected_work = disconnected.iter().fold(0u128, |work, block| work.saturating_add(block.work()));
+        if branch_work <= disconnected_work {
+            return Ok(());
+        }
+        
+        // Пересчитываем балансы на копии, чтобы при ошибке цепочка не изменилась
+        let mut balances = self.balances.lock()
+            .map_err(|_| Error::Blockchain("Не удалось получить блокировку balances".to_string()))?
+            .clone();
+        let mut nonces = self.nonces.lock()
+            .map_err(|_| Error::Blockchain("Не удалось получить блокировку nonces".to_string()))?
+            .clone();
+        for block in disconnected.iter().rev() {
+            Self::revert_transactions(&mut balances, &mut nonces, block.transactions())?;
+        }
+        
+        for (index, block) in branch.iter().enumerate() {
+            let applied = Self::apply_transactions(&mut balances, &mut nonces, block.transactions())
+                .and_then(|_| Self::check_state_root(&balances, block));
+            if let Err(e) = applied {
+                let mut side_blocks = self.side_blocks.lock()
+                    .map_err(|_| Error::Blockchain("Не удалось получить блокировку side_blocks".to_string()))?;
+                for invalid in &branch[index..] {
+                    side_blocks.remove(&invalid.hash());
+                }
+                return Err(e);
+            }
+        }
+        
+        // Сохраняем блоки новой ветки и новую высоту одной пачкой
+        let new_tip = branch.last().expect("Ветка содержит хотя бы один блок").clone();
+        let mut entries = Vec::with_capacity(branch.len() * 2 + 1);
+        for block in &branch {
+            let block_data = self.codec.encode_block(block)?;
+            entries.push((format!("block:{}", block.height()).into_bytes(), block_data.clone()));
+            entries.push((format!("block_by_hash:{}", hex::encode(block.hash())).into_bytes(), block_data));
+        }
+        let last_height_data = bincode::serialize(&new_tip.height())
+            .map_err(|e| Error::Serialization(format!("Не удалось сериализовать высоту последнего блока: {}", e)))?;
+        entries.push((b"last_height".to_vec(), last_height_data));
+        
+        self.storage.put_batch(&entries).await?;
+        
+        // Обновляем индекс блоков по высоте
+        let mut blocks_by_height = self.blocks_by_height.lock()
+            .map_err(|_| Error::Blockchain("Не удалось получить блокировку blocks_by_height".to_string()))?;
+        for block in &disconnected {
+            blocks_by_height.remove(&block.height());
+        }
+        for block in &branch {
+            blocks_by_height.insert(block.height(), block.hash());
+        }
+        drop(blocks_by_height);
+        
+        self.total_work = self.total_work
+            .saturating_sub(disconnected_work)
+            .saturating_add(branch_work);
+        
+        // Обновляем индексы балансов и nonce
+        *self.balances.lock()
+            .map_err(|_| Error::Blockchain("Не удалось получить блокировку balances".to_string()))? = balances;
+        *self.nonces.lock()
+            .map_err(|_| Error::Blockchain("Не удалось получить блокировку nonces".to_string()))? = nonces;
+        
+        // Заменяем комиссии отключенных блоков в истории комиссиями новой ветки
+        {
+            let mut recent = self.recent_fees.lock()
+                .map_err(|_| Error::Blockchain("Не удалось получить блокировку recent_fees".to_string()))?;
+            for _ in &disconnected {
+                recent.pop_back();
+            }
+        }
+        for block in &branch {
+            self.record_block_fees(block.transactions())?;
+        }
+        
+        // Отключенные блоки остаются в буфере, чтобы к их ветке можно было вернуться
+        {
+            let mut side_blocks = self.side_blocks.lock()
+                .map_err(|_| Error::Blockchain("Не удалось получить блокировку side_blocks".to_string()))?;
+            for block in &branch {
+                side_blocks.remove(&block.hash());
+            }
+            for block in &disconnected {
+                side_blocks.insert(block.hash(), block.clone());
+            }
+        }
+        
+        // Возвращаем в пул транзакции, не вошедшие в новую ветку
+        let confirmed: HashSet<Vec<u8>> = branch.iter()
+            .flat_map(|block| block.transactions())
+            .map(|tx| tx.id())
+            .collect();
+        let returned: Vec<BasicTransaction> = disconnected.iter()
+            .flat_map(|block| block.transactions())
+            .filter(|tx| !tx.is_coinbase() && !confirmed.contains(&tx.id()))
+            .cloned()
+            .collect();
+        
+        let mut pool = self.transaction_pool.lock()
+            .map_err(|_| Error::Blockchain("Не удалось получить блокировку пула транзакций".to_string()))?;
+        pool.restore(&returned);
+        for block in &branch {
+            pool.remove_confirmed(block.transactions());
+        }
+        drop(pool);
+        
+        // Устанавливаем последний блок
+        *self.last_block.lock()
+            .map_err(|_| Error::Blockchain("Не удалось получить блокировку last_block".to_string()))? = Some(new_tip);
+        
+        tracing::info!(
+            "Реорганизация цепочки: отключено блоков {}, подключено {}, возвращено в пул транзакций {}",
+            disconnected.len(), branch.len(), returned.len()
+        );
+        
+        // Отсутствие подписчиков не является ошибкой
+        let _ = self.reorg_tx.send(ChainReorg {
+            fork_height,
+            disconnected: disconnected.iter().map(|block| block.hash()).collect(),
+            connected: branch.iter().map(|block| block.hash()).collect(),
+            returned_transactions: returned,
+        });
+        
+        Ok(())
+    }
+    
+    /// Инициализировать блокчейн
+    pub async fn initialize(&mut self) -> Result<()> {
+        // Приводим хранимые данные к актуальной схеме
+        self.migrations.run(&mut *self.storage).await?;
+        
+        // Проверяем, есть ли уже блоки в хранилище
+        let genesis_key = b"block:0".to_vec();
+        
+        // После обрезки тело генезис-блока удалено, но цепочка существует
+        if self.storage.has(&genesis_key).await? || self.storage.has(PRUNED_HEIGHT_KEY).await? {
+            // Загружаем последний блок
+            let last_height_data = self.storage.get(b"last_height").await?
+                .ok_or_else(|| Error::Blockchain("Не найдена высота последнего блока".to_string()))?;
+            
+            let last_height = bincode::deserialize::<u64>(&last_height_data)
+                .map_err(|e| Error::Serialization(format!("Не удалось десериализовать высоту последнего блока: {}", e)))?;
+            
+            let last_block_key = format!("block:{}", last_height).into_bytes();
+            let last_block_data = self.storage.get(&last_block_key).await?
+                .ok_or_else(|| Error::Blockchain("Не найден последний блок".to_string()))?;
+            
+            let last_block = self.codec.decode_block(&last_block_data)?;
+            
+            // Загружаем границу обрезки; состояние до нее берется из снимка
+            self.pruned_height = match self.storage.get(PRUNED_HEIGHT_KEY).await? {
+                Some(data) => Some(bincode::deserialize::<u64>(&data)
+                    .map_err(|e| Error::Serialization(format!("Не удалось десериализовать высоту обрезки: {}", e)))?),
+                None => None,
+            };
+            
+            let (mut balances, mut nonces, mut total_work) = match self.pruned_height {
+                Some(_) => {
+                    let snapshot_data = self.storage.get(STATE_SNAPSHOT_KEY).await?
+                        .ok_or_else(|| Error::Blockchain("Не найден снимок состояния обрезанной цепочки".to_string()))?;
+                    let snapshot: StateSnapshot = bincode::deserialize(&snapshot_data)
+                        .map_err(|e| Error::Serialization(format!("Не удалось десериализовать снимок состояния: {}", e)))?;
+                    
+                    (snapshot.balances, snapshot.nonces, snapshot.total_work)
+                }
+                // Воспроизводим подтвержденные транзакции для индекса балансов
+                None => (self.initial_balances.clone(), HashMap::new(), 0u128),
+            };
+            let first_height = self.pruned_height.map(|height| height + 1).unwrap_or(0);
+            
+            let header_keys: Vec<Vec<u8>> = (0..first_height)
+                .map(|height| format!("header:{}", height).into_bytes())
+                .collect();
+            let headers_data = self.storage.get_many(&header_keys).await?;
+            
+            let block_keys: Vec<Vec<u8>> = (first_height..=last_height)
+                .map(|height| format!("block:{}", height).into_bytes())
+                .collect();
+            let blocks_data = self.storage.get_many(&block_keys).await?;
+            
+            // Загружаем индекс блоков по высоте
+            let mut blocks_by_height = self.blocks_by_height.lock()
+                .map_err(|_| Error::Blockchain("Не удалось получить блокировку blocks_by_height".to_string()))?;
+            
+            for (height, header_data) in (0..first_height).zip(headers_data) {
+                if let Some(header_data) = header_data {
+                    let header: BlockHeader = bincode::deserialize(&header_data)
+                        .map_err(|e| Error::Serialization(format!("Не удалось десериализовать заголовок блока: {}", e)))?;
+                    
+                    blocks_by_height.insert(height, header.hash());
+                }
+            }
+            
+            for (height, block_data) in (first_height..=last_height).zip(blocks_data) {
+                if let Some(block_data) = block_data {
+                    let block = self.codec.decode_block(&block_data)?;
+                    
+                    blocks_by_height.insert(height, block.hash());
+                    total_work = total_work.saturating_add(block.work());
+                    Self::apply_transactions(&mut balances, &mut nonces, block.transactions())?;
+                    self.record_block_fees(block.transactions())?;
+                    
+                    // Восстанавливаем ожидаемые nonce отправителей
+                    if let Ok(mut pool) = self.transaction_pool.lock() {
+                        pool.remove_confirmed(block.transactions());
+                    }
+                }
+            }
+            
+            *self.balances.lock()
+                .map_err(|_| Error::Blockchain("Не удалось получить блокировку balances".to_string()))? = balances;
+            *self.nonces.lock()
+                .map_err(|_| Error::Blockchain("Не удалось получить блокировку nonces".to_string()))? = nonces;
+            self.total_work = total_work;
+            
+            // Устанавливаем последний блок
+            let mut last_block_lock = self.last_block.lock()
+                .map_err(|_| Error::Blockchain("Не удалось получить блокировку last_block".to_string()))?;
             *last_block_lock = Some(last_block);
         } else {
             // Создаем генезис-блок
-            let genesis = BasicBlock::genesis();
+            let genesis = BasicBlock::genesis_with_hash_algorithm(self.hash_algorithm);
             
             // Сохраняем генезис-блок
-            let genesis_data = bincode::serialize(&genesis)
-                .map_err(|e| Error::Serialization(format!("Не удалось сериализовать генезис-блок: {}", e)))?;
+            let genesis_data = self.codec.encode_block(&genesis)?;
             
             self.storage.put(&genesis_key, &genesis_data).await?;
             
+            let genesis_hash_key = format!("block_by_hash:{}", hex::encode(genesis.hash())).into_bytes();
+            self.storage.put(&genesis_hash_key, &genesis_data).await?;
+            
             // Обновляем индекс блоков по высоте
-            let mut blocks_by_height = self.blocks_by_height.lock()
-                .map_err(|_| Error::Blockchain("Не удалось получить блокировку blocks_by_height".to_string()))?;
-            
-            blocks_by_height.insert(0, genesis.hash());
+            self.blocks_by_height.lock()
+                .map_err(|_| Error::Blockchain("Не удалось получить блокировку blocks_by_height".to_string()))?
+                .insert(0, genesis.hash());
             
             // Сохраняем высоту последнего блока
             let last_height_data = bincode::serialize(&0u64)
@@ -368,12 +2128,18 @@ impl BasicBlockchain {
             
             self.storage.put(b"last_height", &last_height_data).await?;
             
+            *self.balances.lock()
+                .map_err(|_| Error::Blockchain("Не удалось получить блокировку balances".to_string()))? = self.initial_balances.clone();
+            self.total_work = genesis.work();
+            
             // Устанавливаем последний блок
             let mut last_block_lock = self.last_block.lock()
                 .map_err(|_| Error::Blockchain("Не удалось получить блокировку last_block".to_string()))?;
             *last_block_lock = Some(genesis);
         }
         
+        self.current_difficulty = self.next_difficulty().await?;
+        
         Ok(())
     }
 }
@@ -394,8 +2160,7 @@ impl Blockchain for BasicBlockchain {
         let block_key = format!("block_by_hash:{}", hex::encode(hash)).into_bytes();
         
         if let Some(block_data) = self.storage.get(&block_key).await? {
-            let block: BasicBlock = bincode::deserialize(&block_data)
-                .map_err(|e| Error::Serialization(format!("Не удалось десериализовать блок: {}", e)))?;
+            let block = self.codec.decode_block(&block_data)?;
             
             Ok(Some(block))
         } else {
@@ -404,11 +2169,16 @@ impl Blockchain for BasicBlockchain {
     }
     
     async fn get_block_by_height(&self, height: u64) -> Result<Option<Self::BlockType>> {
+        if self.pruned_height.is_some_and(|pruned| height <= pruned) {
+            return Err(Error::Blockchain(format!(
+                "Тело блока на высоте {} удалено при обрезке, доступен только заголовок", height
+            )));
+        }
+        
         let block_key = format!("block:{}", height).into_bytes();
         
         if let Some(block_data) = self.storage.get(&block_key).await? {
-            let block: BasicBlock = bincode::deserialize(&block_data)
-                .map_err(|e| Error::Serialization(format!("Не удалось десериализовать блок: {}", e)))?;
+            let block = self.codec.decode_block(&block_data)?;
             
             Ok(Some(block))
         } else {
@@ -416,69 +2186,135 @@ impl Blockchain for BasicBlockchain {
         }
     }
     
-    async fn add_block(&mut self, block: Self::BlockType) -> Result<()> {
+    async fn add_block(&mut self, block: impl Into<Self::BlockType> + Send) -> Result<()> {
+        let block = block.into();
+        
         // Проверяем валидность блока
         if !block.is_valid() {
             return Err(Error::Blockchain("Блок не валиден".to_string()));
         }
         
-        // Проверяем, что предыдущий блок существует
-        let last_block = self.get_last_block().await?;
-        
-        if block.previous_hash() != last_block.hash() {
-            return Err(Error::Blockchain("Предыдущий хеш блока не соответствует хешу последнего блока".to_string()));
+        if block.transactions().len() > self.max_block_transactions {
+            return Err(Error::Blockchain(format!(
+                "Блок содержит {} транзакций, допустимо не больше {}",
+                block.transactions().len(), self.max_block_transactions
+            )));
         }
         
-        // Проверяем высоту блока
-        if block.height() != last_block.height() + 1 {
-            return Err(Error::Blockchain("Высота блока не соответствует ожидаемой".to_string()));
+        if block.hash_algorithm() != self.hash_algorithm {
+            return Err(Error::Blockchain(format!(
+                "Алгоритм хеширования блока {:?} не совпадает с алгоритмом цепочки {:?}",
+                block.hash_algorithm(), self.hash_algorithm
+            )));
         }
         
-        // Сериализуем блок
-        let block_data = bincode::serialize(&block)
-            .map_err(|e| Error::Serialization(format!("Не удалось сериализовать блок: {}", e)))?;
+        self.validate_coinbase(&block)?;
         
-        // Сохраняем блок по высоте
-        let block_key = format!("block:{}", block.height()).into_bytes();
-        self.storage.put(&block_key, &block_data).await?;
+        let last_block = self.get_last_block().await?;
         
-        // Сохраняем блок по хешу
-        let block_hash_key = format!("block_by_hash:{}", hex::encode(block.hash())).into_bytes();
-        self.storage.put(&block_hash_key, &block_data).await?;
+        // Блок продолжает основную цепочку
+        if block.previous_hash() == last_block.hash() {
+            // Проверяем высоту блока
+            if block.height() != last_block.height() + 1 {
+                return Err(Error::Blockchain("Высота блока не соответствует ожидаемой".to_string()));
+            }
+            
+            // Проверяем сложность блока
+            let expected_difficulty = self.next_difficulty().await?;
+            if block.difficulty() != expected_difficulty {
+                return Err(Error::Blockchain(format!(
+                    "Сложность блока {} не соответствует ожидаемой {}",
+                    block.difficulty(), expected_difficulty
+                )));
+            }
+            
+            self.extend_chain(block).await?;
+            self.current_difficulty = self.next_difficulty().await?;
+            
+            return Ok(());
+        }
         
-        // Обновляем индекс блоков по высоте
-        let mut blocks_by_height = self.blocks_by_height.lock()
-            .map_err(|_| Error::Blockchain("Не удалось получить блокировку blocks_by_height".to_string()))?;
+        let already_known = self.blocks_by_height.lock()
+            .map_err(|_| Error::Blockchain("Не удалось получить блокировку blocks_by_height".to_string()))?
+            .get(&block.height())
+            .map(|hash| *hash == block.hash())
+            .unwrap_or(false);
+        if already_known {
+            return Err(Error::Blockchain("Блок уже есть в цепочке".to_string()));
+        }
         
-        blocks_by_height.insert(block.height(), block.hash());
+        // Блок продолжает другую ветку: проверяем, что предыдущий блок известен
+        let parent = self.branch_parent(block.previous_hash()).await?
+            .ok_or_else(|| Error::Blockchain("Предыдущий блок не найден".to_string()))?;
         
-        // Обновляем высоту последнего блока
-        let last_height_data = bincode::serialize(&block.height())
-            .map_err(|e| Error::Serialization(format!("Не удалось сериализовать высоту последнего блока: {}", e)))?;
+        if block.height() != parent.height() + 1 {
+            return Err(Error::Blockchain("Высота блока не соответствует ожидаемой".to_string()));
+        }
         
-        self.storage.put(b"last_height", &last_height_data).await?;
+        // Сложность сверяется с пересчетом по предкам самой ветки, иначе
+        // ветка из легких блоков могла бы вытеснить основную цепочку
+        let expected_difficulty = self.next_difficulty_after(&parent).await?;
+        if block.difficulty() != expected_difficulty {
+            return Err(Error::Blockchain(format!(
+                "Сложность блока {} не соответствует ожидаемой {}",
+                block.difficulty(), expected_difficulty
+            )));
+        }
         
-        // Устанавливаем последний блок
-        let mut last_block_lock = self.last_block.lock()
-            .map_err(|_| Error::Blockchain("Не удалось получить блокировку last_block".to_string()))?;
-        *last_block_lock = Some(block);
+        self.side_blocks.lock()
+            .map_err(|_| Error::Blockchain("Не удалось получить блокировку side_blocks".to_string()))?
+            .insert(block.hash(), block.clone());
+        
+        // Переключаемся на ветку, только если она набрала больше работы, чем основная
+        self.reorganize(block).await?;
+        self.current_difficulty = self.next_difficulty().await?;
         
         Ok(())
     }
     
-    async fn add_transaction(&mut self, tx: Self::TransactionType) -> Result<()> {
+    async fn add_transaction(&mut self, tx: impl Into<Self::TransactionType> + Send) -> Result<()> {
+        let tx = tx.into();
+        
         // Проверяем валидность транзакции
         if !tx.is_valid() {
             return Err(Error::Blockchain("Транзакция не валидна".to_string()));
         }
         
-        // Добавляем транзакцию в пул
+        // Coinbase создается майнером вместе с блоком и не распространяется через пул
+        if tx.is_coinbase() {
+            return Err(Error::Blockchain("Coinbase-транзакция не может быть добавлена в пул".to_string()));
+        }
+        
+        // Проверяем минимальную комиссию; освобожденные отправители не проверяются
+        let exempt = self.fee_exempt_senders.contains(tx.sender());
+        if !exempt && tx.fee() < self.min_relay_fee {
+            return Err(Error::Blockchain(format!(
+                "Комиссия {} ниже минимальной {}",
+                format_amount(tx.fee()), format_amount(self.min_relay_fee)
+            )));
+        }
+        
+        let balance = self.get_balance(tx.sender())?;
+        
         let mut pool = self.transaction_pool.lock()
             .map_err(|_| Error::Blockchain("Не удалось получить блокировку пула транзакций".to_string()))?;
         
-        pool.insert(tx);
+        // Учитываем средства, уже зарезервированные транзакциями отправителя в пуле
+        let pending: u64 = pool.iter()
+            .filter(|pending| pending.sender() == tx.sender() && pending.id() != tx.id())
+            .map(|pending| pending.total_cost())
+            .fold(0u64, |acc, amount| acc.saturating_add(amount));
         
-        Ok(())
+        let available = balance.saturating_sub(pending);
+        if tx.total_cost() > available {
+            return Err(Error::Blockchain(format!(
+                "Недостаточно средств у отправителя: доступно {}, требуется {}",
+                format_amount(available), format_amount(tx.total_cost())
+            )));
+        }
+        
+        // Добавляем транзакцию в пул
+        pool.insert(tx)
     }
     
     async fn get_transaction(&self, id: &[u8]) -> Result<Option<Self::TransactionType>> {
@@ -486,10 +2322,8 @@ impl Blockchain for BasicBlockchain {
         let pool = self.transaction_pool.lock()
             .map_err(|_| Error::Blockchain("Не удалось получить блокировку пула транзакций".to_string()))?;
         
-        for tx in pool.iter() {
-            if tx.id() == id {
-                return Ok(Some(tx.clone()));
-            }
+        if let Some(tx) = pool.get(id) {
+            return Ok(Some(tx.clone()));
         }
         
         // Если не нашли в пуле, ищем в блоках
@@ -506,28 +2340,841 @@ impl Blockchain for BasicBlockchain {
     }
     
     async fn is_chain_valid(&self) -> Result<bool> {
-        let blocks_by_height = self.blocks_by_height.lock()
-            .map_err(|_| Error::Blockchain("Не удалось получить блокировку blocks_by_height".to_string()))?;
+        // Идем от вершины к генезису по ссылкам на предыдущий блок, не
+        // полагаясь на то, что высоты в индексе идут подряд
+        let mut block = self.get_last_block().await?;
         
-        let mut previous_hash = Vec::new();
-        
-        for height in 0..blocks_by_height.len() as u64 {
-            let block = self.get_block_by_height(height).await?
-                .ok_or_else(|| Error::Blockchain(format!("Не найден блок на высоте {}", height)))?;
-            
+        loop {
             // Проверяем валидность блока
             if !block.is_valid() {
                 return Ok(false);
             }
             
+            if block.height() == 0 {
+                return Ok(true);
+            }
+            
+            // Ниже границы обрезки тел блоков нет, проверяем связность и
+            // proof-of-work по заголовкам
+            if self.pruned_height.is_some_and(|pruned| block.height() - 1 <= pruned) {
+                let mut expected_hash = block.previous_hash().to_vec();
+                
+                for height in (0..block.height()).rev() {
+                    let header = match self.get_header_by_height(height).await? {
+                        Some(header) => header,
+                        None => return Ok(false),
+                    };
+                    
+                    if header.height != height || header.hash() != expected_hash || !header.meets_difficulty() {
+                        return Ok(false);
+                    }
+                    
+                    expected_hash = header.previous_hash;
+                }
+                
+                return Ok(true);
+            }
+            
             // Проверяем связность цепочки
-            if height > 0 && block.previous_hash() != previous_hash {
+            let parent = match self.get_block_by_hash(block.previous_hash()).await? {
+                Some(parent) => parent,
+                None => return Ok(false),
+            };
+            
+            if parent.hash() != block.previous_hash() || parent.height() + 1 != block.height() {
                 return Ok(false);
             }
             
-            previous_hash = block.hash();
+            block = parent;
+        }
+    }
+} 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use crate::crypto::Key;
+    use crate::storage::memory::MemoryStorage;
+    use crate::blockchain::spv::verify_spv_proof;
+    
+    fn transfer(from: &Ed25519KeyPair, to: &[u8], amount: u64) -> BasicTransaction {
+        transfer_with_nonce(from, to, amount, 0)
+    }
+    
+    fn transfer_with_nonce(from: &Ed25519KeyPair, to: &[u8], amount: u64, nonce: u64) -> BasicTransaction {
+        let mut tx = BasicTransaction::new(from.public_bytes(), to.to_vec(), amount, Vec::new()).with_nonce(nonce);
+        tx.sign(from).unwrap();
+        tx
+    }
+    
+    async fn funded_chain(owner: &Ed25519KeyPair, balance: u64) -> BasicBlockchain {
+        let balances = HashMap::from([(owner.public_bytes(), balance)]);
+        let mut chain = BasicBlockchain::with_storage(Box::new(MemoryStorage::new("test")), 1)
+            .with_initial_balances(balances);
+        chain.initialize().await.unwrap();
+        chain
+    }
+    
+    /// Следующий блок основной цепочки с coinbase перед `transactions`
+    async fn next_block(chain: &BasicBlockchain, transactions: Vec<BasicTransaction>) -> BasicBlock {
+        let last = chain.get_last_block().await.unwrap();
+        let height = last.height() + 1;
+        let coinbase = BasicTransaction::coinbase(b"miner".to_vec(), chain.coinbase_amount(&transactions).unwrap(), height);
+        let difficulty = chain.next_difficulty().await.unwrap();
+        let transactions = [vec![coinbase], transactions].concat();
+        // Для недопустимых транзакций корень не вычислить, такой блок все равно отвергается
+        let state_root = chain.next_state_root(&transactions).unwrap_or_default();
+        BasicBlock::new(last.hash(), height, difficulty, transactions, Vec::new())
+            .with_state_root(state_root)
+    }
+    
+    #[test]
+    fn signature_is_bound_to_sender() {
+        let alice = Ed25519KeyPair::generate().unwrap();
+        let bob = Ed25519KeyPair::generate().unwrap();
+        let mut tx = transfer(&alice, &bob.public_bytes(), 10);
+        assert!(tx.verify_signature().unwrap());
+        assert!(tx.is_valid());
+        
+        tx.sender = bob.public_bytes();
+        assert!(!tx.verify_signature().unwrap());
+        assert!(!tx.is_valid());
+    }
+    
+    #[test]
+    fn decimal_amounts_are_exact() {
+        let sum = parse_amount("0.1").unwrap() + parse_amount("0.2").unwrap();
+        assert_eq!(sum, parse_amount("0.3").unwrap());
+        assert_eq!(format_amount(sum), "0.3");
+        
+        assert_eq!(parse_amount("12.5").unwrap(), 12 * UNITS_PER_COIN + UNITS_PER_COIN / 2);
+        assert_eq!(parse_amount(".00000001").unwrap(), 1);
+        assert_eq!(format_amount(UNITS_PER_COIN), "1");
+        assert_eq!(format_amount(1), "0.00000001");
+        
+        let tx = BasicTransaction::with_decimal_amount(vec![1], vec![2], "0.7", Vec::new()).unwrap();
+        assert_eq!(tx.amount(), 70_000_000);
+    }
+    
+    #[test]
+    fn malformed_amounts_are_rejected() {
+        for amount in ["", ".", "abc", "1.2.3", "-1", "0.123456789", "184467440737.1"] {
+            assert!(parse_amount(amount).is_err(), "{} должна быть отклонена", amount);
+        }
+    }
+    
+    #[tokio::test]
+    async fn overdraft_is_rejected() {
+        let alice = Ed25519KeyPair::generate().unwrap();
+        let bob = Ed25519KeyPair::generate().unwrap();
+        let mut chain = funded_chain(&alice, 100).await;
+        
+        assert!(chain.add_transaction(transfer(&alice, &bob.public_bytes(), 150)).await.is_err());
+        
+        let payment = transfer(&alice, &bob.public_bytes(), 60);
+        chain.add_transaction(payment.clone()).await.unwrap();
+        let block = next_block(&chain, vec![payment]).await;
+        chain.add_block(block).await.unwrap();
+        assert_eq!(chain.get_balance(&alice.public_bytes()).unwrap(), 40);
+        assert_eq!(chain.get_balance(&bob.public_bytes()).unwrap(), 60);
+        
+        let overdraft = transfer_with_nonce(&alice, &bob.public_bytes(), 50, 1);
+        let block = next_block(&chain, vec![overdraft]).await;
+        assert!(chain.add_block(block).await.is_err());
+        assert_eq!(chain.get_balance(&alice.public_bytes()).unwrap(), 40);
+        assert_eq!(chain.get_balance(&bob.public_bytes()).unwrap(), 60);
+    }
+    
+    fn block_with_difficulty(transactions: Vec<BasicTransaction>, difficulty: u32) -> BasicBlock {
+        BasicBlock::new(vec![0; 32], 1, difficulty, transactions, b"test".to_vec())
+    }
+    
+    #[test]
+    fn difficulty_counts_leading_zero_bits() {
+        let mut hash = vec![0xff; 32];
+        assert!(meets_difficulty(&hash, 0));
+        assert!(!meets_difficulty(&hash, 1));
+        
+        hash[0] = 0x00;
+        assert_eq!(leading_zero_bits(&hash), 8);
+        assert!(meets_difficulty(&hash, 8));
+        assert!(!meets_difficulty(&hash, 9));
+        
+        // Сложность больше 64 проверяется по всему хешу
+        let mut hash = vec![0x00; 32];
+        hash[10] = 0x01;
+        assert_eq!(leading_zero_bits(&hash), 87);
+        assert!(meets_difficulty(&hash, 80));
+        hash[9] = 0x80;
+        assert!(!meets_difficulty(&hash, 80));
+    }
+    
+    #[test]
+    fn mined_blocks_meet_their_difficulty() {
+        for difficulty in [0, 8] {
+            let block = block_with_difficulty(Vec::new(), difficulty);
+            assert!(leading_zero_bits(&block.hash()) >= difficulty);
+            assert!(block.is_valid());
+        }
+    }
+    
+    #[tokio::test]
+    async fn cancelled_mining_returns_promptly() {
+        let mut block = block_with_difficulty(Vec::new(), 0);
+        block.difficulty = 200;
+        let cancel = CancellationToken::new();
+        
+        let mining = tokio::spawn({
+            let cancel = cancel.clone();
+            async move { block.mine_async(cancel).await }
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        cancel.cancel();
+        
+        let mined = tokio::time::timeout(Duration::from_secs(2), mining).await.unwrap().unwrap().unwrap();
+        assert!(!mined);
+    }
+    
+    #[test]
+    fn parallel_mined_block_is_valid() {
+        let mut block = block_with_difficulty(Vec::new(), 0);
+        block.difficulty = 12;
+        block.mine_parallel(4);
+        
+        assert!(meets_difficulty(&block.hash(), 12));
+        assert!(block.is_valid());
+    }
+    
+    #[tokio::test]
+    async fn old_schema_store_is_migrated() {
+        // Хранилище старой схемы: генезис-блок сохранен только по высоте
+        let genesis = BasicBlock::genesis();
+        let mut storage = MemoryStorage::new("old");
+        storage.put(b"block:0", &bincode::serialize(&genesis).unwrap()).await.unwrap();
+        storage.put(b"last_height", &bincode::serialize(&0u64).unwrap()).await.unwrap();
+        assert_eq!(MigrationRunner::current_version(&storage).await.unwrap(), 0);
+        
+        let mut chain = BasicBlockchain::with_storage(Box::new(storage), 1);
+        chain.initialize().await.unwrap();
+        
+        assert_eq!(MigrationRunner::current_version(&*chain.storage).await.unwrap(), 1);
+        let found = chain.get_block_by_hash(&genesis.hash()).await.unwrap().unwrap();
+        assert_eq!(found.hash(), genesis.hash());
+    }
+    
+    /// Кодек блоков на основе JSON
+    struct JsonCodec;
+    
+    impl WireCodec<BasicBlock, BasicTransaction> for JsonCodec {
+        fn encode_block(&self, block: &BasicBlock) -> Result<Vec<u8>> {
+            serde_json::to_vec(block).map_err(|e| Error::Serialization(e.to_string()))
+        }
+        
+        fn decode_block(&self, data: &[u8]) -> Result<BasicBlock> {
+            serde_json::from_slice(data).map_err(|e| Error::Serialization(e.to_string()))
+        }
+    }
+    
+    #[tokio::test]
+    async fn migration_reads_blocks_with_chain_codec() {
+        let genesis = BasicBlock::genesis();
+        let mut storage = MemoryStorage::new("json");
+        storage.put(b"block:0", &JsonCodec.encode_block(&genesis).unwrap()).await.unwrap();
+        storage.put(b"last_height", &bincode::serialize(&0u64).unwrap()).await.unwrap();
+        
+        let mut chain = BasicBlockchain::with_storage(Box::new(storage), 1).with_codec(Arc::new(JsonCodec));
+        chain.initialize().await.unwrap();
+        
+        let found = chain.get_block_by_hash(&genesis.hash()).await.unwrap().unwrap();
+        assert_eq!(found.hash(), genesis.hash());
+    }
+    
+    #[test]
+    fn mine_and_is_valid_agree_on_random_blocks() {
+        use rand::{Rng, RngCore};
+        
+        let mut rng = rand::thread_rng();
+        for _ in 0..200 {
+            let mut previous_hash = vec![0; 32];
+            rng.fill_bytes(&mut previous_hash);
+            let mut data = vec![0; rng.gen_range(0..64)];
+            rng.fill_bytes(&mut data);
+            // Сложности вокруг границ байтов проверяются чаще всего
+            let difficulty = rng.gen_range(0..=12);
+            
+            let block = BasicBlock::new(previous_hash, rng.gen(), difficulty, Vec::new(), data);
+            assert!(meets_difficulty(&block.hash(), difficulty));
+            assert!(block.is_valid(), "Блок со сложностью {} не прошел проверку", difficulty);
+        }
+    }
+    
+    #[tokio::test]
+    async fn account_proof_verifies_against_block_header() {
+        let alice = Ed25519KeyPair::generate().unwrap();
+        let bob = Ed25519KeyPair::generate().unwrap();
+        let mut chain = funded_chain(&alice, 100).await;
+        
+        let block = next_block(&chain, vec![transfer(&alice, &bob.public_bytes(), 30)]).await;
+        chain.add_block(block).await.unwrap();
+        
+        // Легкому клиенту достаточно заголовка последнего блока
+        let last = chain.get_last_block().await.unwrap();
+        let proof = chain.account_proof(&bob.public_bytes()).unwrap();
+        assert_eq!(proof.balance, Some(30));
+        assert!(proof.verify(&bob.public_bytes(), last.state_root()));
+        
+        let mut forged = proof.clone();
+        forged.balance = Some(100);
+        assert!(!forged.verify(&bob.public_bytes(), last.state_root()));
+    }
+    
+    #[tokio::test]
+    async fn block_with_wrong_state_root_is_rejected() {
+        let alice = Ed25519KeyPair::generate().unwrap();
+        let bob = Ed25519KeyPair::generate().unwrap();
+        let mut chain = funded_chain(&alice, 100).await;
+        let payment = transfer(&alice, &bob.public_bytes(), 30);
+        
+        // Корень до платежа не соответствует балансам после блока
+        let stale_root = chain.state_root().unwrap();
+        let block = next_block(&chain, vec![payment.clone()]).await.with_state_root(stale_root);
+        assert!(chain.add_block(block).await.is_err());
+        assert_eq!(chain.get_balance(&bob.public_bytes()).unwrap(), 0);
+        
+        let block = next_block(&chain, vec![payment]).await;
+        chain.add_block(block).await.unwrap();
+        assert_eq!(chain.get_balance(&bob.public_bytes()).unwrap(), 30);
+    }
+    
+    #[tokio::test]
+    async fn min_relay_fee_is_enforced_except_for_exempt_senders() {
+        let alice = Ed25519KeyPair::generate().unwrap();
+        let bob = Ed25519KeyPair::generate().unwrap();
+        let with_fee = |from: &Ed25519KeyPair, fee: u64| {
+            let mut tx = BasicTransaction::new(from.public_bytes(), b"carol".to_vec(), 10, Vec::new()).with_fee(fee);
+            tx.sign(from).unwrap();
+            tx
+        };
+        
+        let mut chain = funded_chain(&alice, 100).await
+            .with_min_relay_fee(5)
+            .with_fee_exempt_sender(bob.public_bytes());
+        let result = chain.add_transaction(with_fee(&alice, 4)).await;
+        assert!(matches!(result, Err(Error::Blockchain(_))));
+        chain.add_transaction(with_fee(&alice, 5)).await.unwrap();
+        
+        // Освобожденный отправитель проходит проверку комиссии и упирается в баланс
+        let result = chain.add_transaction(with_fee(&bob, 0)).await;
+        assert!(matches!(result, Err(Error::Blockchain(message)) if message.contains("средств")));
+        
+        // Без минимальной комиссии транзакции без комиссии принимаются
+        let mut chain = funded_chain(&alice, 100).await;
+        chain.add_transaction(with_fee(&alice, 0)).await.unwrap();
+    }
+    
+    #[tokio::test]
+    async fn fee_estimate_rises_with_urgency() {
+        let alice = Ed25519KeyPair::generate().unwrap();
+        let mut chain = funded_chain(&alice, 10_000).await.with_min_relay_fee(1);
+        assert_eq!(chain.estimate_fee(1), 1);
+        
+        // Два блока с комиссиями 1..=20
+        for round in 0..2u64 {
+            let transactions = (0..10u64)
+                .map(|i| {
+                    let nonce = round * 10 + i;
+                    let mut tx = BasicTransaction::new(alice.public_bytes(), b"bob".to_vec(), 1, Vec::new())
+                        .with_nonce(nonce)
+                        .with_fee(nonce + 1);
+                    tx.sign(&alice).unwrap();
+                    tx
+                })
+                .collect();
+            let block = next_block(&chain, transactions).await;
+            chain.add_block(block).await.unwrap();
+        }
+        
+        let urgent = chain.estimate_fee(1);
+        let normal = chain.estimate_fee(3);
+        let relaxed = chain.estimate_fee(10);
+        assert_eq!((urgent, normal, relaxed), (10, 5, 2));
+        
+        // Оценка не опускается ниже минимальной комиссии
+        let chain = chain.with_min_relay_fee(4);
+        assert_eq!(chain.estimate_fee(10), 4);
+    }
+    
+    async fn empty_chain() -> BasicBlockchain {
+        BasicBlockchain::new(1).await.unwrap()
+    }
+    
+    /// Цепочка с пересчетом сложности каждые два блока и целевым интервалом в час
+    async fn retargeting_chain(difficulty: u32) -> BasicBlockchain {
+        let storage = Box::new(MemoryStorage::new("test"));
+        let mut chain = BasicBlockchain::with_retargeting(storage, difficulty, Duration::from_secs(3600), 2);
+        chain.initialize().await.unwrap();
+        chain
+    }
+    
+    /// Добавить в цепочку `count` пустых блоков с данными `tag`
+    async fn append_blocks(chain: &mut BasicBlockchain, count: usize, tag: &[u8]) {
+        for _ in 0..count {
+            let last = chain.get_last_block().await.unwrap();
+            let difficulty = chain.next_difficulty().await.unwrap();
+            let block = child(chain, &last, tag, difficulty).await;
+            chain.add_block(block).await.unwrap();
+        }
+    }
+    
+    /// Добавить в цепочку `count` пустых блоков, появлявшихся каждые `spacing` секунд
+    async fn append_blocks_every(chain: &mut BasicBlockchain, count: usize, tag: &[u8], spacing: u64) {
+        for _ in 0..count {
+            let last = chain.get_last_block().await.unwrap();
+            let difficulty = chain.next_difficulty().await.unwrap();
+            let block = child_at(chain, &last, tag, difficulty, last.timestamp() + spacing).await;
+            chain.add_block(block).await.unwrap();
+        }
+    }
+    
+    /// Независимая копия цепочки на отдельном хранилище
+    async fn copy_chain(chain: &BasicBlockchain) -> BasicBlockchain {
+        let mut storage = MemoryStorage::new("copy");
+        for key in chain.storage.keys_with_prefix(b"").await.unwrap() {
+            let value = chain.storage.get(&key).await.unwrap().unwrap();
+            storage.put(&key, &value).await.unwrap();
+        }
+        
+        let mut copy = BasicBlockchain::with_storage(Box::new(storage), 1);
+        copy.initialize().await.unwrap();
+        copy
+    }
+    
+    /// Пустой блок-потомок `parent` с данными `tag`
+    ///
+    /// Coinbase начисляет награду по умолчанию адресу `tag`. Родитель уже
+    /// должен быть в `chain`: по нему вычисляется корень состояния.
+    async fn child(chain: &BasicBlockchain, parent: &BasicBlock, tag: &[u8], difficulty: u32) -> BasicBlock {
+        let height = parent.height() + 1;
+        let transactions = vec![BasicTransaction::coinbase(tag.to_vec(), DEFAULT_BLOCK_REWARD, height)];
+        let state_root = chain.state_root_after(&parent.hash(), &transactions).await.unwrap();
+        BasicBlock::new(parent.hash(), height, difficulty, transactions, tag.to_vec())
+            .with_state_root(state_root)
+    }
+    
+    /// Пустой блок-потомок `parent` с заданным временем появления
+    async fn child_at(chain: &BasicBlockchain, parent: &BasicBlock, tag: &[u8], difficulty: u32, timestamp: u64) -> BasicBlock {
+        let mut block = child(chain, parent, tag, difficulty).await;
+        block.timestamp = timestamp;
+        block.mine();
+        block
+    }
+    
+    #[tokio::test]
+    async fn locator_finds_common_ancestor_of_diverged_chains() {
+        let mut main = empty_chain().await;
+        append_blocks(&mut main, 5, b"main").await;
+        let mut fork = copy_chain(&main).await;
+        append_blocks(&mut main, 10, b"main").await;
+        append_blocks(&mut fork, 3, b"fork").await;
+        
+        let genesis = main.get_block_by_height(0).await.unwrap().unwrap().hash();
+        let fork_locator = fork.block_locator();
+        assert_eq!(fork_locator.len(), 9);
+        assert_eq!(fork_locator[0], fork.get_last_block().await.unwrap().hash());
+        assert_eq!(fork_locator.last(), Some(&genesis));
+        
+        // Короткий локатор перечисляет все блоки подряд, поэтому предок находится точно
+        assert_eq!(main.find_common_height(&fork_locator).unwrap(), Some(5));
+        
+        // После первых десяти хешей шаг удваивается: 15..=6, 4, 0
+        let main_locator = main.block_locator();
+        assert_eq!(main_locator.len(), 12);
+        assert_eq!(main_locator.last(), Some(&genesis));
+        assert_eq!(fork.find_common_height(&main_locator).unwrap(), Some(4));
+        
+        assert_eq!(main.find_common_height(&[vec![0xab; 32]]).unwrap(), None);
+    }
+    
+    #[tokio::test]
+    async fn longer_side_branch_wins() {
+        let mut chain = empty_chain().await;
+        append_blocks(&mut chain, 3, b"main").await;
+        let fork_point = chain.get_block_by_height(1).await.unwrap().unwrap();
+        let old_tip = chain.get_last_block().await.unwrap();
+        let mut reorgs = chain.reorgs();
+        
+        // Ветка той же длины буферизуется, но не становится основной
+        let b2 = child(&chain, &fork_point, b"side", 1).await;
+        chain.add_block(b2.clone()).await.unwrap();
+        let b3 = child(&chain, &b2, b"side", 1).await;
+        chain.add_block(b3.clone()).await.unwrap();
+        assert_eq!(chain.get_last_block().await.unwrap().hash(), old_tip.hash());
+        assert!(reorgs.try_recv().is_err());
+        
+        let b4 = child(&chain, &b3, b"side", 1).await;
+        chain.add_block(b4.clone()).await.unwrap();
+        assert_eq!(chain.get_last_block().await.unwrap().hash(), b4.hash());
+        assert_eq!(chain.get_block_by_height(2).await.unwrap().unwrap().hash(), b2.hash());
+        
+        let reorg = reorgs.try_recv().unwrap();
+        assert_eq!(reorg.fork_height, 1);
+        assert_eq!(reorg.disconnected.len(), 2);
+        assert_eq!(reorg.disconnected[1], old_tip.hash());
+        assert_eq!(reorg.connected, vec![b2.hash(), b3.hash(), b4.hash()]);
+        
+        // Блок с неизвестным родителем отклоняется
+        let orphan = BasicBlock::new(vec![7; 32], 5, 1, Vec::new(), b"orphan".to_vec());
+        assert!(chain.add_block(orphan).await.is_err());
+    }
+    
+    #[tokio::test]
+    async fn harder_short_branch_beats_longer_easy_one() {
+        // Основная цепочка идет в целевом темпе, и ее сложность остается 1
+        let mut chain = retargeting_chain(1).await;
+        append_blocks_every(&mut chain, 10, b"easy", 3600).await;
+        assert_eq!(chain.next_difficulty().await.unwrap(), 1);
+        let fork_point = chain.get_block_by_height(3).await.unwrap().unwrap();
+        let easy_tip = chain.get_last_block().await.unwrap();
+        
+        // Блоки побочной ветки появляются быстро, и ее сложность растет
+        let b4 = child_at(&chain, &fork_point, b"hard", 1, fork_point.timestamp()).await;
+        chain.add_block(b4.clone()).await.unwrap();
+        let b5 = child_at(&chain, &b4, b"hard", 1, fork_point.timestamp()).await;
+        chain.add_block(b5.clone()).await.unwrap();
+        let b6 = child_at(&chain, &b5, b"hard", 3, fork_point.timestamp()).await;
+        chain.add_block(b6.clone()).await.unwrap();
+        let b7 = child_at(&chain, &b6, b"hard", 3, fork_point.timestamp()).await;
+        assert_eq!(chain.get_last_block().await.unwrap().hash(), easy_tip.hash());
+        
+        chain.add_block(b7.clone()).await.unwrap();
+        assert_eq!(chain.get_last_block().await.unwrap().hash(), b7.hash());
+        assert_eq!(chain.get_last_block().await.unwrap().height(), 7);
+    }
+    
+    #[tokio::test]
+    async fn side_branch_difficulty_follows_its_own_ancestors() {
+        let mut chain = retargeting_chain(1).await;
+        append_blocks_every(&mut chain, 10, b"main", 3600).await;
+        let fork_point = chain.get_block_by_height(3).await.unwrap().unwrap();
+        
+        // Завышенная сложность не позволяет побочной ветке набрать работу даром
+        let heavy = child_at(&chain, &fork_point, b"heavy", 8, fork_point.timestamp()).await;
+        assert!(chain.add_block(heavy).await.is_err());
+        
+        // После быстрых блоков ветки сложность основной цепочки (1) уже не подходит
+        let b4 = child_at(&chain, &fork_point, b"side", 1, fork_point.timestamp()).await;
+        chain.add_block(b4.clone()).await.unwrap();
+        let b5 = child_at(&chain, &b4, b"side", 1, fork_point.timestamp()).await;
+        chain.add_block(b5.clone()).await.unwrap();
+        let easy = child_at(&chain, &b5, b"side", 1, fork_point.timestamp()).await;
+        assert!(chain.add_block(easy).await.is_err());
+        let b6 = child_at(&chain, &b5, b"side", 3, fork_point.timestamp()).await;
+        chain.add_block(b6).await.unwrap();
+    }
+    
+    #[tokio::test]
+    async fn block_time_drives_difficulty() {
+        // Блоки появляются быстрее цели: сложность растет на максимальный шаг
+        let mut fast = retargeting_chain(4).await;
+        append_blocks_every(&mut fast, 3, b"fast", 0).await;
+        assert_eq!(fast.next_difficulty().await.unwrap(), 4 + MAX_RETARGET_STEP as u32);
+        
+        // Блоки появляются медленнее цели: сложность падает
+        let mut slow = retargeting_chain(4).await;
+        append_blocks_every(&mut slow, 3, b"slow", 4 * 3600).await;
+        assert_eq!(slow.next_difficulty().await.unwrap(), 2);
+        
+        // Блок со сложностью, отличной от пересчитанной, отклоняется
+        let last = fast.get_last_block().await.unwrap();
+        let block = child(&fast, &last, b"fast", 4).await;
+        assert!(fast.add_block(block).await.is_err());
+    }
+    
+    #[tokio::test]
+    async fn block_must_carry_single_valid_coinbase() {
+        let alice = Ed25519KeyPair::generate().unwrap();
+        let bob = Ed25519KeyPair::generate().unwrap();
+        let mut chain = funded_chain(&alice, 100).await;
+        let mut payment = BasicTransaction::new(alice.public_bytes(), bob.public_bytes(), 60, Vec::new()).with_fee(5);
+        payment.sign(&alice).unwrap();
+        let last = chain.get_last_block().await.unwrap();
+        let coinbase = |amount: u64| BasicTransaction::coinbase(b"miner".to_vec(), amount, 1);
+        
+        // Coinbase не распространяется через пул
+        assert!(chain.add_transaction(coinbase(DEFAULT_BLOCK_REWARD)).await.is_err());
+        
+        // Две coinbase в одном блоке
+        let twice = vec![coinbase(DEFAULT_BLOCK_REWARD + 5), coinbase(DEFAULT_BLOCK_REWARD), payment.clone()];
+        assert!(chain.add_block(BasicBlock::new(last.hash(), 1, 1, twice, Vec::new())).await.is_err());
+        
+        // Сумма coinbase без комиссии
+        let short = vec![coinbase(DEFAULT_BLOCK_REWARD), payment.clone()];
+        assert!(chain.add_block(BasicBlock::new(last.hash(), 1, 1, short, Vec::new())).await.is_err());
+        
+        // Блок без coinbase
+        assert!(chain.add_block(BasicBlock::new(last.hash(), 1, 1, vec![payment.clone()], Vec::new())).await.is_err());
+        
+        let block = next_block(&chain, vec![payment]).await;
+        chain.add_block(block).await.unwrap();
+        assert_eq!(chain.get_balance(b"miner").unwrap(), DEFAULT_BLOCK_REWARD + 5);
+        assert_eq!(chain.get_balance(&alice.public_bytes()).unwrap(), 35);
+        assert_eq!(chain.get_balance(&bob.public_bytes()).unwrap(), 60);
+    }
+    
+    #[tokio::test]
+    async fn higher_fee_transaction_is_mined_first() {
+        let alice = Ed25519KeyPair::generate().unwrap();
+        let bob = Ed25519KeyPair::generate().unwrap();
+        let balances = HashMap::from([(alice.public_bytes(), 100), (bob.public_bytes(), 100)]);
+        let mut chain = BasicBlockchain::with_storage(Box::new(MemoryStorage::new("test")), 1)
+            .with_initial_balances(balances);
+        chain.initialize().await.unwrap();
+        
+        let with_fee = |from: &Ed25519KeyPair, fee: u64| {
+            let mut tx = BasicTransaction::new(from.public_bytes(), b"carol".to_vec(), 10, Vec::new()).with_fee(fee);
+            tx.sign(from).unwrap();
+            tx
+        };
+        let cheap = with_fee(&alice, 1);
+        let generous = with_fee(&bob, 9);
+        chain.add_transaction(cheap.clone()).await.unwrap();
+        chain.add_transaction(generous.clone()).await.unwrap();
+        
+        // В блоке есть место только для одной транзакции из пула
+        let block = chain.build_block(b"miner".to_vec(), 1).await.unwrap();
+        assert_eq!(block.transactions().len(), 2);
+        assert_eq!(block.transactions()[1].id(), generous.id());
+        chain.add_block(block).await.unwrap();
+        
+        let pool = chain.get_transaction_pool().await.unwrap();
+        assert_eq!(pool.len(), 1);
+        assert_eq!(pool[0].id(), cheap.id());
+    }
+    
+    #[tokio::test]
+    async fn block_is_capped_at_max_transactions() {
+        let alice = Ed25519KeyPair::generate().unwrap();
+        let mut chain = funded_chain(&alice, 1000).await.with_max_block_transactions(10);
+        for nonce in 0..100 {
+            chain.add_transaction(transfer_with_nonce(&alice, b"bob", 1, nonce)).await.unwrap();
+        }
+        
+        let block = chain.build_block(b"miner".to_vec(), 100).await.unwrap();
+        assert_eq!(block.transactions().len(), 10);
+        chain.add_block(block).await.unwrap();
+        assert_eq!(chain.get_transaction_pool().await.unwrap().len(), 91);
+        
+        // Блок сверх ограничения отклоняется
+        let pending = chain.get_transaction_pool().await.unwrap();
+        let oversized = next_block(&chain, pending[..10].to_vec()).await;
+        assert_eq!(oversized.transactions().len(), 11);
+        assert!(chain.add_block(oversized).await.is_err());
+    }
+    
+    #[tokio::test]
+    async fn two_full_balance_transactions_are_not_both_pooled() {
+        let alice = Ed25519KeyPair::generate().unwrap();
+        let mut chain = funded_chain(&alice, 100).await;
+        
+        chain.add_transaction(transfer_with_nonce(&alice, b"bob", 100, 0)).await.unwrap();
+        let result = chain.add_transaction(transfer_with_nonce(&alice, b"carol", 100, 1)).await;
+        assert!(matches!(result, Err(Error::Blockchain(_))));
+        assert_eq!(chain.get_transaction_pool().await.unwrap().len(), 1);
+        
+        // Остаток после уже ожидающих списаний по-прежнему можно потратить
+        let mut chain = funded_chain(&alice, 100).await;
+        chain.add_transaction(transfer_with_nonce(&alice, b"bob", 60, 0)).await.unwrap();
+        chain.add_transaction(transfer_with_nonce(&alice, b"carol", 40, 1)).await.unwrap();
+        assert!(chain.add_transaction(transfer_with_nonce(&alice, b"dave", 1, 2)).await.is_err());
+    }
+    
+    #[tokio::test]
+    async fn block_nonces_must_be_sequential() {
+        let alice = Ed25519KeyPair::generate().unwrap();
+        let mut chain = funded_chain(&alice, 100).await;
+        let first = transfer_with_nonce(&alice, b"bob", 10, 0);
+        
+        let block = next_block(&chain, vec![first.clone()]).await;
+        chain.add_block(block).await.unwrap();
+        assert_eq!(chain.get_nonce(&alice.public_bytes()).unwrap(), 1);
+        
+        // Повтор уже подтвержденной транзакции
+        let replay = next_block(&chain, vec![first]).await;
+        assert!(chain.add_block(replay).await.is_err());
+        
+        // Пропуск в nonce
+        let gap = next_block(&chain, vec![transfer_with_nonce(&alice, b"bob", 10, 2)]).await;
+        assert!(chain.add_block(gap).await.is_err());
+        
+        let block = next_block(&chain, vec![transfer_with_nonce(&alice, b"bob", 10, 1)]).await;
+        chain.add_block(block).await.unwrap();
+        assert_eq!(chain.get_nonce(&alice.public_bytes()).unwrap(), 2);
+        assert_eq!(chain.get_balance(b"bob").unwrap(), 20);
+    }
+    
+    #[tokio::test]
+    async fn corrupted_previous_hash_invalidates_chain() {
+        let mut chain = empty_chain().await;
+        append_blocks(&mut chain, 3, b"main").await;
+        assert!(chain.is_chain_valid().await.unwrap());
+        
+        // Подменяем сохраненный блок 2 блоком с другим предыдущим хешем
+        let original = chain.get_block_by_height(2).await.unwrap().unwrap();
+        let mut corrupted = original.clone();
+        corrupted.previous_hash = vec![0xff; 32];
+        corrupted.mine();
+        assert!(corrupted.is_valid());
+        let key = format!("block_by_hash:{}", hex::encode(original.hash())).into_bytes();
+        chain.storage.put(&key, &chain.codec.encode_block(&corrupted).unwrap()).await.unwrap();
+        
+        assert!(!chain.is_chain_valid().await.unwrap());
+    }
+    
+    #[tokio::test]
+    async fn new_creates_initialized_memory_chain() {
+        let chain = BasicBlockchain::new(3).await.unwrap();
+        
+        assert_eq!(chain.get_difficulty(), 3);
+        assert_eq!(chain.get_chain_length().await, 1);
+        assert_eq!(chain.get_last_block().await.unwrap().height(), 0);
+        assert!(chain.get_pending_transactions().await.is_empty());
+    }
+    
+    #[tokio::test]
+    async fn query_helpers_follow_chain_state() {
+        let alice = Ed25519KeyPair::generate().unwrap();
+        let bob = Ed25519KeyPair::generate().unwrap();
+        let mut chain = funded_chain(&alice, 100).await;
+        assert_eq!(chain.get_difficulty(), 1);
+        
+        let payment = transfer(&alice, &bob.public_bytes(), 40);
+        chain.add_transaction(payment.clone()).await.unwrap();
+        let pending = chain.get_pending_transactions().await;
+        assert_eq!(pending.iter().map(|tx| tx.id()).collect::<Vec<_>>(), vec![payment.id()]);
+        
+        let block = next_block(&chain, pending).await;
+        assert!(meets_difficulty(&block.calculate_hash(), block.difficulty()));
+        assert_eq!(block.get_nonce(), block.nonce);
+        
+        chain.add_block(block).await.unwrap();
+        assert_eq!(chain.get_chain_length().await, 2);
+        assert!(chain.get_pending_transactions().await.is_empty());
+    }
+    
+    #[tokio::test]
+    async fn example_style_arguments_are_accepted() {
+        let alice = Ed25519KeyPair::generate().unwrap();
+        let mut chain = funded_chain(&alice, 10 * UNITS_PER_COIN).await;
+        
+        // Число с плавающей точкой задает сумму в монетах, целое — в минимальных единицах
+        let tx = BasicTransaction::new(alice.public_bytes(), vec![2], 0.5, "data".to_string());
+        assert_eq!(tx.amount(), UNITS_PER_COIN / 2);
+        assert_eq!(BasicTransaction::new(vec![1], vec![2], 7, Vec::new()).amount(), 7);
+        
+        let mut boxed = Box::new(tx);
+        boxed.sign(&alice).unwrap();
+        chain.add_transaction(boxed).await.unwrap();
+        
+        let block = next_block(&chain, chain.get_pending_transactions().await).await;
+        chain.add_block(Box::new(block)).await.unwrap();
+        assert_eq!(chain.get_balance(&[2]).unwrap(), UNITS_PER_COIN / 2);
+    }
+    
+    #[tokio::test]
+    async fn spv_proof_verifies_against_block_header() {
+        let alice = Ed25519KeyPair::generate().unwrap();
+        let mut chain = funded_chain(&alice, 100).await;
+        let payments: Vec<BasicTransaction> = (0..3)
+            .map(|nonce| transfer_with_nonce(&alice, b"bob", 10, nonce))
+            .collect();
+        let block = next_block(&chain, payments.clone()).await;
+        chain.add_block(block.clone()).await.unwrap();
+        
+        let tx_id = payments[1].id();
+        let proof = chain.get_spv_proof(&tx_id).await.unwrap().unwrap();
+        assert_eq!(proof.block_hash(), block.hash());
+        assert!(verify_spv_proof(&proof, &tx_id, &block.hash()));
+        
+        // Доказательство не подходит для другой транзакции или другого блока
+        assert!(!verify_spv_proof(&proof, &payments[0].id(), &block.hash()));
+        assert!(!verify_spv_proof(&proof, &tx_id, block.previous_hash()));
+        
+        // Измененный путь в дереве Меркла не сходится к корню из заголовка
+        let mut tampered = proof.clone();
+        tampered.merkle_path[0].0[0] ^= 0x01;
+        assert!(!verify_spv_proof(&tampered, &tx_id, &block.hash()));
+        
+        assert!(chain.get_spv_proof(b"unknown").await.unwrap().is_none());
+    }
+    
+    #[tokio::test]
+    async fn pruning_drops_bodies_and_keeps_headers() {
+        let mut chain = empty_chain().await;
+        append_blocks(&mut chain, 9, b"main").await;
+        assert_eq!(chain.storage.keys_with_prefix(b"block:").await.unwrap().len(), 10);
+        let old = chain.get_block_by_height(2).await.unwrap().unwrap();
+        
+        // Остаются тела вершины и трех блоков под ней
+        assert_eq!(chain.prune(3).await.unwrap(), 6);
+        assert_eq!(chain.pruned_height(), Some(5));
+        assert_eq!(chain.storage.keys_with_prefix(b"block:").await.unwrap().len(), 4);
+        
+        assert!(chain.get_block_by_height(2).await.is_err());
+        let header = chain.get_header_by_height(2).await.unwrap().unwrap();
+        assert_eq!(header.hash(), old.hash());
+        assert!(chain.get_block_by_height(9).await.unwrap().is_some());
+        assert!(chain.is_chain_valid().await.unwrap());
+        
+        // Повторная обрезка без новых блоков ничего не удаляет
+        assert_eq!(chain.prune(3).await.unwrap(), 0);
+    }
+    
+    #[tokio::test]
+    async fn export_import_round_trip() {
+        let mut source = empty_chain().await;
+        append_blocks(&mut source, 5, b"main").await;
+        let mut bytes = Vec::new();
+        assert_eq!(source.export(&mut bytes).await.unwrap(), 6);
+        
+        let mut target = empty_chain().await;
+        assert_eq!(target.import(&bytes[..]).await.unwrap(), 5);
+        let tip = target.get_last_block().await.unwrap();
+        assert_eq!(tip.hash(), source.get_last_block().await.unwrap().hash());
+        assert_eq!(tip.height(), 5);
+        assert!(target.is_chain_valid().await.unwrap());
+        
+        // Уже известные блоки пропускаются
+        assert_eq!(target.import(&bytes[..]).await.unwrap(), 0);
+        
+        // Поток, оборванный посреди блока, отклоняется
+        let mut fresh = empty_chain().await;
+        assert!(fresh.import(&bytes[..bytes.len() - 1]).await.is_err());
+    }
+    
+    #[tokio::test]
+    async fn blake3_chain_mines_and_validates() {
+        let storage = Box::new(MemoryStorage::new("test"));
+        let mut chain = BasicBlockchain::with_storage(storage, 4).with_hash_algorithm(HashAlgorithm::Blake3);
+        chain.initialize().await.unwrap();
+        
+        let genesis = chain.get_block_by_height(0).await.unwrap().unwrap();
+        assert_eq!(genesis.hash_algorithm(), HashAlgorithm::Blake3);
+        assert_ne!(genesis.hash(), BasicBlock::genesis().hash());
+        
+        for _ in 0..3 {
+            let block = chain.build_block(b"miner".to_vec(), 10).await.unwrap();
+            assert_eq!(block.hash_algorithm(), HashAlgorithm::Blake3);
+            assert!(block.header().meets_difficulty());
+            chain.add_block(block).await.unwrap();
         }
+        assert_eq!(chain.get_last_block().await.unwrap().height(), 3);
+        assert!(chain.is_chain_valid().await.unwrap());
         
-        Ok(true)
+        // Блок с хешем SHA-256 не подходит для цепочки на BLAKE3
+        let last = chain.get_last_block().await.unwrap();
+        let difficulty = chain.next_difficulty().await.unwrap();
+        let block = child(&chain, &last, b"sha", difficulty).await;
+        assert!(chain.add_block(block).await.is_err());
     }
-} 
\ No newline at end of file
+}
@@ -0,0 +1,88 @@
+ use crate::error::{Error, Result};
+use super::{Block, Transaction};
+
+/// Трейт кодека для передачи и хранения блоков и транзакций
+///
+/// Хеши блоков и транзакций вычисляются по каноническому представлению
+/// полей, а не по закодированным байтам, поэтому выбор кодека на них не влияет.
+/// Реализации по умолчанию используют bincode.
+pub trait WireCodec<B: Block, T: Transaction>: Send + Sync {
+    /// Закодировать блок
+    fn encode_block(&self, block: &B) -> Result<Vec<u8>> {
+        bincode::serialize(block)
+            .map_err(|e| Error::Serialization(format!("Не удалось сериализовать блок: {}", e)))
+    }
+    
+    /// Декодировать блок
+    fn decode_block(&self, data: &[u8]) -> Result<B> {
+        bincode::deserialize(data)
+            .map_err(|e| Error::Serialization(format!("Не удалось десериализовать блок: {}", e)))
+    }
+    
+    /// Закодировать транзакцию
+    fn encode_transaction(&self, tx: &T) -> Result<Vec<u8>> {
+        bincode::serialize(tx)
+            .map_err(|e| Error::Serialization(format!("Не удалось сериализовать транзакцию: {}", e)))
+    }
+    
+    /// Декодировать транзакцию
+    fn decode_transaction(&self, data: &[u8]) -> Result<T> {
+        bincode::deserialize(data)
+            .map_err(|e| Error::Serialization(format!("Не удалось десериализовать транзакцию: {}", e)))
+    }
+}
+
+/// Кодек на основе bincode
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BincodeCodec;
+
+impl<B: Block, T: Transaction> WireCodec<B, T> for BincodeCodec {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blockchain::basic::{BasicBlock, BasicTransaction};
+    
+    /// Кодек на основе CBOR
+    struct CborCodec;
+    
+    impl WireCodec<BasicBlock, BasicTransaction> for CborCodec {
+        fn encode_block(&self, block: &BasicBlock) -> Result<Vec<u8>> {
+            let mut data = Vec::new();
+            ciborium::ser::into_writer(block, &mut data)
+                .map_err(|e| Error::Serialization(e.to_string()))?;
+            Ok(data)
+        }
+        
+        fn decode_block(&self, data: &[u8]) -> Result<BasicBlock> {
+            ciborium::de::from_reader(data).map_err(|e| Error::Serialization(e.to_string()))
+        }
+        
+        fn encode_transaction(&self, tx: &BasicTransaction) -> Result<Vec<u8>> {
+            let mut data = Vec::new();
+            ciborium::ser::into_writer(tx, &mut data)
+                .map_err(|e| Error::Serialization(e.to_string()))?;
+            Ok(data)
+        }
+        
+        fn decode_transaction(&self, data: &[u8]) -> Result<BasicTransaction> {
+            ciborium::de::from_reader(data).map_err(|e| Error::Serialization(e.to_string()))
+        }
+    }
+    
+    #[test]
+    fn cbor_round_trip_keeps_hashes() {
+        let block = BasicBlock::genesis();
+        let encoded = CborCodec.encode_block(&block).unwrap();
+        let bincode_encoded = WireCodec::<BasicBlock, BasicTransaction>::encode_block(&BincodeCodec, &block).unwrap();
+        assert_ne!(encoded, bincode_encoded);
+        
+        let decoded = CborCodec.decode_block(&encoded).unwrap();
+        assert_eq!(decoded.hash(), block.hash());
+        assert!(decoded.is_valid());
+        
+        let tx = BasicTransaction::new(vec![1; 32], vec![2; 32], 5, b"memo".to_vec());
+        let decoded = CborCodec.decode_transaction(&CborCodec.encode_transaction(&tx).unwrap()).unwrap();
+        assert_eq!(decoded.id(), tx.id());
+    }
+}
@@ -0,0 +1,187 @@
+ use std::cmp::Reverse;
+use std::collections::{BTreeMap, BinaryHeap, HashMap};
+
+use crate::error::{Error, Result};
+use super::Transaction;
+use super::basic::BasicTransaction;
+
+/// Пул неподтвержденных транзакций с учетом зависимостей по nonce
+///
+/// Транзакции одного отправителя упорядочиваются по nonce. Транзакция
+/// с nonce `n + 1`, пришедшая раньше транзакции с nonce `n`, удерживается
+/// в пуле, пока не появится её предшественник.
+#[derive(Debug, Default)]
+pub struct Mempool {
+    /// Транзакции, сгруппированные по отправителю и упорядоченные по nonce
+    by_sender: HashMap<Vec<u8>, BTreeMap<u64, BasicTransaction>>,
+    /// Следующий ожидаемый nonce для каждого отправителя
+    next_nonce: HashMap<Vec<u8>, u64>,
+}
+
+impl Mempool {
+    /// Создать пустой пул
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Получить следующий ожидаемый nonce отправителя
+    pub fn expected_nonce(&self, sender: &[u8]) -> u64 {
+        self.next_nonce.get(sender).copied().unwrap_or(0)
+    }
+    
+    /// Добавить транзакцию в пул
+    pub fn insert(&mut self, tx: BasicTransaction) -> Result<()> {
+        if tx.nonce() < self.expected_nonce(tx.sender()) {
+            return Err(Error::Blockchain(format!(
+                "Nonce транзакции {} уже использован отправителем", tx.nonce()
+            )));
+        }
+        
+        let queue = self.by_sender.entry(tx.sender().to_vec()).or_default();
+        
+        if let Some(existing) = queue.get(&tx.nonce()) {
+            if existing.id() == tx.id() {
+                return Ok(());
+            }
+            return Err(Error::Blockchain(format!(
+                "В пуле уже есть транзакция отправителя с nonce {}", tx.nonce()
+            )));
+        }
+        
+        queue.insert(tx.nonce(), tx);
+        Ok(())
+    }
+    
+    /// Найти транзакцию по идентификатору
+    pub fn get(&self, id: &[u8]) -> Option<&BasicTransaction> {
+        self.iter().find(|tx| tx.id() == id)
+    }
+    
+    /// Итератор по всем транзакциям пула
+    pub fn iter(&self) -> impl Iterator<Item = &BasicTransaction> {
+        self.by_sender.values().flat_map(|queue| queue.values())
+    }
+    
+    /// Количество транзакций в пуле
+    pub fn len(&self) -> usize {
+        self.by_sender.values().map(|queue| queue.len()).sum()
+    }
+    
+    /// Пуст ли пул
+    pub fn is_empty(&self) -> bool {
+        self.by_sender.is_empty()
+    }
+    
+    /// Выбрать транзакции для нового блока
+    ///
+    /// Транзакции выбираются по убыванию комиссии. Для каждого отправителя
+    /// соблюдается порядок nonce: очередная транзакция становится доступной
+    /// для выбора только после своего предшественника, начиная с ожидаемого
+    /// nonce. Транзакции после пропуска в nonce остаются в пуле до появления
+    /// недостающих предшественников.
+    pub fn select_transactions_for_block(&self, limit: usize) -> Vec<BasicTransaction> {
+        // Кандидаты — первые доступные транзакции каждого отправителя; при равной
+        // комиссии первым идет отправитель с меньшим адресом для детерминированного результата
+        let mut candidates = BinaryHeap::new();
+        for (sender, queue) in &self.by_sender {
+            let expected = self.expected_nonce(sender);
+            if let Some(tx) = queue.get(&expected) {
+                candidates.push((tx.fee(), Reverse(sender), expected));
+            }
+        }
+        
+        let mut selected = Vec::new();
+        
+        while selected.len() < limit {
+            let (_, Reverse(sender), nonce) = match candidates.pop() {
+                Some(candidate) => candidate,
+                None => break,
+            };
+            
+            let queue = &self.by_sender[sender];
+            selected.push(queue[&nonce].clone());
+            
+            if let Some(next) = queue.get(&(nonce + 1)) {
+                candidates.push((next.fee(), Reverse(sender), nonce + 1));
+            }
+        }
+        
+        selected
+    }
+    
+    /// Вернуть в пул транзакции отключенных при реорганизации блоков
+    ///
+    /// Ожидаемый nonce отправителей откатывается к наименьшему nonce
+    /// возвращенных транзакций. Конфликтующие с пулом транзакции пропускаются.
+    pub fn restore(&mut self, transactions: &[BasicTransaction]) {
+        for tx in transactions {
+            if let Some(next) = self.next_nonce.get_mut(tx.sender()) {
+                *next = (*next).min(tx.nonce());
+            }
+        }
+        
+        for tx in transactions {
+            if let Err(e) = self.insert(tx.clone()) {
+                tracing::debug!("Транзакция не возвращена в пул: {}", e);
+            }
+        }
+    }
+    
+    /// Удалить из пула транзакции, подтвержденные в блоке
+    pub fn remove_confirmed(&mut self, transactions: &[BasicTransaction]) {
+        // У coinbase нет отправителя, поэтому nonce она не расходует
+        for tx in transactions.iter().filter(|tx| !tx.is_coinbase()) {
+            let next = self.next_nonce.entry(tx.sender().to_vec()).or_insert(0);
+            *next = (*next).max(tx.nonce() + 1);
+            let next = *next;
+            
+            if let Some(queue) = self.by_sender.get_mut(tx.sender()) {
+                // Транзакции с использованным nonce больше не могут попасть в блок
+                queue.retain(|&nonce, _| nonce >= next);
+                if queue.is_empty() {
+                    self.by_sender.remove(tx.sender());
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn tx(sender: u8, nonce: u64) -> BasicTransaction {
+        BasicTransaction::new(vec![sender], vec![9], 5, Vec::new()).with_nonce(nonce)
+    }
+    
+    #[test]
+    fn orphan_waits_for_predecessor() {
+        let mut pool = Mempool::new();
+        pool.insert(tx(1, 1)).unwrap();
+        assert_eq!(pool.len(), 1);
+        assert!(pool.select_transactions_for_block(10).is_empty());
+        
+        pool.insert(tx(1, 0)).unwrap();
+        let selected = pool.select_transactions_for_block(10);
+        assert_eq!(selected.iter().map(|tx| tx.nonce()).collect::<Vec<_>>(), vec![0, 1]);
+        
+        pool.remove_confirmed(&selected);
+        assert!(pool.is_empty());
+        assert_eq!(pool.expected_nonce(&[1]), 2);
+        assert!(pool.insert(tx(1, 0)).is_err());
+    }
+    
+    #[test]
+    fn selection_stops_at_nonce_gap() {
+        let mut pool = Mempool::new();
+        for nonce in [0, 1, 3] {
+            pool.insert(tx(1, nonce)).unwrap();
+        }
+        pool.insert(tx(2, 0)).unwrap();
+        
+        let selected = pool.select_transactions_for_block(10);
+        assert_eq!(selected.len(), 3);
+        assert!(selected.iter().all(|tx| tx.nonce() != 3));
+        assert_eq!(pool.select_transactions_for_block(1).len(), 1);
+    }
+}
@@ -0,0 +1,140 @@
+use crate::crypto::sha256;
+
+/// Префикс хеша листа
+///
+/// Листья и внутренние узлы хешируются с разными префиксами, чтобы хеш
+/// внутреннего узла нельзя было выдать за идентификатор транзакции.
+const LEAF_PREFIX: u8 = 0x00;
+
+/// Префикс хеша внутреннего узла
+const NODE_PREFIX: u8 = 0x01;
+
+/// Элемент доказательства включения: хеш соседнего узла и признак того,
+/// что сосед находится справа от текущего узла
+pub type ProofStep = (Vec<u8>, bool);
+
+/// Вычислить хеш листа
+fn hash_leaf(leaf: &[u8]) -> Vec<u8> {
+    let mut data = Vec::with_capacity(1 + leaf.len());
+    data.push(LEAF_PREFIX);
+    data.extend_from_slice(leaf);
+    sha256(&data)
+}
+
+/// Вычислить хеш родительского узла
+fn hash_pair(left: &[u8], right: &[u8]) -> Vec<u8> {
+    let mut data = Vec::with_capacity(1 + left.len() + right.len());
+    data.push(NODE_PREFIX);
+    data.extend_from_slice(left);
+    data.extend_from_slice(right);
+    sha256(&data)
+}
+
+/// Построить следующий уровень дерева
+///
+/// При нечетном количестве узлов последний узел дублируется.
+fn next_level(level: &[Vec<u8>]) -> Vec<Vec<u8>> {
+    level.chunks(2)
+        .map(|pair| match pair {
+            [left, right] => hash_pair(left, right),
+            [single] => hash_pair(single, single),
+            _ => unreachable!(),
+        })
+        .collect()
+}
+
+/// Вычислить корень дерева Меркла для списка листьев
+///
+/// Для пустого списка возвращается 32 нулевых байта.
+pub fn merkle_root(leaves: &[Vec<u8>]) -> Vec<u8> {
+    if leaves.is_empty() {
+        return vec![0; 32];
+    }
+    
+    let mut level: Vec<Vec<u8>> = leaves.iter().map(|leaf| hash_leaf(leaf)).collect();
+    while level.len() > 1 {
+        level = next_level(&level);
+    }
+    
+    level.remove(0)
+}
+
+/// Построить доказательство включения листа с заданным индексом
+pub fn merkle_proof(leaves: &[Vec<u8>], index: usize) -> Option<Vec<ProofStep>> {
+    if index >= leaves.len() {
+        return None;
+    }
+    
+    let mut proof = Vec::new();
+    let mut level: Vec<Vec<u8>> = leaves.iter().map(|leaf| hash_leaf(leaf)).collect();
+    let mut index = index;
+    
+    while level.len() > 1 {
+        let sibling_is_right = index.is_multiple_of(2);
+        let sibling_index = if sibling_is_right { index + 1 } else { index - 1 };
+        // Если соседа нет, узел был продублирован
+        let sibling = level.get(sibling_index).unwrap_or(&level[index]).clone();
+        proof.push((sibling, sibling_is_right));
+        
+        level = next_level(&level);
+        index /= 2;
+    }
+    
+    Some(proof)
+}
+
+/// Проверить доказательство включения листа в дерево с заданным корнем
+pub fn verify_proof(leaf: &[u8], proof: &[ProofStep], root: &[u8]) -> bool {
+    let computed = proof.iter().fold(hash_leaf(leaf), |acc, (sibling, sibling_is_right)| {
+        if *sibling_is_right {
+            hash_pair(&acc, sibling)
+        } else {
+            hash_pair(sibling, &acc)
+        }
+    });
+    
+    computed == root
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn leaves(count: u8) -> Vec<Vec<u8>> {
+        (0..count).map(|i| sha256(&[i])).collect()
+    }
+    
+    #[test]
+    fn proofs_verify_for_included_leaves() {
+        for count in 1..=7 {
+            let leaves = leaves(count);
+            let root = merkle_root(&leaves);
+            for (index, leaf) in leaves.iter().enumerate() {
+                let proof = merkle_proof(&leaves, index).unwrap();
+                assert!(verify_proof(leaf, &proof, &root));
+            }
+        }
+    }
+    
+    #[test]
+    fn proof_is_rejected_for_absent_leaf() {
+        let leaves = leaves(5);
+        let root = merkle_root(&leaves);
+        let proof = merkle_proof(&leaves, 2).unwrap();
+        
+        assert!(!verify_proof(&sha256(b"absent"), &proof, &root));
+        assert!(merkle_proof(&leaves, 5).is_none());
+    }
+    
+    #[test]
+    fn inner_node_is_not_accepted_as_leaf() {
+        let leaves = leaves(4);
+        let root = merkle_root(&leaves);
+        let inner = hash_pair(&hash_leaf(&leaves[0]), &hash_leaf(&leaves[1]));
+        let proof = merkle_proof(&leaves, 2).unwrap();
+        
+        // Доказательство для внутреннего узла совпадает с хвостом доказательства листа
+        assert!(!verify_proof(&inner, &proof[1..], &root));
+        assert_eq!(merkle_root(&[]), vec![0; 32]);
+    }
+}
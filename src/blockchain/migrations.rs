@@ -0,0 +1,61 @@
+ use async_trait::async_trait;
+use std::sync::Arc;
+
+use crate::error::Result;
+use crate::storage::Storage;
+use crate::storage::migration::{Migration, MigrationRunner};
+use super::Block;
+use super::basic::{BasicBlock, BasicTransaction};
+use super::codec::WireCodec;
+
+/// Миграция, создающая индекс блоков по хешу
+///
+/// Ранние версии сохраняли генезис-блок только по высоте, из-за чего
+/// `get_block_by_hash` не находил его. Блоки декодируются тем же кодеком,
+/// которым их записал блокчейн.
+pub struct BlockHashIndexMigration {
+    /// Кодек сохраненных блоков
+    codec: Arc<dyn WireCodec<BasicBlock, BasicTransaction>>,
+}
+
+impl BlockHashIndexMigration {
+    /// Создать миграцию, читающую блоки указанным кодеком
+    pub fn new(codec: Arc<dyn WireCodec<BasicBlock, BasicTransaction>>) -> Self {
+        Self { codec }
+    }
+}
+
+#[async_trait]
+impl Migration for BlockHashIndexMigration {
+    fn version(&self) -> u32 {
+        1
+    }
+    
+    fn description(&self) -> &str {
+        "Индекс блоков по хешу"
+    }
+    
+    async fn apply(&self, storage: &mut dyn Storage) -> Result<()> {
+        for key in storage.keys_with_prefix(b"block:").await? {
+            let block_data = match storage.get(&key).await? {
+                Some(data) => data,
+                None => continue,
+            };
+            
+            let block = self.codec.decode_block(&block_data)?;
+            
+            let hash_key = format!("block_by_hash:{}", hex::encode(block.hash())).into_bytes();
+            if !storage.has(&hash_key).await? {
+                storage.put(&hash_key, &block_data).await?;
+            }
+        }
+        
+        Ok(())
+    }
+}
+
+/// Миграции, необходимые для `BasicBlockchain` с указанным кодеком блоков
+pub fn default_migrations(codec: Arc<dyn WireCodec<BasicBlock, BasicTransaction>>) -> MigrationRunner {
+    MigrationRunner::new()
+        .with_migration(Box::new(BlockHashIndexMigration::new(codec)))
+}
\ No newline at end of file
@@ -56,10 +56,12 @@ pub trait Blockchain: Send + Sync {
     async fn get_block_by_height(&self, height: u64) -> Result<Option<Self::BlockType>>;
     
     /// Добавить новый блок
-    async fn add_block(&mut self, block: Self::BlockType) -> Result<()>;
+    ///
+    /// Принимает блок или значение, преобразуемое в него (например, `Box` с блоком).
+    async fn add_block(&mut self, block: impl Into<Self::BlockType> + Send) -> Result<()>;
     
     /// Добавить новую транзакцию в пул
-    async fn add_transaction(&mut self, tx: Self::TransactionType) -> Result<()>;
+    async fn add_transaction(&mut self, tx: impl Into<Self::TransactionType> + Send) -> Result<()>;
     
     /// Получить транзакцию по ID
     async fn get_transaction(&self, id: &[u8]) -> Result<Option<Self::TransactionType>>;
@@ -71,4 +73,11 @@ pub trait Blockchain: Send + Sync {
     async fn is_chain_valid(&self) -> Result<bool>;
 }
 
-pub mod basic; 
\ No newline at end of file
+pub mod basic;
+pub mod mempool;
+pub mod merkle;
+pub mod spv;
+pub mod state;
+pub mod pos;
+pub mod migrations;
+pub mod codec; 
\ No newline at end of file
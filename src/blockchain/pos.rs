@@ -0,0 +1,150 @@
+ use serde::{Serialize, Deserialize};
+
+use crate::crypto::Key;
+use crate::crypto::ed25519::Ed25519KeyPair;
+use crate::crypto::vrf::{vrf_prove, vrf_verify};
+use crate::error::{Error, Result};
+
+/// Валидатор с долей (stake)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Validator {
+    /// Публичный ключ Ed25519 валидатора
+    pub public_key: Vec<u8>,
+    /// Доля валидатора
+    pub stake: u64,
+}
+
+/// Заявка валидатора на право предложить блок
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProposerClaim {
+    /// Публичный ключ валидатора
+    pub public_key: Vec<u8>,
+    /// Выход VRF для зерна раунда
+    pub output: Vec<u8>,
+    /// Доказательство VRF
+    pub proof: Vec<u8>,
+}
+
+/// Выбор предлагающего блок валидатора на основе доли и VRF
+///
+/// Каждый валидатор вычисляет VRF от зерна раунда. Побеждает заявка
+/// с наименьшим значением `выход / доля`, поэтому вероятность выбора
+/// пропорциональна доле, а результат проверяем любым узлом.
+#[derive(Debug, Clone, Default)]
+pub struct ProofOfStake {
+    /// Зарегистрированные валидаторы
+    validators: Vec<Validator>,
+}
+
+impl ProofOfStake {
+    /// Создать механизм выбора с заданным набором валидаторов
+    pub fn new(validators: Vec<Validator>) -> Self {
+        Self { validators }
+    }
+    
+    /// Получить список валидаторов
+    pub fn validators(&self) -> &[Validator] {
+        &self.validators
+    }
+    
+    /// Получить долю валидатора по публичному ключу
+    pub fn stake_of(&self, public_key: &[u8]) -> Option<u64> {
+        self.validators.iter()
+            .find(|v| v.public_key == public_key)
+            .map(|v| v.stake)
+    }
+    
+    /// Создать заявку на право предложить блок для заданного зерна
+    pub fn create_claim(&self, keypair: &Ed25519KeyPair, seed: &[u8]) -> Result<ProposerClaim> {
+        let public_key = keypair.public_bytes();
+        if self.stake_of(&public_key).unwrap_or(0) == 0 {
+            return Err(Error::Blockchain("Ключ не принадлежит валидатору с ненулевой долей".to_string()));
+        }
+        
+        let (output, proof) = vrf_prove(keypair, seed)?;
+        
+        Ok(ProposerClaim {
+            public_key,
+            output,
+            proof,
+        })
+    }
+    
+    /// Проверить заявку валидатора
+    pub fn verify_claim(&self, seed: &[u8], claim: &ProposerClaim) -> Result<bool> {
+        if self.stake_of(&claim.public_key).unwrap_or(0) == 0 {
+            return Ok(false);
+        }
+        
+        vrf_verify(&claim.public_key, seed, &claim.output, &claim.proof)
+    }
+    
+    /// Выбрать предлагающего блок среди заявок
+    ///
+    /// Невалидные заявки и заявки от неизвестных валидаторов отбрасываются.
+    /// Возвращает публичный ключ победителя.
+    pub fn select_proposer(&self, seed: &[u8], claims: &[ProposerClaim]) -> Result<Option<Vec<u8>>> {
+        let mut best: Option<(u128, &ProposerClaim)> = None;
+        
+        for claim in claims {
+            if !self.verify_claim(seed, claim)? {
+                continue;
+            }
+            
+            let stake = self.stake_of(&claim.public_key).unwrap_or(0) as u128;
+            let score = Self::output_value(&claim.output) as u128 / stake;
+            
+            // При равенстве побеждает меньший публичный ключ, чтобы результат был детерминированным
+            let better = match best {
+                None => true,
+                Some((best_score, best_claim)) => {
+                    score < best_score || (score == best_score && claim.public_key < best_claim.public_key)
+                }
+            };
+            
+            if better {
+                best = Some((score, claim));
+            }
+        }
+        
+        Ok(best.map(|(_, claim)| claim.public_key.clone()))
+    }
+    
+    /// Интерпретировать первые 8 байт выхода VRF как число
+    fn output_value(output: &[u8]) -> u64 {
+        let mut bytes = [0u8; 8];
+        let len = output.len().min(8);
+        bytes[..len].copy_from_slice(&output[..len]);
+        u64::from_be_bytes(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn forged_and_unknown_claims_are_ignored() {
+        let alice = Ed25519KeyPair::generate().unwrap();
+        let bob = Ed25519KeyPair::generate().unwrap();
+        let outsider = Ed25519KeyPair::generate().unwrap();
+        let pos = ProofOfStake::new(vec![
+            Validator { public_key: alice.public_bytes(), stake: 10 },
+            Validator { public_key: bob.public_bytes(), stake: 30 },
+        ]);
+        
+        let alice_claim = pos.create_claim(&alice, b"seed").unwrap();
+        let mut forged = pos.create_claim(&bob, b"seed").unwrap();
+        forged.output = vec![0; 32];
+        assert!(!pos.verify_claim(b"seed", &forged).unwrap());
+        assert!(pos.create_claim(&outsider, b"seed").is_err());
+        
+        let winner = pos.select_proposer(b"seed", &[alice_claim.clone(), forged]).unwrap();
+        assert_eq!(winner, Some(alice.public_bytes()));
+        
+        let bob_claim = pos.create_claim(&bob, b"seed").unwrap();
+        let first = pos.select_proposer(b"seed", &[alice_claim.clone(), bob_claim.clone()]).unwrap();
+        let second = pos.select_proposer(b"seed", &[bob_claim, alice_claim]).unwrap();
+        assert_eq!(first, second);
+    }
+}
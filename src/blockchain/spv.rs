@@ -0,0 +1,35 @@
+use serde::{Serialize, Deserialize};
+
+use super::basic::{BlockHeader, meets_difficulty};
+use super::merkle::{self, ProofStep};
+
+/// Доказательство включения транзакции в блок для легкого клиента
+///
+/// Состоит из заголовка блока и пути в дереве Меркла его транзакций.
+/// Для проверки не нужны ни тело блока, ни остальная цепочка.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SpvProof {
+    /// Заголовок блока, содержащего транзакцию
+    pub header: BlockHeader,
+    /// Путь от транзакции до корня дерева Меркла
+    pub merkle_path: Vec<ProofStep>,
+}
+
+impl SpvProof {
+    /// Хеш блока, к которому относится доказательство
+    pub fn block_hash(&self) -> Vec<u8> {
+        self.header.hash()
+    }
+}
+
+/// Проверить доказательство включения транзакции `tx_id` в блок `expected_block_hash`
+///
+/// Заголовок должен давать ожидаемый хеш и удовлетворять своей сложности,
+/// а путь в дереве Меркла — приводить от транзакции к корню из заголовка.
+pub fn verify_spv_proof(proof: &SpvProof, tx_id: &[u8], expected_block_hash: &[u8]) -> bool {
+    let block_hash = proof.header.hash();
+    
+    block_hash == expected_block_hash
+        && meets_difficulty(&block_hash, proof.header.difficulty)
+        && merkle::verify_proof(tx_id, &proof.merkle_path, &proof.header.merkle_root)
+}
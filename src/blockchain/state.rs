@@ -0,0 +1,231 @@
+use serde::{Serialize, Deserialize};
+use std::collections::{BTreeMap, HashMap};
+
+use crate::crypto::sha256;
+
+/// Глубина дерева (количество бит в ключе)
+const TREE_DEPTH: usize = 256;
+
+/// Хеш пустого поддерева на любом уровне
+const EMPTY_HASH: [u8; 32] = [0; 32];
+
+/// Префикс хеша листа
+const LEAF_PREFIX: u8 = 0x00;
+
+/// Префикс хеша внутреннего узла
+const NODE_PREFIX: u8 = 0x01;
+
+/// Доказательство состояния счета в разреженном дереве Меркла
+///
+/// При `balance == None` доказывает отсутствие счета в дереве.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccountProof {
+    /// Баланс счета, если счет присутствует в дереве
+    pub balance: Option<u64>,
+    /// Хеши соседних поддеревьев, начиная с корня
+    pub siblings: Vec<[u8; 32]>,
+}
+
+impl AccountProof {
+    /// Проверить доказательство для адреса относительно корня состояния
+    pub fn verify(&self, address: &[u8], state_root: &[u8]) -> bool {
+        if self.siblings.len() != TREE_DEPTH {
+            return false;
+        }
+        
+        let key = account_key(address);
+        let mut hash = match self.balance {
+            Some(balance) => leaf_hash(&key, balance),
+            None => EMPTY_HASH,
+        };
+        
+        // Поднимаемся от листа к корню
+        for depth in (0..TREE_DEPTH).rev() {
+            let sibling = &self.siblings[depth];
+            hash = if bit(&key, depth) {
+                node_hash(sibling, &hash)
+            } else {
+                node_hash(&hash, sibling)
+            };
+        }
+        
+        hash[..] == *state_root
+    }
+}
+
+/// Разреженное дерево Меркла балансов счетов
+///
+/// Ключ листа — SHA-256 адреса, поэтому каждому адресу соответствует
+/// фиксированная позиция, а отсутствие счета доказывается пустым листом.
+/// Счета с нулевым балансом не хранятся, чтобы корень не зависел от них.
+#[derive(Debug, Clone, Default)]
+pub struct StateTree {
+    /// Балансы по ключу листа
+    leaves: BTreeMap<[u8; 32], u64>,
+}
+
+impl StateTree {
+    /// Создать пустое дерево
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Построить дерево из таблицы балансов
+    pub fn from_balances(balances: &HashMap<Vec<u8>, u64>) -> Self {
+        let mut tree = Self::new();
+        for (address, &balance) in balances {
+            tree.set(address, balance);
+        }
+        tree
+    }
+    
+    /// Установить баланс счета
+    pub fn set(&mut self, address: &[u8], balance: u64) {
+        let key = account_key(address);
+        if balance == 0 {
+            self.leaves.remove(&key);
+        } else {
+            self.leaves.insert(key, balance);
+        }
+    }
+    
+    /// Вычислить корень состояния
+    pub fn root(&self) -> Vec<u8> {
+        let leaves: Vec<([u8; 32], u64)> = self.leaves.iter().map(|(k, v)| (*k, *v)).collect();
+        subtree_hash(&leaves, 0).to_vec()
+    }
+    
+    /// Построить доказательство состояния счета
+    pub fn proof(&self, address: &[u8]) -> AccountProof {
+        let key = account_key(address);
+        let mut leaves: Vec<([u8; 32], u64)> = self.leaves.iter().map(|(k, v)| (*k, *v)).collect();
+        let mut siblings = Vec::with_capacity(TREE_DEPTH);
+        
+        // Спускаемся от корня, отделяя на каждом уровне поддерево соседа
+        for depth in 0..TREE_DEPTH {
+            let (same, other): (Vec<_>, Vec<_>) = leaves.into_iter()
+                .partition(|(k, _)| bit(k, depth) == bit(&key, depth));
+            siblings.push(subtree_hash(&other, depth + 1));
+            leaves = same;
+        }
+        
+        AccountProof {
+            balance: self.leaves.get(&key).copied(),
+            siblings,
+        }
+    }
+}
+
+/// Вычислить ключ листа для адреса
+fn account_key(address: &[u8]) -> [u8; 32] {
+    let mut key = [0u8; 32];
+    key.copy_from_slice(&sha256(address));
+    key
+}
+
+/// Получить бит ключа на заданной глубине (старший бит первого байта — глубина 0)
+fn bit(key: &[u8; 32], depth: usize) -> bool {
+    key[depth / 8] & (0x80 >> (depth % 8)) != 0
+}
+
+/// Вычислить хеш листа
+fn leaf_hash(key: &[u8; 32], balance: u64) -> [u8; 32] {
+    let mut data = Vec::with_capacity(1 + 32 + 8);
+    data.push(LEAF_PREFIX);
+    data.extend_from_slice(key);
+    data.extend_from_slice(&balance.to_be_bytes());
+    to_array(sha256(&data))
+}
+
+/// Вычислить хеш внутреннего узла
+///
+/// Узел с двумя пустыми потомками сам считается пустым, поэтому пустые
+/// поддеревья любой высоты имеют один и тот же хеш.
+fn node_hash(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    if *left == EMPTY_HASH && *right == EMPTY_HASH {
+        return EMPTY_HASH;
+    }
+    
+    let mut data = Vec::with_capacity(1 + 64);
+    data.push(NODE_PREFIX);
+    data.extend_from_slice(left);
+    data.extend_from_slice(right);
+    to_array(sha256(&data))
+}
+
+/// Вычислить хеш поддерева на заданной глубине для листьев с общим префиксом
+fn subtree_hash(leaves: &[([u8; 32], u64)], depth: usize) -> [u8; 32] {
+    match leaves {
+        [] => EMPTY_HASH,
+        [(key, balance)] if depth == TREE_DEPTH => leaf_hash(key, *balance),
+        _ => {
+            let (left, right): (Vec<_>, Vec<_>) = leaves.iter().copied().partition(|(k, _)| !bit(k, depth));
+            node_hash(&subtree_hash(&left, depth + 1), &subtree_hash(&right, depth + 1))
+        }
+    }
+}
+
+/// Преобразовать хеш SHA-256 в массив
+fn to_array(hash: Vec<u8>) -> [u8; 32] {
+    let mut out = [0u8; 32];
+    out.copy_from_slice(&hash);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn tree() -> StateTree {
+        let mut balances = HashMap::new();
+        balances.insert(b"alice".to_vec(), 100);
+        balances.insert(b"bob".to_vec(), 42);
+        balances.insert(b"carol".to_vec(), 7);
+        StateTree::from_balances(&balances)
+    }
+    
+    #[test]
+    fn existing_account_proof_verifies() {
+        let tree = tree();
+        let root = tree.root();
+        
+        let proof = tree.proof(b"bob");
+        assert_eq!(proof.balance, Some(42));
+        assert!(proof.verify(b"bob", &root));
+        
+        // Подмененный баланс или чужой адрес не проходят проверку
+        let mut forged = proof.clone();
+        forged.balance = Some(1_000);
+        assert!(!forged.verify(b"bob", &root));
+        assert!(!proof.verify(b"alice", &root));
+    }
+    
+    #[test]
+    fn absent_account_has_non_membership_proof() {
+        let tree = tree();
+        let root = tree.root();
+        
+        let proof = tree.proof(b"mallory");
+        assert_eq!(proof.balance, None);
+        assert!(proof.verify(b"mallory", &root));
+        
+        // Отсутствие существующего счета доказать нельзя
+        let mut forged = tree.proof(b"alice");
+        forged.balance = None;
+        assert!(!forged.verify(b"alice", &root));
+    }
+    
+    #[test]
+    fn zero_balances_do_not_change_root() {
+        let mut tree = tree();
+        let root = tree.root();
+        
+        tree.set(b"dave", 0);
+        assert_eq!(tree.root(), root);
+        
+        tree.set(b"bob", 0);
+        assert_ne!(tree.root(), root);
+        assert!(tree.proof(b"bob").verify(b"bob", &tree.root()));
+        assert_eq!(StateTree::new().root(), EMPTY_HASH.to_vec());
+    }
+}
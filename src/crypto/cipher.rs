@@ -0,0 +1,347 @@
+ use aes_gcm::Aes256Gcm;
+use async_trait::async_trait;
+use chacha20poly1305::aead::{self, Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::ChaCha20Poly1305;
+use rand::RngCore;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::error::{Error, Result};
+use super::{Cipher, StreamCipher};
+
+/// Длина ключа шифрования в байтах
+pub const KEY_LEN: usize = 32;
+
+/// Длина nonce в байтах
+pub const NONCE_LEN: usize = 12;
+
+/// Размер блока открытого текста при потоковом шифровании
+pub const STREAM_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Длина случайного префикса nonce в заголовке потока
+const STREAM_PREFIX_LEN: usize = 7;
+
+/// Шифр ChaCha20-Poly1305
+///
+/// Результат шифрования имеет вид `nonce || ciphertext || tag`,
+/// где nonce генерируется случайно для каждого сообщения.
+pub struct ChaCha20Poly1305Cipher {
+    /// Экземпляр AEAD шифра
+    cipher: ChaCha20Poly1305,
+}
+
+impl ChaCha20Poly1305Cipher {
+    /// Создать шифр из 32-байтового ключа
+    pub fn new(key: &[u8]) -> Result<Self> {
+        if key.len() != KEY_LEN {
+            return Err(Error::Crypto("Некорректная длина ключа ChaCha20-Poly1305".to_string()));
+        }
+        
+        let cipher = ChaCha20Poly1305::new_from_slice(key)
+            .map_err(|_| Error::Crypto("Не удалось создать шифр ChaCha20-Poly1305".to_string()))?;
+        
+        Ok(Self { cipher })
+    }
+}
+
+impl Cipher for ChaCha20Poly1305Cipher {
+    fn encrypt(&self, data: &[u8]) -> Result<Vec<u8>> {
+        seal(&self.cipher, data, "ChaCha20-Poly1305")
+    }
+    
+    fn decrypt(&self, data: &[u8]) -> Result<Vec<u8>> {
+        open(&self.cipher, data, "ChaCha20-Poly1305")
+    }
+}
+
+#[async_trait]
+impl StreamCipher for ChaCha20Poly1305Cipher {
+    async fn encrypt_stream<R, W>(&self, reader: &mut R, writer: &mut W) -> Result<u64>
+    where
+        R: AsyncRead + Unpin + Send,
+        W: AsyncWrite + Unpin + Send,
+    {
+        seal_stream(&self.cipher, reader, writer, "ChaCha20-Poly1305").await
+    }
+    
+    async fn decrypt_stream<R, W>(&self, reader: &mut R, writer: &mut W) -> Result<u64>
+    where
+        R: AsyncRead + Unpin + Send,
+        W: AsyncWrite + Unpin + Send,
+    {
+        open_stream(&self.cipher, reader, writer, "ChaCha20-Poly1305").await
+    }
+}
+
+/// Шифр AES-256-GCM
+///
+/// Использует тот же формат `nonce || ciphertext || tag`, что и
+/// [`ChaCha20Poly1305Cipher`], поэтому шифры взаимозаменяемы.
+pub struct Aes256GcmCipher {
+    /// Экземпляр AEAD шифра
+    cipher: Aes256Gcm,
+}
+
+impl Aes256GcmCipher {
+    /// Создать шифр из 32-байтового ключа
+    pub fn new(key: &[u8]) -> Result<Self> {
+        if key.len() != KEY_LEN {
+            return Err(Error::Crypto("Некорректная длина ключа AES-256-GCM".to_string()));
+        }
+        
+        let cipher = Aes256Gcm::new_from_slice(key)
+            .map_err(|_| Error::Crypto("Не удалось создать шифр AES-256-GCM".to_string()))?;
+        
+        Ok(Self { cipher })
+    }
+}
+
+impl Cipher for Aes256GcmCipher {
+    fn encrypt(&self, data: &[u8]) -> Result<Vec<u8>> {
+        seal(&self.cipher, data, "AES-256-GCM")
+    }
+    
+    fn decrypt(&self, data: &[u8]) -> Result<Vec<u8>> {
+        open(&self.cipher, data, "AES-256-GCM")
+    }
+}
+
+#[async_trait]
+impl StreamCipher for Aes256GcmCipher {
+    async fn encrypt_stream<R, W>(&self, reader: &mut R, writer: &mut W) -> Result<u64>
+    where
+        R: AsyncRead + Unpin + Send,
+        W: AsyncWrite + Unpin + Send,
+    {
+        seal_stream(&self.cipher, reader, writer, "AES-256-GCM").await
+    }
+    
+    async fn decrypt_stream<R, W>(&self, reader: &mut R, writer: &mut W) -> Result<u64>
+    where
+        R: AsyncRead + Unpin + Send,
+        W: AsyncWrite + Unpin + Send,
+    {
+        open_stream(&self.cipher, reader, writer, "AES-256-GCM").await
+    }
+}
+
+/// Зашифровать данные со случайным nonce и добавить его в начало результата
+fn seal<A: Aead + AeadCore>(cipher: &A, data: &[u8], name: &str) -> Result<Vec<u8>> {
+    let nonce = A::generate_nonce(&mut OsRng);
+    let ciphertext = cipher.encrypt(&nonce, data)
+        .map_err(|_| Error::Crypto(format!("Ошибка шифрования {}", name)))?;
+    
+    let mut output = Vec::with_capacity(nonce.len() + ciphertext.len());
+    output.extend_from_slice(&nonce);
+    output.extend_from_slice(&ciphertext);
+    Ok(output)
+}
+
+/// Извлечь nonce из начала данных и расшифровать остаток
+fn open<A: Aead + AeadCore>(cipher: &A, data: &[u8], name: &str) -> Result<Vec<u8>> {
+    if data.len() < NONCE_LEN {
+        return Err(Error::Crypto("Зашифрованные данные слишком короткие".to_string()));
+    }
+    
+    let (nonce, ciphertext) = data.split_at(NONCE_LEN);
+    cipher.decrypt(aead::Nonce::<A>::from_slice(nonce), ciphertext)
+        .map_err(|_| Error::Crypto(format!("Ошибка аутентификации при расшифровке {}", name)))
+}
+
+/// Построить nonce блока потока: префикс потока, номер блока и признак последнего блока
+///
+/// Признак последнего блока входит в nonce, поэтому усечение потока
+/// или перестановка блоков обнаруживаются при расшифровке.
+fn stream_nonce(prefix: &[u8; STREAM_PREFIX_LEN], counter: u32, last: bool) -> [u8; NONCE_LEN] {
+    let mut nonce = [0u8; NONCE_LEN];
+    nonce[..STREAM_PREFIX_LEN].copy_from_slice(prefix);
+    nonce[STREAM_PREFIX_LEN..STREAM_PREFIX_LEN + 4].copy_from_slice(&counter.to_be_bytes());
+    nonce[NONCE_LEN - 1] = last as u8;
+    nonce
+}
+
+/// Прочитать блок до заполнения буфера или конца потока
+async fn read_chunk<R: AsyncRead + Unpin>(reader: &mut R, buffer: &mut [u8]) -> Result<usize> {
+    let mut filled = 0;
+    while filled < buffer.len() {
+        let n = reader.read(&mut buffer[filled..]).await?;
+        if n == 0 {
+            break;
+        }
+        filled += n;
+    }
+    Ok(filled)
+}
+
+/// Зашифровать поток блоками
+///
+/// Формат: `префикс (7 байт)`, затем для каждого блока
+/// `признак последнего блока (1 байт) || длина (4 байта, BE) || ciphertext || tag`.
+async fn seal_stream<A, R, W>(cipher: &A, reader: &mut R, writer: &mut W, name: &str) -> Result<u64>
+where
+    A: Aead + Sync,
+    R: AsyncRead + Unpin + Send,
+    W: AsyncWrite + Unpin + Send,
+{
+    let mut prefix = [0u8; STREAM_PREFIX_LEN];
+    OsRng.fill_bytes(&mut prefix);
+    writer.write_all(&prefix).await?;
+    
+    let mut current = vec![0u8; STREAM_CHUNK_SIZE];
+    let mut next = vec![0u8; STREAM_CHUNK_SIZE];
+    let mut current_len = read_chunk(reader, &mut current).await?;
+    let mut counter: u32 = 0;
+    let mut total = 0u64;
+    
+    loop {
+        // Читаем следующий блок заранее, чтобы знать, является ли текущий последним
+        let next_len = if current_len == STREAM_CHUNK_SIZE {
+            read_chunk(reader, &mut next).await?
+        } else {
+            0
+        };
+        let last = next_len == 0;
+        
+        let nonce = stream_nonce(&prefix, counter, last);
+        let ciphertext = cipher.encrypt(aead::Nonce::<A>::from_slice(&nonce), &current[..current_len])
+            .map_err(|_| Error::Crypto(format!("Ошибка шифрования {}", name)))?;
+        
+        writer.write_all(&[last as u8]).await?;
+        writer.write_all(&(ciphertext.len() as u32).to_be_bytes()).await?;
+        writer.write_all(&ciphertext).await?;
+        total += current_len as u64;
+        
+        if last {
+            break;
+        }
+        
+        counter = counter.checked_add(1)
+            .ok_or_else(|| Error::Crypto("Превышено максимальное количество блоков в потоке".to_string()))?;
+        std::mem::swap(&mut current, &mut next);
+        current_len = next_len;
+    }
+    
+    writer.flush().await?;
+    Ok(total)
+}
+
+/// Расшифровать поток, созданный [`seal_stream`]
+async fn open_stream<A, R, W>(cipher: &A, reader: &mut R, writer: &mut W, name: &str) -> Result<u64>
+where
+    A: Aead + Sync,
+    R: AsyncRead + Unpin + Send,
+    W: AsyncWrite + Unpin + Send,
+{
+    let truncated = || Error::Crypto("Зашифрованный поток усечен".to_string());
+    
+    let mut prefix = [0u8; STREAM_PREFIX_LEN];
+    reader.read_exact(&mut prefix).await.map_err(|_| truncated())?;
+    
+    // Максимальный размер блока: открытый текст плюс тег аутентификации
+    let max_frame_len = STREAM_CHUNK_SIZE + 16;
+    let mut counter: u32 = 0;
+    let mut total = 0u64;
+    
+    loop {
+        let mut header = [0u8; 5];
+        reader.read_exact(&mut header).await.map_err(|_| truncated())?;
+        
+        let last = match header[0] {
+            0 => false,
+            1 => true,
+            _ => return Err(Error::Crypto("Некорректный заголовок блока потока".to_string())),
+        };
+        
+        let len = u32::from_be_bytes([header[1], header[2], header[3], header[4]]) as usize;
+        if len > max_frame_len {
+            return Err(Error::Crypto("Некорректная длина блока потока".to_string()));
+        }
+        
+        let mut ciphertext = vec![0u8; len];
+        reader.read_exact(&mut ciphertext).await.map_err(|_| truncated())?;
+        
+        let nonce = stream_nonce(&prefix, counter, last);
+        let plaintext = cipher.decrypt(aead::Nonce::<A>::from_slice(&nonce), ciphertext.as_slice())
+            .map_err(|_| Error::Crypto(format!("Ошибка аутентификации блока потока {}", name)))?;
+        
+        writer.write_all(&plaintext).await?;
+        total += plaintext.len() as u64;
+        
+        if last {
+            break;
+        }
+        
+        counter = counter.checked_add(1)
+            .ok_or_else(|| Error::Crypto("Превышено максимальное количество блоков в потоке".to_string()))?;
+    }
+    
+    writer.flush().await?;
+    Ok(total)
+} 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn chacha_round_trip_and_tamper_detection() {
+        let cipher = ChaCha20Poly1305Cipher::new(&[7u8; KEY_LEN]).unwrap();
+        let mut sealed = cipher.encrypt(b"secret payload").unwrap();
+        assert_eq!(cipher.decrypt(&sealed).unwrap(), b"secret payload");
+        
+        let last = sealed.len() - 1;
+        sealed[last] ^= 0x01;
+        assert!(cipher.decrypt(&sealed).is_err());
+        
+        assert!(ChaCha20Poly1305Cipher::new(&[0u8; 16]).is_err());
+        assert!(cipher.decrypt(&[0u8; 4]).is_err());
+    }
+    
+    #[test]
+    fn aes_gcm_known_answer() {
+        // Тестовые векторы 13 и 14 из спецификации GCM (McGrew, Viega): нулевые ключ и nonce
+        let cipher = Aes256GcmCipher::new(&[0u8; KEY_LEN]).unwrap();
+        
+        let mut empty = vec![0u8; NONCE_LEN];
+        empty.extend_from_slice(&hex::decode("530f8afbc74536b9a963b4f1c4cb738b").unwrap());
+        assert!(cipher.decrypt(&empty).unwrap().is_empty());
+        
+        let mut block = vec![0u8; NONCE_LEN];
+        block.extend_from_slice(&hex::decode("cea7403d4d606b6e074ec5d3baf39d18").unwrap());
+        block.extend_from_slice(&hex::decode("d0d1c8a799996bf0265b98b5d48ab919").unwrap());
+        assert_eq!(cipher.decrypt(&block).unwrap(), vec![0u8; 16]);
+    }
+    
+    #[test]
+    fn aes_gcm_round_trip_and_tamper_detection() {
+        let cipher = Aes256GcmCipher::new(&[9u8; KEY_LEN]).unwrap();
+        let mut sealed = cipher.encrypt(b"secret payload").unwrap();
+        assert_eq!(cipher.decrypt(&sealed).unwrap(), b"secret payload");
+        
+        sealed[NONCE_LEN] ^= 0x80;
+        assert!(cipher.decrypt(&sealed).is_err());
+    }
+    
+    #[tokio::test]
+    async fn stream_round_trip_and_tampered_chunk() {
+        let cipher = ChaCha20Poly1305Cipher::new(&[3u8; KEY_LEN]).unwrap();
+        let payload: Vec<u8> = (0..3 * 1024 * 1024 + 123).map(|i| (i % 251) as u8).collect();
+        
+        let mut sealed = Vec::new();
+        let read = cipher.encrypt_stream(&mut payload.as_slice(), &mut sealed).await.unwrap();
+        assert_eq!(read, payload.len() as u64);
+        
+        let mut opened = Vec::new();
+        let written = cipher.decrypt_stream(&mut sealed.as_slice(), &mut opened).await.unwrap();
+        assert_eq!(written, payload.len() as u64);
+        assert_eq!(opened, payload);
+        
+        // Портим байт во втором блоке потока
+        let mut tampered = sealed.clone();
+        tampered[STREAM_PREFIX_LEN + 5 + STREAM_CHUNK_SIZE + 16 + 5 + 10] ^= 0x01;
+        assert!(cipher.decrypt_stream(&mut tampered.as_slice(), &mut Vec::new()).await.is_err());
+        
+        // Поток без последнего блока не принимается
+        let truncated = &sealed[..sealed.len() - 100];
+        assert!(cipher.decrypt_stream(&mut &truncated[..], &mut Vec::new()).await.is_err());
+    }
+}
@@ -1,10 +1,14 @@
 use ed25519_dalek::{Signer as Ed25519Signer, Verifier, Signature, SigningKey, VerifyingKey};
 use rand::rngs::OsRng;
+use zeroize::Zeroizing;
 
 use crate::error::{Error, Result};
 use super::{Key, Signer};
 
 /// Пара ключей Ed25519
+///
+/// Байты приватного ключа обнуляются при удалении пары: `SigningKey`
+/// собран с поддержкой `zeroize` и очищает себя в `Drop`.
 pub struct Ed25519KeyPair {
     /// Приватный ключ для подписи
     private_key: Option<SigningKey>,
@@ -24,6 +28,11 @@ impl Ed25519KeyPair {
         })
     }
     
+    /// Получить публичный ключ
+    pub fn public_key(&self) -> Vec<u8> {
+        self.public_key.to_bytes().to_vec()
+    }
+    
     /// Создать пару ключей из существующего приватного ключа
     pub fn from_private_key(private_bytes: &[u8]) -> Result<Self> {
         if private_bytes.len() != 32 {
@@ -68,8 +77,8 @@ impl Key for Ed25519KeyPair {
         self.public_key.to_bytes().to_vec()
     }
     
-    fn private_bytes(&self) -> Option<Vec<u8>> {
-        self.private_key.as_ref().map(|pk| pk.to_bytes().to_vec())
+    fn private_bytes(&self) -> Option<Zeroizing<Vec<u8>>> {
+        self.private_key.as_ref().map(|pk| Zeroizing::new(pk.to_bytes().to_vec()))
     }
 }
 
@@ -99,4 +108,77 @@ impl Signer for Ed25519KeyPair {
             Err(_) => Ok(false),
         }
     }
-} 
\ No newline at end of file
+}
+
+/// Проверить пачку подписей Ed25519 одной операцией
+///
+/// Каждый элемент — публичный ключ, сообщение и подпись. Возвращает `Ok(true)`,
+/// только если верны все подписи; какая именно подпись неверна, пакетная
+/// проверка не сообщает. Ключи и подписи некорректной длины дают ошибку.
+pub fn verify_batch(items: &[(&[u8], &[u8], &[u8])]) -> Result<bool> {
+    if items.is_empty() {
+        return Ok(true);
+    }
+    
+    let mut messages = Vec::with_capacity(items.len());
+    let mut signatures = Vec::with_capacity(items.len());
+    let mut public_keys = Vec::with_capacity(items.len());
+    
+    for (public_bytes, message, signature) in items {
+        let public_bytes: &[u8; 32] = (*public_bytes).try_into()
+            .map_err(|_| Error::Crypto("Некорректная длина публичного ключа Ed25519".to_string()))?;
+        let signature: &[u8; 64] = (*signature).try_into()
+            .map_err(|_| Error::Crypto("Некорректная длина подписи Ed25519".to_string()))?;
+        
+        // Некорректная точка кривой означает неверную подпись, а не ошибку формата
+        match VerifyingKey::from_bytes(public_bytes) {
+            Ok(public_key) => public_keys.push(public_key),
+            Err(_) => return Ok(false),
+        }
+        signatures.push(Signature::from_bytes(signature));
+        messages.push(*message);
+    }
+    
+    Ok(ed25519_dalek::verify_batch(&messages, &signatures, &public_keys).is_ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn private_bytes_are_zeroizing_and_restore_key() {
+        let pair = Ed25519KeyPair::generate().unwrap();
+        let secret: Zeroizing<Vec<u8>> = pair.private_bytes().unwrap();
+        let restored = Ed25519KeyPair::from_private_key(&secret).unwrap();
+        assert_eq!(restored.public_bytes(), pair.public_bytes());
+        
+        let public_only = Ed25519KeyPair::from_public_key(&pair.public_bytes()).unwrap();
+        assert!(public_only.private_bytes().is_none());
+    }
+    
+    #[test]
+    fn batch_with_one_bad_signature_fails() {
+        let pairs: Vec<Ed25519KeyPair> = (0..4).map(|_| Ed25519KeyPair::generate().unwrap()).collect();
+        let public_keys: Vec<Vec<u8>> = pairs.iter().map(|pair| pair.public_bytes()).collect();
+        let messages: Vec<Vec<u8>> = (0..4u8).map(|i| vec![i; 32]).collect();
+        let mut signatures: Vec<Vec<u8>> = pairs.iter().zip(&messages)
+            .map(|(pair, message)| pair.sign(message).unwrap())
+            .collect();
+        
+        let batch = |signatures: &[Vec<u8>]| {
+            let items: Vec<(&[u8], &[u8], &[u8])> = (0..4)
+                .map(|i| (&public_keys[i][..], &messages[i][..], &signatures[i][..]))
+                .collect();
+            verify_batch(&items).unwrap()
+        };
+        assert!(batch(&signatures));
+        assert!(verify_batch(&[]).unwrap());
+        
+        // Подпись чужого сообщения проваливает всю пачку
+        signatures[2] = pairs[2].sign(b"other").unwrap();
+        assert!(!batch(&signatures));
+        
+        assert!(verify_batch(&[(&public_keys[0][..], &messages[0][..], &[0u8; 63][..])]).is_err());
+    }
+}
@@ -0,0 +1,135 @@
+use argon2::Argon2;
+use rand::RngCore;
+use rand::rngs::OsRng;
+use zeroize::Zeroizing;
+
+use crate::error::{Error, Result};
+use crate::storage::Storage;
+use super::{Cipher, Key};
+use super::cipher::{ChaCha20Poly1305Cipher, KEY_LEN};
+use super::ed25519::Ed25519KeyPair;
+
+/// Длина соли для вывода ключа из парольной фразы
+const SALT_LEN: usize = 16;
+
+/// Хранилище ключей, защищенных парольной фразой
+///
+/// Приватный ключ шифруется ChaCha20-Poly1305 ключом, выведенным из
+/// парольной фразы через Argon2id со случайной солью. Запись имеет вид
+/// `salt || nonce || ciphertext || tag` и хранится под ключом `key:<name>`.
+/// Неверная парольная фраза обнаруживается при проверке тега.
+pub struct KeyStore<S: Storage> {
+    /// Вложенное хранилище
+    storage: S,
+    /// Парольная фраза
+    passphrase: Zeroizing<Vec<u8>>,
+}
+
+impl<S: Storage> KeyStore<S> {
+    /// Создать хранилище ключей поверх `storage` с заданной парольной фразой
+    pub fn new(storage: S, passphrase: &str) -> Self {
+        Self {
+            storage,
+            passphrase: Zeroizing::new(passphrase.as_bytes().to_vec()),
+        }
+    }
+    
+    /// Сохранить приватный ключ под именем `name`
+    ///
+    /// Существующий ключ с тем же именем перезаписывается.
+    pub async fn save_key(&mut self, name: &str, key: &dyn Key) -> Result<()> {
+        let private_bytes = key.private_bytes()
+            .ok_or_else(|| Error::Crypto(format!("Ключ {} не содержит приватной части", name)))?;
+        
+        let mut salt = [0u8; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+        
+        let cipher = self.derive_cipher(&salt)?;
+        let encrypted = cipher.encrypt(&private_bytes)?;
+        
+        let mut record = Vec::with_capacity(SALT_LEN + encrypted.len());
+        record.extend_from_slice(&salt);
+        record.extend_from_slice(&encrypted);
+        
+        self.storage.put(&Self::storage_key(name), &record).await
+    }
+    
+    /// Загрузить пару ключей Ed25519, сохраненную под именем `name`
+    pub async fn load_key(&self, name: &str) -> Result<Ed25519KeyPair> {
+        let record = self.storage.get(&Self::storage_key(name)).await?
+            .ok_or_else(|| Error::Crypto(format!("Ключ {} не найден", name)))?;
+        
+        if record.len() < SALT_LEN {
+            return Err(Error::Crypto(format!("Запись ключа {} повреждена", name)));
+        }
+        let (salt, encrypted) = record.split_at(SALT_LEN);
+        
+        let cipher = self.derive_cipher(salt)?;
+        let private_bytes = Zeroizing::new(cipher.decrypt(encrypted).map_err(|_| {
+            Error::Crypto(format!("Не удалось расшифровать ключ {}: неверная парольная фраза или поврежденные данные", name))
+        })?);
+        
+        Ed25519KeyPair::from_private_key(&private_bytes)
+    }
+    
+    /// Проверить, сохранен ли ключ с именем `name`
+    pub async fn has_key(&self, name: &str) -> Result<bool> {
+        self.storage.has(&Self::storage_key(name)).await
+    }
+    
+    /// Удалить ключ с именем `name`
+    pub async fn delete_key(&mut self, name: &str) -> Result<()> {
+        self.storage.delete(&Self::storage_key(name)).await
+    }
+    
+    /// Извлечь вложенное хранилище
+    pub fn into_inner(self) -> S {
+        self.storage
+    }
+    
+    /// Вывести шифр из парольной фразы и соли
+    fn derive_cipher(&self, salt: &[u8]) -> Result<ChaCha20Poly1305Cipher> {
+        let mut key = Zeroizing::new([0u8; KEY_LEN]);
+        Argon2::default()
+            .hash_password_into(&self.passphrase, salt, &mut key[..])
+            .map_err(|e| Error::Crypto(format!("Не удалось вывести ключ из парольной фразы: {}", e)))?;
+        
+        ChaCha20Poly1305Cipher::new(&key[..])
+    }
+    
+    /// Ключ записи в хранилище
+    fn storage_key(name: &str) -> Vec<u8> {
+        format!("key:{}", name).into_bytes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::Signer;
+    use crate::storage::memory::MemoryStorage;
+    
+    #[tokio::test]
+    async fn saved_key_reloads_only_with_correct_passphrase() {
+        let pair = Ed25519KeyPair::generate().unwrap();
+        let mut store = KeyStore::new(MemoryStorage::new("keys"), "correct horse");
+        store.save_key("node", &pair).await.unwrap();
+        assert!(store.has_key("node").await.unwrap());
+        
+        // Приватный ключ не хранится в открытом виде
+        let storage = store.into_inner();
+        let record = storage.get(b"key:node").await.unwrap().unwrap();
+        let secret = pair.private_bytes().unwrap();
+        assert!(!record.windows(secret.len()).any(|window| window == &secret[..]));
+        
+        let store = KeyStore::new(storage, "correct horse");
+        let loaded = store.load_key("node").await.unwrap();
+        assert_eq!(loaded.public_bytes(), pair.public_bytes());
+        let signature = loaded.sign(b"data").unwrap();
+        assert!(pair.verify(b"data", &signature).unwrap());
+        
+        let store = KeyStore::new(store.into_inner(), "wrong horse");
+        assert!(matches!(store.load_key("node").await, Err(Error::Crypto(_))));
+        assert!(store.load_key("missing").await.is_err());
+    }
+}
@@ -0,0 +1,102 @@
+use bip39::Mnemonic;
+use hmac::{Hmac, Mac};
+use sha2::Sha512;
+use zeroize::Zeroizing;
+
+use crate::error::{Error, Result};
+use super::ed25519::Ed25519KeyPair;
+
+/// Путь вывода ключа `m/44'/0'/0'`, все уровни усиленные
+///
+/// SLIP-10 для Ed25519 допускает только усиленный вывод.
+pub const DERIVATION_PATH: [u32; 3] = [44, 0, 0];
+
+/// Флаг усиленного индекса
+const HARDENED: u32 = 0x8000_0000;
+
+/// Ключ HMAC для вычисления мастер-ключа Ed25519 по SLIP-10
+const SLIP10_ED25519_KEY: &[u8] = b"ed25519 seed";
+
+type HmacSha512 = Hmac<Sha512>;
+
+/// Результат шага SLIP-10: ключ и код цепочки
+type Slip10Output = (Zeroizing<[u8; 32]>, Zeroizing<[u8; 32]>);
+
+/// Сгенерировать мнемоническую фразу BIP39 из `word_count` английских слов
+///
+/// Допустимы 12, 15, 18, 21 и 24 слова.
+pub fn generate_mnemonic(word_count: usize) -> Result<String> {
+    let mnemonic = Mnemonic::generate(word_count)
+        .map_err(|e| Error::Crypto(format!("Не удалось сгенерировать мнемоническую фразу: {}", e)))?;
+    
+    Ok(mnemonic.to_string())
+}
+
+/// Восстановить пару ключей Ed25519 из мнемонической фразы BIP39
+///
+/// Seed вычисляется по BIP39 с парольной фразой `passphrase`, ключ выводится
+/// из него по SLIP-10 для Ed25519 по пути [`DERIVATION_PATH`]. Фраза с
+/// неизвестными словами или неверной контрольной суммой отклоняется.
+pub fn keypair_from_mnemonic(phrase: &str, passphrase: &str) -> Result<Ed25519KeyPair> {
+    let mnemonic = Mnemonic::parse_normalized(phrase)
+        .map_err(|e| Error::Crypto(format!("Некорректная мнемоническая фраза: {}", e)))?;
+    let seed = Zeroizing::new(mnemonic.to_seed_normalized(passphrase));
+    
+    let (mut key, mut chain_code) = slip10_step(SLIP10_ED25519_KEY, &seed[..])?;
+    for index in DERIVATION_PATH {
+        let mut data = Zeroizing::new(Vec::with_capacity(37));
+        data.push(0);
+        data.extend_from_slice(&key[..]);
+        data.extend_from_slice(&(index | HARDENED).to_be_bytes());
+        
+        (key, chain_code) = slip10_step(&chain_code[..], &data)?;
+    }
+    
+    Ed25519KeyPair::from_private_key(&key[..])
+}
+
+/// Один шаг SLIP-10: HMAC-SHA512, делящийся на ключ и код цепочки
+fn slip10_step(hmac_key: &[u8], data: &[u8]) -> Result<Slip10Output> {
+    let mut mac = HmacSha512::new_from_slice(hmac_key)
+        .map_err(|_| Error::Crypto("Не удалось создать HMAC-SHA512".to_string()))?;
+    mac.update(data);
+    let mut output = Zeroizing::new([0u8; 64]);
+    output.copy_from_slice(&mac.finalize().into_bytes());
+    
+    let mut key = Zeroizing::new([0u8; 32]);
+    let mut chain_code = Zeroizing::new([0u8; 32]);
+    key.copy_from_slice(&output[..32]);
+    chain_code.copy_from_slice(&output[32..]);
+    
+    Ok((key, chain_code))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::Key;
+    
+    #[test]
+    fn mnemonic_restores_same_keypair() {
+        let phrase = generate_mnemonic(12).unwrap();
+        assert_eq!(phrase.split_whitespace().count(), 12);
+        
+        let first = keypair_from_mnemonic(&phrase, "").unwrap();
+        let second = keypair_from_mnemonic(&phrase, "").unwrap();
+        assert_eq!(first.public_bytes(), second.public_bytes());
+        
+        // Другая парольная фраза дает другой ключ
+        let other = keypair_from_mnemonic(&phrase, "secret").unwrap();
+        assert_ne!(first.public_bytes(), other.public_bytes());
+        
+        assert!(generate_mnemonic(13).is_err());
+    }
+    
+    #[test]
+    fn corrupted_word_is_rejected() {
+        let phrase = generate_mnemonic(12).unwrap();
+        let mut words: Vec<&str> = phrase.split_whitespace().collect();
+        words[5] = "notaword";
+        assert!(keypair_from_mnemonic(&words.join(" "), "").is_err());
+    }
+}
@@ -1,3 +1,8 @@
+use async_trait::async_trait;
+use serde::{Serialize, Deserialize};
+use tokio::io::{AsyncRead, AsyncWrite};
+use zeroize::Zeroizing;
+
 use crate::error::Result;
 
 /// Трейт для криптографического ключа
@@ -6,7 +11,9 @@ pub trait Key {
     fn public_bytes(&self) -> Vec<u8>;
     
     /// Получить байтовое представление приватного ключа (если доступен)
-    fn private_bytes(&self) -> Option<Vec<u8>>;
+    ///
+    /// Буфер обнуляется при освобождении, чтобы копия ключа не оставалась в памяти.
+    fn private_bytes(&self) -> Option<Zeroizing<Vec<u8>>>;
 }
 
 /// Трейт для подписи данных
@@ -27,18 +34,63 @@ pub trait Cipher {
     fn decrypt(&self, data: &[u8]) -> Result<Vec<u8>>;
 }
 
+/// Трейт для потокового шифрования данных
+///
+/// Данные обрабатываются блоками, каждый блок шифруется и аутентифицируется
+/// отдельно, поэтому весь поток не нужно держать в памяти.
+#[async_trait]
+pub trait StreamCipher {
+    /// Зашифровать поток, возвращает количество прочитанных байт открытого текста
+    async fn encrypt_stream<R, W>(&self, reader: &mut R, writer: &mut W) -> Result<u64>
+    where
+        R: AsyncRead + Unpin + Send,
+        W: AsyncWrite + Unpin + Send;
+    
+    /// Расшифровать поток, возвращает количество записанных байт открытого текста
+    async fn decrypt_stream<R, W>(&self, reader: &mut R, writer: &mut W) -> Result<u64>
+    where
+        R: AsyncRead + Unpin + Send,
+        W: AsyncWrite + Unpin + Send;
+}
+
 /// Создать новую пару ключей Ed25519
-pub fn generate_ed25519_keypair() -> Result<Box<dyn Key + Send + Sync>> {
-    // В реальной реализации здесь будет генерация ключей
-    // Для простоты возвращаем заглушку
-    Ok(Box::new(ed25519::Ed25519KeyPair::generate()?))
+///
+/// Возвращает конкретный тип, а не `Box<dyn Key>`, чтобы ключом можно было подписывать.
+pub fn generate_ed25519_keypair() -> Result<ed25519::Ed25519KeyPair> {
+    ed25519::Ed25519KeyPair::generate()
 }
 
 /// Создать новую пару ключей X25519 для обмена ключами по Диффи-Хеллману
 pub fn generate_x25519_keypair() -> Result<Box<dyn Key + Send + Sync>> {
-    // В реальной реализации здесь будет генерация ключей
-    // Для простоты возвращаем заглушку
-    unimplemented!("Генерация X25519 ключей пока не реализована")
+    Ok(Box::new(x25519::X25519KeyPair::generate()?))
+}
+
+/// Создать новую пару ключей secp256k1
+pub fn generate_secp256k1_keypair() -> Result<Box<dyn Key + Send + Sync>> {
+    Ok(Box::new(secp256k1::Secp256k1KeyPair::generate()?))
+}
+
+/// Алгоритм хеширования, выбираемый в конфигурации
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum HashAlgorithm {
+    /// SHA-256, 32 байта
+    #[default]
+    Sha256,
+    /// BLAKE3, 32 байта
+    Blake3,
+    /// Keccak-256, 32 байта
+    Keccak256,
+}
+
+impl HashAlgorithm {
+    /// Хешировать данные выбранным алгоритмом
+    pub fn hash(&self, data: &[u8]) -> Vec<u8> {
+        match self {
+            HashAlgorithm::Sha256 => sha256(data),
+            HashAlgorithm::Blake3 => blake3(data),
+            HashAlgorithm::Keccak256 => keccak256(data),
+        }
+    }
 }
 
 /// Хешировать данные с использованием SHA-256
@@ -49,10 +101,89 @@ pub fn sha256(data: &[u8]) -> Vec<u8> {
     hasher.finalize().to_vec()
 }
 
+/// Вывести ключевой материал из секрета по HKDF-SHA256 (RFC 5869)
+///
+/// Подходит для получения сеансовых ключей из общего секрета
+/// `X25519KeyPair::diffie_hellman`. Возвращает `out_len` байт.
+/// Паникует, если `out_len` больше 255 * 32 байт — предела HKDF-SHA256.
+pub fn hkdf_sha256(ikm: &[u8], salt: &[u8], info: &[u8], out_len: usize) -> Vec<u8> {
+    let hkdf = hkdf::Hkdf::<sha2::Sha256>::new(Some(salt), ikm);
+    let mut okm = vec![0u8; out_len];
+    hkdf.expand(info, &mut okm)
+        .expect("Длина вывода HKDF-SHA256 не должна превышать 255 * 32 байт");
+    okm
+}
+
 /// Хешировать данные с использованием BLAKE3
 pub fn blake3(data: &[u8]) -> Vec<u8> {
     blake3::hash(data).as_bytes().to_vec()
 }
 
+/// Хешировать данные с использованием BLAKE2b, результат — 64 байта
+pub fn blake2b(data: &[u8]) -> Vec<u8> {
+    use blake2::{Blake2b512, Digest};
+    Blake2b512::digest(data).to_vec()
+}
+
+/// Хешировать данные с использованием Keccak-256, результат — 32 байта
+///
+/// Это исходный Keccak с дополнением, принятым в Ethereum, а не
+/// стандартизированный NIST SHA3-256: для одних данных хеши различаются.
+pub fn keccak256(data: &[u8]) -> Vec<u8> {
+    use sha3::{Digest, Keccak256};
+    Keccak256::digest(data).to_vec()
+}
+
 pub mod ed25519;
-pub mod x25519; 
\ No newline at end of file
+pub mod x25519;
+pub mod secp256k1;
+pub mod cipher;
+pub mod keystore;
+pub mod mnemonic;
+pub mod vrf;
+
+pub use mnemonic::{generate_mnemonic, keypair_from_mnemonic}; 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn hkdf_sha256_matches_rfc5869_case_1() {
+        let ikm = [0x0b; 22];
+        let salt: Vec<u8> = (0x00..=0x0c).collect();
+        let info: Vec<u8> = (0xf0..=0xf9).collect();
+        
+        let okm = hkdf_sha256(&ikm, &salt, &info, 42);
+        assert_eq!(
+            hex::encode(okm),
+            "3cb25f25faacd57a90434f64d0362f2a2d2d0a90cf1a5a4c5db02d56ecc4c5bf34007208d5b887185865"
+        );
+    }
+    
+    #[test]
+    fn blake2b_known_answers() {
+        assert_eq!(
+            hex::encode(blake2b(b"")),
+            "786a02f742015903c6c6fd852552d272912f4740e15847618a86e217f71f5419\
+             d25e1031afee585313896444934eb04b903a685b1448b755d56f701afe9be2ce"
+        );
+        assert_eq!(
+            hex::encode(blake2b(b"abc")),
+            "ba80a53f981c4d0d6a2797b69f12f6e94c212f14685ac4b74b12bb6fdbffa2d1\
+             7d87c5392aab792dc252d5de4533cc9518d38aa8dbf1925ab92386edd4009923"
+        );
+    }
+    
+    #[test]
+    fn keccak256_known_answers() {
+        assert_eq!(
+            hex::encode(keccak256(b"")),
+            "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470"
+        );
+        assert_eq!(
+            hex::encode(keccak256(b"abc")),
+            "4e03657aea45a94fc7d47ba826c8d667c0d1e6e33a64a036ec44f58fa12d6c45"
+        );
+    }
+}
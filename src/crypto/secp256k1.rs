@@ -0,0 +1,153 @@
+use rand::rngs::OsRng;
+use secp256k1::{ecdsa::Signature, Message, PublicKey, SecretKey, SECP256K1};
+use zeroize::Zeroizing;
+
+use crate::error::{Error, Result};
+use super::{Key, Signer, sha256};
+
+/// Длина сжатого публичного ключа secp256k1 в байтах
+pub const PUBLIC_KEY_LEN: usize = 33;
+
+/// Пара ключей secp256k1 для подписей ECDSA
+///
+/// Публичный ключ представляется в сжатом формате SEC1 длиной 33 байта:
+/// префикс `0x02` или `0x03` по четности координаты Y и 32 байта
+/// координаты X. Подписывается SHA-256 от данных, подпись хранится
+/// в компактном формате `r || s` длиной 64 байта с нормализованным `s`.
+pub struct Secp256k1KeyPair {
+    /// Приватный ключ для подписи
+    private_key: Option<SecretKey>,
+    /// Публичный ключ для проверки
+    public_key: PublicKey,
+}
+
+impl Secp256k1KeyPair {
+    /// Создать новую пару ключей
+    pub fn generate() -> Result<Self> {
+        let private_key = SecretKey::new(&mut OsRng);
+        let public_key = PublicKey::from_secret_key(SECP256K1, &private_key);
+        
+        Ok(Self {
+            private_key: Some(private_key),
+            public_key,
+        })
+    }
+    
+    /// Создать пару ключей из существующего 32-байтового приватного ключа
+    pub fn from_private_key(private_bytes: &[u8]) -> Result<Self> {
+        if private_bytes.len() != 32 {
+            return Err(Error::Crypto("Некорректная длина приватного ключа secp256k1".to_string()));
+        }
+        
+        let private_key = SecretKey::from_slice(private_bytes)
+            .map_err(|e| Error::Crypto(format!("Некорректный приватный ключ secp256k1: {}", e)))?;
+        let public_key = PublicKey::from_secret_key(SECP256K1, &private_key);
+        
+        Ok(Self {
+            private_key: Some(private_key),
+            public_key,
+        })
+    }
+    
+    /// Создать пару ключей только с публичным ключом (для проверки)
+    ///
+    /// Принимается сжатый (33 байта) или несжатый (65 байт) формат SEC1.
+    pub fn from_public_key(public_bytes: &[u8]) -> Result<Self> {
+        let public_key = PublicKey::from_slice(public_bytes)
+            .map_err(|e| Error::Crypto(format!("Некорректный публичный ключ secp256k1: {}", e)))?;
+        
+        Ok(Self {
+            private_key: None,
+            public_key,
+        })
+    }
+    
+    /// Подготовить сообщение для подписи: SHA-256 от данных
+    fn message(data: &[u8]) -> Result<Message> {
+        Message::from_digest_slice(&sha256(data))
+            .map_err(|e| Error::Crypto(format!("Не удалось подготовить сообщение secp256k1: {}", e)))
+    }
+}
+
+impl Drop for Secp256k1KeyPair {
+    fn drop(&mut self) {
+        if let Some(private_key) = self.private_key.as_mut() {
+            private_key.non_secure_erase();
+        }
+    }
+}
+
+impl Key for Secp256k1KeyPair {
+    fn public_bytes(&self) -> Vec<u8> {
+        self.public_key.serialize().to_vec()
+    }
+    
+    fn private_bytes(&self) -> Option<Zeroizing<Vec<u8>>> {
+        self.private_key.as_ref().map(|sk| Zeroizing::new(sk.secret_bytes().to_vec()))
+    }
+}
+
+impl Signer for Secp256k1KeyPair {
+    fn sign(&self, data: &[u8]) -> Result<Vec<u8>> {
+        if let Some(private_key) = &self.private_key {
+            let signature = SECP256K1.sign_ecdsa(&Self::message(data)?, private_key);
+            Ok(signature.serialize_compact().to_vec())
+        } else {
+            Err(Error::Crypto("Отсутствует приватный ключ для подписи".to_string()))
+        }
+    }
+    
+    fn verify(&self, data: &[u8], signature: &[u8]) -> Result<bool> {
+        if signature.len() != 64 {
+            return Err(Error::Crypto("Некорректная длина подписи secp256k1".to_string()));
+        }
+        
+        let sig = Signature::from_compact(signature)
+            .map_err(|e| Error::Crypto(format!("Некорректная подпись secp256k1: {}", e)))?;
+        
+        match SECP256K1.verify_ecdsa(&Self::message(data)?, &sig, &self.public_key) {
+            Ok(_) => Ok(true),
+            Err(_) => Ok(false),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn sign_and_verify() {
+        let pair = Secp256k1KeyPair::generate().unwrap();
+        assert_eq!(pair.public_bytes().len(), PUBLIC_KEY_LEN);
+        
+        let signature = pair.sign(b"data").unwrap();
+        assert_eq!(signature.len(), 64);
+        assert!(pair.verify(b"data", &signature).unwrap());
+        assert!(!pair.verify(b"other", &signature).unwrap());
+        
+        let public_only = Secp256k1KeyPair::from_public_key(&pair.public_bytes()).unwrap();
+        assert!(public_only.verify(b"data", &signature).unwrap());
+        assert!(public_only.sign(b"data").is_err());
+    }
+    
+    #[test]
+    fn known_answer_for_private_key_one() {
+        let mut private_key = [0u8; 32];
+        private_key[31] = 1;
+        let pair = Secp256k1KeyPair::from_private_key(&private_key).unwrap();
+        
+        // Точка G в сжатом формате
+        assert_eq!(
+            hex::encode(pair.public_bytes()),
+            "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798"
+        );
+        
+        // Детерминированная подпись RFC 6979 над SHA-256("Satoshi Nakamoto")
+        assert_eq!(
+            hex::encode(pair.sign(b"Satoshi Nakamoto").unwrap()),
+            "934b1ea10a4b3c1757e2b0c017d0b6143ce3c9a7e6a4a49860d7a6ab210ee3d8\
+             2442ce9d2b916064108014783e923ec36b49743e2ffa1c4496f01a512aafd9e5"
+        );
+    }
+}
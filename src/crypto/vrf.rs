@@ -0,0 +1,247 @@
+//! Верифицируемая случайная функция ECVRF-EDWARDS25519-SHA512-TAI (RFC 9381)
+//!
+//! Ключи VRF совпадают с ключами Ed25519: секретный скаляр и префикс для
+//! вычисления nonce получаются из приватного ключа так же, как в RFC 8032.
+//! Для одного публичного ключа и входа существует ровно один выход, который
+//! проходит проверку, поэтому владелец ключа не может подобрать выгодный результат.
+
+use curve25519_dalek::edwards::{CompressedEdwardsY, EdwardsPoint};
+use curve25519_dalek::scalar::{clamp_integer, Scalar};
+use sha2::{Digest, Sha512};
+
+use crate::error::{Error, Result};
+use super::Key;
+use super::ed25519::Ed25519KeyPair;
+
+/// Идентификатор набора ECVRF-EDWARDS25519-SHA512-TAI
+const SUITE: u8 = 0x03;
+
+/// Длина доказательства: точка Gamma, вызов c и ответ s
+pub const PROOF_LEN: usize = 80;
+
+/// Длина вызова c в байтах
+const CHALLENGE_LEN: usize = 16;
+
+/// Вычислить выход верифицируемой случайной функции (VRF)
+///
+/// Возвращает пару `(output, proof)`: выход длиной 64 байта и доказательство
+/// длиной `PROOF_LEN`. Доказательство детерминировано и зависит только от
+/// ключа и входа.
+pub fn vrf_prove(keypair: &Ed25519KeyPair, input: &[u8]) -> Result<(Vec<u8>, Vec<u8>)> {
+    let seed = keypair.private_bytes()
+        .ok_or_else(|| Error::Crypto("Для доказательства VRF нужен приватный ключ".to_string()))?;
+    
+    let hashed: [u8; 64] = Sha512::digest(&seed).into();
+    let mut scalar_bytes = [0u8; 32];
+    scalar_bytes.copy_from_slice(&hashed[..32]);
+    let secret = Scalar::from_bytes_mod_order(clamp_integer(scalar_bytes));
+    
+    let h = encode_to_curve(&keypair.public_bytes(), input)?;
+    
+    // Nonce вычисляется детерминированно, как в RFC 8032
+    let nonce_hash: [u8; 64] = Sha512::new()
+        .chain_update(&hashed[32..])
+        .chain_update(h.compress().to_bytes())
+        .finalize()
+        .into();
+    let nonce = Scalar::from_bytes_mod_order_wide(&nonce_hash);
+    
+    Ok(prove_with_nonce(&secret, &h, &nonce))
+}
+
+/// Построить доказательство для точки входа `h` с заданным nonce
+fn prove_with_nonce(secret: &Scalar, h: &EdwardsPoint, nonce: &Scalar) -> (Vec<u8>, Vec<u8>) {
+    let gamma = h * secret;
+    let c = challenge(&EdwardsPoint::mul_base(secret), h, &gamma, &EdwardsPoint::mul_base(nonce), &(h * nonce));
+    let s = nonce + c * secret;
+    
+    let mut proof = Vec::with_capacity(PROOF_LEN);
+    proof.extend_from_slice(&gamma.compress().to_bytes());
+    proof.extend_from_slice(&c.to_bytes()[..CHALLENGE_LEN]);
+    proof.extend_from_slice(&s.to_bytes());
+    
+    (proof_to_hash(&gamma), proof)
+}
+
+/// Проверить выход и доказательство VRF
+pub fn vrf_verify(public_key: &[u8], input: &[u8], output: &[u8], proof: &[u8]) -> Result<bool> {
+    if proof.len() != PROOF_LEN {
+        return Ok(false);
+    }
+    
+    let public_point = match decode_point(public_key) {
+        Some(point) if !point.is_small_order() => point,
+        _ => return Ok(false),
+    };
+    
+    let gamma = match decode_point(&proof[..32]) {
+        Some(point) => point,
+        None => return Ok(false),
+    };
+    
+    let mut c_bytes = [0u8; 32];
+    c_bytes[..CHALLENGE_LEN].copy_from_slice(&proof[32..32 + CHALLENGE_LEN]);
+    let c = Scalar::from_bytes_mod_order(c_bytes);
+    
+    let mut s_bytes = [0u8; 32];
+    s_bytes.copy_from_slice(&proof[32 + CHALLENGE_LEN..]);
+    // Неканоническая запись s дала бы второе доказательство для того же выхода
+    let s = match Option::<Scalar>::from(Scalar::from_canonical_bytes(s_bytes)) {
+        Some(s) => s,
+        None => return Ok(false),
+    };
+    
+    let h = encode_to_curve(public_key, input)?;
+    let u = EdwardsPoint::mul_base(&s) - public_point * c;
+    let v = h * s - gamma * c;
+    
+    if challenge(&public_point, &h, &gamma, &u, &v) != c {
+        return Ok(false);
+    }
+    
+    Ok(proof_to_hash(&gamma) == output)
+}
+
+/// Отобразить вход на точку кривой методом try-and-increment
+fn encode_to_curve(public_key: &[u8], input: &[u8]) -> Result<EdwardsPoint> {
+    for counter in 0..=u8::MAX {
+        let hash = Sha512::new()
+            .chain_update([SUITE, 0x01])
+            .chain_update(public_key)
+            .chain_update(input)
+            .chain_update([counter, 0x00])
+            .finalize();
+        
+        if let Some(point) = decode_point(&hash[..32]) {
+            return Ok(point.mul_by_cofactor());
+        }
+    }
+    
+    Err(Error::Crypto("Не удалось отобразить вход VRF на точку кривой".to_string()))
+}
+
+/// Вычислить вызов c по точкам доказательства
+fn challenge(
+    public_point: &EdwardsPoint,
+    h: &EdwardsPoint,
+    gamma: &EdwardsPoint,
+    u: &EdwardsPoint,
+    v: &EdwardsPoint,
+) -> Scalar {
+    let mut hasher = Sha512::new().chain_update([SUITE, 0x02]);
+    for point in [public_point, h, gamma, u, v] {
+        hasher.update(point.compress().to_bytes());
+    }
+    let hash = hasher.chain_update([0x00]).finalize();
+    
+    let mut c_bytes = [0u8; 32];
+    c_bytes[..CHALLENGE_LEN].copy_from_slice(&hash[..CHALLENGE_LEN]);
+    Scalar::from_bytes_mod_order(c_bytes)
+}
+
+/// Вычислить выход VRF по точке Gamma
+fn proof_to_hash(gamma: &EdwardsPoint) -> Vec<u8> {
+    Sha512::new()
+        .chain_update([SUITE, 0x03])
+        .chain_update(gamma.mul_by_cofactor().compress().to_bytes())
+        .chain_update([0x00])
+        .finalize()
+        .to_vec()
+}
+
+/// Разобрать сжатую точку кривой
+///
+/// Неканоническая запись точки отвергается, чтобы у точки было одно представление.
+fn decode_point(bytes: &[u8]) -> Option<EdwardsPoint> {
+    let compressed = CompressedEdwardsY::from_slice(bytes).ok()?;
+    let point = compressed.decompress()?;
+    (point.compress() == compressed).then_some(point)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::{sha256, Key};
+    
+    #[test]
+    fn matches_rfc_9381_test_vector() {
+        let secret = hex::decode("9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60").unwrap();
+        let keypair = Ed25519KeyPair::from_private_key(&secret).unwrap();
+        let (output, proof) = vrf_prove(&keypair, b"").unwrap();
+        
+        assert_eq!(
+            hex::encode(&proof),
+            "8657106690b5526245a92b003bb079ccd1a92130477671f6fc01ad16f26f723f\
+             26f8a57ccaed74ee1b190bed1f479d9727d2d0f9b005a6e456a35d4fb0daab12\
+             68a1b0db10836d9826a528ca76567805"
+        );
+        assert_eq!(
+            hex::encode(&output),
+            "90cf1df3b703cce59e2a35b925d411164068269d7b2d29f3301c03dd757876ff\
+             66b71dda49d2de59d03450451af026798e8f81cd2e333de5cdf4f3e140fdd8ae"
+        );
+        assert!(vrf_verify(&keypair.public_bytes(), b"", &output, &proof).unwrap());
+    }
+    
+    #[test]
+    fn output_is_reproducible() {
+        let keypair = Ed25519KeyPair::generate().unwrap();
+        let (output, proof) = vrf_prove(&keypair, b"round-1").unwrap();
+        assert_eq!(vrf_prove(&keypair, b"round-1").unwrap(), (output.clone(), proof.clone()));
+        assert_ne!(vrf_prove(&keypair, b"round-2").unwrap().0, output);
+        
+        assert!(vrf_verify(&keypair.public_bytes(), b"round-1", &output, &proof).unwrap());
+    }
+    
+    #[test]
+    fn forged_proof_is_rejected() {
+        let keypair = Ed25519KeyPair::generate().unwrap();
+        let other = Ed25519KeyPair::generate().unwrap();
+        let (output, mut proof) = vrf_prove(&keypair, b"round-1").unwrap();
+        
+        assert!(!vrf_verify(&other.public_bytes(), b"round-1", &output, &proof).unwrap());
+        assert!(!vrf_verify(&keypair.public_bytes(), b"round-2", &output, &proof).unwrap());
+        assert!(!vrf_verify(&keypair.public_bytes(), b"round-1", &sha256(b"forged"), &proof).unwrap());
+        
+        proof[0] ^= 0x01;
+        assert!(!vrf_verify(&keypair.public_bytes(), b"round-1", &output, &proof).unwrap());
+    }
+    
+    #[test]
+    fn second_valid_output_cannot_exist() {
+        let keypair = Ed25519KeyPair::generate().unwrap();
+        let public_key = keypair.public_bytes();
+        let (output, proof) = vrf_prove(&keypair, b"round-1").unwrap();
+        
+        let hashed: [u8; 64] = Sha512::digest(keypair.private_bytes().unwrap()).into();
+        let mut scalar_bytes = [0u8; 32];
+        scalar_bytes.copy_from_slice(&hashed[..32]);
+        let secret = Scalar::from_bytes_mod_order(clamp_integer(scalar_bytes));
+        let h = encode_to_curve(&public_key, b"round-1").unwrap();
+        
+        // Доказательство с другим nonce проходит проверку, но выход тот же
+        let (other_output, other_proof) = prove_with_nonce(&secret, &h, &Scalar::from(7u64));
+        assert_ne!(other_proof, proof);
+        assert_eq!(other_output, output);
+        assert!(vrf_verify(&public_key, b"round-1", &output, &other_proof).unwrap());
+        
+        // Доказательство другого выхода не сходится ни с одним nonce
+        let mut forged = proof.clone();
+        forged[..32].copy_from_slice(&(h * (secret + Scalar::ONE)).compress().to_bytes());
+        let forged_output = proof_to_hash(&decode_point(&forged[..32]).unwrap());
+        assert!(!vrf_verify(&public_key, b"round-1", &forged_output, &forged).unwrap());
+        
+        // Неканоническая запись s (s + l) не дает второго доказательства
+        let order = hex::decode("edd3f55c1a631258d69cf7a2def9de1400000000000000000000000000000010").unwrap();
+        let mut shifted = [0u8; 32];
+        let mut carry = 0u16;
+        for (i, byte) in shifted.iter_mut().enumerate() {
+            let sum = proof[48 + i] as u16 + order[i] as u16 + carry;
+            *byte = sum as u8;
+            carry = sum >> 8;
+        }
+        let mut non_canonical = proof.clone();
+        non_canonical[48..].copy_from_slice(&shifted);
+        assert!(!vrf_verify(&public_key, b"round-1", &output, &non_canonical).unwrap());
+    }
+}
@@ -0,0 +1,145 @@
+ use rand::rngs::OsRng;
+use x25519_dalek::{PublicKey, StaticSecret};
+use zeroize::Zeroizing;
+
+use crate::error::{Error, Result};
+use super::Key;
+
+/// Пара ключей X25519 для обмена ключами по Диффи-Хеллману
+///
+/// Байты секретного ключа обнуляются при удалении пары: `StaticSecret`
+/// собран с поддержкой `zeroize` и очищает себя в `Drop`.
+pub struct X25519KeyPair {
+    /// Секретный ключ
+    private_key: Option<StaticSecret>,
+    /// Публичный ключ
+    public_key: PublicKey,
+}
+
+impl X25519KeyPair {
+    /// Создать новую пару ключей
+    pub fn generate() -> Result<Self> {
+        let private_key = StaticSecret::random_from_rng(OsRng);
+        let public_key = PublicKey::from(&private_key);
+        
+        Ok(Self {
+            private_key: Some(private_key),
+            public_key,
+        })
+    }
+    
+    /// Создать пару ключей из существующего приватного ключа
+    pub fn from_private_key(private_bytes: &[u8]) -> Result<Self> {
+        if private_bytes.len() != 32 {
+            return Err(Error::Crypto("Некорректная длина приватного ключа X25519".to_string()));
+        }
+        
+        let bytes: [u8; 32] = private_bytes.try_into().map_err(|_| {
+            Error::Crypto("Не удалось преобразовать байты в ключ X25519".to_string())
+        })?;
+        
+        let private_key = StaticSecret::from(bytes);
+        let public_key = PublicKey::from(&private_key);
+        
+        Ok(Self {
+            private_key: Some(private_key),
+            public_key,
+        })
+    }
+    
+    /// Создать пару ключей только с публичным ключом
+    pub fn from_public_key(public_bytes: &[u8]) -> Result<Self> {
+        if public_bytes.len() != 32 {
+            return Err(Error::Crypto("Некорректная длина публичного ключа X25519".to_string()));
+        }
+        
+        let bytes: [u8; 32] = public_bytes.try_into().map_err(|_| {
+            Error::Crypto("Не удалось преобразовать байты в публичный ключ X25519".to_string())
+        })?;
+        
+        Ok(Self {
+            private_key: None,
+            public_key: PublicKey::from(bytes),
+        })
+    }
+    
+    /// Вычислить общий секрет с публичным ключом другой стороны
+    pub fn diffie_hellman(&self, their_public: &[u8]) -> Result<[u8; 32]> {
+        let private_key = self.private_key.as_ref()
+            .ok_or_else(|| Error::Crypto("Отсутствует приватный ключ для обмена ключами".to_string()))?;
+        
+        if their_public.len() != 32 {
+            return Err(Error::Crypto("Некорректная длина публичного ключа X25519".to_string()));
+        }
+        
+        let bytes: [u8; 32] = their_public.try_into().map_err(|_| {
+            Error::Crypto("Не удалось преобразовать байты в публичный ключ X25519".to_string())
+        })?;
+        
+        let shared_secret = private_key.diffie_hellman(&PublicKey::from(bytes));
+        Ok(shared_secret.to_bytes())
+    }
+}
+
+impl Key for X25519KeyPair {
+    fn public_bytes(&self) -> Vec<u8> {
+        self.public_key.as_bytes().to_vec()
+    }
+    
+    fn private_bytes(&self) -> Option<Zeroizing<Vec<u8>>> {
+        self.private_key.as_ref().map(|sk| Zeroizing::new(sk.to_bytes().to_vec()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn secret_of(pair: &X25519KeyPair) -> StaticSecret {
+        let bytes: [u8; 32] = pair.private_bytes().unwrap().as_slice().try_into().unwrap();
+        StaticSecret::from(bytes)
+    }
+    
+    fn public_of(pair: &X25519KeyPair) -> PublicKey {
+        let bytes: [u8; 32] = pair.public_bytes().try_into().unwrap();
+        PublicKey::from(bytes)
+    }
+    
+    #[test]
+    fn generated_keypairs_derive_matching_secrets() {
+        let alice = X25519KeyPair::generate().unwrap();
+        let bob = X25519KeyPair::generate().unwrap();
+        assert_ne!(alice.public_bytes(), bob.public_bytes());
+        
+        let alice_shared = secret_of(&alice).diffie_hellman(&public_of(&bob));
+        let bob_shared = secret_of(&bob).diffie_hellman(&public_of(&alice));
+        assert_eq!(alice_shared.as_bytes(), bob_shared.as_bytes());
+    }
+    
+    #[test]
+    fn private_key_restores_public_key() {
+        let pair = X25519KeyPair::generate().unwrap();
+        let restored = X25519KeyPair::from_private_key(&pair.private_bytes().unwrap()).unwrap();
+        assert_eq!(restored.public_bytes(), pair.public_bytes());
+        
+        assert!(X25519KeyPair::from_private_key(&[0u8; 31]).is_err());
+    }
+    
+    #[test]
+    fn diffie_hellman_is_symmetric() {
+        let alice = X25519KeyPair::generate().unwrap();
+        let bob = X25519KeyPair::generate().unwrap();
+        
+        let alice_shared = alice.diffie_hellman(&bob.public_bytes()).unwrap();
+        let bob_shared = bob.diffie_hellman(&alice.public_bytes()).unwrap();
+        assert_eq!(alice_shared, bob_shared);
+    }
+    
+    #[test]
+    fn diffie_hellman_requires_private_key() {
+        let alice = X25519KeyPair::generate().unwrap();
+        let public_only = X25519KeyPair::from_public_key(&alice.public_bytes()).unwrap();
+        assert!(public_only.diffie_hellman(&alice.public_bytes()).is_err());
+        assert!(alice.diffie_hellman(&[1u8; 16]).is_err());
+    }
+}
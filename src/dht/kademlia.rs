@@ -1,25 +1,80 @@
 use async_trait::async_trait;
-use std::collections::{HashMap, HashSet, VecDeque};
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 use tokio::sync::mpsc;
 use tokio::task::JoinHandle;
 use tokio::time;
 
+use crate::crypto::sha256;
 use crate::error::{Error, Result};
 use crate::types::{PeerId, PeerInfo};
 use crate::network::message::{Message, MessageType};
+use crate::network::rtt::RttEstimator;
+use crate::storage::Storage;
 use super::Dht;
 
-/// Константа для настройки размера k-bucket в Kademlia
-const K: usize = 20;
+/// Размер k-bucket в Kademlia по умолчанию
+pub const K: usize = 20;
 
-/// Константа для настройки alpha параметра в Kademlia (количество параллельных запросов)
-const ALPHA: usize = 3;
+/// Alpha параметр Kademlia по умолчанию (количество параллельных запросов)
+pub const ALPHA: usize = 3;
 
-/// Время жизни записи в хранилище (24 часа)
+/// Время жизни записи в хранилище по умолчанию (24 часа)
 const VALUE_TTL: Duration = Duration::from_secs(24 * 60 * 60);
 
+/// Ключ, под которым таблица маршрутизации сохраняется в хранилище
+const ROUTING_TABLE_KEY: &[u8] = b"dht:routing_table";
+
+/// Общий таймаут итеративного поиска по умолчанию
+const DEFAULT_LOOKUP_DEADLINE: Duration = Duration::from_secs(10);
+
+/// Период обслуживания DHT по умолчанию
+const DEFAULT_MAINTENANCE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Период повторной рассылки хранимых значений по умолчанию (1 час)
+const DEFAULT_REPUBLISH_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Параметры Kademlia DHT
+///
+/// Значения по умолчанию рассчитаны на большую публичную сеть. В небольших
+/// закрытых сетях имеет смысл уменьшить `k`, иначе каждый узел знает всех.
+#[derive(Debug, Clone)]
+pub struct KademliaConfig {
+    /// Размер k-bucket и количество узлов в ответах
+    pub k: usize,
+    /// Количество параллельных запросов при поиске
+    pub alpha: usize,
+    /// Время жизни записи в хранилище
+    pub value_ttl: Duration,
+}
+
+impl Default for KademliaConfig {
+    fn default() -> Self {
+        Self {
+            k: K,
+            alpha: ALPHA,
+            value_ttl: VALUE_TTL,
+        }
+    }
+}
+
+impl KademliaConfig {
+    /// Проверить согласованность параметров
+    fn validate(&self) -> Result<()> {
+        if self.k == 0 || self.alpha == 0 {
+            return Err(Error::Dht("Параметры k и alpha должны быть больше нуля".to_string()));
+        }
+        
+        if self.alpha > self.k {
+            return Err(Error::Dht(format!("Параметр alpha ({}) не может превышать k ({})", self.alpha, self.k)));
+        }
+        
+        Ok(())
+    }
+}
+
 /// Запись в хранилище DHT
 struct DhtValue {
     /// Значение
@@ -28,18 +83,52 @@ struct DhtValue {
     timestamp: Instant,
 }
 
+/// Запись k-bucket
+#[derive(Debug, Clone)]
+struct BucketEntry {
+    /// Информация об узле
+    peer: PeerInfo,
+    /// Время, когда узел последний раз был активен
+    last_seen: Instant,
+}
+
+/// k-bucket; давно активные узлы определяются по времени последней активности
+type KBucket = Vec<BucketEntry>;
+
+/// Результат итеративного поиска
+enum LookupResult {
+    /// Ближайшие к цели узлы
+    Peers(Vec<PeerInfo>),
+    /// Найденное значение
+    Value(Vec<u8>),
+}
+
 /// Реализация DHT на основе алгоритма Kademlia
 pub struct KademliaDht {
     /// Идентификатор текущего узла
     local_id: PeerId,
     /// Таблица маршрутизации (k-buckets)
-    routing_table: Arc<Mutex<Vec<HashSet<PeerInfo>>>>,
+    routing_table: Arc<Mutex<Vec<KBucket>>>,
     /// Хранилище значений
     storage: Arc<Mutex<HashMap<Vec<u8>, DhtValue>>>,
+    /// Оценки времени приема-передачи для узлов
+    rtt_estimators: Arc<Mutex<HashMap<PeerId, RttEstimator>>>,
     /// Количество бит в идентификаторе узла
     id_bits: usize,
+    /// Параметры DHT
+    config: KademliaConfig,
+    /// Общий таймаут итеративного поиска
+    lookup_deadline: Duration,
+    /// Хранилище для сохранения таблицы маршрутизации между перезапусками
+    persistence: Option<Arc<tokio::sync::Mutex<Box<dyn Storage>>>>,
     /// Задача для обслуживания DHT
     maintenance_task: Option<JoinHandle<()>>,
+    /// Приостановлено ли периодическое обслуживание
+    maintenance_paused: Arc<AtomicBool>,
+    /// Период обслуживания
+    maintenance_interval: Duration,
+    /// Период повторной рассылки хранимых значений
+    republish_interval: Duration,
     /// Канал для отправки сообщений в сеть
     network_tx: Option<mpsc::Sender<Message>>,
     /// Канал для получения сообщений из сети
@@ -49,28 +138,105 @@ pub struct KademliaDht {
 }
 
 impl KademliaDht {
-    /// Создать новый экземпляр Kademlia DHT
-    pub fn new(local_id: PeerId) -> Self {
+    /// Создать новый экземпляр Kademlia DHT с параметрами по умолчанию
+    ///
+    /// Идентификатор узла должен иметь стандартную длину `PEER_ID_LEN`.
+    pub fn new(local_id: PeerId) -> Result<Self> {
+        Self::with_config(local_id, KademliaConfig::default())
+    }
+    
+    /// Создать новый экземпляр Kademlia DHT с заданными параметрами
+    ///
+    /// Идентификатор узла должен иметь стандартную длину `PEER_ID_LEN`.
+    pub fn with_config(local_id: PeerId, config: KademliaConfig) -> Result<Self> {
+        config.validate()?;
+        let local_id = PeerId::try_new(local_id.as_bytes().to_vec())?;
+        Ok(Self::build(local_id, config))
+    }
+    
+    /// Внутренний метод создания DHT
+    fn build(local_id: PeerId, config: KademliaConfig) -> Self {
         let id_bits = 256; // Предполагаем 256-битные идентификаторы
         let mut routing_table = Vec::with_capacity(id_bits);
         
         // Инициализируем таблицу маршрутизации
         for _ in 0..id_bits {
-            routing_table.push(HashSet::with_capacity(K));
+            routing_table.push(KBucket::new());
         }
         
         Self {
             local_id,
             routing_table: Arc::new(Mutex::new(routing_table)),
             storage: Arc::new(Mutex::new(HashMap::new())),
+            rtt_estimators: Arc::new(Mutex::new(HashMap::new())),
             id_bits,
+            config,
+            lookup_deadline: DEFAULT_LOOKUP_DEADLINE,
+            persistence: None,
             maintenance_task: None,
+            maintenance_paused: Arc::new(AtomicBool::new(false)),
+            maintenance_interval: DEFAULT_MAINTENANCE_INTERVAL,
+            republish_interval: DEFAULT_REPUBLISH_INTERVAL,
             network_tx: None,
             network_rx: None,
             started: false,
         }
     }
     
+    /// Установить параметры k (размер k-bucket) и alpha (параллелизм поиска)
+    pub fn with_params(mut self, k: usize, alpha: usize) -> Result<Self> {
+        let config = KademliaConfig { k, alpha, ..self.config.clone() };
+        config.validate()?;
+        
+        self.config = config;
+        Ok(self)
+    }
+    
+    /// Получить размер k-bucket
+    pub fn k(&self) -> usize {
+        self.config.k
+    }
+    
+    /// Получить количество параллельных запросов при поиске
+    pub fn alpha(&self) -> usize {
+        self.config.alpha
+    }
+    
+    /// Получить параметры DHT
+    pub fn config(&self) -> &KademliaConfig {
+        &self.config
+    }
+    
+    /// Установить общий таймаут итеративного поиска
+    pub fn with_lookup_deadline(mut self, deadline: Duration) -> Self {
+        self.lookup_deadline = deadline;
+        self
+    }
+    
+    /// Установить период обслуживания DHT
+    pub fn with_maintenance_interval(mut self, interval: Duration) -> Self {
+        self.maintenance_interval = interval;
+        self
+    }
+    
+    /// Установить период повторной рассылки хранимых значений
+    ///
+    /// Рассылка выполняется задачей обслуживания, поэтому фактический период
+    /// кратен периоду обслуживания.
+    pub fn with_republish_interval(mut self, interval: Duration) -> Self {
+        self.republish_interval = interval;
+        self
+    }
+    
+    /// Сохранять таблицу маршрутизации в хранилище
+    ///
+    /// Таблица восстанавливается при `start` и периодически сохраняется
+    /// задачей обслуживания, а также при `stop`.
+    pub fn with_storage(mut self, storage: Box<dyn Storage>) -> Self {
+        self.persistence = Some(Arc::new(tokio::sync::Mutex::new(storage)));
+        self
+    }
+    
     /// Установить каналы для обмена сообщениями с сетью
     pub fn with_network_channels(
         mut self,
@@ -82,21 +248,53 @@ impl KademliaDht {
         self
     }
     
+    /// Учесть измеренное время ответа узла
+    pub fn record_rtt(&self, peer_id: &PeerId, rtt: Duration) {
+        if let Ok(mut estimators) = self.rtt_estimators.lock() {
+            estimators.entry(peer_id.clone()).or_default().record(rtt);
+        }
+    }
+    
+    /// Получить адаптивный таймаут запросов поиска к узлу
+    pub fn lookup_timeout(&self, peer_id: &PeerId) -> Duration {
+        self.rtt_estimators.lock()
+            .ok()
+            .and_then(|estimators| estimators.get(peer_id).map(|e| e.timeout()))
+            .unwrap_or_else(|| RttEstimator::new().timeout())
+    }
+    
     /// Вычислить XOR-расстояние между двумя идентификаторами
+    ///
+    /// Более короткий идентификатор дополняется нулями слева, поэтому
+    /// расстояние всегда имеет длину более длинного идентификатора и
+    /// расстояния одинаковой длины сравниваются лексикографически.
     fn xor_distance(id1: &PeerId, id2: &PeerId) -> Vec<u8> {
         let id1_bytes = id1.as_bytes();
         let id2_bytes = id2.as_bytes();
         
-        // Выбираем минимальную длину для XOR
-        let len = std::cmp::min(id1_bytes.len(), id2_bytes.len());
+        let len = std::cmp::max(id1_bytes.len(), id2_bytes.len());
+        let pad1 = len - id1_bytes.len();
+        let pad2 = len - id2_bytes.len();
         
-        // Вычисляем XOR
-        let mut result = Vec::with_capacity(len);
-        for i in 0..len {
-            result.push(id1_bytes[i] ^ id2_bytes[i]);
+        // Вычисляем XOR с учетом дополнения
+        (0..len)
+            .map(|i| {
+                let a = if i < pad1 { 0 } else { id1_bytes[i - pad1] };
+                let b = if i < pad2 { 0 } else { id2_bytes[i - pad2] };
+                a ^ b
+            })
+            .collect()
+    }
+    
+    /// Проверить, что длина идентификатора соответствует размеру пространства ключей
+    fn check_id_len(&self, id: &PeerId) -> Result<()> {
+        if id.as_bytes().len() * 8 != self.id_bits {
+            return Err(Error::Dht(format!(
+                "Идентификатор {} имеет длину {} бит, ожидается {}",
+                id, id.as_bytes().len() * 8, self.id_bits
+            )));
         }
-        
-        result
+        Ok(())
     }
     
     /// Получить индекс k-bucket для заданного расстояния
@@ -116,26 +314,465 @@ impl KademliaDht {
         0 // Если все биты нулевые (расстояние = 0)
     }
     
+    /// Отсортировать узлы по расстоянию до целевого ID
+    fn sort_by_distance(target: &PeerId, peers: &mut [PeerInfo]) {
+        peers.sort_by(|a, b| {
+            let dist_a = Self::xor_distance(target, &a.id);
+            let dist_b = Self::xor_distance(target, &b.id);
+            dist_a.cmp(&dist_b)
+        });
+    }
+    
+    /// Обработать входящий запрос DHT от другого узла
+    ///
+    /// Отвечает на `Ping`, на `FindNode` — ближайшими известными узлами, сохраняет значения
+    /// из `Store` и отвечает на `Get` значением или ближайшими к ключу узлами.
+    /// Сообщения других типов игнорируются.
+    pub async fn handle_request(&mut self, message: &Message) -> Result<()> {
+        match message.message_type {
+            MessageType::Ping => self.respond(message, MessageType::Pong, Vec::new()).await,
+            MessageType::Store => {
+                let (key, value): (Vec<u8>, Vec<u8>) = bincode::deserialize(&message.data)
+                    .map_err(|e| Error::Serialization(format!("Некорректный запрос Store: {}", e)))?;
+                self.store_local(&key, &value)
+            }
+            MessageType::Get => {
+                if let Some(value) = self.get_local(&message.data) {
+                    return self.respond(message, MessageType::Value, value).await;
+                }
+                
+                let target = Self::key_target(&message.data);
+                // Запрашивающий узел исключается из ответа, поэтому берем на один больше
+                let mut closest = self.closest_peers(&target, self.config.k + 1)?;
+                closest.retain(|peer| peer.id != message.from);
+                closest.truncate(self.config.k);
+                
+                let data = bincode::serialize(&closest)
+                    .map_err(|e| Error::Serialization(format!("Не удалось сериализовать ответ: {}", e)))?;
+                self.respond(message, MessageType::NodeResponse, data).await
+            }
+            MessageType::FindNode => {
+                let target: PeerId = bincode::deserialize(&message.data)
+                    .map_err(|e| Error::Serialization(format!("Некорректный запрос FindNode: {}", e)))?;
+                
+                // Запрашивающий узел исключается из ответа, поэтому берем на один больше
+                let mut closest = self.closest_peers(&target, self.config.k + 1)?;
+                closest.retain(|peer| peer.id != message.from);
+                closest.truncate(self.config.k);
+                
+                let data = bincode::serialize(&closest)
+                    .map_err(|e| Error::Serialization(format!("Не удалось сериализовать ответ: {}", e)))?;
+                self.respond(message, MessageType::NodeResponse, data).await
+            }
+            _ => Ok(()),
+        }
+    }
+    
+    /// Найти до `limit` ближайших к `target` узлов в таблице маршрутизации
+    ///
+    /// В отличие от `get_closest_peers` не ограничивает результат k узлами.
+    fn closest_peers(&self, target: &PeerId, limit: usize) -> Result<Vec<PeerInfo>> {
+        // Вычисляем расстояние до целевого ID
+        let target_distance = Self::xor_distance(&self.local_id, target);
+        let bucket_idx = Self::bucket_index(&target_distance);
+        
+        // Получаем ближайшие узлы из таблицы маршрутизации
+        let mut result = Vec::new();
+        
+        if let Ok(routing_table) = self.routing_table.lock() {
+            // Сначала добавляем узлы из целевого бакета
+            result.extend(routing_table[bucket_idx].iter().map(|entry| entry.peer.clone()));
+            
+            // Затем добавляем узлы из соседних бакетов
+            let mut i = 1;
+            while result.len() < limit && (bucket_idx >= i || bucket_idx + i < self.id_bits) {
+                if bucket_idx >= i {
+                    result.extend(routing_table[bucket_idx - i].iter().map(|entry| entry.peer.clone()));
+                }
+                
+                if bucket_idx + i < self.id_bits {
+                    result.extend(routing_table[bucket_idx + i].iter().map(|entry| entry.peer.clone()));
+                }
+                
+                i += 1;
+            }
+        } else {
+            return Err(Error::Dht("Не удалось получить блокировку таблицы маршрутизации".to_string()));
+        }
+        
+        // Сортируем по расстоянию до целевого ID
+        Self::sort_by_distance(target, &mut result);
+        
+        // Ограничиваем количество результатов
+        if result.len() > limit {
+            result.truncate(limit);
+        }
+        
+        Ok(result)
+    }
+    
+    /// Отметить узел как недавно активный
+    fn touch(&self, peer_id: &PeerId) {
+        let bucket_idx = Self::bucket_index(&Self::xor_distance(&self.local_id, peer_id));
+        
+        if let Ok(mut routing_table) = self.routing_table.lock() {
+            if let Some(entry) = routing_table[bucket_idx].iter_mut().find(|entry| entry.peer.id == *peer_id) {
+                entry.last_seen = Instant::now();
+            }
+        }
+    }
+    
+    /// Отправить ответ на запрос
+    async fn respond(&self, request: &Message, response_type: MessageType, data: Vec<u8>) -> Result<()> {
+        let tx = self.network_tx.as_ref()
+            .ok_or_else(|| Error::Dht("Канал для отправки сообщений не настроен".to_string()))?;
+        
+        let response = Message::new(self.local_id.clone(), Some(request.from.clone()), response_type, data);
+        tx.send(response).await
+            .map_err(|_| Error::Dht("Канал для отправки сообщений закрыт".to_string()))
+    }
+    
+    /// Отправить запрос группе узлов и собрать ответы
+    ///
+    /// Ожидание ограничено наибольшим адаптивным таймаутом среди опрашиваемых
+    /// узлов и общим сроком поиска. Посторонние сообщения, полученные во время
+    /// ожидания, обрабатываются как входящие запросы.
+    async fn query_round(
+        &mut self,
+        peers: &[PeerInfo],
+        request_type: MessageType,
+        payload: &[u8],
+        response_types: &[MessageType],
+        deadline: Instant,
+    ) -> Result<Vec<Message>> {
+        let tx = self.network_tx.clone()
+            .ok_or_else(|| Error::Dht("Канал для отправки сообщений не настроен".to_string()))?;
+        
+        let mut pending: HashMap<PeerId, Instant> = HashMap::new();
+        let mut round_timeout = Duration::ZERO;
+        
+        for peer in peers {
+            let request = Message::new(self.local_id.clone(), Some(peer.id.clone()), request_type, payload.to_vec());
+            tx.send(request).await
+                .map_err(|_| Error::Dht("Канал для отправки сообщений закрыт".to_string()))?;
+            
+            pending.insert(peer.id.clone(), Instant::now());
+            round_timeout = round_timeout.max(self.lookup_timeout(&peer.id));
+        }
+        
+        let round_deadline = std::cmp::min(Instant::now() + round_timeout, deadline);
+        let mut responses = Vec::new();
+        
+        while !pending.is_empty() {
+            let remaining = round_deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                break;
+            }
+            
+            let rx = self.network_rx.as_mut()
+                .ok_or_else(|| Error::Dht("Канал для получения сообщений не настроен".to_string()))?;
+            let message = match time::timeout(remaining, rx.recv()).await {
+                Ok(Some(message)) => message,
+                // Канал закрыт или истек таймаут раунда
+                Ok(None) | Err(_) => break,
+            };
+            
+            if response_types.contains(&message.message_type) {
+                if let Some(sent_at) = pending.remove(&message.from) {
+                    self.record_rtt(&message.from, sent_at.elapsed());
+                    self.touch(&message.from);
+                    responses.push(message);
+                    continue;
+                }
+            }
+            
+            if let Err(e) = self.handle_request(&message).await {
+                tracing::debug!("Не удалось обработать запрос DHT от {}: {}", message.from, e);
+            }
+        }
+        
+        Ok(responses)
+    }
+    
+    /// Итеративный поиск ближайших к цели узлов в сети
+    ///
+    /// На каждом шаге запрашивает `alpha` ближайших еще не опрошенных узлов.
+    /// Если шаг не улучшил набор из `k` ближайших, опрашиваются все оставшиеся
+    /// из них; поиск завершается, когда и это не дает улучшения, или по
+    /// истечении общего таймаута.
+    async fn lookup_nodes(&mut self, target: &PeerId) -> Result<Vec<PeerInfo>> {
+        let request = bincode::serialize(target)
+            .map_err(|e| Error::Serialization(format!("Не удалось сериализовать запрос: {}", e)))?;
+        
+        match self.lookup(target, MessageType::FindNode, &request).await? {
+            LookupResult::Peers(peers) => Ok(peers),
+            LookupResult::Value(_) => Err(Error::Dht("Неожиданный ответ Value на FindNode".to_string())),
+        }
+    }
+    
+    /// Итеративный поиск с запросом заданного типа
+    ///
+    /// Завершается досрочно, если один из узлов ответил `Value`.
+    async fn lookup(&mut self, target: &PeerId, request_type: MessageType, request: &[u8]) -> Result<LookupResult> {
+        let k = self.config.k;
+        let mut shortlist = self.get_closest_peers(target, k).await?;
+        
+        if self.network_tx.is_none() || self.network_rx.is_none() {
+            return Ok(LookupResult::Peers(shortlist));
+        }
+        
+        let deadline = Instant::now() + self.lookup_deadline;
+        let mut queried: HashSet<PeerId> = HashSet::new();
+        let mut stalled = false;
+        
+        while Instant::now() < deadline {
+            let width = if stalled { k } else { self.config.alpha };
+            let batch: Vec<PeerInfo> = shortlist.iter()
+                .filter(|peer| !queried.contains(&peer.id))
+                .take(width)
+                .cloned()
+                .collect();
+            
+            if batch.is_empty() {
+                break;
+            }
+            
+            let previous: Vec<PeerId> = shortlist.iter().map(|peer| peer.id.clone()).collect();
+            let responses = self.query_round(
+                &batch,
+                request_type,
+                request,
+                &[MessageType::NodeResponse, MessageType::Value],
+                deadline,
+            ).await?;
+            
+            if let Some(found) = responses.iter().find(|m| m.message_type == MessageType::Value) {
+                return Ok(LookupResult::Value(found.data.clone()));
+            }
+            
+            queried.extend(batch.iter().map(|peer| peer.id.clone()));
+            
+            // Не ответившие узлы исключаются из результата
+            let responded: HashSet<PeerId> = responses.iter().map(|m| m.from.clone()).collect();
+            shortlist.retain(|peer| !batch.iter().any(|b| b.id == peer.id) || responded.contains(&peer.id));
+            
+            for response in responses {
+                let peers: Vec<PeerInfo> = match bincode::deserialize(&response.data) {
+                    Ok(peers) => peers,
+                    Err(e) => {
+                        tracing::debug!("Некорректный ответ NodeResponse от {}: {}", response.from, e);
+                        continue;
+                    }
+                };
+                
+                for peer in peers {
+                    // Узлы с некорректной длиной идентификатора отбрасываются
+                    if peer.id == self.local_id
+                        || self.check_id_len(&peer.id).is_err()
+                        || shortlist.iter().any(|p| p.id == peer.id)
+                    {
+                        continue;
+                    }
+                    
+                    self.add_peer(peer.clone()).await?;
+                    shortlist.push(peer);
+                }
+            }
+            
+            Self::sort_by_distance(target, &mut shortlist);
+            shortlist.truncate(k);
+            
+            let current: Vec<PeerId> = shortlist.iter().map(|peer| peer.id.clone()).collect();
+            let improved = current != previous;
+            
+            if !improved && stalled {
+                break;
+            }
+            stalled = !improved;
+        }
+        
+        Ok(LookupResult::Peers(shortlist))
+    }
+    
+    /// Получить идентификатор цели поиска для ключа значения
+    fn key_target(key: &[u8]) -> PeerId {
+        PeerId::try_new(sha256(key)).expect("Дайджест SHA-256 имеет длину идентификатора")
+    }
+    
+    /// Сохранить значение в локальном хранилище
+    fn store_local(&self, key: &[u8], value: &[u8]) -> Result<()> {
+        let mut storage = self.storage.lock()
+            .map_err(|_| Error::Dht("Не удалось получить блокировку хранилища".to_string()))?;
+        
+        storage.insert(
+            key.to_vec(),
+            DhtValue {
+                value: value.to_vec(),
+                timestamp: Instant::now(),
+            },
+        );
+        
+        Ok(())
+    }
+    
+    /// Получить значение из локального хранилища
+    fn get_local(&self, key: &[u8]) -> Option<Vec<u8>> {
+        self.storage.lock()
+            .ok()
+            .and_then(|storage| storage.get(key).map(|entry| entry.value.clone()))
+    }
+    
+    /// Сохранить узлы таблицы маршрутизации с известными адресами
+    async fn save_routing_table(
+        routing_table: &Mutex<Vec<KBucket>>,
+        persistence: &tokio::sync::Mutex<Box<dyn Storage>>,
+    ) -> Result<()> {
+        let peers: Vec<PeerInfo> = {
+            let routing_table = routing_table.lock()
+                .map_err(|_| Error::Dht("Не удалось получить блокировку таблицы маршрутизации".to_string()))?;
+            routing_table.iter()
+                .flat_map(|bucket| bucket.iter())
+                .filter(|entry| entry.peer.address.is_some())
+                .map(|entry| entry.peer.clone())
+                .collect()
+        };
+        
+        let data = bincode::serialize(&peers)
+            .map_err(|e| Error::Serialization(format!("Не удалось сериализовать таблицу маршрутизации: {}", e)))?;
+        
+        persistence.lock().await.put(ROUTING_TABLE_KEY, &data).await
+    }
+    
+    /// Восстановить таблицу маршрутизации из хранилища
+    async fn load_routing_table(&mut self) -> Result<()> {
+        let persistence = match &self.persistence {
+            Some(persistence) => Arc::clone(persistence),
+            None => return Ok(()),
+        };
+        
+        let data = persistence.lock().await.get(ROUTING_TABLE_KEY).await?;
+        let peers: Vec<PeerInfo> = match data {
+            Some(data) => bincode::deserialize(&data)
+                .map_err(|e| Error::Serialization(format!("Не удалось десериализовать таблицу маршрутизации: {}", e)))?,
+            None => return Ok(()),
+        };
+        
+        for peer in peers {
+            if peer.address.is_some() && peer.id != self.local_id && self.check_id_len(&peer.id).is_ok() {
+                self.add_peer(peer).await?;
+            }
+        }
+        
+        Ok(())
+    }
+    
+    /// Приостановить периодическое обслуживание DHT
+    ///
+    /// Хранимые значения и таблица маршрутизации сохраняются, а DHT продолжает
+    /// отвечать на запросы; пропускаются очистка, сохранение таблицы и
+    /// повторная рассылка значений.
+    pub fn pause(&self) {
+        self.maintenance_paused.store(true, Ordering::SeqCst);
+    }
+    
+    /// Возобновить периодическое обслуживание DHT
+    pub fn resume(&self) {
+        self.maintenance_paused.store(false, Ordering::SeqCst);
+    }
+    
+    /// Приостановлено ли периодическое обслуживание DHT
+    pub fn is_paused(&self) -> bool {
+        self.maintenance_paused.load(Ordering::SeqCst)
+    }
+    
+    /// Повторно разослать хранимые значения ближайшим к их ключам узлам
+    ///
+    /// Значения отправляются узлам из таблицы маршрутизации, чтобы они
+    /// сохранялись в сети после ухода узлов, получивших их ранее.
+    async fn republish_values(
+        local_id: &PeerId,
+        routing_table: &Mutex<Vec<KBucket>>,
+        storage: &Mutex<HashMap<Vec<u8>, DhtValue>>,
+        tx: &mpsc::Sender<Message>,
+        k: usize,
+    ) -> Result<()> {
+        let values: Vec<(Vec<u8>, Vec<u8>)> = {
+            let storage = storage.lock()
+                .map_err(|_| Error::Dht("Не удалось получить блокировку хранилища".to_string()))?;
+            storage.iter().map(|(key, entry)| (key.clone(), entry.value.clone())).collect()
+        };
+        
+        let peers: Vec<PeerInfo> = {
+            let routing_table = routing_table.lock()
+                .map_err(|_| Error::Dht("Не удалось получить блокировку таблицы маршрутизации".to_string()))?;
+            routing_table.iter().flatten().map(|entry| entry.peer.clone()).collect()
+        };
+        
+        for (key, value) in values {
+            let mut closest = peers.clone();
+            Self::sort_by_distance(&Self::key_target(&key), &mut closest);
+            closest.truncate(k);
+            
+            let data = bincode::serialize(&(&key, &value))
+                .map_err(|e| Error::Serialization(format!("Не удалось сериализовать значение: {}", e)))?;
+            
+            for peer in closest {
+                let request = Message::new(local_id.clone(), Some(peer.id), MessageType::Store, data.clone());
+                tx.send(request).await
+                    .map_err(|_| Error::Dht("Канал для отправки сообщений закрыт".to_string()))?;
+            }
+        }
+        
+        Ok(())
+    }
+    
     /// Запустить задачу обслуживания DHT
     fn start_maintenance_task(&mut self) -> Result<()> {
         let routing_table = Arc::clone(&self.routing_table);
         let storage = Arc::clone(&self.storage);
+        let persistence = self.persistence.clone();
+        let value_ttl = self.config.value_ttl;
+        let paused = Arc::clone(&self.maintenance_paused);
         let local_id = self.local_id.clone();
+        let network_tx = self.network_tx.clone();
+        let k = self.config.k;
+        let maintenance_interval = self.maintenance_interval;
+        let republish_interval = self.republish_interval;
         
         // Запускаем периодическое обслуживание DHT
         self.maintenance_task = Some(tokio::spawn(async move {
-            let mut interval = time::interval(Duration::from_secs(60));
+            let mut interval = time::interval(maintenance_interval);
+            let mut last_republish = Instant::now();
             
             loop {
                 interval.tick().await;
                 
+                if paused.load(Ordering::SeqCst) {
+                    continue;
+                }
+                
                 // Очистка устаревших значений в хранилище
                 if let Ok(mut storage_lock) = storage.lock() {
-                    storage_lock.retain(|_, value| value.timestamp.elapsed() < VALUE_TTL);
+                    storage_lock.retain(|_, value| value.timestamp.elapsed() < value_ttl);
+                }
+                
+                // Сохранение таблицы маршрутизации
+                if let Some(persistence) = &persistence {
+                    if let Err(e) = Self::save_routing_table(&routing_table, persistence).await {
+                        tracing::warn!("Не удалось сохранить таблицу маршрутизации: {}", e);
+                    }
                 }
                 
-                // Обновление маршрутов (в реальной реализации)
-                // ...
+                // Повторная рассылка хранимых значений
+                if let Some(tx) = &network_tx {
+                    if last_republish.elapsed() >= republish_interval {
+                        last_republish = Instant::now();
+                        
+                        if let Err(e) = Self::republish_values(&local_id, &routing_table, &storage, tx, k).await {
+                            tracing::warn!("Не удалось повторно разослать значения DHT: {}", e);
+                        }
+                    }
+                }
             }
         }));
         
@@ -150,6 +787,9 @@ impl Dht for KademliaDht {
             return Ok(());
         }
         
+        // Восстанавливаем сохраненную таблицу маршрутизации
+        self.load_routing_table().await?;
+        
         // Запускаем задачу обслуживания
         self.start_maintenance_task()?;
         
@@ -167,123 +807,510 @@ impl Dht for KademliaDht {
             task.abort();
         }
         
+        // Сохраняем таблицу маршрутизации
+        if let Some(persistence) = &self.persistence {
+            Self::save_routing_table(&self.routing_table, persistence).await?;
+        }
+        
         self.started = false;
         Ok(())
     }
     
     async fn find_nodes(&mut self, target: &PeerId) -> Result<Vec<PeerInfo>> {
-        // Получаем ближайшие узлы из таблицы маршрутизации
-        let mut closest = self.get_closest_peers(target, K).await?;
-        
-        // Если у нас есть канал для обмена сообщениями, отправляем запросы в сеть
-        if let Some(tx) = &self.network_tx {
-            // Реализация алгоритма поиска Kademlia
-            // (в реальной реализации здесь будет полный алгоритм поиска)
-            // ...
-        }
-        
-        Ok(closest)
+        // Без каналов обмена сообщениями возвращаются узлы из таблицы маршрутизации
+        self.lookup_nodes(target).await
     }
     
     async fn find_value(&mut self, key: &[u8]) -> Result<Option<Vec<u8>>> {
         // Проверяем локальное хранилище
-        if let Ok(storage) = self.storage.lock() {
-            if let Some(entry) = storage.get(key) {
-                return Ok(Some(entry.value.clone()));
-            }
+        if let Some(value) = self.get_local(key) {
+            return Ok(Some(value));
         }
         
         // Если значения нет локально, ищем в сети
-        // (в реальной реализации)
-        // ...
-        
-        Ok(None)
+        let target = Self::key_target(key);
+        match self.lookup(&target, MessageType::Get, key).await? {
+            LookupResult::Value(value) => {
+                // Кешируем найденное значение
+                self.store_local(key, &value)?;
+                Ok(Some(value))
+            }
+            LookupResult::Peers(_) => Ok(None),
+        }
     }
     
     async fn store(&mut self, key: &[u8], value: &[u8]) -> Result<()> {
         // Сохраняем значение локально
-        if let Ok(mut storage) = self.storage.lock() {
-            storage.insert(
-                key.to_vec(),
-                DhtValue {
-                    value: value.to_vec(),
-                    timestamp: Instant::now(),
-                },
-            );
-        } else {
-            return Err(Error::Dht("Не удалось получить блокировку хранилища".to_string()));
-        }
+        self.store_local(key, value)?;
         
-        // Репликация значения в сети
-        // (в реальной реализации)
-        // ...
+        let tx = match &self.network_tx {
+            Some(tx) => tx.clone(),
+            None => return Ok(()),
+        };
+        
+        // Репликация значения на k ближайших к ключу узлов
+        let started = Instant::now();
+        let closest = self.lookup_nodes(&Self::key_target(key)).await?;
+        let data = bincode::serialize(&(key, value))
+            .map_err(|e| Error::Serialization(format!("Не удалось сериализовать значение: {}", e)))?;
+        
+        for peer in closest {
+            let remaining = self.lookup_deadline.saturating_sub(started.elapsed());
+            let request = Message::new(self.local_id.clone(), Some(peer.id.clone()), MessageType::Store, data.clone());
+            
+            match time::timeout(remaining, tx.send(request)).await {
+                Ok(Ok(())) => {}
+                Ok(Err(_)) => return Err(Error::Dht("Канал для отправки сообщений закрыт".to_string())),
+                Err(_) => return Err(Error::Timeout(format!("Репликация значения на {}", peer.id))),
+            }
+        }
         
         Ok(())
     }
     
     async fn add_peer(&mut self, peer: PeerInfo) -> Result<()> {
+        self.check_id_len(&peer.id)?;
+        
         // Вычисляем расстояние до узла
         let distance = Self::xor_distance(&self.local_id, &peer.id);
         let bucket_idx = Self::bucket_index(&distance);
         
         // Добавляем узел в соответствующий k-bucket
-        if let Ok(mut routing_table) = self.routing_table.lock() {
-            // Если k-bucket полон, применяем правила замены
-            if routing_table[bucket_idx].len() >= K {
-                // В реальной реализации здесь будет проверка доступности старого узла
-                // и замена при необходимости
-                // ...
+        let least_recent = {
+            let mut routing_table = self.routing_table.lock()
+                .map_err(|_| Error::Dht("Не удалось получить блокировку таблицы маршрутизации".to_string()))?;
+            let bucket = &mut routing_table[bucket_idx];
+            
+            if let Some(entry) = bucket.iter_mut().find(|entry| entry.peer.id == peer.id) {
+                // Известный узел обновляется и отмечается как недавно активный
+                *entry = BucketEntry { peer, last_seen: Instant::now() };
+                return Ok(());
+            }
+            
+            if bucket.len() < self.config.k {
+                bucket.push(BucketEntry { peer, last_seen: Instant::now() });
+                return Ok(());
+            }
+            
+            // Кандидат на вытеснение — узел, дольше всех не проявлявший активности
+            match bucket.iter().min_by_key(|entry| entry.last_seen) {
+                Some(entry) => entry.peer.clone(),
+                None => return Ok(()),
+            }
+        };
+        
+        // k-bucket полон: новый узел вытесняет давно активный, только если тот не отвечает
+        if self.network_tx.is_none() || self.network_rx.is_none() {
+            return Ok(());
+        }
+        
+        let deadline = Instant::now() + self.lookup_timeout(&least_recent.id);
+        let responses = self.query_round(
+            std::slice::from_ref(&least_recent),
+            MessageType::Ping,
+            &[],
+            &[MessageType::Pong],
+            deadline,
+        ).await?;
+        
+        let mut routing_table = self.routing_table.lock()
+            .map_err(|_| Error::Dht("Не удалось получить блокировку таблицы маршрутизации".to_string()))?;
+        let bucket = &mut routing_table[bucket_idx];
+        
+        if let Some(entry) = bucket.iter_mut().find(|entry| entry.peer.id == least_recent.id) {
+            if responses.is_empty() {
+                // Узел не ответил, заменяем его новым
+                *entry = BucketEntry { peer, last_seen: Instant::now() };
             } else {
-                routing_table[bucket_idx].insert(peer);
+                // Узел жив, он остается в бакете, а новый узел отбрасывается
+                entry.last_seen = Instant::now();
             }
-        } else {
-            return Err(Error::Dht("Не удалось получить блокировку таблицы маршрутизации".to_string()));
+        } else if bucket.len() < self.config.k {
+            // Бакет изменился во время проверки и в нем освободилось место
+            bucket.push(BucketEntry { peer, last_seen: Instant::now() });
         }
         
         Ok(())
     }
     
     async fn get_closest_peers(&mut self, target: &PeerId, limit: usize) -> Result<Vec<PeerInfo>> {
-        // Вычисляем расстояние до целевого ID
-        let target_distance = Self::xor_distance(&self.local_id, target);
-        let bucket_idx = Self::bucket_index(&target_distance);
+        self.check_id_len(target)?;
         
-        // Получаем ближайшие узлы из таблицы маршрутизации
-        let mut result = Vec::new();
+        // Больше k узлов не возвращаем независимо от запрошенного количества
+        let limit = limit.min(self.config.k);
         
-        if let Ok(routing_table) = self.routing_table.lock() {
-            // Сначала добавляем узлы из целевого бакета
-            result.extend(routing_table[bucket_idx].iter().cloned());
-            
-            // Затем добавляем узлы из соседних бакетов
-            let mut i = 1;
-            while result.len() < limit && (bucket_idx >= i || bucket_idx + i < self.id_bits) {
-                if bucket_idx >= i {
-                    result.extend(routing_table[bucket_idx - i].iter().cloned());
-                }
-                
-                if bucket_idx + i < self.id_bits {
-                    result.extend(routing_table[bucket_idx + i].iter().cloned());
-                }
-                
-                i += 1;
-            }
+        self.closest_peers(target, limit)
+    }
+} 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::memory::MemoryStorage;
+    use crate::types::Endpoint;
+    
+    #[tokio::test]
+    async fn small_params_limit_buckets_and_lookups() {
+        let mut dht = KademliaDht::new(PeerId::new(vec![0xff; 32])).unwrap().with_params(2, 1).unwrap();
+        assert_eq!((dht.k(), dht.alpha()), (2, 1));
+        
+        // Все четыре узла попадают в один k-bucket
+        for id in [0x01, 0x02, 0x03, 0x04] {
+            dht.add_peer(peer(id)).await.unwrap();
+        }
+        let stored: usize = dht.routing_table.lock().unwrap().iter().map(|bucket| bucket.len()).sum();
+        assert_eq!(stored, 2);
+        
+        let found = dht.find_nodes(&PeerId::new(vec![0; 32])).await.unwrap();
+        assert_eq!(found.len(), 2);
+        
+        assert!(KademliaDht::new(PeerId::new(vec![0xff; 32])).unwrap().with_params(2, 3).is_err());
+        assert!(KademliaDht::new(PeerId::new(vec![0xff; 32])).unwrap().with_params(0, 0).is_err());
+    }
+    
+    #[tokio::test]
+    async fn lookup_round_queries_alpha_peers() {
+        let (tx, mut rx) = mpsc::channel(16);
+        let (_in_tx, in_rx) = mpsc::channel(16);
+        let mut dht = KademliaDht::new(PeerId::new(vec![0xff; 32])).unwrap().with_params(3, 2).unwrap()
+            .with_network_channels(tx, in_rx);
+        for id in [0x01, 0x41, 0x81] {
+            dht.add_peer(peer(id)).await.unwrap();
+        }
+        
+        // Узлы не отвечают, поэтому поиск остается в первом раунде
+        let lookup = tokio::spawn(async move { dht.find_nodes(&PeerId::new(vec![0; 32])).await });
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        
+        let mut sent = 0;
+        while rx.try_recv().is_ok() {
+            sent += 1;
+        }
+        assert_eq!(sent, 2);
+        lookup.abort();
+    }
+    
+    fn peer(id: u8) -> PeerInfo {
+        PeerInfo {
+            id: PeerId::new(vec![id; 32]),
+            address: Some(Endpoint::tcp("127.0.0.1", 9000 + id as u16)),
+            protocols: vec!["tcp".to_string()],
+            client_version: "test".to_string(),
+        }
+    }
+    
+    /// Узел имитируемой сети
+    #[derive(Default)]
+    struct MockPeer {
+        /// Узлы, возвращаемые в ответ на FindNode и Get
+        known: Vec<PeerInfo>,
+        /// Значения, сохраненные запросами Store
+        values: HashMap<Vec<u8>, Vec<u8>>,
+        /// Количество полученных запросов поиска и сохранения
+        queries: usize,
+        /// Количество полученных ping
+        pings: usize,
+    }
+    
+    type MockNetwork = Arc<Mutex<HashMap<PeerId, MockPeer>>>;
+    
+    /// Построить сеть из списков соседей каждого узла
+    fn mock_network(links: Vec<(u8, Vec<u8>)>) -> MockNetwork {
+        let mut network = HashMap::new();
+        for (id, known) in links {
+            let known = known.into_iter().map(peer).collect();
+            network.insert(peer(id).id, MockPeer { known, ..Default::default() });
+        }
+        Arc::new(Mutex::new(network))
+    }
+    
+    /// Ответ имитируемого узла на запрос
+    fn mock_response(network: &MockNetwork, request: &Message) -> Option<Message> {
+        let mut network = network.lock().unwrap();
+        let peer = network.get_mut(request.to.as_ref()?)?;
+        if request.message_type == MessageType::Ping {
+            peer.pings += 1;
         } else {
-            return Err(Error::Dht("Не удалось получить блокировку таблицы маршрутизации".to_string()));
+            peer.queries += 1;
         }
         
-        // Сортируем по расстоянию до целевого ID
-        result.sort_by(|a, b| {
-            let dist_a = Self::xor_distance(target, &a.id);
-            let dist_b = Self::xor_distance(target, &b.id);
-            dist_a.cmp(&dist_b)
+        let known = bincode::serialize(&peer.known).unwrap();
+        match request.message_type {
+            MessageType::Ping => Some(request.create_response(MessageType::Pong, Vec::new())),
+            MessageType::FindNode => Some(request.create_response(MessageType::NodeResponse, known)),
+            MessageType::Store => {
+                let (key, value): (Vec<u8>, Vec<u8>) = bincode::deserialize(&request.data).unwrap();
+                peer.values.insert(key, value);
+                None
+            }
+            MessageType::Get => Some(match peer.values.get(&request.data) {
+                Some(value) => request.create_response(MessageType::Value, value.clone()),
+                None => request.create_response(MessageType::NodeResponse, known),
+            }),
+            _ => None,
+        }
+    }
+    
+    /// Подключить DHT к имитируемой сети через каналы сообщений
+    fn connect(dht: KademliaDht, network: &MockNetwork) -> KademliaDht {
+        let (out_tx, mut out_rx) = mpsc::channel::<Message>(64);
+        let (in_tx, in_rx) = mpsc::channel(64);
+        let network = Arc::clone(network);
+        
+        tokio::spawn(async move {
+            while let Some(request) = out_rx.recv().await {
+                if let Some(response) = mock_response(&network, &request) {
+                    if in_tx.send(response).await.is_err() {
+                        break;
+                    }
+                }
+            }
         });
         
-        // Ограничиваем количество результатов
-        if result.len() > limit {
-            result.truncate(limit);
+        dht.with_network_channels(out_tx, in_rx)
+    }
+    
+    #[tokio::test]
+    async fn iterative_lookup_converges_on_closest_peers() {
+        // Цель — нулевой идентификатор, поэтому расстояние до узла [n; 32] растет с n
+        let network = mock_network(vec![
+            (0x80, vec![0x40, 0x41]),
+            (0x40, vec![0x10, 0x80]),
+            (0x41, vec![0x11]),
+            (0x10, vec![0x01, 0x02]),
+            (0x11, vec![]),
+            (0x01, vec![0x02]),
+            (0x02, vec![0x01]),
+        ]);
+        let dht = KademliaDht::new(PeerId::new(vec![0xff; 32])).unwrap().with_params(3, 2).unwrap();
+        let mut dht = connect(dht, &network);
+        dht.add_peer(peer(0x80)).await.unwrap();
+        
+        let found = dht.find_nodes(&PeerId::new(vec![0; 32])).await.unwrap();
+        let ids: Vec<PeerId> = found.into_iter().map(|peer| peer.id).collect();
+        assert_eq!(ids, vec![peer(0x01).id, peer(0x02).id, peer(0x10).id]);
+        
+        // Каждый узел опрашивается не более одного раза
+        let network = network.lock().unwrap();
+        assert!(network.values().all(|peer| peer.queries <= 1));
+        assert_eq!(network[&peer(0x01).id].queries, 1);
+    }
+    
+    #[tokio::test]
+    async fn lookup_without_network_uses_routing_table() {
+        let mut dht = KademliaDht::new(PeerId::new(vec![0xff; 32])).unwrap();
+        dht.add_peer(peer(0x80)).await.unwrap();
+        
+        let found = dht.find_nodes(&PeerId::new(vec![0; 32])).await.unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].id, peer(0x80).id);
+    }
+    
+    #[tokio::test]
+    async fn stored_value_is_replicated_and_found_remotely() {
+        let network = mock_network(vec![
+            (0x10, vec![0x20, 0x30]),
+            (0x20, vec![0x10, 0x30]),
+            (0x30, vec![0x10, 0x20]),
+        ]);
+        
+        let alice = KademliaDht::new(PeerId::new(vec![0xa0; 32])).unwrap().with_lookup_deadline(Duration::from_secs(2));
+        let mut alice = connect(alice, &network);
+        alice.add_peer(peer(0x10)).await.unwrap();
+        alice.store(b"key", b"value").await.unwrap();
+        
+        // Запросы Store обрабатываются сетью асинхронно
+        let replicated = || network.lock().unwrap().values().filter(|peer| peer.values.contains_key(&b"key"[..])).count();
+        let started = Instant::now();
+        while replicated() < 3 && started.elapsed() < Duration::from_secs(2) {
+            time::sleep(Duration::from_millis(10)).await;
         }
+        assert_eq!(replicated(), 3);
         
-        Ok(result)
+        // Другой узел находит значение в сети и кеширует его
+        let bob = KademliaDht::new(PeerId::new(vec![0xb0; 32])).unwrap().with_lookup_deadline(Duration::from_secs(2));
+        let mut bob = connect(bob, &network);
+        bob.add_peer(peer(0x30)).await.unwrap();
+        assert_eq!(bob.find_value(b"key").await.unwrap(), Some(b"value".to_vec()));
+        assert_eq!(bob.get_local(b"key"), Some(b"value".to_vec()));
+        
+        assert_eq!(bob.find_value(b"missing").await.unwrap(), None);
+    }
+    
+    #[tokio::test]
+    async fn routing_table_is_restored_from_storage() {
+        let storage = MemoryStorage::new("dht");
+        let local_id = PeerId::new(vec![0xff; 32]);
+        
+        let mut dht = KademliaDht::new(local_id.clone()).unwrap().with_storage(Box::new(storage.clone()));
+        dht.start().await.unwrap();
+        dht.add_peer(peer(0x10)).await.unwrap();
+        dht.add_peer(peer(0x20)).await.unwrap();
+        // Узел без адреса не сохраняется
+        dht.add_peer(PeerInfo { address: None, ..peer(0x30) }).await.unwrap();
+        dht.stop().await.unwrap();
+        
+        let mut restarted = KademliaDht::new(local_id).unwrap().with_storage(Box::new(storage));
+        assert!(restarted.get_closest_peers(&PeerId::new(vec![0; 32]), K).await.unwrap().is_empty());
+        restarted.start().await.unwrap();
+        
+        let mut restored: Vec<PeerId> = restarted.get_closest_peers(&PeerId::new(vec![0; 32]), K).await.unwrap()
+            .into_iter()
+            .map(|peer| peer.id)
+            .collect();
+        restored.sort_by_key(|id| id.as_bytes().to_vec());
+        assert_eq!(restored, vec![peer(0x10).id, peer(0x20).id]);
+        restarted.stop().await.unwrap();
+    }
+    
+    /// DHT с k = 2, в котором узлы 0x01 и 0x02 заполняют один k-bucket
+    async fn full_bucket(network: &MockNetwork) -> KademliaDht {
+        let dht = KademliaDht::new(PeerId::new(vec![0xff; 32])).unwrap().with_params(2, 1).unwrap();
+        let mut dht = connect(dht, network);
+        dht.add_peer(peer(0x01)).await.unwrap();
+        dht.add_peer(peer(0x02)).await.unwrap();
+        // Короткий таймаут ping для давно активного узла
+        dht.record_rtt(&peer(0x01).id, Duration::from_millis(1));
+        dht
+    }
+    
+    async fn bucket_ids(dht: &mut KademliaDht) -> Vec<PeerId> {
+        let mut ids: Vec<PeerId> = dht.get_closest_peers(&peer(0x01).id, 10).await.unwrap()
+            .into_iter()
+            .map(|peer| peer.id)
+            .collect();
+        ids.sort_by_key(|id| id.as_bytes().to_vec());
+        ids
+    }
+    
+    #[tokio::test]
+    async fn stale_peer_is_replaced() {
+        // Узел 0x01 отсутствует в сети и не отвечает на ping
+        let network = mock_network(vec![(0x02, vec![])]);
+        let mut dht = full_bucket(&network).await;
+        
+        dht.add_peer(peer(0x03)).await.unwrap();
+        assert_eq!(bucket_ids(&mut dht).await, vec![peer(0x02).id, peer(0x03).id]);
+    }
+    
+    #[tokio::test]
+    async fn live_peer_is_retained() {
+        let network = mock_network(vec![(0x01, vec![]), (0x02, vec![])]);
+        let mut dht = full_bucket(&network).await;
+        
+        dht.add_peer(peer(0x03)).await.unwrap();
+        assert_eq!(bucket_ids(&mut dht).await, vec![peer(0x01).id, peer(0x02).id]);
+        assert_eq!(network.lock().unwrap()[&peer(0x01).id].pings, 1);
+        
+        // Ответивший узел стал недавно активным, поэтому следующим проверяется 0x02
+        dht.add_peer(peer(0x04)).await.unwrap();
+        assert_eq!(network.lock().unwrap()[&peer(0x02).id].pings, 1);
+    }
+    
+    #[tokio::test]
+    async fn distances_are_consistent_for_mixed_lengths() {
+        let id = PeerId::new(vec![0x5a; 32]);
+        assert!(KademliaDht::xor_distance(&id, &id).iter().all(|&byte| byte == 0));
+        
+        // Короткий идентификатор дополняется нулями слева
+        let short = PeerId::new(vec![0x5a; 20]);
+        let distance = KademliaDht::xor_distance(&id, &short);
+        assert_eq!(distance.len(), 32);
+        assert_eq!(&distance[..12], &[0x5a; 12]);
+        assert!(distance[12..].iter().all(|&byte| byte == 0));
+        assert_eq!(distance, KademliaDht::xor_distance(&short, &id));
+        
+        let mut dht = KademliaDht::new(PeerId::new(vec![0xff; 32])).unwrap();
+        let result = dht.add_peer(PeerInfo { id: short.clone(), ..peer(0x01) }).await;
+        assert!(matches!(result, Err(Error::Dht(_))));
+        assert!(dht.get_closest_peers(&short, K).await.is_err());
+        
+        // Порядок по расстоянию не зависит от исходного порядка
+        let target = PeerId::new(vec![0; 32]);
+        let mut forward = vec![peer(0x04), peer(0x01), peer(0x03), peer(0x02)];
+        let mut backward: Vec<PeerInfo> = forward.iter().rev().cloned().collect();
+        KademliaDht::sort_by_distance(&target, &mut forward);
+        KademliaDht::sort_by_distance(&target, &mut backward);
+        let ids = |peers: &[PeerInfo]| peers.iter().map(|peer| peer.id.clone()).collect::<Vec<_>>();
+        assert_eq!(ids(&forward), ids(&backward));
+        assert_eq!(forward[0].id, peer(0x01).id);
+    }
+    
+    #[tokio::test]
+    async fn configured_k_limits_returned_peers() {
+        let config = KademliaConfig { k: 4, alpha: 2, ..Default::default() };
+        let mut dht = KademliaDht::with_config(PeerId::new(vec![0xff; 32]), config).unwrap();
+        assert_eq!((dht.k(), dht.alpha()), (4, 2));
+        
+        // Узлы из разных k-bucket
+        for id in [0x01, 0x02, 0x41, 0x42, 0x81, 0x82, 0xc1, 0xe1, 0xf1, 0xf9, 0xfd, 0xfe] {
+            dht.add_peer(peer(id)).await.unwrap();
+        }
+        
+        for target in [0x00, 0x80, 0xf0] {
+            let closest = dht.get_closest_peers(&PeerId::new(vec![target; 32]), 20).await.unwrap();
+            assert_eq!(closest.len(), 4);
+        }
+        
+        let invalid = KademliaConfig { k: 2, alpha: 3, ..Default::default() };
+        assert!(KademliaDht::with_config(PeerId::new(vec![0xff; 32]), invalid).is_err());
+    }
+    
+    #[tokio::test]
+    async fn find_node_reply_without_requester_stays_full() {
+        let config = KademliaConfig { k: 4, alpha: 2, ..Default::default() };
+        let (tx, mut rx) = mpsc::channel(8);
+        let (_in_tx, in_rx) = mpsc::channel(8);
+        let mut dht = KademliaDht::with_config(PeerId::new(vec![0xff; 32]), config).unwrap()
+            .with_network_channels(tx, in_rx);
+        for id in [0x01, 0x02, 0x41, 0x42, 0x81, 0x82, 0xc1, 0xe1] {
+            dht.add_peer(peer(id)).await.unwrap();
+        }
+        
+        // Запрашивающий узел сам ближе всех к цели
+        let target = peer(0x01).id;
+        let data = bincode::serialize(&target).unwrap();
+        let request = Message::new(target.clone(), Some(PeerId::new(vec![0xff; 32])), MessageType::FindNode, data);
+        dht.handle_request(&request).await.unwrap();
+        
+        let response = rx.recv().await.unwrap();
+        let closest: Vec<PeerInfo> = bincode::deserialize(&response.data).unwrap();
+        assert_eq!(closest.len(), 4);
+        assert!(closest.iter().all(|info| info.id != target));
     }
-} 
\ No newline at end of file
+    
+    #[tokio::test]
+    async fn paused_maintenance_does_not_republish() {
+        let network = mock_network(vec![(0x10, vec![])]);
+        let dht = KademliaDht::new(PeerId::new(vec![0xff; 32])).unwrap()
+            .with_maintenance_interval(Duration::from_millis(20))
+            .with_republish_interval(Duration::from_millis(20));
+        let mut dht = connect(dht, &network);
+        dht.add_peer(peer(0x10)).await.unwrap();
+        dht.store_local(b"key", b"value").unwrap();
+        let republished = || network.lock().unwrap()[&peer(0x10).id].values.contains_key(&b"key"[..]);
+        
+        dht.pause();
+        assert!(dht.is_paused());
+        dht.start().await.unwrap();
+        time::sleep(Duration::from_millis(150)).await;
+        assert!(!republished());
+        assert_eq!(dht.find_value(b"key").await.unwrap(), Some(b"value".to_vec()));
+        
+        dht.resume();
+        assert!(!dht.is_paused());
+        let started = Instant::now();
+        while !republished() && started.elapsed() < Duration::from_secs(2) {
+            time::sleep(Duration::from_millis(10)).await;
+        }
+        assert!(republished());
+        
+        dht.stop().await.unwrap();
+    }
+    
+    #[test]
+    fn local_id_of_wrong_length_is_rejected() {
+        assert!(KademliaDht::new(PeerId::new(Vec::new())).is_err());
+        assert!(KademliaDht::with_config(PeerId::new(vec![1; 16]), KademliaConfig::default()).is_err());
+    }
+}
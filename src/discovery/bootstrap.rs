@@ -0,0 +1,188 @@
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::sync::broadcast;
+
+use crate::error::{Error, Result};
+use crate::network::announce::Announcement;
+use crate::network::message::{Message, MessageType, SerializationFormat};
+use crate::transport::Transport;
+use crate::transport::tcp::TcpTransport;
+use crate::transport::websocket::WebSocketTransport;
+use crate::types::{Endpoint, PeerId, PeerInfo, TransportType};
+use super::Discovery;
+
+/// Таймаут ожидания ответа от начального узла по умолчанию
+const DEFAULT_SEED_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Механизм обнаружения по заданному списку начальных узлов
+///
+/// При каждом вызове `discover` опрашивает начальные узлы запросом
+/// `MessageType::Identify` и принимает в ответ подписанное объявление
+/// с информацией об узле. Недоступные узлы пропускаются.
+pub struct BootstrapDiscovery {
+    /// Идентификатор текущего узла
+    peer_id: PeerId,
+    /// Адреса начальных узлов
+    seeds: Vec<String>,
+    /// Таймаут ожидания ответа от одного начального узла
+    timeout: Duration,
+    /// Найденные узлы по адресу начального узла
+    discovered_peers: HashMap<String, PeerInfo>,
+    /// Запущен ли механизм обнаружения
+    started: bool,
+}
+
+impl BootstrapDiscovery {
+    /// Создать механизм обнаружения по списку адресов вида `хост:порт`
+    ///
+    /// Адреса с указанием схемы (`ws://хост:порт/путь`) опрашиваются
+    /// соответствующим транспортом.
+    pub fn new(peer_id: PeerId, seeds: Vec<String>) -> Self {
+        Self {
+            peer_id,
+            seeds,
+            timeout: DEFAULT_SEED_TIMEOUT,
+            discovered_peers: HashMap::new(),
+            started: false,
+        }
+    }
+    
+    /// Установить таймаут ожидания ответа от начального узла
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+    
+    /// Создать транспорт для опроса начального узла
+    fn transport_for(endpoint: &Endpoint) -> Result<Box<dyn Transport>> {
+        match endpoint.transport {
+            TransportType::Tcp => Ok(Box::new(TcpTransport::new())),
+            TransportType::WebSocket => Ok(Box::new(WebSocketTransport::new())),
+            TransportType::Custom(_) => Err(Error::Discovery(format!("Неподдерживаемый адрес начального узла: {}", endpoint))),
+        }
+    }
+    
+    /// Запросить информацию у начального узла
+    async fn identify(&self, seed: &str) -> Result<PeerInfo> {
+        let endpoint: Endpoint = seed.parse()?;
+        let mut transport = Self::transport_for(&endpoint)?;
+        
+        // Подписываемся до отправки, чтобы не пропустить ответ
+        let mut rx = transport.incoming();
+        
+        let request = Message::new(self.peer_id.clone(), None, MessageType::Identify, Vec::new());
+        let payload = request.to_bytes(SerializationFormat::default())?;
+        
+        let exchange = async {
+            transport.connect(&endpoint).await?;
+            transport.send_to(&endpoint, &payload).await?;
+            
+            loop {
+                match rx.recv().await {
+                    Ok((data, _)) => {
+                        let message = match Message::from_bytes(&data) {
+                            Ok(message) if message.message_type == MessageType::Announce => message,
+                            _ => continue,
+                        };
+                        
+                        let announcement = Announcement::from_bytes(&message.data)?;
+                        if announcement.info.id != message.from || !announcement.verify()? {
+                            return Err(Error::Discovery(format!("Неверное объявление от {}", seed)));
+                        }
+                        
+                        return Ok(announcement.info);
+                    }
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => {
+                        return Err(Error::Discovery(format!("Соединение с {} закрыто", seed)));
+                    }
+                }
+            }
+        };
+        
+        let result = tokio::time::timeout(self.timeout, exchange).await
+            .unwrap_or_else(|_| Err(Error::Timeout(format!("Нет ответа от {} за {:?}", seed, self.timeout))));
+        
+        let _ = transport.close().await;
+        result
+    }
+}
+
+#[async_trait]
+impl Discovery for BootstrapDiscovery {
+    fn name(&self) -> &str {
+        "bootstrap"
+    }
+    
+    async fn start(&mut self) -> Result<()> {
+        self.started = true;
+        Ok(())
+    }
+    
+    async fn stop(&mut self) -> Result<()> {
+        self.started = false;
+        Ok(())
+    }
+    
+    async fn discover(&mut self) -> Result<Vec<PeerInfo>> {
+        if !self.started {
+            return Err(Error::Discovery("Обнаружение по начальным узлам не запущено".to_string()));
+        }
+        
+        let this = &*self;
+        let results = futures::future::join_all(
+            this.seeds.iter().map(|seed| async move { (seed.clone(), this.identify(seed).await) })
+        ).await;
+        
+        for (seed, result) in results {
+            match result {
+                // Собственный адрес в списке начальных узлов не считаем находкой
+                Ok(info) if info.id == self.peer_id => {}
+                Ok(info) => {
+                    self.discovered_peers.insert(seed, info);
+                }
+                Err(e) => {
+                    tracing::warn!("Начальный узел {} недоступен: {}", seed, e);
+                }
+            }
+        }
+        
+        Ok(self.discovered_peers.values().cloned().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::{NetworkNode, Node};
+    
+    /// Найти свободный локальный порт
+    fn free_port() -> u16 {
+        std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port()
+    }
+    
+    #[tokio::test]
+    async fn running_node_is_discovered_via_its_address() {
+        let port = free_port();
+        let mut node = Node::builder()
+            .with_port(port)
+            .with_transport(TransportType::Tcp, Box::new(TcpTransport::new()))
+            .build()
+            .unwrap();
+        node.connect().await.unwrap();
+        let expected = node.local_info();
+        
+        // Недоступный начальный узел пропускается
+        let seeds = vec![format!("127.0.0.1:{}", port), format!("127.0.0.1:{}", free_port())];
+        let mut discovery = BootstrapDiscovery::new(PeerId::new(vec![9; 32]), seeds)
+            .with_timeout(Duration::from_secs(2));
+        assert!(discovery.discover().await.is_err());
+        discovery.start().await.unwrap();
+        
+        let found = discovery.discover().await.unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].id, expected.id);
+        assert_eq!(found[0].address, expected.address);
+    }
+}
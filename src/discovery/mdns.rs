@@ -1,5 +1,7 @@
 use async_trait::async_trait;
-use std::collections::HashSet;
+use futures::stream::{self, BoxStream, StreamExt};
+use mdns_sd::{ServiceDaemon, ServiceEvent, ServiceInfo};
+use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
 use tokio::sync::mpsc;
@@ -7,9 +9,18 @@ use tokio::task::JoinHandle;
 use tokio::time;
 
 use crate::error::{Error, Result};
-use crate::types::{PeerId, PeerInfo};
+use crate::types::{Endpoint, PeerId, PeerInfo};
 use super::Discovery;
 
+/// Ключ TXT записи с идентификатором узла
+const TXT_PEER_ID: &str = "peer_id";
+
+/// Ключ TXT записи с версией клиента
+const TXT_VERSION: &str = "version";
+
+/// Максимальная длина метки DNS
+const MAX_LABEL_LEN: usize = 63;
+
 /// Реализация механизма обнаружения на основе mDNS
 pub struct MdnsDiscovery {
     /// Идентификатор текущего узла
@@ -21,11 +32,13 @@ pub struct MdnsDiscovery {
     /// Интервал объявления в секундах
     announce_interval: u64,
     /// Найденные узлы
-    discovered_peers: Arc<Mutex<HashSet<PeerInfo>>>,
+    discovered_peers: Arc<Mutex<HashMap<PeerId, PeerInfo>>>,
     /// Задача объявления
     announce_task: Option<JoinHandle<()>>,
     /// Задача обнаружения
     discovery_task: Option<JoinHandle<()>>,
+    /// Демон mDNS, работающий пока запущен механизм обнаружения
+    daemon: Option<ServiceDaemon>,
     /// Канал для получения обнаруженных узлов
     discovery_rx: mpsc::Receiver<PeerInfo>,
     /// Канал для отправки обнаруженных узлов
@@ -44,9 +57,10 @@ impl MdnsDiscovery {
             service_name: "noxy".to_string(),
             port,
             announce_interval: 30,
-            discovered_peers: Arc::new(Mutex::new(HashSet::new())),
+            discovered_peers: Arc::new(Mutex::new(HashMap::new())),
             announce_task: None,
             discovery_task: None,
+            daemon: None,
             discovery_rx,
             discovery_tx,
             started: false,
@@ -65,26 +79,84 @@ impl MdnsDiscovery {
         self
     }
     
+    /// Зарегистрировать обнаруженный узел
+    ///
+    /// Новые узлы сразу передаются в поток `discovered_stream`.
+    fn record_peer(
+        discovered_peers: &Mutex<HashMap<PeerId, PeerInfo>>,
+        tx: &mpsc::Sender<PeerInfo>,
+        peer: PeerInfo,
+    ) {
+        let is_new = match discovered_peers.lock() {
+            Ok(mut peers) => peers.insert(peer.id.clone(), peer.clone()).is_none(),
+            Err(_) => return,
+        };
+        
+        if is_new {
+            // Если получатель не успевает, узел все равно останется в discovered_peers
+            let _ = tx.try_send(peer);
+        }
+    }
+    
+    /// Тип сервиса mDNS, например `_noxy._udp.local.`
+    fn service_type(&self) -> String {
+        format!("_{}._udp.local.", self.service_name)
+    }
+    
+    /// Построить описание сервиса текущего узла
+    fn service_info(&self) -> Result<ServiceInfo> {
+        // Имя экземпляра должно помещаться в одну метку DNS
+        let mut instance = hex::encode(self.peer_id.as_bytes());
+        instance.truncate(MAX_LABEL_LEN);
+        let host_name = format!("{}.local.", instance);
+        
+        let mut properties = HashMap::new();
+        properties.insert(TXT_PEER_ID.to_string(), hex::encode(self.peer_id.as_bytes()));
+        properties.insert(TXT_VERSION.to_string(), format!("noxy/{}", crate::VERSION));
+        
+        ServiceInfo::new(&self.service_type(), &instance, &host_name, "", self.port, properties)
+            .map(ServiceInfo::enable_addr_auto)
+            .map_err(|e| Error::Discovery(format!("Некорректное описание сервиса mDNS: {}", e)))
+    }
+    
+    /// Преобразовать найденный сервис в информацию об узле
+    ///
+    /// Сервисы без корректного идентификатора или адреса пропускаются.
+    fn peer_from_service(info: &ServiceInfo) -> Option<PeerInfo> {
+        let id = hex::decode(info.get_property_val_str(TXT_PEER_ID)?).ok()?;
+        let host = info.get_addresses().iter().next()?.to_string();
+        let client_version = info.get_property_val_str(TXT_VERSION).unwrap_or_default().to_string();
+        
+        Some(PeerInfo {
+            id: PeerId::try_new(id).ok()?,
+            address: Some(Endpoint::tcp(host, info.get_port())),
+            protocols: vec!["tcp".to_string()],
+            client_version,
+        })
+    }
+    
     /// Запустить задачу объявления
-    fn start_announce_task(&mut self) -> Result<()> {
-        let peer_id = self.peer_id.clone();
-        let service_name = self.service_name.clone();
-        let port = self.port;
-        let interval = self.announce_interval;
+    ///
+    /// Демон отвечает на запросы сам; задача лишь периодически повторяет
+    /// регистрацию, чтобы объявление не терялось в сетях с потерей пакетов.
+    fn start_announce_task(&mut self, daemon: ServiceDaemon) -> Result<()> {
+        let info = self.service_info()?;
+        daemon.register(info.clone())
+            .map_err(|e| Error::Discovery(format!("Не удалось зарегистрировать сервис mDNS: {}", e)))?;
         
-        // В реальной реализации здесь будет код для взаимодействия с mDNS через libp2p
-        // Для упрощения примера используем заглушку
+        let interval = self.announce_interval.max(1);
         
         self.announce_task = Some(tokio::spawn(async move {
             let mut interval = time::interval(Duration::from_secs(interval));
+            // Первый тик срабатывает сразу, а регистрация уже выполнена
+            interval.tick().await;
             
             loop {
                 interval.tick().await;
                 
-                // Отправляем объявление через mDNS
-                // (заглушка)
-                println!("Отправлено mDNS объявление для {}/{} на порту {}", 
-                         service_name, peer_id, port);
+                if let Err(e) = daemon.register(info.clone()) {
+                    tracing::debug!("Не удалось повторить объявление mDNS: {}", e);
+                }
             }
         }));
         
@@ -92,28 +164,25 @@ impl MdnsDiscovery {
     }
     
     /// Запустить задачу обнаружения
-    fn start_discovery_task(&mut self) -> Result<()> {
-        let service_name = self.service_name.clone();
+    fn start_discovery_task(&mut self, daemon: &ServiceDaemon) -> Result<()> {
+        let receiver = daemon.browse(&self.service_type())
+            .map_err(|e| Error::Discovery(format!("Не удалось начать поиск сервисов mDNS: {}", e)))?;
+        
+        let local_id = self.peer_id.clone();
         let tx = self.discovery_tx.clone();
         let discovered_peers = Arc::clone(&self.discovered_peers);
         
-        // В реальной реализации здесь будет код для прослушивания mDNS через libp2p
-        // Для упрощения примера используем заглушку
-        
         self.discovery_task = Some(tokio::spawn(async move {
-            // Имитация обнаружения узлов
-            let mut interval = time::interval(Duration::from_secs(5));
-            
-            loop {
-                interval.tick().await;
-                
-                // Эмулируем обнаружение нового узла
-                // В реальной реализации здесь будет обработка mDNS ответов
-                
-                // (заглушка для примера)
-                // Добавляем в список только для тестирования
-                if let Ok(mut peers) = discovered_peers.lock() {
-                    // В реальности здесь будет обработка ответов от mDNS
+            while let Ok(event) = receiver.recv_async().await {
+                if let ServiceEvent::ServiceResolved(info) = event {
+                    match Self::peer_from_service(&info) {
+                        // Собственное объявление узла игнорируем
+                        Some(peer) if peer.id == local_id => {}
+                        Some(peer) => Self::record_peer(&discovered_peers, &tx, peer),
+                        None => {
+                            tracing::debug!("Пропущен сервис mDNS без идентификатора узла: {}", info.get_fullname());
+                        }
+                    }
                 }
             }
         }));
@@ -133,12 +202,22 @@ impl Discovery for MdnsDiscovery {
             return Ok(());
         }
         
+        let daemon = ServiceDaemon::new()
+            .map_err(|e| Error::Discovery(format!("Не удалось запустить демон mDNS: {}", e)))?;
+        
         // Запускаем задачу объявления
-        self.start_announce_task()?;
+        self.start_announce_task(daemon.clone())?;
         
         // Запускаем задачу обнаружения
-        self.start_discovery_task()?;
+        if let Err(e) = self.start_discovery_task(&daemon) {
+            if let Some(task) = self.announce_task.take() {
+                task.abort();
+            }
+            let _ = daemon.shutdown();
+            return Err(e);
+        }
         
+        self.daemon = Some(daemon);
         self.started = true;
         Ok(())
     }
@@ -158,6 +237,14 @@ impl Discovery for MdnsDiscovery {
             task.abort();
         }
         
+        // Отзываем объявление и останавливаем демон
+        if let Some(daemon) = self.daemon.take() {
+            if let Ok(info) = self.service_info() {
+                let _ = daemon.unregister(info.get_fullname());
+            }
+            let _ = daemon.shutdown();
+        }
+        
         self.started = false;
         Ok(())
     }
@@ -171,6 +258,91 @@ impl Discovery for MdnsDiscovery {
         let peers = self.discovered_peers.lock()
             .map_err(|_| Error::Discovery("Не удалось получить блокировку discovered_peers".to_string()))?;
         
-        Ok(peers.iter().cloned().collect())
+        Ok(peers.values().cloned().collect())
+    }
+    
+    fn discovered_stream(&mut self) -> BoxStream<'_, PeerInfo> {
+        let rx = &mut self.discovery_rx;
+        stream::poll_fn(move |cx| rx.poll_recv(cx)).boxed()
+    }
+} 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::StreamExt;
+    
+    #[tokio::test]
+    async fn new_peer_is_pushed_to_stream() {
+        let mut discovery = MdnsDiscovery::new(PeerId::new(vec![1; 32]), 9000);
+        let discovered_peers = Arc::clone(&discovery.discovered_peers);
+        let tx = discovery.discovery_tx.clone();
+        let peer = PeerInfo {
+            id: PeerId::new(vec![2; 32]),
+            address: None,
+            protocols: Vec::new(),
+            client_version: "test".to_string(),
+        };
+        let expected = peer.id.clone();
+        
+        tokio::spawn(async move {
+            time::sleep(Duration::from_millis(50)).await;
+            MdnsDiscovery::record_peer(&discovered_peers, &tx, peer.clone());
+            // Повторно найденный узел в поток не попадает
+            MdnsDiscovery::record_peer(&discovered_peers, &tx, peer);
+        });
+        
+        let mut stream = discovery.discovered_stream();
+        let found = time::timeout(Duration::from_secs(1), stream.next()).await.unwrap().unwrap();
+        assert_eq!(found.id, expected);
+        assert!(time::timeout(Duration::from_millis(100), stream.next()).await.is_err());
+    }
+    
+    /// Требует сети с поддержкой multicast, поэтому по умолчанию не запускается
+    #[tokio::test]
+    #[ignore]
+    async fn two_instances_discover_each_other() {
+        let service = format!("noxytest{}", std::process::id());
+        let mut alice = MdnsDiscovery::new(PeerId::new(vec![1; 32]), 9201).with_service_name(service.clone());
+        let mut bob = MdnsDiscovery::new(PeerId::new(vec![2; 32]), 9202).with_service_name(service);
+        alice.start().await.unwrap();
+        bob.start().await.unwrap();
+        
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(10);
+        loop {
+            let seen_by_alice = alice.discover().await.unwrap();
+            let seen_by_bob = bob.discover().await.unwrap();
+            if seen_by_alice.iter().any(|p| p.id == bob.peer_id) && seen_by_bob.iter().any(|p| p.id == alice.peer_id) {
+                assert!(seen_by_alice.iter().all(|p| p.id != alice.peer_id));
+                break;
+            }
+            assert!(tokio::time::Instant::now() < deadline, "Узлы не обнаружили друг друга");
+            time::sleep(Duration::from_millis(100)).await;
+        }
+        
+        alice.stop().await.unwrap();
+        bob.stop().await.unwrap();
+    }
+    
+    #[test]
+    fn advertised_service_describes_peer() {
+        let peer_id = PeerId::new(vec![7; 32]);
+        let discovery = MdnsDiscovery::new(peer_id.clone(), 9100);
+        let info = discovery.service_info().unwrap();
+        assert_eq!(info.get_type(), "_noxy._udp.local.");
+        assert_eq!(info.get_port(), 9100);
+        assert_eq!(info.get_property_val_str(TXT_PEER_ID), Some(hex::encode(peer_id.as_bytes()).as_str()));
+        
+        // Ответ с адресом преобразуется в запись об узле
+        let mut properties = HashMap::new();
+        properties.insert(TXT_PEER_ID.to_string(), hex::encode(peer_id.as_bytes()));
+        let resolved = ServiceInfo::new("_noxy._udp.local.", "peer", "peer.local.", "127.0.0.1", 9100, properties).unwrap();
+        let peer = MdnsDiscovery::peer_from_service(&resolved).unwrap();
+        assert_eq!(peer.id, peer_id);
+        assert_eq!(peer.address, Some(Endpoint::tcp("127.0.0.1", 9100)));
+        
+        // Сервис без идентификатора пропускается
+        let anonymous = ServiceInfo::new("_noxy._udp.local.", "anon", "anon.local.", "127.0.0.1", 9100, HashMap::<String, String>::new()).unwrap();
+        assert!(MdnsDiscovery::peer_from_service(&anonymous).is_none());
     }
-} 
\ No newline at end of file
+}
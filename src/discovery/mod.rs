@@ -1,7 +1,13 @@
 use async_trait::async_trait;
+use futures::stream::{self, BoxStream, StreamExt};
+use std::collections::{HashSet, VecDeque};
+use std::time::Duration;
 use crate::error::Result;
 use crate::types::PeerInfo;
 
+/// Интервал опроса `discover` в потоке обнаруженных узлов по умолчанию
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
 /// Трейт для механизмов обнаружения узлов
 #[async_trait]
 pub trait Discovery: Send + Sync {
@@ -16,6 +22,37 @@ pub trait Discovery: Send + Sync {
     
     /// Обнаружить узлы в сети
     async fn discover(&mut self) -> Result<Vec<PeerInfo>>;
+    
+    /// Получить поток вновь обнаруженных узлов
+    ///
+    /// Реализация по умолчанию периодически вызывает `discover` и выдает
+    /// только узлы, которые еще не встречались. Механизмы, умеющие сообщать
+    /// о находках сразу, должны переопределить этот метод.
+    fn discovered_stream(&mut self) -> BoxStream<'_, PeerInfo> {
+        let state = (self, HashSet::new(), VecDeque::new());
+        
+        stream::unfold(state, |(discovery, mut seen, mut pending)| async move {
+            loop {
+                if let Some(peer) = pending.pop_front() {
+                    return Some((peer, (discovery, seen, pending)));
+                }
+                
+                // Ошибки отдельного опроса не прерывают поток
+                if let Ok(peers) = discovery.discover().await {
+                    for peer in peers {
+                        if seen.insert(peer.id.clone()) {
+                            pending.push_back(peer);
+                        }
+                    }
+                }
+                
+                if pending.is_empty() {
+                    tokio::time::sleep(DEFAULT_POLL_INTERVAL).await;
+                }
+            }
+        })
+        .boxed()
+    }
 }
 
 pub mod mdns;
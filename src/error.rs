@@ -40,6 +40,10 @@ pub enum Error {
     #[error("Ошибка хранилища: {0}")]
     Storage(String),
 
+    /// Истекло время ожидания
+    #[error("Истекло время ожидания: {0}")]
+    Timeout(String),
+
     /// Неизвестная ошибка
     #[error("Неизвестная ошибка: {0}")]
     Unknown(String),
@@ -6,6 +6,7 @@
 //!
 //! ```rust,no_run
 //! use noxy::prelude::*;
+//! use tokio_stream::StreamExt;
 //! use tokio;
 //!
 //! #[tokio::main]
@@ -111,10 +112,14 @@ pub mod types;
 
 /// Re-exports of main components for convenience
 pub mod prelude {
-    pub use crate::network::{Node, NodeBuilder};
-    pub use crate::network::message::Message;
+    pub use crate::network::{Node, NodeBuilder, NodeMode, NetworkNode, BroadcastReport};
+    pub use crate::network::incoming::IncomingMessages;
+    pub use crate::network::pubsub::PubSub;
+    pub use crate::network::peer::PeerEvent;
+    pub use crate::network::stats::NodeStats;
+    pub use crate::network::message::{Message, SerializationFormat};
     pub use crate::error::Error;
-    pub use crate::types::PeerId;
+    pub use crate::types::{PeerId, Endpoint};
     
     // Blockchain components
     pub use crate::blockchain::{Block, Transaction, Blockchain};
@@ -0,0 +1,113 @@
+ use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use serde::{Serialize, Deserialize};
+
+use crate::crypto::{Key, Signer};
+use crate::crypto::ed25519::Ed25519KeyPair;
+use crate::error::{Error, Result};
+use crate::types::PeerInfo;
+
+/// Допустимое опережение часов объявляющего узла
+///
+/// Объявления с временной меткой дальше в будущем отвергаются.
+pub const MAX_CLOCK_SKEW: Duration = Duration::from_secs(30);
+
+/// Подписанное объявление о присутствии узла
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Announcement {
+    /// Информация об объявляющем узле
+    pub info: PeerInfo,
+    /// Публичный ключ Ed25519 узла
+    pub public_key: Vec<u8>,
+    /// Временная метка объявления в миллисекундах
+    pub timestamp: u64,
+    /// Подпись информации, ключа и временной метки
+    pub signature: Vec<u8>,
+}
+
+impl Announcement {
+    /// Создать и подписать объявление
+    pub fn new_signed(info: PeerInfo, keypair: &Ed25519KeyPair) -> Result<Self> {
+        let timestamp = now_millis();
+        
+        let public_key = keypair.public_bytes();
+        let signature = keypair.sign(&Self::signing_bytes(&info, &public_key, timestamp)?)?;
+        
+        Ok(Self {
+            info,
+            public_key,
+            timestamp,
+            signature,
+        })
+    }
+    
+    /// Проверить подпись объявления
+    pub fn verify(&self) -> Result<bool> {
+        let verifier = Ed25519KeyPair::from_public_key(&self.public_key)?;
+        let data = Self::signing_bytes(&self.info, &self.public_key, self.timestamp)?;
+        verifier.verify(&data, &self.signature)
+    }
+    
+    /// Опережает ли временная метка объявления текущее время больше чем на `MAX_CLOCK_SKEW`
+    pub fn is_from_future(&self) -> bool {
+        self.timestamp > now_millis().saturating_add(MAX_CLOCK_SKEW.as_millis() as u64)
+    }
+    
+    /// Сериализовать объявление
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        bincode::serialize(self)
+            .map_err(|e| Error::Serialization(format!("Не удалось сериализовать объявление: {}", e)))
+    }
+    
+    /// Десериализовать объявление
+    pub fn from_bytes(data: &[u8]) -> Result<Self> {
+        bincode::deserialize(data)
+            .map_err(|e| Error::Serialization(format!("Не удалось десериализовать объявление: {}", e)))
+    }
+    
+    /// Данные для подписи
+    fn signing_bytes(info: &PeerInfo, public_key: &[u8], timestamp: u64) -> Result<Vec<u8>> {
+        bincode::serialize(&(info, public_key, timestamp))
+            .map_err(|e| Error::Serialization(format!("Не удалось сериализовать объявление: {}", e)))
+    }
+}
+
+/// Текущее время в миллисекундах от начала эпохи
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("Время до начала эпохи")
+        .as_millis() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::PeerId;
+    
+    fn signed_at(keypair: &Ed25519KeyPair, timestamp: u64) -> Announcement {
+        let info = PeerInfo {
+            id: PeerId::from_public_key(&keypair.public_bytes()),
+            address: None,
+            protocols: Vec::new(),
+            client_version: "test".to_string(),
+        };
+        let public_key = keypair.public_bytes();
+        let signature = keypair.sign(&Announcement::signing_bytes(&info, &public_key, timestamp).unwrap()).unwrap();
+        Announcement { info, public_key, timestamp, signature }
+    }
+    
+    #[test]
+    fn timestamp_beyond_clock_skew_is_from_future() {
+        let keypair = Ed25519KeyPair::generate().unwrap();
+        let skew = MAX_CLOCK_SKEW.as_millis() as u64;
+        
+        let fresh = signed_at(&keypair, now_millis() + skew / 2);
+        assert!(fresh.verify().unwrap());
+        assert!(!fresh.is_from_future());
+        
+        // Подпись верна, но метка слишком далеко в будущем
+        let future = signed_at(&keypair, now_millis() + 2 * skew);
+        assert!(future.verify().unwrap());
+        assert!(future.is_from_future());
+    }
+}
@@ -0,0 +1,244 @@
+use std::collections::{BTreeMap, HashMap};
+use std::time::{Duration, Instant};
+use serde::{Serialize, Deserialize};
+
+use crate::error::{Error, Result};
+use crate::types::PeerId;
+use super::message::MessageType;
+
+/// Размер данных одной части по умолчанию
+///
+/// Оставляет запас под заголовок сообщения и части в кадре 64 КБ.
+pub const DEFAULT_CHUNK_SIZE: usize = 60 * 1024;
+
+/// Время ожидания недостающих частей передачи по умолчанию
+pub const DEFAULT_REASSEMBLY_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Максимальный размер собранных данных одной передачи
+pub const MAX_TRANSFER_SIZE: usize = 64 * 1024 * 1024;
+
+/// Максимальное количество одновременно собираемых передач
+const MAX_PENDING_TRANSFERS: usize = 256;
+
+/// Часть данных, разделенных для передачи по сети
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Chunk {
+    /// Идентификатор передачи, общий для всех ее частей
+    pub transfer_id: [u8; 16],
+    /// Порядковый номер части, начиная с нуля
+    pub index: u32,
+    /// Общее количество частей передачи
+    pub total: u32,
+    /// Тип собранного сообщения
+    pub message_type: MessageType,
+    /// Данные части
+    pub data: Vec<u8>,
+}
+
+impl Chunk {
+    /// Разделить данные на части не длиннее `chunk_size` байт
+    ///
+    /// Пустые данные передаются одной пустой частью.
+    pub fn split(transfer_id: [u8; 16], message_type: MessageType, data: &[u8], chunk_size: usize) -> Result<Vec<Self>> {
+        if data.len() > MAX_TRANSFER_SIZE {
+            return Err(Error::Network(format!(
+                "Размер данных {} превышает максимальный размер передачи {}", data.len(), MAX_TRANSFER_SIZE
+            )));
+        }
+        
+        let chunk_size = chunk_size.max(1);
+        let total = data.len().div_ceil(chunk_size).max(1) as u32;
+        
+        let mut chunks = data.chunks(chunk_size)
+            .enumerate()
+            .map(|(index, part)| Self {
+                transfer_id,
+                index: index as u32,
+                total,
+                message_type,
+                data: part.to_vec(),
+            })
+            .collect::<Vec<_>>();
+        
+        if chunks.is_empty() {
+            chunks.push(Self {
+                transfer_id,
+                index: 0,
+                total,
+                message_type,
+                data: Vec::new(),
+            });
+        }
+        
+        Ok(chunks)
+    }
+    
+    /// Сериализовать часть
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        bincode::serialize(self)
+            .map_err(|e| Error::Serialization(format!("Не удалось сериализовать часть данных: {}", e)))
+    }
+    
+    /// Десериализовать часть
+    pub fn from_bytes(data: &[u8]) -> Result<Self> {
+        bincode::deserialize(data)
+            .map_err(|e| Error::Serialization(format!("Не удалось десериализовать часть данных: {}", e)))
+    }
+}
+
+/// Незавершенная передача
+#[derive(Debug)]
+struct Transfer {
+    /// Тип собранного сообщения
+    message_type: MessageType,
+    /// Общее количество частей
+    total: u32,
+    /// Полученные части по порядковому номеру
+    parts: BTreeMap<u32, Vec<u8>>,
+    /// Суммарный размер полученных данных
+    size: usize,
+    /// Время получения первой части
+    started: Instant,
+}
+
+/// Сборщик разделенных на части сообщений
+///
+/// Части принимаются в любом порядке; повторно полученные части
+/// игнорируются. Передачи, не собранные за время ожидания, отбрасываются.
+#[derive(Debug)]
+pub struct Reassembler {
+    /// Время ожидания недостающих частей
+    timeout: Duration,
+    /// Незавершенные передачи по отправителю и идентификатору передачи
+    transfers: HashMap<(PeerId, [u8; 16]), Transfer>,
+}
+
+impl Reassembler {
+    /// Создать сборщик с заданным временем ожидания
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            timeout,
+            transfers: HashMap::new(),
+        }
+    }
+    
+    /// Принять часть от отправителя
+    ///
+    /// Возвращает тип и данные сообщения, когда получены все его части.
+    /// Части с неверным номером, противоречащие уже полученным или
+    /// превышающие `MAX_TRANSFER_SIZE`, отклоняются вместе со всей передачей.
+    pub fn insert(&mut self, from: &PeerId, chunk: Chunk) -> Result<Option<(MessageType, Vec<u8>)>> {
+        let now = Instant::now();
+        self.prune(now);
+        
+        if chunk.total == 0 || chunk.index >= chunk.total {
+            return Err(Error::Network(format!(
+                "Неверный номер части {} из {}", chunk.index, chunk.total
+            )));
+        }
+        
+        let key = (from.clone(), chunk.transfer_id);
+        if !self.transfers.contains_key(&key) {
+            if self.transfers.len() >= MAX_PENDING_TRANSFERS {
+                return Err(Error::Network("Слишком много незавершенных передач".to_string()));
+            }
+            self.transfers.insert(key.clone(), Transfer {
+                message_type: chunk.message_type,
+                total: chunk.total,
+                parts: BTreeMap::new(),
+                size: 0,
+                started: now,
+            });
+        }
+        
+        let transfer = self.transfers.get_mut(&key).expect("Передача только что добавлена");
+        if transfer.total != chunk.total || transfer.message_type != chunk.message_type {
+            self.transfers.remove(&key);
+            return Err(Error::Network("Часть не соответствует передаче".to_string()));
+        }
+        
+        if transfer.parts.contains_key(&chunk.index) {
+            return Ok(None);
+        }
+        
+        transfer.size += chunk.data.len();
+        if transfer.size > MAX_TRANSFER_SIZE {
+            self.transfers.remove(&key);
+            return Err(Error::Network(format!(
+                "Размер передачи превышает максимальный размер {}", MAX_TRANSFER_SIZE
+            )));
+        }
+        
+        transfer.parts.insert(chunk.index, chunk.data);
+        
+        if transfer.parts.len() < transfer.total as usize {
+            return Ok(None);
+        }
+        
+        let transfer = self.transfers.remove(&key).expect("Передача найдена выше");
+        let mut data = Vec::with_capacity(transfer.size);
+        for part in transfer.parts.into_values() {
+            data.extend_from_slice(&part);
+        }
+        
+        Ok(Some((transfer.message_type, data)))
+    }
+    
+    /// Количество незавершенных передач
+    pub fn pending(&self) -> usize {
+        self.transfers.len()
+    }
+    
+    /// Отбросить передачи, не собранные за время ожидания
+    fn prune(&mut self, now: Instant) {
+        let timeout = self.timeout;
+        self.transfers.retain(|(from, _), transfer| {
+            let alive = now.duration_since(transfer.started) < timeout;
+            if !alive {
+                tracing::debug!("Отброшена незавершенная передача от {}", from);
+            }
+            alive
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn chunks_reassemble_in_any_order() {
+        let data: Vec<u8> = (0..200_000u32).map(|i| i as u8).collect();
+        let mut chunks = Chunk::split([7; 16], MessageType::Data, &data, 64 * 1024).unwrap();
+        assert_eq!(chunks.len(), 4);
+        assert!(chunks.iter().all(|chunk| chunk.data.len() <= 64 * 1024));
+        
+        let from = PeerId::new(vec![1; 32]);
+        let mut reassembler = Reassembler::new(Duration::from_secs(1));
+        let first = chunks.remove(0);
+        for chunk in chunks.into_iter().rev() {
+            assert!(reassembler.insert(&from, chunk.clone()).unwrap().is_none());
+            // Повторная часть игнорируется
+            assert!(reassembler.insert(&from, chunk).unwrap().is_none());
+        }
+        assert_eq!(reassembler.pending(), 1);
+        
+        let (message_type, assembled) = reassembler.insert(&from, first).unwrap().unwrap();
+        assert_eq!(message_type, MessageType::Data);
+        assert_eq!(assembled, data);
+        assert_eq!(reassembler.pending(), 0);
+    }
+    
+    #[test]
+    fn stale_transfer_is_dropped() {
+        let chunks = Chunk::split([1; 16], MessageType::Data, &[0u8; 100], 40).unwrap();
+        let from = PeerId::new(vec![1; 32]);
+        let mut reassembler = Reassembler::new(Duration::from_millis(20));
+        assert!(reassembler.insert(&from, chunks[0].clone()).unwrap().is_none());
+        
+        std::thread::sleep(Duration::from_millis(40));
+        assert!(reassembler.insert(&from, chunks[1].clone()).unwrap().is_none());
+        assert!(reassembler.insert(&from, chunks[2].clone()).unwrap().is_none());
+        assert_eq!(reassembler.pending(), 1);
+    }
+}
@@ -0,0 +1,101 @@
+use serde::{Serialize, Deserialize};
+
+use crate::crypto::{Key, Signer};
+use crate::crypto::ed25519::Ed25519KeyPair;
+use crate::error::{Error, Result};
+use crate::types::PeerId;
+
+/// Размер случайного вызова в байтах
+pub const CHALLENGE_LEN: usize = 32;
+
+/// Префикс подписываемых данных, отделяющий подписи ответов от других подписей узла
+const DOMAIN: &[u8] = b"noxy/handshake/v1";
+
+/// Вызов, который узел должен подписать своим приватным ключом
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Challenge {
+    /// Случайное значение
+    pub nonce: [u8; CHALLENGE_LEN],
+}
+
+impl Challenge {
+    /// Создать вызов со случайным значением
+    pub fn random() -> Self {
+        let mut nonce = [0u8; CHALLENGE_LEN];
+        rand::Rng::fill(&mut rand::thread_rng(), &mut nonce);
+        Self { nonce }
+    }
+    
+    /// Сериализовать вызов
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        bincode::serialize(self)
+            .map_err(|e| Error::Serialization(format!("Не удалось сериализовать вызов: {}", e)))
+    }
+    
+    /// Десериализовать вызов
+    pub fn from_bytes(data: &[u8]) -> Result<Self> {
+        bincode::deserialize(data)
+            .map_err(|e| Error::Serialization(format!("Не удалось десериализовать вызов: {}", e)))
+    }
+}
+
+/// Ответ на вызов, доказывающий владение ключом заявленного идентификатора
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChallengeResponse {
+    /// Заявленный идентификатор узла
+    pub peer_id: PeerId,
+    /// Публичный ключ Ed25519 узла
+    pub public_key: Vec<u8>,
+    /// Подпись вызова и идентификатора
+    pub signature: Vec<u8>,
+}
+
+impl ChallengeResponse {
+    /// Подписать вызов ключом узла
+    pub fn sign(challenge: &Challenge, peer_id: PeerId, keypair: &Ed25519KeyPair) -> Result<Self> {
+        let signature = keypair.sign(&Self::signing_bytes(challenge, &peer_id))?;
+        
+        Ok(Self {
+            peer_id,
+            public_key: keypair.public_bytes(),
+            signature,
+        })
+    }
+    
+    /// Проверить ответ на выданный вызов
+    ///
+    /// Ответ принимается, только если идентификатор совпадает с ожидаемым,
+    /// выведен из приложенного публичного ключа и подпись вызова верна.
+    pub fn verify(&self, challenge: &Challenge, expected: &PeerId) -> Result<bool> {
+        if self.peer_id != *expected || PeerId::from_public_key(&self.public_key) != self.peer_id {
+            return Ok(false);
+        }
+        
+        let verifier = match Ed25519KeyPair::from_public_key(&self.public_key) {
+            Ok(verifier) => verifier,
+            Err(_) => return Ok(false),
+        };
+        verifier.verify(&Self::signing_bytes(challenge, &self.peer_id), &self.signature)
+    }
+    
+    /// Сериализовать ответ
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        bincode::serialize(self)
+            .map_err(|e| Error::Serialization(format!("Не удалось сериализовать ответ на вызов: {}", e)))
+    }
+    
+    /// Десериализовать ответ
+    pub fn from_bytes(data: &[u8]) -> Result<Self> {
+        bincode::deserialize(data)
+            .map_err(|e| Error::Serialization(format!("Не удалось десериализовать ответ на вызов: {}", e)))
+    }
+    
+    /// Данные для подписи
+    fn signing_bytes(challenge: &Challenge, peer_id: &PeerId) -> Vec<u8> {
+        let mut data = Vec::with_capacity(DOMAIN.len() + CHALLENGE_LEN + peer_id.as_bytes().len());
+        data.extend_from_slice(DOMAIN);
+        data.extend_from_slice(&challenge.nonce);
+        data.extend_from_slice(peer_id.as_bytes());
+        data
+    }
+}
@@ -0,0 +1,121 @@
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use futures::stream::Stream;
+use tokio::sync::broadcast;
+use tokio_stream::wrappers::errors::BroadcastStreamRecvError;
+use tokio_stream::wrappers::BroadcastStream;
+
+use super::message::Message;
+
+/// Поток входящих сообщений узла
+///
+/// Незавершенное ожидание хранится внутри потока, а не в future `next()`,
+/// поэтому отмена `next()` (например, в ветке `tokio::select!`, которая
+/// не была выбрана) не теряет сообщений: следующий вызов продолжит с того же места.
+///
+/// Если подписчик отстал и часть сообщений была вытеснена из канала, поток
+/// пропускает их, учитывает в [`IncomingMessages::lagged`] и продолжает
+/// с самого старого доступного сообщения. Поток завершается, когда узел удален.
+pub struct IncomingMessages {
+    /// Подписка на широковещательный канал входящих сообщений
+    inner: BroadcastStream<Message>,
+    /// Получатель для создания новых подписок
+    resubscribe_from: broadcast::Receiver<Message>,
+    /// Количество пропущенных из-за отставания сообщений
+    lagged: u64,
+}
+
+impl IncomingMessages {
+    /// Создать поток из подписки на канал входящих сообщений
+    pub(crate) fn new(rx: broadcast::Receiver<Message>) -> Self {
+        Self {
+            resubscribe_from: rx.resubscribe(),
+            inner: BroadcastStream::new(rx),
+            lagged: 0,
+        }
+    }
+    
+    /// Количество сообщений, пропущенных из-за отставания подписчика
+    pub fn lagged(&self) -> u64 {
+        self.lagged
+    }
+    
+    /// Создать независимый поток, получающий сообщения, поступившие после вызова
+    pub fn resubscribe(&self) -> Self {
+        Self::new(self.resubscribe_from.resubscribe())
+    }
+}
+
+impl Stream for IncomingMessages {
+    type Item = Message;
+    
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            match Pin::new(&mut self.inner).poll_next(cx) {
+                Poll::Ready(Some(Ok(message))) => return Poll::Ready(Some(message)),
+                Poll::Ready(Some(Err(BroadcastStreamRecvError::Lagged(skipped)))) => {
+                    tracing::warn!("Пропущено {} входящих сообщений из-за отставания подписчика", skipped);
+                    self.lagged = self.lagged.saturating_add(skipped);
+                }
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use futures::StreamExt;
+    use crate::network::message::MessageType;
+    use crate::types::PeerId;
+    
+    fn message(n: u8) -> Message {
+        Message::new(PeerId::new(vec![1; 32]), None, MessageType::Data, vec![n])
+    }
+    
+    #[tokio::test]
+    async fn no_messages_lost_across_select() {
+        let (tx, rx) = broadcast::channel(64);
+        let mut incoming = IncomingMessages::new(rx);
+        
+        tokio::spawn(async move {
+            for n in 0..50 {
+                tx.send(message(n)).unwrap();
+                if n % 5 == 0 {
+                    tokio::time::sleep(Duration::from_millis(3)).await;
+                }
+            }
+        });
+        
+        let mut received = Vec::new();
+        let mut ticks = 0;
+        while received.len() < 50 {
+            tokio::select! {
+                message = incoming.next() => received.push(message.unwrap().data[0]),
+                // Таймер часто выигрывает и отменяет ожидание next()
+                _ = tokio::time::sleep(Duration::from_micros(500)) => ticks += 1,
+            }
+            assert!(ticks < 10_000, "Сообщения перестали поступать");
+        }
+        
+        assert_eq!(received, (0..50).collect::<Vec<u8>>());
+        assert_eq!(incoming.lagged(), 0);
+    }
+    
+    #[tokio::test]
+    async fn lagging_subscriber_skips_and_counts() {
+        let (tx, rx) = broadcast::channel(4);
+        let mut incoming = IncomingMessages::new(rx);
+        for n in 0..10 {
+            tx.send(message(n)).unwrap();
+        }
+        drop(tx);
+        
+        let received: Vec<u8> = incoming.by_ref().map(|message| message.data[0]).collect().await;
+        assert_eq!(received, vec![6, 7, 8, 9]);
+        assert_eq!(incoming.lagged(), 6);
+    }
+}
@@ -1,6 +1,63 @@
 use std::time::{SystemTime, UNIX_EPOCH};
 use serde::{Serialize, Deserialize};
-use crate::types::PeerId;
+use serde::de::DeserializeOwned;
+use crate::crypto::Signer;
+use crate::crypto::ed25519::Ed25519KeyPair;
+use crate::error::{Error, Result};
+use crate::types::{Endpoint, PeerId};
+
+/// Начальное количество пересылок сообщения по умолчанию
+pub const DEFAULT_TTL: u8 = 8;
+
+/// Размер данных, начиная с которого `new_compressed` сжимает их
+pub const DEFAULT_COMPRESSION_THRESHOLD: usize = 512;
+
+/// Максимальный размер данных после распаковки
+pub const MAX_DECOMPRESSED_SIZE: usize = 16 * 1024 * 1024;
+
+/// Уровень сжатия zstd
+const COMPRESSION_LEVEL: i32 = 3;
+
+/// Значение TTL для сообщений, сериализованных до появления поля
+fn default_ttl() -> u8 {
+    DEFAULT_TTL
+}
+
+/// Формат сериализации сообщений на проводе
+///
+/// Перед сериализованным сообщением записывается байт формата, поэтому
+/// получатель разбирает сообщение независимо от своего формата.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SerializationFormat {
+    /// Компактный двоичный формат bincode
+    #[default]
+    Bincode,
+    /// JSON, удобный для отладки и других языков
+    Json,
+    /// CBOR (RFC 8949)
+    Cbor,
+}
+
+impl SerializationFormat {
+    /// Байт формата в начале кадра
+    fn tag(self) -> u8 {
+        match self {
+            SerializationFormat::Bincode => 0,
+            SerializationFormat::Json => 1,
+            SerializationFormat::Cbor => 2,
+        }
+    }
+    
+    /// Определить формат по байту кадра
+    fn from_tag(tag: u8) -> Result<Self> {
+        match tag {
+            0 => Ok(SerializationFormat::Bincode),
+            1 => Ok(SerializationFormat::Json),
+            2 => Ok(SerializationFormat::Cbor),
+            _ => Err(Error::Serialization(format!("Неизвестный формат сообщения: {}", tag))),
+        }
+    }
+}
 
 /// Типы сообщений
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -23,6 +80,20 @@ pub enum MessageType {
     Get,
     /// Ответ с данными
     Value,
+    /// Вызов для проверки владения ключом
+    Challenge,
+    /// Подписанный ответ на вызов
+    ChallengeResponse,
+    /// Запрос информации об узле (ответом служит объявление)
+    Identify,
+    /// Запрос, ожидающий ответа с тем же идентификатором
+    Request,
+    /// Ответ на запрос с идентификатором запроса
+    Response,
+    /// Сообщение темы публикации-подписки
+    Publish,
+    /// Часть сообщения, разделенного на части при отправке
+    Chunk,
     /// Пользовательский тип сообщения
     Custom(u8),
 }
@@ -42,6 +113,32 @@ pub struct Message {
     pub timestamp: u64,
     /// Уникальный идентификатор сообщения
     pub id: [u8; 16],
+    /// Оставшееся количество пересылок
+    ///
+    /// Уменьшается при каждой пересылке; сообщение с исчерпанным TTL дальше
+    /// не пересылается. Самоописываемые форматы (JSON, CBOR) подставляют
+    /// `DEFAULT_TTL` в сообщения без этого поля. Bincode кодирует поля по
+    /// позиции и значение по умолчанию применить не может, поэтому кадры
+    /// bincode, записанные до появления поля, не декодируются.
+    #[serde(default = "default_ttl")]
+    pub ttl: u8,
+    /// Подпись отправителя
+    ///
+    /// Покрывает отправителя, получателя, тип, данные, временную метку и
+    /// идентификатор, но не TTL, поэтому сохраняется при пересылке.
+    #[serde(default)]
+    pub signature: Option<Vec<u8>>,
+    /// Признак того, что данные сжаты zstd
+    ///
+    /// Узел распаковывает данные при приеме, поэтому подписчики всегда
+    /// получают исходные данные.
+    #[serde(default)]
+    pub compressed: bool,
+    /// Адрес, с которого получено сообщение
+    ///
+    /// Заполняется узлом при приеме и не передается по сети.
+    #[serde(skip)]
+    pub source: Option<Endpoint>,
 }
 
 impl Message {
@@ -74,9 +171,134 @@ impl Message {
             data,
             timestamp,
             id,
+            ttl: DEFAULT_TTL,
+            signature: None,
+            compressed: false,
+            source: None,
+        }
+    }
+    
+    /// Создать сообщение со сжатыми данными
+    ///
+    /// Данные короче `DEFAULT_COMPRESSION_THRESHOLD` не сжимаются: для них
+    /// выигрыш меньше затрат на сжатие.
+    pub fn new_compressed(from: PeerId, to: Option<PeerId>, message_type: MessageType, data: Vec<u8>) -> Result<Self> {
+        let mut message = Self::new(from, to, message_type, data);
+        if message.data.len() >= DEFAULT_COMPRESSION_THRESHOLD {
+            message.compress()?;
+        }
+        Ok(message)
+    }
+    
+    /// Сжать данные сообщения, если они еще не сжаты
+    ///
+    /// Подпись покрывает исходные данные, поэтому сжатие ее не нарушает.
+    pub fn compress(&mut self) -> Result<()> {
+        if self.compressed {
+            return Ok(());
+        }
+        
+        self.data = zstd::bulk::compress(&self.data, COMPRESSION_LEVEL)
+            .map_err(|e| Error::Serialization(format!("Не удалось сжать данные сообщения: {}", e)))?;
+        self.compressed = true;
+        Ok(())
+    }
+    
+    /// Распаковать данные сообщения, если они сжаты
+    ///
+    /// Данные, которые после распаковки превышают `MAX_DECOMPRESSED_SIZE`, отклоняются.
+    pub fn decompress(&mut self) -> Result<()> {
+        if !self.compressed {
+            return Ok(());
+        }
+        
+        self.data = zstd::bulk::decompress(&self.data, MAX_DECOMPRESSED_SIZE)
+            .map_err(|e| Error::Serialization(format!("Не удалось распаковать данные сообщения: {}", e)))?;
+        self.compressed = false;
+        Ok(())
+    }
+    
+    /// Установить начальное количество пересылок
+    pub fn with_ttl(mut self, ttl: u8) -> Self {
+        self.ttl = ttl;
+        self
+    }
+    
+    /// Подписать сообщение ключом отправителя
+    pub fn sign(&mut self, signer: &dyn Signer) -> Result<()> {
+        self.signature = Some(signer.sign(&self.signing_bytes()?)?);
+        Ok(())
+    }
+    
+    /// Проверить подпись сообщения публичным ключом Ed25519 отправителя
+    ///
+    /// Подпись проверяется по исходным данным, поэтому сжатое сообщение
+    /// нужно сначала распаковать. Для неподписанного сообщения возвращает `Ok(false)`.
+    pub fn verify(&self, pubkey: &[u8]) -> Result<bool> {
+        let signature = match &self.signature {
+            Some(signature) => signature,
+            None => return Ok(false),
+        };
+        
+        Ed25519KeyPair::from_public_key(pubkey)?.verify(&self.signing_bytes()?, signature)
+    }
+    
+    /// Данные, покрываемые подписью
+    fn signing_bytes(&self) -> Result<Vec<u8>> {
+        bincode::serialize(&(&self.from, &self.to, &self.message_type, &self.data, self.timestamp, &self.id))
+            .map_err(|e| Error::Serialization(format!("Не удалось сериализовать данные для подписи сообщения: {}", e)))
+    }
+    
+    /// Получить копию сообщения для пересылки
+    ///
+    /// Копия сохраняет идентификатор и имеет TTL на единицу меньше. Если
+    /// после уменьшения TTL станет нулевым, возвращается `None`.
+    pub fn forwarded(&self) -> Option<Self> {
+        let ttl = self.ttl.checked_sub(1).filter(|&ttl| ttl > 0)?;
+        
+        let mut copy = self.clone();
+        copy.ttl = ttl;
+        copy.source = None;
+        Some(copy)
+    }
+    
+    /// Сериализовать сообщение в кадр заданного формата
+    pub fn to_bytes(&self, format: SerializationFormat) -> Result<Vec<u8>> {
+        let mut frame = vec![format.tag()];
+        
+        match format {
+            SerializationFormat::Bincode => bincode::serialize_into(&mut frame, self)
+                .map_err(|e| Error::Serialization(format!("Не удалось сериализовать сообщение: {}", e)))?,
+            SerializationFormat::Json => serde_json::to_writer(&mut frame, self)
+                .map_err(|e| Error::Serialization(format!("Не удалось сериализовать сообщение в JSON: {}", e)))?,
+            SerializationFormat::Cbor => ciborium::into_writer(self, &mut frame)
+                .map_err(|e| Error::Serialization(format!("Не удалось сериализовать сообщение в CBOR: {}", e)))?,
+        }
+        
+        Ok(frame)
+    }
+    
+    /// Десериализовать сообщение из кадра, формат определяется по первому байту
+    pub fn from_bytes(frame: &[u8]) -> Result<Self> {
+        let (&tag, body) = frame.split_first()
+            .ok_or_else(|| Error::Serialization("Пустой кадр сообщения".to_string()))?;
+        
+        match SerializationFormat::from_tag(tag)? {
+            SerializationFormat::Bincode => bincode::deserialize(body)
+                .map_err(|e| Error::Serialization(format!("Не удалось десериализовать сообщение: {}", e))),
+            SerializationFormat::Json => serde_json::from_slice(body)
+                .map_err(|e| Error::Serialization(format!("Не удалось десериализовать сообщение из JSON: {}", e))),
+            SerializationFormat::Cbor => ciborium::from_reader(body)
+                .map_err(|e| Error::Serialization(format!("Не удалось десериализовать сообщение из CBOR: {}", e))),
         }
     }
     
+    /// Десериализовать данные сообщения, отправленные через `send_typed`/`broadcast_typed`
+    pub fn decode<T: DeserializeOwned>(&self) -> Result<T> {
+        bincode::deserialize(&self.data)
+            .map_err(|e| Error::Serialization(format!("Не удалось десериализовать данные сообщения: {}", e)))
+    }
+    
     /// Создать ответ на это сообщение
     pub fn create_response(&self, response_type: MessageType, data: Vec<u8>) -> Self {
         Self::new(
@@ -86,4 +308,64 @@ impl Message {
             data,
         )
     }
-} 
\ No newline at end of file
+} 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn message_without_ttl_gets_default() {
+        let message = Message::new(PeerId::new(vec![1; 32]), None, MessageType::Data, b"payload".to_vec()).with_ttl(3);
+        
+        // Сообщение в формате JSON, записанное до появления поля
+        let mut json = serde_json::to_value(&message).unwrap();
+        json.as_object_mut().unwrap().remove("ttl");
+        let decoded: Message = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(decoded.ttl, DEFAULT_TTL);
+        assert_eq!(decoded.id, message.id);
+        
+        let mut cbor = Vec::new();
+        ciborium::ser::into_writer(&json, &mut cbor).unwrap();
+        let decoded: Message = ciborium::de::from_reader(cbor.as_slice()).unwrap();
+        assert_eq!(decoded.ttl, DEFAULT_TTL);
+    }
+    
+    #[test]
+    fn large_redundant_payload_round_trips_compressed() {
+        let payload = b"noxy block ".repeat(10_000);
+        let mut message = Message::new_compressed(PeerId::new(vec![1; 32]), None, MessageType::Data, payload.clone()).unwrap();
+        assert!(message.compressed);
+        assert!(message.data.len() < payload.len() / 10);
+        
+        message.decompress().unwrap();
+        assert!(!message.compressed);
+        assert_eq!(message.data, payload);
+        
+        // Короткие данные не сжимаются
+        let short = Message::new_compressed(PeerId::new(vec![1; 32]), None, MessageType::Data, b"short".to_vec()).unwrap();
+        assert!(!short.compressed);
+    }
+    
+    #[test]
+    fn message_round_trips_in_every_format() {
+        let mut message = Message::new(PeerId::new(vec![1; 32]), Some(PeerId::new(vec![2; 32])), MessageType::Data, b"payload".to_vec());
+        message.signature = Some(vec![7; 64]);
+        
+        for format in [SerializationFormat::Bincode, SerializationFormat::Json, SerializationFormat::Cbor] {
+            let frame = message.to_bytes(format).unwrap();
+            assert_eq!(frame[0], format.tag());
+            
+            let decoded = Message::from_bytes(&frame).unwrap();
+            assert_eq!(decoded.id, message.id);
+            assert_eq!(decoded.from, message.from);
+            assert_eq!(decoded.to, message.to);
+            assert_eq!(decoded.data, message.data);
+            assert_eq!(decoded.signature, message.signature);
+            assert_eq!(decoded.ttl, message.ttl);
+        }
+        
+        assert!(Message::from_bytes(&[]).is_err());
+        assert!(Message::from_bytes(&[9, 0, 0]).is_err());
+    }
+}
@@ -1,19 +1,134 @@
 pub mod message;
 pub mod peer;
+pub mod rtt;
+pub mod announce;
+pub mod chunk;
+pub mod handshake;
+pub mod incoming;
+pub mod pubsub;
+pub mod rate_limit;
+pub mod seen;
+pub mod stats;
 
 use std::collections::{HashMap, HashSet};
+use std::net::SocketAddr;
 use std::sync::{Arc, Mutex};
-use tokio::sync::{mpsc, broadcast};
+use std::future::Future;
+use std::time::{Duration, Instant};
+use tokio::sync::{broadcast, oneshot, RwLock};
+use tokio::task::JoinHandle;
+use tokio_stream::wrappers::BroadcastStream;
 use futures::stream::{Stream, StreamExt};
 use async_trait::async_trait;
+use serde::Serialize;
 
 use crate::error::{Error, Result};
-use crate::types::{PeerId, PeerAddress, PeerInfo, TransportType};
+use crate::types::{Endpoint, PeerId, PeerInfo, TransportType};
 use crate::transport::Transport;
 use crate::discovery::Discovery;
+use crate::discovery::mdns::MdnsDiscovery;
 use crate::dht::Dht;
-use self::message::Message;
-use self::peer::Peer;
+use crate::dht::kademlia::KademliaDht;
+use crate::crypto::Key;
+use crate::crypto::ed25519::Ed25519KeyPair;
+use self::announce::Announcement;
+use self::chunk::{Chunk, Reassembler};
+use self::handshake::{Challenge, ChallengeResponse};
+use self::incoming::IncomingMessages;
+use self::message::{Message, MessageType, SerializationFormat};
+use self::peer::{Peer, PeerEvent, PeerStatus};
+use self::rate_limit::{RateLimit, RateLimiter};
+use self::seen::SeenCache;
+use self::stats::{NodeStats, StatsCounters};
+
+/// Количество одновременных отправок при рассылке по умолчанию
+const DEFAULT_BROADCAST_CONCURRENCY: usize = 16;
+
+/// Интервал объявления о присутствии по умолчанию
+const DEFAULT_ANNOUNCE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Минимальный интервал между запусками обнаружения узлов по умолчанию
+const DEFAULT_DISCOVERY_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Количество запоминаемых идентификаторов входящих сообщений по умолчанию
+const DEFAULT_SEEN_CACHE_SIZE: usize = 4096;
+
+/// Максимальное количество попыток переподключения по умолчанию
+const DEFAULT_MAX_RECONNECT_ATTEMPTS: u32 = 5;
+
+/// Задержка перед первой попыткой переподключения
+const RECONNECT_BASE_DELAY: Duration = Duration::from_secs(1);
+
+/// Максимальная задержка между попытками переподключения
+const RECONNECT_MAX_DELAY: Duration = Duration::from_secs(60);
+
+/// Режим работы узла
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NodeMode {
+    /// Полноценный узел: принимает соединения, объявляет себя и отправляет сообщения
+    #[default]
+    Full,
+    /// Пассивный наблюдатель: не принимает входящие соединения и ничего не отправляет,
+    /// но подключается к известным пирам и получает их сообщения
+    Observer,
+}
+
+/// Настраиваемые параметры узла
+#[derive(Debug, Clone)]
+pub struct NodeConfig {
+    /// Таймаут запросов до появления измерений RTT
+    pub default_timeout: Duration,
+    /// Фиксированный таймаут отправки одному пиру (если не задан, используется адаптивный)
+    pub send_timeout: Option<Duration>,
+    /// Максимальное количество одновременных отправок при рассылке
+    pub broadcast_concurrency: usize,
+    /// Интервал между объявлениями о присутствии (нулевой интервал отключает объявления)
+    pub announce_interval: Duration,
+    /// Минимальный интервал между запусками обнаружения узлов
+    pub discovery_interval: Duration,
+    /// Максимальное количество известных пиров (если не задано, не ограничено)
+    pub max_peers: Option<usize>,
+    /// Режим работы узла
+    pub mode: NodeMode,
+    /// Количество запоминаемых идентификаторов сообщений для отбрасывания повторов
+    pub seen_cache_size: usize,
+    /// Максимальное количество попыток переподключения к потерянному пиру
+    pub max_reconnect_attempts: u32,
+    /// Ограничение частоты входящих сообщений от одного пира (если не задано, не ограничено)
+    pub rate_limit: Option<RateLimit>,
+    /// Подписывать исходящие сообщения и отбрасывать входящие без верной подписи
+    pub signed_messages: bool,
+    /// Размер данных, начиная с которого исходящие сообщения сжимаются (если не задан, не сжимаются)
+    pub compression_threshold: Option<usize>,
+    /// Формат сериализации исходящих сообщений
+    pub serialization: SerializationFormat,
+    /// Размер данных одной части при отправке через `send_chunked`
+    pub chunk_size: usize,
+    /// Время ожидания недостающих частей входящей передачи
+    pub reassembly_timeout: Duration,
+}
+
+impl Default for NodeConfig {
+    fn default() -> Self {
+        Self {
+            default_timeout: rtt::DEFAULT_TIMEOUT,
+            send_timeout: None,
+            broadcast_concurrency: DEFAULT_BROADCAST_CONCURRENCY,
+            announce_interval: DEFAULT_ANNOUNCE_INTERVAL,
+            discovery_interval: DEFAULT_DISCOVERY_INTERVAL,
+            max_peers: None,
+            mode: NodeMode::Full,
+            seen_cache_size: DEFAULT_SEEN_CACHE_SIZE,
+            max_reconnect_attempts: DEFAULT_MAX_RECONNECT_ATTEMPTS,
+            rate_limit: None,
+            signed_messages: false,
+            compression_threshold: None,
+            serialization: SerializationFormat::Bincode,
+            chunk_size: chunk::DEFAULT_CHUNK_SIZE,
+            reassembly_timeout: chunk::DEFAULT_REASSEMBLY_TIMEOUT,
+        }
+    }
+}
 
 /// Интерфейс сетевого узла
 #[async_trait]
@@ -34,270 +149,3142 @@ pub trait NetworkNode: Send + Sync {
     async fn send_to(&mut self, peer_id: &PeerId, data: &[u8]) -> Result<()>;
     
     /// Отправить сообщение всем известным узлам
-    async fn broadcast(&mut self, data: &[u8]) -> Result<()>;
+    async fn broadcast(&mut self, data: &[u8]) -> Result<BroadcastReport>;
     
     /// Получить список известных узлов
     fn peers(&self) -> Vec<PeerInfo>;
     
     /// Получить поток входящих сообщений
-    fn incoming(&self) -> Box<dyn Stream<Item = Message> + Unpin + Send>;
+    ///
+    /// Поток можно безопасно опрашивать в `tokio::select!`: отмена ожидания не теряет сообщений.
+    fn incoming(&self) -> IncomingMessages;
 }
 
-/// Основной узел сети
-pub struct Node {
+/// Результат отправки сообщения одному пиру
+enum SendOutcome {
+    Delivered,
+    Failed(String),
+    TimedOut,
+}
+
+/// Отчет о широковещательной рассылке
+#[derive(Debug, Clone, Default)]
+pub struct BroadcastReport {
+    /// Пиры, которым сообщение было доставлено
+    pub delivered: Vec<PeerId>,
+    /// Пиры, отправка которым завершилась ошибкой
+    pub failed: Vec<(PeerId, String)>,
+    /// Пиры, не ответившие за отведенное время
+    pub timed_out: Vec<PeerId>,
+    /// Пиры, адрес которых не удалось определить
+    pub unresolved: Vec<PeerId>,
+}
+
+impl BroadcastReport {
+    /// Общее количество пиров, которым выполнялась отправка
+    pub fn total(&self) -> usize {
+        self.delivered.len() + self.failed.len() + self.timed_out.len() + self.unresolved.len()
+    }
+    
+    /// Доставлено ли сообщение всем пирам
+    pub fn is_complete(&self) -> bool {
+        self.failed.is_empty() && self.timed_out.is_empty() && self.unresolved.is_empty()
+    }
+}
+
+/// Ожидающие ответа запросы: идентификатор запроса -> (адресат, канал для ответа)
+type PendingRequests = HashMap<[u8; 16], (PeerId, oneshot::Sender<Message>)>;
+
+/// Заблокированные пиры: идентификатор -> время окончания блокировки (`None` — бессрочно)
+type BanList = HashMap<PeerId, Option<Instant>>;
+
+/// Открытые ключи пиров для проверки подписей: идентификатор -> ключ Ed25519
+type PeerKeys = HashMap<PeerId, Vec<u8>>;
+
+/// Действует ли блокировка пира
+fn is_banned_in(bans: &BanList, peer_id: &PeerId) -> bool {
+    match bans.get(peer_id) {
+        Some(Some(until)) => *until > Instant::now(),
+        Some(None) => true,
+        None => false,
+    }
+}
+
+/// Транспорт, общий для узла и его фоновых задач
+type SharedTransport = Arc<RwLock<Box<dyn Transport>>>;
+
+/// Состояние узла, общее для узла и его фоновых задач
+///
+/// Фоновые задачи получают копию, ссылающуюся на те же данные, что и узел.
+#[derive(Clone)]
+struct NodeShared {
     /// Идентификатор узла
     peer_id: PeerId,
     /// Адрес для прослушивания
     listen_addr: String,
     /// Порт для прослушивания
     port: u16,
+    /// Внешний адрес и порт, сообщаемые другим узлам вместо адреса прослушивания
+    external_addr: Option<(String, u16)>,
+    /// Ключевая пара узла для подписи объявлений
+    keypair: Arc<Ed25519KeyPair>,
+    /// Параметры узла
+    config: Arc<NodeConfig>,
     /// Список транспортных протоколов
-    transports: HashMap<TransportType, Box<dyn Transport>>,
-    /// Список механизмов обнаружения
-    discoveries: Vec<Box<dyn Discovery>>,
-    /// Распределенная хеш-таблица
-    dht: Option<Box<dyn Dht>>,
+    transports: HashMap<TransportType, SharedTransport>,
     /// Известные узлы
     peers: Arc<Mutex<HashMap<PeerId, Peer>>>,
-    /// Канал для отправки сообщений
-    message_tx: mpsc::Sender<Message>,
-    /// Канал для получения сообщений
-    message_rx: mpsc::Receiver<Message>,
     /// Широковещательный канал для входящих сообщений
     broadcast_tx: broadcast::Sender<Message>,
+    /// Канал событий изменения состояния пиров
+    events_tx: broadcast::Sender<PeerEvent>,
+    /// Ожидающие ответа запросы по идентификатору сообщения
+    pending_requests: Arc<Mutex<PendingRequests>>,
+    /// Идентификаторы недавно полученных и отправленных рассылкой сообщений
+    seen_messages: Arc<Mutex<SeenCache>>,
+    /// Заблокированные пиры
+    banned: Arc<Mutex<BanList>>,
+    /// Ограничитель частоты входящих сообщений от пиров
+    rate_limiter: Option<Arc<Mutex<RateLimiter>>>,
+    /// Счетчики отправленных и принятых сообщений
+    stats: Arc<StatsCounters>,
+    /// Публичные ключи пиров, полученные из объявлений
+    peer_keys: Arc<Mutex<PeerKeys>>,
+    /// Сборщик входящих сообщений, разделенных на части
+    reassembler: Arc<Mutex<Reassembler>>,
+    /// Временные метки последних принятых объявлений от пиров
+    announce_seen: Arc<Mutex<HashMap<PeerId, u64>>>,
+    /// Закрепленные ключи пиров, идентификатор которых не выводится из ключа
+    pinned_keys: Arc<Mutex<HashMap<PeerId, Vec<u8>>>>,
+    /// Запланированные переподключения к потерянным пирам
+    reconnect_at: Arc<Mutex<HashMap<PeerId, Instant>>>,
+    /// Пиры, доказавшие владение ключом своего идентификатора
+    verified_peers: Arc<Mutex<HashSet<PeerId>>>,
+}
+
+/// Основной узел сети
+pub struct Node {
+    /// Состояние, общее с фоновыми задачами
+    shared: NodeShared,
+    /// Список механизмов обнаружения
+    discoveries: Vec<Box<dyn Discovery>>,
+    /// Распределенная хеш-таблица
+    dht: Option<Box<dyn Dht>>,
+    /// Время создания узла
+    started_at: Instant,
+    /// Фоновые задачи узла
+    tasks: Vec<JoinHandle<()>>,
+    /// Время и результат последнего запуска обнаружения узлов
+    last_discovery: Option<(Instant, Vec<PeerInfo>)>,
     /// Состояние подключения
     connected: bool,
 }
 
-impl Node {
-    /// Создать новый узел с помощью NodeBuilder
-    pub fn builder() -> NodeBuilder {
-        NodeBuilder::new()
+impl NodeShared {
+    /// Цикл приема данных от транспорта
+    ///
+    /// Десериализует входящие кадры в сообщения и публикует их подписчикам `incoming`.
+    /// Адрес отправителя сохраняется в `Message::source`, а время последнего
+    /// контакта с известным отправителем обновляется. Ответы на запросы,
+    /// сделанные через `request` и `ping`, передаются ожидающему вызову, а не подписчикам.
+    /// Повторно полученные сообщения (например, пришедшие по другому пути
+    /// при пересылке), сообщения заблокированных пиров, сообщения сверх
+    /// ограничения частоты и, если включена подпись сообщений, сообщения
+    /// без верной подписи отправителя отбрасываются. Части сообщений,
+    /// отправленных через `send_chunked`, собираются, и подписчики получают
+    /// одно собранное сообщение с идентификатором передачи.
+    /// Объявления о присутствии учитываются в списке пиров, а на вызовы проверки
+    /// ключа, запросы информации и `Ping` узел отвечает сам.
+    async fn receive_loop(self, mut rx: broadcast::Receiver<(Vec<u8>, SocketAddr)>, transport_type: TransportType) {
+        loop {
+            match rx.recv().await {
+                Ok((data, addr)) => {
+                    match Message::from_bytes(&data) {
+                        Ok(mut message) => {
+                            self.stats.record_received(data.len());
+                            
+                            if is_banned_in(&self.banned.lock().expect("Не удалось получить блокировку banned"), &message.from) {
+                                continue;
+                            }
+                            
+                            if let Some(limiter) = &self.rate_limiter {
+                                if !limiter.lock().expect("Не удалось получить блокировку rate_limiter").allow(&message.from) {
+                                    tracing::debug!("Превышено ограничение частоты сообщений от {}", message.from);
+                                    continue;
+                                }
+                            }
+                            
+                            if let Err(e) = message.decompress() {
+                                tracing::debug!("Отброшено сообщение от {}: {}", message.from, e);
+                                continue;
+                            }
+                            
+                            if self.config.signed_messages && !self.verify_incoming(&message) {
+                                tracing::debug!("Отброшено сообщение без верной подписи от {}", message.from);
+                                continue;
+                            }
+                            
+                            message.source = Some(Endpoint::new(transport_type.clone(), addr.ip().to_string(), addr.port()));
+                            
+                            if let Some(peer) = self.peers.lock().expect("Не удалось получить блокировку peers").get_mut(&message.from) {
+                                peer.update_last_seen();
+                            }
+                            
+                            if message.message_type == MessageType::Chunk {
+                                if !self.seen_messages.lock().expect("Не удалось получить блокировку seen_messages").insert(message.id) {
+                                    continue;
+                                }
+                                
+                                match self.reassemble(message) {
+                                    Ok(Some(assembled)) => message = assembled,
+                                    Ok(None) => continue,
+                                    Err(e) => {
+                                        tracing::debug!("Отброшена часть сообщения от {}: {}", addr, e);
+                                        continue;
+                                    }
+                                }
+                            }
+                            
+                            // Ответы повторяют идентификатор запроса, поэтому в кэш не попадают
+                            let is_reply = matches!(message.message_type, MessageType::Response | MessageType::Pong);
+                            if !is_reply
+                                && !self.seen_messages.lock().expect("Не удалось получить блокировку seen_messages").insert(message.id)
+                            {
+                                continue;
+                            }
+                            
+                            if message.message_type == MessageType::Announce {
+                                if let Err(e) = self.handle_announce(&message) {
+                                    tracing::debug!("Отброшено объявление от {}: {}", message.from, e);
+                                }
+                                continue;
+                            }
+                            
+                            // На вызов отвечаем в отдельной задаче, чтобы медленный пир не задерживал прием
+                            if message.message_type == MessageType::Challenge {
+                                let shared = self.clone();
+                                tokio::spawn(async move {
+                                    if let Err(e) = shared.handle_challenge(&message).await {
+                                        tracing::debug!("Не удалось ответить на вызов от {}: {}", message.from, e);
+                                    }
+                                });
+                                continue;
+                            }
+                            
+                            if message.message_type == MessageType::Identify {
+                                let shared = self.clone();
+                                tokio::spawn(async move {
+                                    if let Err(e) = shared.handle_identify(&message).await {
+                                        tracing::debug!("Не удалось ответить на запрос информации от {}: {}", message.from, e);
+                                    }
+                                });
+                                continue;
+                            }
+                            
+                            // Наблюдатель на `Ping` не отвечает, поскольку ничего не отправляет
+                            if message.message_type == MessageType::Ping {
+                                if self.config.mode != NodeMode::Observer {
+                                    let shared = self.clone();
+                                    tokio::spawn(async move {
+                                        if let Err(e) = shared.handle_ping(&message).await {
+                                            tracing::debug!("Не удалось ответить на ping от {}: {}", message.from, e);
+                                        }
+                                    });
+                                }
+                                continue;
+                            }
+                            
+                            if is_reply {
+                                let mut pending = self.pending_requests.lock().expect("Не удалось получить блокировку pending_requests");
+                                // Ответ принимаем только от того пира, которому был отправлен запрос
+                                let matches = pending.get(&message.id)
+                                    .map(|(peer_id, _)| *peer_id == message.from)
+                                    .unwrap_or(false);
+                                if matches {
+                                    if let Some((_, waiter)) = pending.remove(&message.id) {
+                                        let _ = waiter.send(message);
+                                    }
+                                    continue;
+                                }
+                            }
+                            
+                            // Отсутствие подписчиков не является ошибкой
+                            let _ = self.broadcast_tx.send(message);
+                        }
+                        Err(e) => {
+                            tracing::debug!("Не удалось десериализовать сообщение от {}: {}", addr, e);
+                        }
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    tracing::warn!("Пропущено {} входящих сообщений транспорта", skipped);
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
     }
     
-    /// Внутренний метод создания узла
-    fn new(
-        peer_id: PeerId,
-        listen_addr: String,
-        port: u16,
-        transports: HashMap<TransportType, Box<dyn Transport>>,
-        discoveries: Vec<Box<dyn Discovery>>,
-        dht: Option<Box<dyn Dht>>,
-    ) -> Self {
-        let (message_tx, message_rx) = mpsc::channel(100);
-        let (broadcast_tx, _) = broadcast::channel(100);
+    /// Передать часть сообщения сборщику
+    ///
+    /// Когда получены все части, возвращает собранное сообщение с
+    /// идентификатором передачи и адресом, с которого пришла последняя часть.
+    fn reassemble(&self, message: Message) -> Result<Option<Message>> {
+        let chunk = Chunk::from_bytes(&message.data)?;
+        let transfer_id = chunk.transfer_id;
         
-        Self {
-            peer_id,
-            listen_addr,
-            port,
-            transports,
-            discoveries,
-            dht,
-            peers: Arc::new(Mutex::new(HashMap::new())),
-            message_tx,
-            message_rx,
-            broadcast_tx,
-            connected: false,
-        }
-    }
-}
-
-#[async_trait]
-impl NetworkNode for Node {
-    fn peer_id(&self) -> &PeerId {
-        &self.peer_id
+        let assembled = self.reassembler.lock().expect("Не удалось получить блокировку reassembler")
+            .insert(&message.from, chunk)?;
+        
+        Ok(assembled.map(|(message_type, data)| {
+            let mut assembled = Message::new(message.from, message.to, message_type, data);
+            assembled.id = transfer_id;
+            assembled.timestamp = message.timestamp;
+            assembled.source = message.source;
+            assembled
+        }))
     }
     
-    async fn connect(&mut self) -> Result<()> {
-        if self.connected {
-            return Ok(());
+    /// Проверить подпись входящего сообщения ключом отправителя
+    ///
+    /// Ключ отправителя берется из ранее принятого объявления. Объявление
+    /// само несет ключ: ключ запоминается, только если он принадлежит
+    /// отправителю (см. `key_belongs_to`), а объявление и сообщение подписаны
+    /// этим ключом. Поэтому первым объявившийся узел не может присвоить
+    /// чужой идентификатор.
+    fn verify_incoming(&self, message: &Message) -> bool {
+        if message.message_type == MessageType::Announce {
+            let announcement = match Announcement::from_bytes(&message.data) {
+                Ok(announcement) => announcement,
+                Err(_) => return false,
+            };
+            
+            let valid = announcement.info.id == message.from
+                && self.key_belongs_to(&message.from, &announcement.public_key)
+                && announcement.verify().unwrap_or(false)
+                && message.verify(&announcement.public_key).unwrap_or(false);
+            if valid {
+                self.peer_keys.lock().expect("Не удалось получить блокировку peer_keys")
+                    .insert(message.from.clone(), announcement.public_key);
+            }
+            return valid;
         }
         
-        // Запускаем все транспортные протоколы
-        for transport in self.transports.values_mut() {
-            transport.listen(&self.listen_addr, self.port).await?;
+        let key = self.peer_keys.lock().expect("Не удалось получить блокировку peer_keys")
+            .get(&message.from)
+            .cloned();
+        match key {
+            Some(key) => message.verify(&key).unwrap_or(false),
+            None => false,
         }
+    }
+    
+    /// Сериализовать исходящее сообщение
+    ///
+    /// Если включена подпись сообщений, собственные неподписанные сообщения
+    /// узла подписываются его ключом. Пересылаемые сообщения других узлов
+    /// сохраняют подпись отправителя. Если включено сжатие, данные от
+    /// `compression_threshold` байт сжимаются после подписи. Сообщение
+    /// кодируется в формате `serialization` с байтом формата в начале.
+    fn encode_outgoing(&self, message: &Message) -> Result<Vec<u8>> {
+        let sign = self.config.signed_messages && message.from == self.peer_id && message.signature.is_none();
+        let compress = !message.compressed && self.config.compression_threshold
+            .map(|threshold| message.data.len() >= threshold)
+            .unwrap_or(false);
         
-        self.connected = true;
-        Ok(())
+        if sign || compress {
+            let mut prepared = message.clone();
+            if sign {
+                prepared.sign(&*self.keypair)?;
+            }
+            if compress {
+                prepared.compress()?;
+            }
+            prepared.to_bytes(self.config.serialization)
+        } else {
+            message.to_bytes(self.config.serialization)
+        }
     }
     
-    async fn disconnect(&mut self) -> Result<()> {
-        if !self.connected {
-            return Ok(());
+    /// Проверить, что узлу разрешено отправлять сообщения
+    fn ensure_can_send(&self) -> Result<()> {
+        if self.config.mode == NodeMode::Observer {
+            return Err(Error::Network("Узел работает в режиме наблюдателя (observer mode)".to_string()));
         }
+        Ok(())
+    }
+    
+    /// Подготовить сообщение к отправке пиру
+    ///
+    /// Возвращает адрес пира, сериализованное сообщение и таймаут отправки.
+    fn prepare_send(&self, peer_id: &PeerId, message: &Message) -> Result<(Endpoint, Vec<u8>, Duration)> {
+        let peers_lock = self.peers.lock().expect("Не удалось получить блокировку peers");
+        let peer = peers_lock.get(peer_id).ok_or_else(|| Error::Network(format!("Пир не найден: {}", peer_id)))?;
         
-        // Останавливаем все транспортные протоколы
-        for transport in self.transports.values_mut() {
-            transport.close().await?;
+        let addr = peer.info().address.clone()
+            .ok_or_else(|| Error::Network(format!("Адрес пира не известен: {}", peer_id)))?;
+        
+        let payload = self.encode_outgoing(message)?;
+        
+        let timeout = self.config.send_timeout.unwrap_or_else(|| peer.timeout());
+        
+        Ok((addr, payload, timeout))
+    }
+    
+    /// Отправить сообщение заданного типа пиру
+    async fn send_message(&self, peer_id: &PeerId, message_type: MessageType, data: &[u8]) -> Result<()> {
+        let message = Message::new(self.peer_id.clone(), Some(peer_id.clone()), message_type, data.to_vec());
+        self.send_prepared(peer_id, &message).await
+    }
+    
+    /// Отправить готовое сообщение пиру
+    async fn send_prepared(&self, peer_id: &PeerId, message: &Message) -> Result<()> {
+        self.ensure_can_send()?;
+        
+        if self.is_banned(peer_id) {
+            return Err(Error::Network(format!("Пир заблокирован: {}", peer_id)));
         }
         
-        self.connected = false;
-        Ok(())
+        let (addr, payload, _) = self.prepare_send(peer_id, message)?;
+        
+        // Выбираем транспорт по типу адреса пира
+        let transport = self.transports.get(&addr.transport)
+            .ok_or_else(|| Error::Network(format!("Нет транспорта для адреса {}", addr)))?;
+        
+        let result = transport.read().await.send_to(&addr, &payload).await;
+        match &result {
+            Ok(()) => {
+                self.stats.record_sent(payload.len());
+                self.mark_connected(peer_id);
+            }
+            Err(_) => self.mark_failed(peer_id),
+        }
+        result
     }
     
-    async fn discover_peers(&mut self) -> Result<Vec<PeerInfo>> {
-        let mut all_peers = Vec::new();
+    /// Разослать сообщение заданного типа всем известным пирам
+    async fn broadcast_message(&self, message_type: MessageType, data: &[u8]) -> BroadcastReport {
+        self.broadcast_message_except(message_type, data, &[]).await
+    }
+    
+    /// Разослать сообщение заданного типа всем известным пирам, кроме исключенных
+    ///
+    /// Сообщение создается без указания получателя. Сообщения с данными
+    /// также публикуются в `incoming` для локальных подписчиков.
+    /// Исключенные пиры не попадают в отчет.
+    async fn broadcast_message_except(
+        &self,
+        message_type: MessageType,
+        data: &[u8],
+        exclude: &[PeerId],
+    ) -> BroadcastReport {
+        let message = Message::new(self.peer_id.clone(), None, message_type, data.to_vec());
+        let report = self.broadcast_prepared(&message, exclude).await;
         
-        // Запускаем все механизмы обнаружения
-        for discovery in &mut self.discoveries {
-            let peers = discovery.discover().await?;
-            all_peers.extend(peers);
+        // Служебные сообщения локальным подписчикам не показываем, чтобы узел
+        // не обрабатывал, например, собственные объявления
+        if message_type == MessageType::Data {
+            // Отсутствие подписчиков не является ошибкой
+            let _ = self.broadcast_tx.send(message);
         }
         
-        // Если включен DHT, используем его для обнаружения
-        if let Some(dht) = &mut self.dht {
-            let peers = dht.find_nodes(&self.peer_id).await?;
-            all_peers.extend(peers);
+        report
+    }
+    
+    /// Разослать готовое сообщение всем известным пирам, кроме исключенных
+    ///
+    /// Сообщение сериализуется один раз и отправляется без изменений.
+    async fn broadcast_prepared(&self, message: &Message, exclude: &[PeerId]) -> BroadcastReport {
+        let mut report = BroadcastReport::default();
+        
+        // Копии, вернувшиеся от пиров, будут отброшены при приеме
+        self.seen_messages.lock().expect("Не удалось получить блокировку seen_messages").insert(message.id);
+        
+        let payload = match self.encode_outgoing(message) {
+            Ok(payload) => payload,
+            Err(e) => {
+                let reason = e.to_string();
+                let peers_lock = self.peers.lock().expect("Не удалось получить блокировку peers");
+                report.failed = peers_lock.keys()
+                    .filter(|id| !exclude.contains(id))
+                    .map(|id| (id.clone(), reason.clone()))
+                    .collect();
+                return report;
+            }
+        };
+        
+        // Собираем адреса и таймауты за одну блокировку, не удерживая ее во время отправки
+        let targets: Vec<(PeerId, Option<Endpoint>, Duration)> = {
+            let peers_lock = self.peers.lock().expect("Не удалось получить блокировку peers");
+            peers_lock.iter()
+                .filter(|(id, _)| !exclude.contains(id))
+                .map(|(id, peer)| {
+                    let timeout = self.config.send_timeout.unwrap_or_else(|| peer.timeout());
+                    (id.clone(), peer.info().address.clone(), timeout)
+                })
+                .collect()
+        };
+        
+        let mut sends = Vec::with_capacity(targets.len());
+        
+        for (peer_id, addr, timeout) in targets {
+            let addr = match addr {
+                Some(addr) => addr,
+                None => {
+                    report.unresolved.push(peer_id);
+                    continue;
+                }
+            };
+            
+            match self.transports.get(&addr.transport) {
+                Some(transport) => sends.push((peer_id, transport, addr, timeout)),
+                None => report.failed.push((peer_id, format!("Нет транспорта для адреса {}", addr))),
+            }
         }
         
-        // Добавляем найденных пиров в список известных
-        let mut peers_lock = self.peers.lock().expect("Не удалось получить блокировку peers");
-        for peer_info in &all_peers {
-            if !peers_lock.contains_key(&peer_info.id) {
-                let peer = Peer::new(peer_info.clone());
-                peers_lock.insert(peer_info.id.clone(), peer);
+        let payload = &payload;
+        // Отправляем параллельно с ограничением, каждая отправка ограничена своим таймаутом,
+        // поэтому зависший пир не задерживает доставку остальным
+        let sends: Vec<_> = sends.into_iter()
+            .map(|(peer_id, transport, addr, timeout)| Self::send_with_timeout(peer_id, transport.as_ref(), addr, payload, timeout))
+            .collect();
+        let results: Vec<(PeerId, SendOutcome)> = futures::stream::iter(sends)
+            .buffer_unordered(self.config.broadcast_concurrency.max(1))
+            .collect()
+            .await;
+        
+        for (peer_id, outcome) in results {
+            match outcome {
+                SendOutcome::Delivered => {
+                    self.stats.record_sent(payload.len());
+                    self.mark_connected(&peer_id);
+                    report.delivered.push(peer_id);
+                }
+                SendOutcome::Failed(reason) => {
+                    self.mark_failed(&peer_id);
+                    report.failed.push((peer_id, reason));
+                }
+                SendOutcome::TimedOut => {
+                    self.mark_failed(&peer_id);
+                    report.timed_out.push(peer_id);
+                }
             }
         }
         
-        Ok(all_peers)
+        report
     }
     
-    async fn send_to(&mut self, peer_id: &PeerId, data: &[u8]) -> Result<()> {
-        // Находим пира по идентификатору
-        let peers_lock = self.peers.lock().expect("Не удалось получить блокировку peers");
-        let peer = peers_lock.get(peer_id).ok_or_else(|| Error::Network(format!("Пир не найден: {}", peer_id)))?;
+    /// Получить информацию о текущем узле для объявления
+    ///
+    /// Если задан внешний адрес, объявляется он, а не адрес прослушивания.
+    fn local_info(&self) -> PeerInfo {
+        // Предпочитаем TCP, если он настроен
+        let transport = if self.transports.contains_key(&TransportType::Tcp) {
+            Some(TransportType::Tcp)
+        } else {
+            self.transports.keys().next().cloned()
+        };
         
-        // Создаем сообщение
-        let message = Message::new_data(self.peer_id.clone(), peer_id.clone(), data.to_vec());
+        let (host, port) = self.external_addr.clone()
+            .unwrap_or_else(|| (self.listen_addr.clone(), self.port));
         
-        // Выбираем транспорт для отправки
-        // Для простоты используем первый доступный транспорт
-        if let Some(transport) = self.transports.values().next() {
-            if let Some(addr) = &peer.info().address {
-                transport.send_to(addr, &bincode::serialize(&message)?).await?;
-                Ok(())
-            } else {
-                Err(Error::Network(format!("Адрес пира не известен: {}", peer_id)))
+        PeerInfo {
+            id: self.peer_id.clone(),
+            address: transport.map(|t| Endpoint::new(t, host, port)),
+            protocols: self.transports.keys().map(|t| t.scheme().to_string()).collect(),
+            client_version: format!("noxy/{}", crate::VERSION),
+        }
+    }
+    
+    /// Цикл периодических объявлений о присутствии
+    ///
+    /// Первое объявление рассылается через `announce_interval` после запуска,
+    /// затем — каждые `announce_interval`. Ошибка одной рассылки не
+    /// прерывает последующие.
+    async fn announce_loop(self) {
+        let interval = self.config.announce_interval;
+        let mut ticks = tokio::time::interval_at(tokio::time::Instant::now() + interval, interval);
+        ticks.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        
+        loop {
+            ticks.tick().await;
+            if let Err(e) = self.announce().await {
+                tracing::warn!("Не удалось разослать объявление о присутствии: {}", e);
             }
-        } else {
-            Err(Error::Network("Нет доступных транспортных протоколов".to_string()))
         }
     }
     
-    async fn broadcast(&mut self, data: &[u8]) -> Result<()> {
-        let peers_lock = self.peers.lock().expect("Не удалось получить блокировку peers");
-        let peer_ids: Vec<PeerId> = peers_lock.keys().cloned().collect();
-        drop(peers_lock);
+    /// Разослать подписанное объявление о присутствии всем известным пирам
+    async fn announce(&self) -> Result<BroadcastReport> {
+        self.ensure_can_send()?;
+        
+        let announcement = Announcement::new_signed(self.local_info(), &self.keypair)?;
+        Ok(self.broadcast_message(MessageType::Announce, &announcement.to_bytes()?).await)
+    }
+    
+    /// Обработать объявление о присутствии от другого узла
+    ///
+    /// Проверяет подпись и добавляет отправителя в список известных узлов.
+    /// Повторные и устаревшие объявления игнорируются.
+    fn handle_announce(&self, message: &Message) -> Result<Option<PeerInfo>> {
+        if message.message_type != MessageType::Announce {
+            return Err(Error::Network("Сообщение не является объявлением".to_string()));
+        }
         
-        for peer_id in peer_ids {
-            // Игнорируем ошибки при отправке отдельным узлам
-            let _ = self.send_to(&peer_id, data).await;
+        let announcement = Announcement::from_bytes(&message.data)?;
+        
+        if announcement.info.id != message.from {
+            return Err(Error::Network("Идентификатор в объявлении не совпадает с отправителем".to_string()));
         }
         
-        Ok(())
+        // Иначе любой узел мог бы объявить себя под чужим идентификатором своим ключом
+        if !self.key_belongs_to(&message.from, &announcement.public_key) {
+            return Err(Error::Network(format!("Ключ объявления не принадлежит {}", message.from)));
+        }
+        
+        if !announcement.verify()? {
+            return Err(Error::Network(format!("Неверная подпись объявления от {}", message.from)));
+        }
+        
+        // Объявление из будущего сделало бы устаревшими все следующие объявления пира
+        if announcement.is_from_future() {
+            return Err(Error::Network(format!("Временная метка объявления от {} в будущем", message.from)));
+        }
+        
+        // Отбрасываем повторы уже обработанных объявлений
+        {
+            let mut seen = self.announce_seen.lock().expect("Не удалось получить блокировку announce_seen");
+            let last = seen.entry(announcement.info.id.clone()).or_insert(0);
+            if announcement.timestamp <= *last {
+                return Ok(None);
+            }
+            *last = announcement.timestamp;
+        }
+        
+        let info = announcement.info;
+        let mut peers_lock = self.peers.lock().expect("Не удалось получить блокировку peers");
+        match peers_lock.get_mut(&info.id) {
+            Some(peer) => {
+                peer.update_info(info.clone());
+                peer.update_last_seen();
+            }
+            None => {
+                let peer = Peer::with_default_timeout(info.clone(), self.config.default_timeout);
+                self.insert_peer(&mut peers_lock, peer);
+            }
+        }
+        
+        Ok(Some(info))
     }
     
-    fn peers(&self) -> Vec<PeerInfo> {
-        let peers_lock = self.peers.lock().expect("Не удалось получить блокировку peers");
-        peers_lock.values().map(|p| p.info().clone()).collect()
+    /// Принадлежит ли публичный ключ пиру
+    ///
+    /// Ключ принадлежит пиру, если идентификатор пира выводится из ключа
+    /// или ключ закреплен за пиром через `Node::pin_peer_key`.
+    fn key_belongs_to(&self, peer_id: &PeerId, public_key: &[u8]) -> bool {
+        if PeerId::from_public_key(public_key) == *peer_id {
+            return true;
+        }
+        
+        self.pinned_keys.lock().expect("Не удалось получить блокировку pinned_keys")
+            .get(peer_id)
+            .map(|pinned| pinned.as_slice() == public_key)
+            .unwrap_or(false)
     }
     
-    fn incoming(&self) -> Box<dyn Stream<Item = Message> + Unpin + Send> {
-        let rx = self.broadcast_tx.subscribe();
-        Box::new(tokio_stream::wrappers::BroadcastStream::new(rx)
-            .filter_map(|r| async move { r.ok() }))
+    /// Ответить на `Ping` другого узла
+    ///
+    /// `Pong` повторяет идентификатор и временную метку `Ping`.
+    async fn handle_ping(&self, message: &Message) -> Result<()> {
+        let mut pong = Message::new(self.peer_id.clone(), Some(message.from.clone()), MessageType::Pong, message.data.clone());
+        pong.id = message.id;
+        
+        self.send_prepared(&message.from, &pong).await
     }
-}
-
-/// Строитель для настройки и создания узла сети
-pub struct NodeBuilder {
-    listen_addr: String,
-    port: u16,
-    transports: HashMap<TransportType, Box<dyn Transport>>,
-    discoveries: Vec<Box<dyn Discovery>>,
-    dht: Option<Box<dyn Dht>>,
-    peer_id: Option<PeerId>,
-}
-
-impl NodeBuilder {
-    /// Создать новый строитель узла
-    pub fn new() -> Self {
-        Self {
-            listen_addr: "127.0.0.1".to_string(),
-            port: 0, // Случайный порт
-            transports: HashMap::new(),
-            discoveries: Vec::new(),
-            dht: None,
-            peer_id: None,
+    
+    /// Ответить на вызов другого узла подписью своего ключа
+    async fn handle_challenge(&self, message: &Message) -> Result<()> {
+        if message.message_type != MessageType::Challenge {
+            return Err(Error::Network("Сообщение не является вызовом".to_string()));
         }
+        
+        let challenge = Challenge::from_bytes(&message.data)?;
+        let response = ChallengeResponse::sign(&challenge, self.peer_id.clone(), &self.keypair)?;
+        
+        self.send_message(&message.from, MessageType::ChallengeResponse, &response.to_bytes()?).await
     }
     
-    /// Установить адрес для прослушивания
-    pub fn with_address(mut self, address: impl Into<String>) -> Self {
-        self.listen_addr = address.into();
-        self
+    /// Удалить пир, не прошедший проверку
+    fn reject_peer(&self, peer_id: &PeerId) {
+        let removed = self.peers.lock().expect("Не удалось получить блокировку peers").remove(peer_id);
+        self.verified_peers.lock().expect("Не удалось получить блокировку verified_peers").remove(peer_id);
+        self.reconnect_at.lock().expect("Не удалось получить блокировку reconnect_at").remove(peer_id);
+        
+        if removed.map(|peer| peer.is_connected()).unwrap_or(false) {
+            self.emit(PeerEvent::Disconnected(peer_id.clone()));
+        }
     }
     
-    /// Установить порт для прослушивания
-    pub fn with_port(mut self, port: u16) -> Self {
-        self.port = port;
-        self
+    /// Заблокирован ли пир
+    fn is_banned(&self, peer_id: &PeerId) -> bool {
+        let mut banned = self.banned.lock().expect("Не удалось получить блокировку banned");
+        if is_banned_in(&banned, peer_id) {
+            return true;
+        }
+        
+        // Истекшую блокировку удаляем
+        banned.remove(peer_id);
+        false
     }
     
-    /// Добавить транспортный протокол
-    pub fn with_transport(mut self, transport_type: TransportType, transport: Box<dyn Transport>) -> Self {
-        self.transports.insert(transport_type, transport);
-        self
+    /// Опубликовать событие пира
+    fn emit(&self, event: PeerEvent) {
+        // Отсутствие подписчиков не является ошибкой
+        let _ = self.events_tx.send(event);
     }
     
-    /// Добавить механизм обнаружения узлов
-    pub fn with_discovery(mut self, discovery: Box<dyn Discovery>) -> Self {
-        self.discoveries.push(discovery);
-        self
+    /// Отметить успешную связь с пиром
+    ///
+    /// Подключенным считается только пир, подтвердивший владение ключом через
+    /// `Node::challenge_peer`; для остальных обновляется лишь время контакта.
+    fn mark_connected(&self, peer_id: &PeerId) {
+        let verified = self.verified_peers.lock().expect("Не удалось получить блокировку verified_peers")
+            .contains(peer_id);
+        
+        let info = {
+            let mut peers_lock = self.peers.lock().expect("Не удалось получить блокировку peers");
+            match peers_lock.get_mut(peer_id) {
+                Some(peer) if verified && !peer.is_connected() => {
+                    peer.set_status(PeerStatus::Connected);
+                    peer.update_last_seen();
+                    Some(peer.info().clone())
+                }
+                Some(peer) => {
+                    peer.update_last_seen();
+                    None
+                }
+                None => None,
+            }
+        };
+        
+        if let Some(info) = info {
+            self.emit(PeerEvent::Connected(info));
+        }
     }
     
-    /// Добавить поддержку mDNS для локального обнаружения
-    pub fn with_mdns(self) -> Self {
-        // Реализация будет добавлена в модуле discovery
-        // Это просто заглушка для интерфейса, описанного в README
-        self
+    /// Отметить ошибку транспорта при связи с пиром
+    ///
+    /// Если пир был подключен, планируется переподключение.
+    fn mark_failed(&self, peer_id: &PeerId) {
+        let state = {
+            let mut peers_lock = self.peers.lock().expect("Не удалось получить блокировку peers");
+            match peers_lock.get_mut(peer_id) {
+                Some(peer) => {
+                    let was_connected = peer.is_connected();
+                    peer.increment_failed_attempts();
+                    peer.set_status(PeerStatus::Disconnected);
+                    Some((was_connected, peer.failed_attempts()))
+                }
+                None => None,
+            }
+        };
+        
+        if let Some((true, attempts)) = state {
+            self.emit(PeerEvent::Disconnected(peer_id.clone()));
+            self.schedule_reconnect(peer_id, attempts);
+        }
     }
     
-    /// Добавить распределенную хеш-таблицу
-    pub fn with_dht(self) -> Self {
-        // Реализация будет добавлена в модуле dht
-        // Это просто заглушка для интерфейса, описанного в README
-        self
+    /// Запланировать переподключение с экспоненциальной задержкой
+    ///
+    /// Задержка удваивается с каждой неудачной попыткой: 1 с, 2 с, 4 с и т.д.,
+    /// но не превышает `RECONNECT_MAX_DELAY`. После `max_reconnect_attempts`
+    /// неудач попытки прекращаются.
+    fn schedule_reconnect(&self, peer_id: &PeerId, failed_attempts: u32) {
+        let mut reconnect_at = self.reconnect_at.lock().expect("Не удалось получить блокировку reconnect_at");
+        
+        if failed_attempts > self.config.max_reconnect_attempts {
+            tracing::debug!("Попытки переподключения к {} исчерпаны", peer_id);
+            reconnect_at.remove(peer_id);
+            return;
+        }
+        
+        let exponent = failed_attempts.saturating_sub(1).min(16);
+        let delay = RECONNECT_BASE_DELAY.saturating_mul(1 << exponent).min(RECONNECT_MAX_DELAY);
+        reconnect_at.insert(peer_id.clone(), Instant::now() + delay);
     }
     
-    /// Установить идентификатор узла
-    pub fn with_peer_id(mut self, peer_id: PeerId) -> Self {
-        self.peer_id = Some(peer_id);
-        self
+    /// Добавить нового пира с учетом ограничения `max_peers`
+    ///
+    /// Если список заполнен, вытесняется неподключенный пир, дольше всех не выходивший на связь.
+    /// Подключенные пиры не вытесняются; если вытеснить некого, новый пир
+    /// не добавляется. Возвращает `true`, если пир добавлен.
+    fn insert_peer(&self, peers: &mut HashMap<PeerId, Peer>, peer: Peer) -> bool {
+        let id = peer.info().id.clone();
+        
+        if self.is_banned(&id) {
+            return false;
+        }
+        
+        if let Some(max_peers) = self.config.max_peers {
+            if !peers.contains_key(&id) && peers.len() >= max_peers {
+                let victim = peers.iter()
+                    .filter(|(_, peer)| !peer.is_connected())
+                    .max_by_key(|(_, peer)| peer.time_since_last_seen())
+                    .map(|(id, _)| id.clone());
+                
+                match victim {
+                    Some(victim) => {
+                        tracing::debug!("Список пиров заполнен, вытесняем {}", victim);
+                        peers.remove(&victim);
+                        self.verified_peers.lock().expect("Не удалось получить блокировку verified_peers")
+                            .remove(&victim);
+                        self.reconnect_at.lock().expect("Не удалось получить блокировку reconnect_at")
+                            .remove(&victim);
+                    }
+                    None => return false,
+                }
+            }
+        }
+        
+        if !peers.contains_key(&id) {
+            self.emit(PeerEvent::Discovered(peer.info().clone()));
+        }
+        peers.insert(id, peer);
+        true
     }
     
-    /// Создать узел с заданными параметрами
-    pub fn build(self) -> Result<Node> {
-        // Если идентификатор не указан, генерируем случайный
-        let peer_id = self.peer_id.unwrap_or_else(|| {
-            // Генерируем случайный ID
-            use rand::Rng;
-            let mut rng = rand::thread_rng();
-            let bytes: Vec<u8> = (0..32).map(|_| rng.gen()).collect();
-            PeerId::new(bytes)
-        });
+    /// Ответить на запрос информации об узле
+    ///
+    /// Ответ — подписанное объявление о присутствии, отправленное по адресу,
+    /// с которого пришел запрос. Запрашивающий не обязан быть известным пиром.
+    async fn handle_identify(&self, message: &Message) -> Result<()> {
+        if message.message_type != MessageType::Identify {
+            return Err(Error::Network("Сообщение не является запросом информации об узле".to_string()));
+        }
         
-        let node = Node::new(
-            peer_id,
-            self.listen_addr,
-            self.port,
-            self.transports,
-            self.discoveries,
-            self.dht,
+        self.ensure_can_send()?;
+        
+        let source = message.source.as_ref()
+            .ok_or_else(|| Error::Network("Неизвестен адрес отправителя запроса".to_string()))?;
+        let transport = self.transports.get(&source.transport)
+            .ok_or_else(|| Error::Network(format!("Нет транспорта для адреса {}", source)))?;
+        
+        let announcement = Announcement::new_signed(self.local_info(), &self.keypair)?;
+        let response = Message::new(
+            self.peer_id.clone(),
+            Some(message.from.clone()),
+            MessageType::Announce,
+            announcement.to_bytes()?,
         );
+        let payload = self.encode_outgoing(&response)?;
         
-        Ok(node)
+        transport.read().await.send_to(source, &payload).await?;
+        self.stats.record_sent(payload.len());
+        Ok(())
+    }
+    
+    /// Отправить данные по адресу пира, ограничив отправку таймаутом
+    async fn send_with_timeout(
+        peer_id: PeerId,
+        transport: &RwLock<Box<dyn Transport>>,
+        addr: Endpoint,
+        payload: &[u8],
+        timeout: Duration,
+    ) -> (PeerId, SendOutcome) {
+        let send = async { transport.read().await.send_to(&addr, payload).await };
+        let outcome = match tokio::time::timeout(timeout, send).await {
+            Ok(Ok(())) => SendOutcome::Delivered,
+            Ok(Err(e)) => SendOutcome::Failed(e.to_string()),
+            Err(_) => SendOutcome::TimedOut,
+        };
+        (peer_id, outcome)
+    }
+}
+
+impl Node {
+    /// Создать новый узел с помощью NodeBuilder
+    pub fn builder() -> NodeBuilder {
+        NodeBuilder::new()
+    }
+    
+    /// Внутренний метод создания узла
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        peer_id: PeerId,
+        listen_addr: String,
+        port: u16,
+        external_addr: Option<(String, u16)>,
+        transports: HashMap<TransportType, Box<dyn Transport>>,
+        discoveries: Vec<Box<dyn Discovery>>,
+        dht: Option<Box<dyn Dht>>,
+        keypair: Ed25519KeyPair,
+        config: NodeConfig,
+    ) -> Self {
+        let (broadcast_tx, _) = broadcast::channel(100);
+        let (events_tx, _) = broadcast::channel(100);
+        
+        let shared = NodeShared {
+            peer_id,
+            listen_addr,
+            port,
+            external_addr,
+            keypair: Arc::new(keypair),
+            transports: transports.into_iter()
+                .map(|(transport_type, transport)| (transport_type, Arc::new(RwLock::new(transport))))
+                .collect(),
+            peers: Arc::new(Mutex::new(HashMap::new())),
+            broadcast_tx,
+            events_tx,
+            pending_requests: Arc::new(Mutex::new(HashMap::new())),
+            seen_messages: Arc::new(Mutex::new(SeenCache::new(config.seen_cache_size))),
+            banned: Arc::new(Mutex::new(HashMap::new())),
+            rate_limiter: config.rate_limit.map(|limit| Arc::new(Mutex::new(RateLimiter::new(limit)))),
+            stats: Arc::new(StatsCounters::default()),
+            peer_keys: Arc::new(Mutex::new(HashMap::new())),
+            reassembler: Arc::new(Mutex::new(Reassembler::new(config.reassembly_timeout))),
+            announce_seen: Arc::new(Mutex::new(HashMap::new())),
+            pinned_keys: Arc::new(Mutex::new(HashMap::new())),
+            reconnect_at: Arc::new(Mutex::new(HashMap::new())),
+            verified_peers: Arc::new(Mutex::new(HashSet::new())),
+            config: Arc::new(config),
+        };
+        
+        Self {
+            shared,
+            discoveries,
+            dht,
+            started_at: Instant::now(),
+            tasks: Vec::new(),
+            last_discovery: None,
+            connected: false,
+        }
+    }
+    
+    /// Учесть измеренное время приема-передачи для пира
+    pub fn record_rtt(&self, peer_id: &PeerId, rtt: Duration) {
+        let mut peers_lock = self.shared.peers.lock().expect("Не удалось получить блокировку peers");
+        if let Some(peer) = peers_lock.get_mut(peer_id) {
+            peer.record_rtt(rtt);
+        }
+    }
+    
+    /// Установить исходящие соединения с пирами
+    ///
+    /// Используется наблюдателем, который не принимает входящих соединений
+    /// и получает сообщения только по собственным соединениям. Ошибки
+    /// подключения к отдельным пирам не прерывают процесс.
+    async fn dial_peers(&mut self, peers: &[PeerInfo]) {
+        for peer in peers {
+            let addr = match &peer.address {
+                Some(addr) => addr,
+                None => continue,
+            };
+            let transport = match self.shared.transports.get(&addr.transport) {
+                Some(transport) => transport,
+                None => continue,
+            };
+            
+            match transport.write().await.connect(addr).await {
+                Ok(()) => self.shared.mark_connected(&peer.id),
+                Err(e) => {
+                    tracing::warn!("Не удалось подключиться к {}: {}", addr, e);
+                    self.shared.mark_failed(&peer.id);
+                }
+            }
+        }
+    }
+    
+    /// Определить адреса пиров без известного адреса через DHT
+    ///
+    /// Возвращает количество пиров, адрес которых удалось найти.
+    pub async fn resolve_missing_addresses(&mut self) -> Result<usize> {
+        let missing: Vec<PeerId> = {
+            let peers_lock = self.shared.peers.lock().expect("Не удалось получить блокировку peers");
+            peers_lock.values()
+                .filter(|peer| peer.info().address.is_none())
+                .map(|peer| peer.info().id.clone())
+                .collect()
+        };
+        
+        let dht = match &mut self.dht {
+            Some(dht) if !missing.is_empty() => dht,
+            _ => return Ok(0),
+        };
+        
+        let mut resolved = 0;
+        for peer_id in missing {
+            // Ошибки поиска отдельного пира не прерывают разрешение остальных
+            let records = match dht.find_nodes(&peer_id).await {
+                Ok(records) => records,
+                Err(_) => continue,
+            };
+            
+            let address = records.into_iter()
+                .find(|record| record.id == peer_id)
+                .and_then(|record| record.address);
+            
+            if let Some(address) = address {
+                let mut peers_lock = self.shared.peers.lock().expect("Не удалось получить блокировку peers");
+                if let Some(peer) = peers_lock.get_mut(&peer_id) {
+                    let mut info = peer.info().clone();
+                    info.address = Some(address);
+                    peer.update_info(info);
+                    resolved += 1;
+                }
+            }
+        }
+        
+        Ok(resolved)
+    }
+    
+    /// Отправить сообщение всем известным узлам, кроме указанных
+    pub async fn broadcast_except(&mut self, data: &[u8], exclude: &[PeerId]) -> Result<BroadcastReport> {
+        self.shared.ensure_can_send()?;
+        
+        // Сначала пытаемся найти адреса пиров, обнаруженных без адреса
+        self.resolve_missing_addresses().await?;
+        
+        Ok(self.shared.broadcast_message_except(MessageType::Data, data, exclude).await)
+    }
+    
+    /// Отправить пиру данные, разделив их на части
+    ///
+    /// Данные делятся на части не длиннее `chunk_size` байт, каждая из которых
+    /// отправляется отдельным сообщением и помещается в кадр транспорта.
+    /// Получатель собирает части в любом порядке и публикует одно сообщение
+    /// с данными. Передача, не собранная за `reassembly_timeout`, отбрасывается.
+    pub async fn send_chunked(&mut self, peer_id: &PeerId, data: &[u8]) -> Result<()> {
+        let mut transfer_id = [0u8; 16];
+        rand::Rng::fill(&mut rand::thread_rng(), &mut transfer_id);
+        
+        for chunk in Chunk::split(transfer_id, MessageType::Data, data, self.shared.config.chunk_size)? {
+            self.shared.send_message(peer_id, MessageType::Chunk, &chunk.to_bytes()?).await?;
+        }
+        
+        Ok(())
+    }
+    
+    /// Отправить пиру значение, сериализованное bincode
+    ///
+    /// Получатель восстанавливает значение через `Message::decode`.
+    pub async fn send_typed<T: Serialize>(&mut self, peer_id: &PeerId, value: &T) -> Result<()> {
+        let data = bincode::serialize(value)
+            .map_err(|e| Error::Serialization(format!("Не удалось сериализовать данные: {}", e)))?;
+        self.send_to(peer_id, &data).await
+    }
+    
+    /// Разослать всем известным пирам значение, сериализованное bincode
+    pub async fn broadcast_typed<T: Serialize>(&mut self, value: &T) -> Result<BroadcastReport> {
+        let data = bincode::serialize(value)
+            .map_err(|e| Error::Serialization(format!("Не удалось сериализовать данные: {}", e)))?;
+        self.broadcast(&data).await
+    }
+    
+    /// Разослать собственное готовое сообщение всем известным пирам
+    ///
+    /// В отличие от `relay`, TTL не уменьшается: сообщение уходит с начальным
+    /// значением, например заданным через `Message::with_ttl`.
+    pub async fn broadcast_own(&self, message: &Message) -> Result<BroadcastReport> {
+        self.shared.ensure_can_send()?;
+        
+        Ok(self.shared.broadcast_prepared(message, &[]).await)
+    }
+    
+    /// Переслать полученное сообщение всем пирам, кроме его автора
+    ///
+    /// Идентификатор сообщения сохраняется, поэтому узлы, отслеживающие уже
+    /// виденные сообщения, распознают пересланную копию. TTL копии уменьшается
+    /// на единицу; сообщение с исчерпанным TTL не пересылается.
+    pub async fn relay(&self, message: &Message) -> Result<BroadcastReport> {
+        self.shared.ensure_can_send()?;
+        
+        match message.forwarded() {
+            Some(copy) => Ok(self.shared.broadcast_prepared(&copy, std::slice::from_ref(&copy.from)).await),
+            None => Ok(BroadcastReport::default()),
+        }
+    }
+    
+    /// Переслать полученное сообщение с данными остальным пирам
+    ///
+    /// Отправитель сообщения исключается из рассылки, чтобы сообщение не
+    /// возвращалось туда, откуда пришло. Сообщение с исчерпанным TTL не пересылается.
+    pub async fn forward_broadcast(&mut self, message: &Message) -> Result<BroadcastReport> {
+        if message.message_type != MessageType::Data {
+            return Err(Error::Network("Пересылать можно только сообщения с данными".to_string()));
+        }
+        
+        // Сначала пытаемся найти адреса пиров, обнаруженных без адреса
+        self.resolve_missing_addresses().await?;
+        
+        self.relay(message).await
+    }
+    
+    /// Получить информацию о текущем узле для объявления
+    ///
+    /// Если задан внешний адрес, объявляется он, а не адрес прослушивания.
+    pub fn local_info(&self) -> PeerInfo {
+        self.shared.local_info()
+    }
+    
+    /// Разослать подписанное объявление о присутствии всем известным пирам
+    ///
+    /// Подключенный узел рассылает объявления сам каждые `announce_interval`;
+    /// метод позволяет объявить себя сразу, не дожидаясь очередного интервала.
+    pub async fn announce_presence(&self) -> Result<BroadcastReport> {
+        self.shared.announce().await
+    }
+    
+    /// Обработать объявление о присутствии от другого узла
+    ///
+    /// Проверяет подпись и добавляет отправителя в список известных узлов.
+    /// Принимаются только объявления, ключ которых принадлежит отправителю:
+    /// идентификатор выводится из ключа или ключ закреплен через `pin_peer_key`.
+    /// Повторные и устаревшие объявления игнорируются.
+    pub fn handle_announce(&self, message: &Message) -> Result<Option<PeerInfo>> {
+        self.shared.handle_announce(message)
+    }
+    
+    /// Закрепить публичный ключ за пиром
+    ///
+    /// Нужно для пиров, идентификатор которых задан явно и не выводится из
+    /// их ключа: объявления такого пира принимаются только с закрепленным ключом.
+    pub fn pin_peer_key(&mut self, peer_id: PeerId, public_key: Vec<u8>) {
+        self.shared.pinned_keys.lock().expect("Не удалось получить блокировку pinned_keys")
+            .insert(peer_id, public_key);
+    }
+    
+    /// Проверить, что пир владеет ключом своего идентификатора
+    ///
+    /// Отправляет пиру случайный вызов и ждет подписанного ответа; подключенные
+    /// узлы отвечают на вызовы сами. Пир считается подключенным только после
+    /// успешной проверки. Если ответ не получен за `timeout` или не прошел
+    /// проверку, пир удаляется из списка известных узлов и возвращается ошибка.
+    pub async fn challenge_peer(&mut self, peer_id: &PeerId, timeout: Duration) -> Result<()> {
+        let challenge = Challenge::random();
+        
+        // Подписываемся до отправки, чтобы не пропустить быстрый ответ
+        let mut rx = self.shared.broadcast_tx.subscribe();
+        self.shared.send_message(peer_id, MessageType::Challenge, &challenge.to_bytes()?).await?;
+        
+        let wait = async {
+            loop {
+                match rx.recv().await {
+                    Ok(message) if message.from == *peer_id
+                        && message.message_type == MessageType::ChallengeResponse => return Ok(message),
+                    Ok(_) => continue,
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        tracing::warn!("Пропущено {} входящих сообщений при ожидании {}", skipped, peer_id);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => {
+                        return Err(Error::Network("Канал входящих сообщений закрыт".to_string()));
+                    }
+                }
+            }
+        };
+        
+        let verified = match tokio::time::timeout(timeout, wait).await {
+            Ok(Ok(message)) => ChallengeResponse::from_bytes(&message.data)
+                .and_then(|response| response.verify(&challenge, peer_id))
+                .unwrap_or(false),
+            Ok(Err(e)) => return Err(e),
+            Err(_) => false,
+        };
+        
+        if !verified {
+            self.shared.reject_peer(peer_id);
+            return Err(Error::Network(format!("Пир {} не подтвердил владение ключом", peer_id)));
+        }
+        
+        self.shared.verified_peers.lock().expect("Не удалось получить блокировку verified_peers")
+            .insert(peer_id.clone());
+        self.shared.mark_connected(peer_id);
+        
+        Ok(())
+    }
+    
+    /// Измерить время приема-передачи до пира
+    ///
+    /// Отправляет пиру `Ping` с временной меткой и ждет `Pong` с тем же
+    /// идентификатором не дольше адаптивного таймаута пира. Измеренное
+    /// значение сохраняется у пира и учитывается в его адаптивном таймауте.
+    pub async fn ping(&mut self, peer_id: &PeerId) -> Result<Duration> {
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_millis() as u64)
+            .unwrap_or(0);
+        let message = Message::new(self.shared.peer_id.clone(), Some(peer_id.clone()), MessageType::Ping, timestamp.to_be_bytes().to_vec());
+        let timeout = self.peer_timeout(peer_id);
+        
+        // Регистрируем ожидание до отправки, чтобы не пропустить быстрый ответ
+        let (waiter, pong) = oneshot::channel();
+        self.shared.pending_requests.lock().expect("Не удалось получить блокировку pending_requests")
+            .insert(message.id, (peer_id.clone(), waiter));
+        
+        let sent_at = Instant::now();
+        if let Err(e) = self.shared.send_prepared(peer_id, &message).await {
+            self.shared.pending_requests.lock().expect("Не удалось получить блокировку pending_requests")
+                .remove(&message.id);
+            return Err(e);
+        }
+        
+        match tokio::time::timeout(timeout, pong).await {
+            Ok(Ok(_)) => {
+                let rtt = sent_at.elapsed();
+                self.record_rtt(peer_id, rtt);
+                Ok(rtt)
+            }
+            Ok(Err(_)) => Err(Error::Network(format!("Ожидание ответа от {} прервано", peer_id))),
+            Err(_) => {
+                self.shared.pending_requests.lock().expect("Не удалось получить блокировку pending_requests")
+                    .remove(&message.id);
+                Err(Error::Timeout(format!("Нет ответа на ping от {} за {:?}", peer_id, timeout)))
+            }
+        }
+    }
+    
+    /// Подтвердил ли пир владение ключом своего идентификатора
+    pub fn is_verified(&self, peer_id: &PeerId) -> bool {
+        self.shared.verified_peers.lock().expect("Не удалось получить блокировку verified_peers")
+            .contains(peer_id)
+    }
+    
+    /// Получить снимок показателей работы узла
+    ///
+    /// Счетчики сообщений читаются без блокировок; для подсчета пиров
+    /// список пиров блокируется на время одного прохода.
+    pub fn stats(&self) -> NodeStats {
+        let mut stats = NodeStats {
+            uptime: self.started_at.elapsed(),
+            ..NodeStats::default()
+        };
+        self.shared.stats.fill(&mut stats);
+        
+        let peers_lock = self.shared.peers.lock().expect("Не удалось получить блокировку peers");
+        stats.peer_count = peers_lock.len();
+        stats.connected_peers = peers_lock.values().filter(|peer| peer.is_connected()).count();
+        
+        stats
+    }
+    
+    /// Количество входящих сообщений, отброшенных из-за ограничения частоты
+    pub fn rate_limited_messages(&self) -> u64 {
+        self.shared.rate_limiter.as_ref()
+            .map(|limiter| limiter.lock().expect("Не удалось получить блокировку rate_limiter").dropped())
+            .unwrap_or(0)
+    }
+    
+    /// Заблокировать пира
+    ///
+    /// Пир удаляется из списка известных узлов, его входящие сообщения
+    /// отбрасываются, а отправка ему и повторное обнаружение запрещены.
+    /// При `duration == None` блокировка бессрочная.
+    pub fn ban_peer(&mut self, peer_id: &PeerId, duration: Option<Duration>) {
+        let until = duration.map(|duration| Instant::now() + duration);
+        self.shared.banned.lock().expect("Не удалось получить блокировку banned").insert(peer_id.clone(), until);
+        self.shared.reject_peer(peer_id);
+    }
+    
+    /// Снять блокировку пира
+    pub fn unban_peer(&mut self, peer_id: &PeerId) {
+        self.shared.banned.lock().expect("Не удалось получить блокировку banned").remove(peer_id);
+    }
+    
+    /// Заблокирован ли пир
+    pub fn is_banned(&self, peer_id: &PeerId) -> bool {
+        self.shared.is_banned(peer_id)
+    }
+    
+    /// Получить поток событий изменения состояния пиров
+    ///
+    /// Поток получает только события, произошедшие после вызова.
+    pub fn events(&self) -> impl Stream<Item = PeerEvent> {
+        BroadcastStream::new(self.shared.events_tx.subscribe()).filter_map(|item| {
+            let event = match item {
+                Ok(event) => Some(event),
+                Err(e) => {
+                    tracing::warn!("Пропущены события пиров: {}", e);
+                    None
+                }
+            };
+            async move { event }
+        })
+    }
+    
+    /// Отметить подключенных пиров, с которыми давно не было контакта, как отключенных
+    ///
+    /// Для каждого такого пира публикуется `PeerEvent::Disconnected`.
+    /// Возвращает идентификаторы отключенных пиров.
+    pub fn mark_stale_peers(&self, timeout: Duration) -> Vec<PeerId> {
+        let stale: Vec<PeerId> = {
+            let mut peers_lock = self.shared.peers.lock().expect("Не удалось получить блокировку peers");
+            peers_lock.iter_mut()
+                .filter(|(_, peer)| peer.is_connected() && peer.is_stale(timeout))
+                .map(|(id, peer)| {
+                    peer.set_status(PeerStatus::Disconnected);
+                    id.clone()
+                })
+                .collect()
+        };
+        
+        for peer_id in &stale {
+            self.shared.emit(PeerEvent::Disconnected(peer_id.clone()));
+        }
+        
+        stale
+    }
+    
+    /// Переподключиться к потерянным пирам, для которых истекла задержка
+    ///
+    /// Предназначено для вызова в цикле обработки событий узла. Неудачная
+    /// попытка планирует следующую с удвоенной задержкой. Возвращает
+    /// идентификаторы пиров, к которым удалось переподключиться.
+    pub async fn reconnect_due(&mut self) -> Vec<PeerId> {
+        let now = Instant::now();
+        let due: Vec<PeerId> = {
+            let mut reconnect_at = self.shared.reconnect_at.lock().expect("Не удалось получить блокировку reconnect_at");
+            let due: Vec<PeerId> = reconnect_at.iter()
+                .filter(|(_, at)| **at <= now)
+                .map(|(id, _)| id.clone())
+                .collect();
+            for peer_id in &due {
+                reconnect_at.remove(peer_id);
+            }
+            due
+        };
+        
+        let mut reconnected = Vec::new();
+        
+        for peer_id in due {
+            let addr = {
+                let peers_lock = self.shared.peers.lock().expect("Не удалось получить блокировку peers");
+                peers_lock.get(&peer_id).and_then(|peer| peer.info().address.clone())
+            };
+            let addr = match addr {
+                Some(addr) => addr,
+                None => continue,
+            };
+            
+            let result = match self.shared.transports.get(&addr.transport) {
+                Some(transport) => transport.write().await.connect(&addr).await,
+                None => continue,
+            };
+            
+            match result {
+                Ok(()) => {
+                    self.shared.mark_connected(&peer_id);
+                    reconnected.push(peer_id);
+                }
+                Err(e) => {
+                    tracing::debug!("Не удалось переподключиться к {}: {}", addr, e);
+                    self.shared.mark_failed(&peer_id);
+                    
+                    let attempts = self.shared.peers.lock().expect("Не удалось получить блокировку peers")
+                        .get(&peer_id)
+                        .map(|peer| peer.failed_attempts());
+                    if let Some(attempts) = attempts {
+                        self.shared.schedule_reconnect(&peer_id, attempts);
+                    }
+                }
+            }
+        }
+        
+        reconnected
+    }
+    
+    /// Отправить запрос пиру и дождаться ответа
+    ///
+    /// Ответ сопоставляется с запросом по идентификатору сообщения, который
+    /// пир возвращает через `respond`. Если ответ не получен за `timeout`,
+    /// возвращается `Error::Network`.
+    pub async fn request(&mut self, peer_id: &PeerId, data: &[u8], timeout: Duration) -> Result<Vec<u8>> {
+        let message = Message::new(self.shared.peer_id.clone(), Some(peer_id.clone()), MessageType::Request, data.to_vec());
+        
+        // Регистрируем ожидание до отправки, чтобы не пропустить быстрый ответ
+        let (waiter, response) = oneshot::channel();
+        self.shared.pending_requests.lock().expect("Не удалось получить блокировку pending_requests")
+            .insert(message.id, (peer_id.clone(), waiter));
+        
+        if let Err(e) = self.shared.send_prepared(peer_id, &message).await {
+            self.shared.pending_requests.lock().expect("Не удалось получить блокировку pending_requests")
+                .remove(&message.id);
+            return Err(e);
+        }
+        
+        match tokio::time::timeout(timeout, response).await {
+            Ok(Ok(response)) => Ok(response.data),
+            Ok(Err(_)) => Err(Error::Network(format!("Ожидание ответа от {} прервано", peer_id))),
+            Err(_) => {
+                self.shared.pending_requests.lock().expect("Не удалось получить блокировку pending_requests")
+                    .remove(&message.id);
+                Err(Error::Network(format!("Нет ответа от {} за {:?}", peer_id, timeout)))
+            }
+        }
+    }
+    
+    /// Ответить на запрос, полученный от другого узла
+    ///
+    /// Ответ получает идентификатор запроса. Если отправитель запроса не
+    /// известен как пир, ответ отправляется по адресу, с которого пришел запрос.
+    pub async fn respond(&self, request: &Message, data: &[u8]) -> Result<()> {
+        if request.message_type != MessageType::Request {
+            return Err(Error::Network("Сообщение не является запросом".to_string()));
+        }
+        
+        let mut response = Message::new(self.shared.peer_id.clone(), Some(request.from.clone()), MessageType::Response, data.to_vec());
+        response.id = request.id;
+        
+        let known = self.shared.peers.lock().expect("Не удалось получить блокировку peers")
+            .get(&request.from)
+            .map(|peer| peer.info().address.is_some())
+            .unwrap_or(false);
+        
+        match (&request.source, known) {
+            (Some(source), false) => {
+                self.shared.ensure_can_send()?;
+                
+                let transport = self.shared.transports.get(&source.transport)
+                    .ok_or_else(|| Error::Network(format!("Нет транспорта для адреса {}", source)))?;
+                let payload = self.shared.encode_outgoing(&response)?;
+                
+                transport.read().await.send_to(source, &payload).await?;
+                self.shared.stats.record_sent(payload.len());
+                Ok(())
+            }
+            _ => self.shared.send_prepared(&request.from, &response).await,
+        }
+    }
+    
+    /// Получить адаптивный таймаут для запросов к пиру
+    ///
+    /// Для неизвестных пиров и пиров без измерений возвращается таймаут по умолчанию.
+    pub fn peer_timeout(&self, peer_id: &PeerId) -> Duration {
+        let peers_lock = self.shared.peers.lock().expect("Не удалось получить блокировку peers");
+        peers_lock.get(peer_id)
+            .map(|peer| peer.timeout())
+            .unwrap_or(self.shared.config.default_timeout)
+    }
+    
+    /// Дождаться следующего входящего сообщения от указанного пира
+    ///
+    /// Подписка создается при вызове, поэтому учитываются только сообщения,
+    /// поступившие после него. По истечении `timeout` возвращается `Error::Timeout`.
+    pub fn next_message_from(
+        &self,
+        peer_id: &PeerId,
+        timeout: Duration,
+    ) -> impl Future<Output = Result<Message>> + Send + 'static {
+        let mut rx = self.shared.broadcast_tx.subscribe();
+        let peer_id = peer_id.clone();
+        
+        async move {
+            let wait = async {
+                loop {
+                    match rx.recv().await {
+                        Ok(message) if message.from == peer_id => return Ok(message),
+                        Ok(_) => continue,
+                        Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                            tracing::warn!("Пропущено {} входящих сообщений при ожидании {}", skipped, peer_id);
+                        }
+                        Err(broadcast::error::RecvError::Closed) => {
+                            return Err(Error::Network("Канал входящих сообщений закрыт".to_string()));
+                        }
+                    }
+                }
+            };
+            
+            tokio::time::timeout(timeout, wait).await
+                .map_err(|_| Error::Timeout(format!("Нет сообщений от {} за {:?}", peer_id, timeout)))?
+        }
+    }
+}
+
+#[async_trait]
+impl NetworkNode for Node {
+    fn peer_id(&self) -> &PeerId {
+        &self.shared.peer_id
+    }
+    
+    async fn connect(&mut self) -> Result<()> {
+        if self.connected {
+            return Ok(());
+        }
+        
+        // Запускаем все транспортные протоколы; наблюдатель входящих соединений не принимает
+        if self.shared.config.mode != NodeMode::Observer {
+            for transport in self.shared.transports.values() {
+                transport.write().await.listen(&self.shared.listen_addr, self.shared.port).await?;
+            }
+        }
+        
+        // Передаем входящие данные транспортов в поток входящих сообщений узла
+        for (transport_type, transport) in &self.shared.transports {
+            let rx = transport.read().await.incoming();
+            self.tasks.push(tokio::spawn(self.shared.clone().receive_loop(rx, transport_type.clone())));
+        }
+        
+        // Наблюдатель себя не объявляет
+        if self.shared.config.mode != NodeMode::Observer && !self.shared.config.announce_interval.is_zero() {
+            self.tasks.push(tokio::spawn(self.shared.clone().announce_loop()));
+        }
+        
+        if self.shared.config.mode == NodeMode::Observer {
+            let known = self.peers();
+            self.dial_peers(&known).await;
+        }
+        
+        self.connected = true;
+        Ok(())
+    }
+    
+    async fn disconnect(&mut self) -> Result<()> {
+        if !self.connected {
+            return Ok(());
+        }
+        
+        // Останавливаем прием входящих сообщений и объявления о присутствии
+        for task in self.tasks.drain(..) {
+            task.abort();
+        }
+        
+        // Останавливаем все транспортные протоколы
+        for transport in self.shared.transports.values() {
+            transport.write().await.close().await?;
+        }
+        
+        self.connected = false;
+        Ok(())
+    }
+    
+    async fn discover_peers(&mut self) -> Result<Vec<PeerInfo>> {
+        // Повторный вызов в пределах интервала возвращает предыдущий результат
+        if let Some((last_run, peers)) = &self.last_discovery {
+            if last_run.elapsed() < self.shared.config.discovery_interval {
+                return Ok(peers.clone());
+            }
+        }
+        
+        let mut all_peers = Vec::new();
+        
+        // Запускаем все механизмы обнаружения
+        for discovery in &mut self.discoveries {
+            let peers = discovery.discover().await?;
+            all_peers.extend(peers);
+        }
+        
+        // Если включен DHT, используем его для обнаружения
+        if let Some(dht) = &mut self.dht {
+            let peers = dht.find_nodes(&self.shared.peer_id).await?;
+            all_peers.extend(peers);
+        }
+        
+        // Добавляем найденных пиров в список известных
+        let mut new_peers = Vec::new();
+        {
+            let mut peers_lock = self.shared.peers.lock().expect("Не удалось получить блокировку peers");
+            for peer_info in &all_peers {
+                if !peers_lock.contains_key(&peer_info.id) {
+                    let peer = Peer::with_default_timeout(peer_info.clone(), self.shared.config.default_timeout);
+                    if self.shared.insert_peer(&mut peers_lock, peer) {
+                        new_peers.push(peer_info.clone());
+                    }
+                }
+            }
+        }
+        
+        // Наблюдатель получает сообщения только по собственным соединениям
+        if self.connected && self.shared.config.mode == NodeMode::Observer {
+            self.dial_peers(&new_peers).await;
+        }
+        
+        self.last_discovery = Some((Instant::now(), all_peers.clone()));
+        
+        Ok(all_peers)
+    }
+    
+    async fn send_to(&mut self, peer_id: &PeerId, data: &[u8]) -> Result<()> {
+        self.shared.send_message(peer_id, MessageType::Data, data).await
+    }
+    
+    async fn broadcast(&mut self, data: &[u8]) -> Result<BroadcastReport> {
+        self.shared.ensure_can_send()?;
+        
+        // Сначала пытаемся найти адреса пиров, обнаруженных без адреса
+        self.resolve_missing_addresses().await?;
+        
+        Ok(self.shared.broadcast_message(MessageType::Data, data).await)
+    }
+    
+    fn peers(&self) -> Vec<PeerInfo> {
+        let peers_lock = self.shared.peers.lock().expect("Не удалось получить блокировку peers");
+        let banned = self.shared.banned.lock().expect("Не удалось получить блокировку banned");
+        peers_lock.values()
+            .filter(|p| !is_banned_in(&banned, &p.info().id))
+            .map(|p| p.info().clone())
+            .collect()
+    }
+    
+    fn incoming(&self) -> IncomingMessages {
+        IncomingMessages::new(self.shared.broadcast_tx.subscribe())
+    }
+}
+
+/// Строитель для настройки и создания узла сети
+pub struct NodeBuilder {
+    listen_addr: String,
+    port: u16,
+    external_addr: Option<(String, u16)>,
+    transports: HashMap<TransportType, Box<dyn Transport>>,
+    discoveries: Vec<Box<dyn Discovery>>,
+    dht: Option<Box<dyn Dht>>,
+    peer_id: Option<PeerId>,
+    keypair: Option<Ed25519KeyPair>,
+    mdns: bool,
+    kademlia: bool,
+    config: NodeConfig,
+}
+
+impl Default for NodeBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NodeBuilder {
+    /// Создать новый строитель узла
+    pub fn new() -> Self {
+        Self {
+            listen_addr: "127.0.0.1".to_string(),
+            port: 0, // Случайный порт
+            external_addr: None,
+            transports: HashMap::new(),
+            discoveries: Vec::new(),
+            dht: None,
+            peer_id: None,
+            keypair: None,
+            mdns: false,
+            kademlia: false,
+            config: NodeConfig::default(),
+        }
+    }
+    
+    /// Установить адрес для прослушивания
+    pub fn with_address(mut self, address: impl Into<String>) -> Self {
+        self.listen_addr = address.into();
+        self
+    }
+    
+    /// Установить порт для прослушивания
+    pub fn with_port(mut self, port: u16) -> Self {
+        self.port = port;
+        self
+    }
+    
+    /// Установить внешний адрес и порт, по которым узел доступен другим узлам
+    ///
+    /// Используется в объявлениях и записях о узле, когда адрес прослушивания
+    /// недоступен извне (например, за NAT с пробросом портов). Прослушивание
+    /// по-прежнему выполняется на адресе и порту, заданных `with_address`/`with_port`.
+    pub fn with_external_address(mut self, address: impl Into<String>, port: u16) -> Self {
+        self.external_addr = Some((address.into(), port));
+        self
+    }
+    
+    /// Добавить транспортный протокол
+    ///
+    /// Транспорты различаются по типу: повторная регистрация того же типа
+    /// заменяет прежний транспорт, а пользовательские транспорты с разными
+    /// именами (`TransportType::custom`) хранятся независимо.
+    pub fn with_transport(mut self, transport_type: TransportType, transport: Box<dyn Transport>) -> Self {
+        self.transports.insert(transport_type, transport);
+        self
+    }
+    
+    /// Добавить механизм обнаружения узлов
+    pub fn with_discovery(mut self, discovery: Box<dyn Discovery>) -> Self {
+        self.discoveries.push(discovery);
+        self
+    }
+    
+    /// Добавить поддержку mDNS для локального обнаружения
+    ///
+    /// Механизм создается при сборке узла, когда известны его идентификатор и порт.
+    pub fn with_mdns(mut self) -> Self {
+        self.mdns = true;
+        self
+    }
+    
+    /// Добавить распределенную хеш-таблицу Kademlia
+    ///
+    /// Таблица создается при сборке узла, когда известен его идентификатор.
+    pub fn with_dht(mut self) -> Self {
+        self.kademlia = true;
+        self
+    }
+    
+    /// Установить идентификатор узла
+    ///
+    /// По умолчанию идентификатор выводится из публичного ключа узла. Объявления
+    /// узла с другим идентификатором другие узлы принимают, только если
+    /// закрепили его ключ через `Node::pin_peer_key`.
+    /// Идентификатор должен иметь длину `PEER_ID_LEN`, иначе `build` вернет ошибку.
+    pub fn with_peer_id(mut self, peer_id: PeerId) -> Self {
+        self.peer_id = Some(peer_id);
+        self
+    }
+    
+    /// Установить таймаут запросов, используемый до появления измерений RTT
+    pub fn with_default_timeout(mut self, timeout: Duration) -> Self {
+        self.config.default_timeout = timeout;
+        self
+    }
+    
+    /// Установить фиксированный таймаут отправки одному пиру
+    pub fn with_send_timeout(mut self, timeout: Duration) -> Self {
+        self.config.send_timeout = Some(timeout);
+        self
+    }
+    
+    /// Установить максимальное количество одновременных отправок при рассылке
+    pub fn with_broadcast_concurrency(mut self, concurrency: usize) -> Self {
+        self.config.broadcast_concurrency = concurrency;
+        self
+    }
+    
+    /// Установить интервал между объявлениями о присутствии
+    ///
+    /// Нулевой интервал отключает периодические объявления.
+    pub fn with_announce_interval(mut self, interval: Duration) -> Self {
+        self.config.announce_interval = interval;
+        self
+    }
+    
+    /// Установить минимальный интервал между запусками обнаружения узлов
+    ///
+    /// Вызовы `discover_peers` внутри интервала возвращают кешированный результат.
+    pub fn with_discovery_interval(mut self, interval: Duration) -> Self {
+        self.config.discovery_interval = interval;
+        self
+    }
+    
+    /// Ограничить количество известных пиров
+    ///
+    /// При заполнении списка новые пиры вытесняют наименее ценных
+    /// неподключенных пиров.
+    pub fn with_max_peers(mut self, max_peers: usize) -> Self {
+        self.config.max_peers = Some(max_peers);
+        self
+    }
+    
+    /// Установить количество запоминаемых идентификаторов сообщений
+    ///
+    /// Повторно полученные сообщения с запомненным идентификатором отбрасываются,
+    /// что предотвращает бесконечную пересылку в сетях с циклами.
+    pub fn with_seen_cache_size(mut self, size: usize) -> Self {
+        self.config.seen_cache_size = size;
+        self
+    }
+    
+    /// Установить максимальное количество попыток переподключения к потерянному пиру
+    pub fn with_max_reconnect_attempts(mut self, attempts: u32) -> Self {
+        self.config.max_reconnect_attempts = attempts;
+        self
+    }
+    
+    /// Ограничить частоту входящих сообщений от каждого пира
+    ///
+    /// Сообщения сверх `messages_per_sec` в секунду (с допустимым всплеском
+    /// до `burst` сообщений подряд) отбрасываются.
+    pub fn with_rate_limit(mut self, messages_per_sec: u32, burst: u32) -> Self {
+        self.config.rate_limit = Some(RateLimit { messages_per_sec, burst });
+        self
+    }
+    
+    /// Включить подпись сообщений
+    ///
+    /// Узел подписывает свои сообщения ключом из `with_keypair` и отбрасывает
+    /// входящие сообщения, подпись которых не проверяется ключом отправителя.
+    /// Ключи пиров узнаются из их подписанных объявлений, поэтому до
+    /// объявления сообщения пира не принимаются. Ключ из объявления
+    /// принимается, только если из него выводится идентификатор пира или он
+    /// закреплен через `Node::pin_peer_key`.
+    pub fn with_signed_messages(mut self) -> Self {
+        self.config.signed_messages = true;
+        self
+    }
+    
+    /// Установить формат сериализации исходящих сообщений
+    ///
+    /// Входящие сообщения разбираются в формате, указанном отправителем,
+    /// поэтому узлы с разными форматами понимают друг друга.
+    pub fn with_serialization(mut self, format: SerializationFormat) -> Self {
+        self.config.serialization = format;
+        self
+    }
+    
+    /// Установить размер данных одной части для `send_chunked`
+    ///
+    /// Размер должен оставлять запас под заголовки в пределах
+    /// максимального размера кадра транспорта.
+    pub fn with_chunk_size(mut self, size: usize) -> Self {
+        self.config.chunk_size = size.max(1);
+        self
+    }
+    
+    /// Установить время ожидания недостающих частей входящей передачи
+    pub fn with_reassembly_timeout(mut self, timeout: Duration) -> Self {
+        self.config.reassembly_timeout = timeout;
+        self
+    }
+    
+    /// Включить сжатие данных исходящих сообщений
+    ///
+    /// Данные от `threshold` байт сжимаются zstd, более короткие
+    /// отправляются как есть. Сжатые сообщения распаковываются при приеме
+    /// независимо от этой настройки.
+    pub fn with_compression(mut self, threshold: usize) -> Self {
+        self.config.compression_threshold = Some(threshold);
+        self
+    }
+    
+    /// Установить режим работы узла
+    pub fn with_mode(mut self, mode: NodeMode) -> Self {
+        self.config.mode = mode;
+        self
+    }
+    
+    /// Установить ключевую пару узла для подписи объявлений
+    pub fn with_keypair(mut self, keypair: Ed25519KeyPair) -> Self {
+        self.keypair = Some(keypair);
+        self
+    }
+    
+    /// Создать узел с заданными параметрами
+    pub fn build(self) -> Result<Node> {
+        // Если ключевая пара не указана, генерируем новую
+        let keypair = match self.keypair {
+            Some(keypair) => keypair,
+            None => Ed25519KeyPair::generate()?,
+        };
+        
+        // Если идентификатор не указан, выводим его из публичного ключа,
+        // чтобы узел мог доказать владение им
+        let peer_id = match self.peer_id {
+            Some(peer_id) => PeerId::try_new(peer_id.as_bytes().to_vec())?,
+            None => PeerId::from_public_key(&keypair.public_bytes()),
+        };
+        
+        let mut discoveries = self.discoveries;
+        if self.mdns {
+            let port = self.external_addr.as_ref().map(|(_, port)| *port).unwrap_or(self.port);
+            discoveries.push(Box::new(MdnsDiscovery::new(peer_id.clone(), port)));
+        }
+        
+        let mut dht = self.dht;
+        if self.kademlia && dht.is_none() {
+            dht = Some(Box::new(KademliaDht::new(peer_id.clone())?));
+        }
+        
+        let node = Node::new(
+            peer_id,
+            self.listen_addr,
+            self.port,
+            self.external_addr,
+            self.transports,
+            discoveries,
+            dht,
+            keypair,
+            self.config,
+        );
+        
+        Ok(node)
+    }
+} 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::SocketAddr;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Instant;
+    use std::sync::atomic::AtomicBool;
+    use serde::Deserialize;
+    
+    /// Общая сеть транспортов в памяти: адрес прослушивания и канал входящих данных
+    type MockNetwork = Arc<Mutex<HashMap<String, broadcast::Sender<(Vec<u8>, SocketAddr)>>>>;
+    
+    /// Отправленные данные и адрес получателя
+    type SentFrame = (Endpoint, Vec<u8>);
+    
+    /// Транспорт в памяти, запоминающий отправленные данные
+    ///
+    /// Данные доставляются транспортам той же сети, слушающим адрес получателя.
+    #[derive(Clone)]
+    struct MockTransport {
+        network: MockNetwork,
+        local: Option<SocketAddr>,
+        incoming_tx: broadcast::Sender<(Vec<u8>, SocketAddr)>,
+        sent: Arc<Mutex<Vec<SentFrame>>>,
+        hanging: Vec<Endpoint>,
+    }
+    
+    impl MockTransport {
+        fn on(network: &MockNetwork) -> Self {
+            Self {
+                network: Arc::clone(network),
+                local: None,
+                incoming_tx: broadcast::channel(100).0,
+                sent: Arc::new(Mutex::new(Vec::new())),
+                hanging: Vec::new(),
+            }
+        }
+    }
+    
+    impl Default for MockTransport {
+        fn default() -> Self {
+            Self::on(&MockNetwork::default())
+        }
+    }
+    
+    #[async_trait]
+    impl Transport for MockTransport {
+        fn transport_type(&self) -> TransportType {
+            TransportType::Tcp
+        }
+        
+        async fn listen(&mut self, address: &str, port: u16) -> Result<()> {
+            let local: SocketAddr = format!("{}:{}", address, port).parse().unwrap();
+            self.network.lock().unwrap().insert(local.to_string(), self.incoming_tx.clone());
+            self.local = Some(local);
+            Ok(())
+        }
+        
+        async fn connect(&mut self, _endpoint: &Endpoint) -> Result<()> {
+            Ok(())
+        }
+        
+        async fn send_to(&self, endpoint: &Endpoint, data: &[u8]) -> Result<()> {
+            if self.hanging.contains(endpoint) {
+                std::future::pending::<()>().await;
+            }
+            self.sent.lock().unwrap().push((endpoint.clone(), data.to_vec()));
+            
+            let target = self.network.lock().unwrap().get(&endpoint.socket_addr()).cloned();
+            if let Some(target) = target {
+                let from = self.local.unwrap_or_else(|| "0.0.0.0:0".parse().unwrap());
+                let _ = target.send((data.to_vec(), from));
+            }
+            Ok(())
+        }
+        
+        fn incoming(&self) -> broadcast::Receiver<(Vec<u8>, SocketAddr)> {
+            self.incoming_tx.subscribe()
+        }
+        
+        async fn close(&mut self) -> Result<()> {
+            if let Some(local) = self.local.take() {
+                self.network.lock().unwrap().remove(&local.to_string());
+            }
+            Ok(())
+        }
+    }
+    
+    /// Механизм обнаружения, возвращающий заранее заданный список узлов
+    struct StaticDiscovery {
+        peers: Vec<PeerInfo>,
+        calls: Arc<AtomicUsize>,
+    }
+    
+    impl StaticDiscovery {
+        fn new(peers: Vec<PeerInfo>) -> Self {
+            Self { peers, calls: Arc::new(AtomicUsize::new(0)) }
+        }
+    }
+    
+    #[async_trait]
+    impl Discovery for StaticDiscovery {
+        fn name(&self) -> &str {
+            "static"
+        }
+        
+        async fn start(&mut self) -> Result<()> {
+            Ok(())
+        }
+        
+        async fn stop(&mut self) -> Result<()> {
+            Ok(())
+        }
+        
+        async fn discover(&mut self) -> Result<Vec<PeerInfo>> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            Ok(self.peers.clone())
+        }
+    }
+    
+    fn keypair(id: u8) -> Ed25519KeyPair {
+        Ed25519KeyPair::from_private_key(&[id; 32]).unwrap()
+    }
+    
+    fn peer_id(id: u8) -> PeerId {
+        PeerId::from_public_key(&keypair(id).public_bytes())
+    }
+    
+    fn peer_info(id: u8, port: u16) -> PeerInfo {
+        PeerInfo {
+            id: peer_id(id),
+            address: Some(Endpoint::tcp("127.0.0.1", port)),
+            protocols: Vec::new(),
+            client_version: "test".to_string(),
+        }
+    }
+    
+    /// Создать узел с транспортом в памяти, слушающий 127.0.0.1:`port`
+    fn node_on(network: &MockNetwork, id: u8, port: u16) -> Node {
+        Node::builder()
+            .with_keypair(keypair(id))
+            .with_port(port)
+            .with_transport(TransportType::Tcp, Box::new(MockTransport::on(network)))
+            .build()
+            .unwrap()
+    }
+    
+    /// Добавить узел `other` в список известных узлов `node`
+    fn introduce(node: &Node, other: &Node) {
+        let info = other.local_info();
+        node.shared.peers.lock().unwrap().insert(info.id.clone(), Peer::new(info));
+    }
+    
+    #[tokio::test]
+    async fn hanging_peer_does_not_delay_broadcast() {
+        let hanging = peer_info(3, 7003);
+        let transport = MockTransport {
+            hanging: vec![hanging.address.clone().unwrap()],
+            ..Default::default()
+        };
+        let sent = transport.sent.clone();
+        let mut node = Node::builder()
+            .with_transport(TransportType::Tcp, Box::new(transport))
+            .with_discovery(Box::new(StaticDiscovery::new(vec![peer_info(1, 7001), peer_info(2, 7002), hanging])))
+            .with_send_timeout(Duration::from_millis(200))
+            .build()
+            .unwrap();
+        node.discover_peers().await.unwrap();
+        
+        let started = Instant::now();
+        let report = node.broadcast(b"hello").await.unwrap();
+        assert!(started.elapsed() < Duration::from_secs(2));
+        
+        assert_eq!(report.delivered.len(), 2);
+        assert_eq!(report.timed_out, vec![peer_id(3)]);
+        assert!(!report.is_complete());
+        assert_eq!(sent.lock().unwrap().len(), 2);
+    }
+    
+    #[tokio::test]
+    async fn peer_learns_endpoints_from_announcement() {
+        let transport = MockTransport::default();
+        let sent = transport.sent.clone();
+        let mut alice = Node::builder()
+            .with_port(7100)
+            .with_transport(TransportType::Tcp, Box::new(transport))
+            .with_discovery(Box::new(StaticDiscovery::new(vec![peer_info(2, 7200)])))
+            .build()
+            .unwrap();
+        let bob = Node::builder()
+            .with_transport(TransportType::Tcp, Box::new(MockTransport::default()))
+            .build()
+            .unwrap();
+        assert!(bob.peers().is_empty());
+        
+        alice.discover_peers().await.unwrap();
+        let report = alice.announce_presence().await.unwrap();
+        assert_eq!(report.delivered.len(), 1);
+        
+        let (_, payload) = sent.lock().unwrap().pop().unwrap();
+        let message = Message::from_bytes(&payload).unwrap();
+        let info = bob.handle_announce(&message).unwrap().unwrap();
+        assert_eq!(info.id, *alice.peer_id());
+        assert_eq!(info.address, Some(Endpoint::tcp("127.0.0.1", 7100)));
+        
+        let known = bob.peers();
+        assert_eq!(known.len(), 1);
+        assert_eq!(known[0].address, alice.local_info().address);
+        
+        // Повтор того же объявления игнорируется
+        assert!(bob.handle_announce(&message).unwrap().is_none());
+    }
+    
+    #[tokio::test]
+    async fn announcement_with_foreign_key_is_rejected() {
+        let transport = MockTransport::default();
+        let sent = transport.sent.clone();
+        let discovery = || Box::new(StaticDiscovery::new(vec![peer_info(2, 7200)]));
+        // Мэллори объявляет себя под идентификатором Алисы раньше самой Алисы
+        let mut mallory = Node::builder()
+            .with_peer_id(peer_id(1))
+            .with_keypair(keypair(3))
+            .with_transport(TransportType::Tcp, Box::new(transport.clone()))
+            .with_discovery(discovery())
+            .build()
+            .unwrap();
+        let mut alice = Node::builder()
+            .with_keypair(keypair(1))
+            .with_transport(TransportType::Tcp, Box::new(transport))
+            .with_discovery(discovery())
+            .build()
+            .unwrap();
+        let bob = Node::builder()
+            .with_transport(TransportType::Tcp, Box::new(MockTransport::default()))
+            .build()
+            .unwrap();
+        
+        mallory.discover_peers().await.unwrap();
+        mallory.announce_presence().await.unwrap();
+        let (_, payload) = sent.lock().unwrap().pop().unwrap();
+        let forged = Message::from_bytes(&payload).unwrap();
+        assert!(matches!(bob.handle_announce(&forged), Err(Error::Network(_))));
+        assert!(bob.peers().is_empty());
+        
+        // Настоящая Алиса принимается и после попытки Мэллори
+        alice.discover_peers().await.unwrap();
+        alice.announce_presence().await.unwrap();
+        let (_, payload) = sent.lock().unwrap().pop().unwrap();
+        let genuine = Message::from_bytes(&payload).unwrap();
+        assert_eq!(bob.handle_announce(&genuine).unwrap().unwrap().id, peer_id(1));
+    }
+    
+    #[tokio::test]
+    async fn pinned_key_admits_explicit_peer_id() {
+        let transport = MockTransport::default();
+        let sent = transport.sent.clone();
+        let carol_id = PeerId::new(vec![9; 32]);
+        let mut carol = Node::builder()
+            .with_peer_id(carol_id.clone())
+            .with_keypair(keypair(9))
+            .with_transport(TransportType::Tcp, Box::new(transport))
+            .with_discovery(Box::new(StaticDiscovery::new(vec![peer_info(2, 7200)])))
+            .build()
+            .unwrap();
+        let mut bob = Node::builder()
+            .with_transport(TransportType::Tcp, Box::new(MockTransport::default()))
+            .build()
+            .unwrap();
+        
+        carol.discover_peers().await.unwrap();
+        carol.announce_presence().await.unwrap();
+        let (_, payload) = sent.lock().unwrap().pop().unwrap();
+        let message = Message::from_bytes(&payload).unwrap();
+        
+        // Идентификатор Кэрол не выводится из ее ключа
+        assert!(matches!(bob.handle_announce(&message), Err(Error::Network(_))));
+        
+        bob.pin_peer_key(carol_id.clone(), keypair(9).public_bytes());
+        assert_eq!(bob.handle_announce(&message).unwrap().unwrap().id, carol_id);
+    }
+    
+    #[tokio::test]
+    async fn connected_node_announces_itself_periodically() {
+        let transport = MockTransport::default();
+        let sent = transport.sent.clone();
+        let mut alice = Node::builder()
+            .with_announce_interval(Duration::from_millis(50))
+            .with_transport(TransportType::Tcp, Box::new(transport))
+            .with_discovery(Box::new(StaticDiscovery::new(vec![peer_info(2, 7200)])))
+            .build()
+            .unwrap();
+        alice.discover_peers().await.unwrap();
+        
+        alice.connect().await.unwrap();
+        tokio::time::sleep(Duration::from_millis(180)).await;
+        assert!(sent.lock().unwrap().len() >= 2);
+        
+        // После отключения объявления прекращаются
+        alice.disconnect().await.unwrap();
+        let count = sent.lock().unwrap().len();
+        tokio::time::sleep(Duration::from_millis(120)).await;
+        assert_eq!(sent.lock().unwrap().len(), count);
+    }
+    
+    #[tokio::test]
+    async fn connected_peer_learns_from_periodic_announcement() {
+        let network = MockNetwork::default();
+        let mut alice = Node::builder()
+            .with_keypair(keypair(1))
+            .with_port(7101)
+            .with_announce_interval(Duration::from_millis(50))
+            .with_transport(TransportType::Tcp, Box::new(MockTransport::on(&network)))
+            .build()
+            .unwrap();
+        let mut bob = node_on(&network, 2, 7102);
+        introduce(&alice, &bob);
+        bob.connect().await.unwrap();
+        alice.connect().await.unwrap();
+        
+        // Боб узнает об Алисе без ручной обработки объявления
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        let known = bob.peers();
+        assert_eq!(known.len(), 1);
+        assert_eq!(known[0].id, *alice.peer_id());
+        assert_eq!(known[0].address, Some(Endpoint::tcp("127.0.0.1", 7101)));
+        
+        alice.disconnect().await.unwrap();
+        bob.disconnect().await.unwrap();
+    }
+    
+    #[tokio::test]
+    async fn discover_peers_is_debounced() {
+        let discovery = StaticDiscovery::new(vec![peer_info(1, 7001)]);
+        let calls = discovery.calls.clone();
+        let mut node = Node::builder()
+            .with_transport(TransportType::Tcp, Box::new(MockTransport::default()))
+            .with_discovery(Box::new(discovery))
+            .with_discovery_interval(Duration::from_millis(300))
+            .build()
+            .unwrap();
+        
+        for _ in 0..5 {
+            assert_eq!(node.discover_peers().await.unwrap().len(), 1);
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        
+        tokio::time::sleep(Duration::from_millis(350)).await;
+        node.discover_peers().await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+    
+    /// DHT с заранее заданными записями узлов
+    #[derive(Default)]
+    struct MockDht {
+        records: Vec<PeerInfo>,
+    }
+    
+    #[async_trait]
+    impl Dht for MockDht {
+        async fn start(&mut self) -> Result<()> {
+            Ok(())
+        }
+        
+        async fn stop(&mut self) -> Result<()> {
+            Ok(())
+        }
+        
+        async fn find_nodes(&mut self, _target: &PeerId) -> Result<Vec<PeerInfo>> {
+            Ok(self.records.clone())
+        }
+        
+        async fn find_value(&mut self, _key: &[u8]) -> Result<Option<Vec<u8>>> {
+            Ok(None)
+        }
+        
+        async fn store(&mut self, _key: &[u8], _value: &[u8]) -> Result<()> {
+            Ok(())
+        }
+        
+        async fn add_peer(&mut self, peer: PeerInfo) -> Result<()> {
+            self.records.push(peer);
+            Ok(())
+        }
+        
+        async fn get_closest_peers(&mut self, _target: &PeerId, limit: usize) -> Result<Vec<PeerInfo>> {
+            Ok(self.records.iter().take(limit).cloned().collect())
+        }
+    }
+    
+    #[tokio::test]
+    async fn missing_address_is_resolved_via_dht() {
+        let transport = MockTransport::default();
+        let sent = transport.sent.clone();
+        let mut node = Node::builder()
+            .with_transport(TransportType::Tcp, Box::new(transport))
+            .build()
+            .unwrap();
+        
+        let mut unresolved = peer_info(5, 7005);
+        unresolved.address = None;
+        node.shared.peers.lock().unwrap().insert(unresolved.id.clone(), Peer::new(unresolved));
+        node.dht = Some(Box::new(MockDht { records: vec![peer_info(5, 7005)] }));
+        
+        let report = node.broadcast(b"hello").await.unwrap();
+        assert_eq!(report.delivered, vec![peer_id(5)]);
+        assert!(report.unresolved.is_empty());
+        
+        let sent = sent.lock().unwrap();
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].0, Endpoint::tcp("127.0.0.1", 7005));
+    }
+    
+    #[tokio::test]
+    async fn next_message_from_resolves_or_times_out() {
+        let network = MockNetwork::default();
+        let mut alice = node_on(&network, 1, 7301);
+        let mut bob = node_on(&network, 2, 7302);
+        introduce(&bob, &alice);
+        alice.connect().await.unwrap();
+        bob.connect().await.unwrap();
+        
+        let waiting = alice.next_message_from(&peer_id(2), Duration::from_secs(2));
+        bob.send_to(&peer_id(1), b"hello").await.unwrap();
+        let message = waiting.await.unwrap();
+        assert_eq!(message.from, peer_id(2));
+        assert_eq!(message.data, b"hello");
+        
+        let silent = alice.next_message_from(&peer_id(3), Duration::from_millis(100)).await;
+        assert!(matches!(silent, Err(Error::Timeout(_))));
+    }
+    
+    #[tokio::test]
+    async fn advertised_info_uses_external_address() {
+        let transport = MockTransport::default();
+        let sent = transport.sent.clone();
+        let mut alice = Node::builder()
+            .with_port(7400)
+            .with_external_address("203.0.113.7", 9400)
+            .with_transport(TransportType::Tcp, Box::new(transport))
+            .with_discovery(Box::new(StaticDiscovery::new(vec![peer_info(2, 7200)])))
+            .build()
+            .unwrap();
+        let external = Some(Endpoint::tcp("203.0.113.7", 9400));
+        assert_eq!(alice.local_info().address, external);
+        
+        // Объявление несет внешний адрес, а не адрес прослушивания
+        alice.discover_peers().await.unwrap();
+        alice.announce_presence().await.unwrap();
+        let (_, payload) = sent.lock().unwrap().pop().unwrap();
+        let message = Message::from_bytes(&payload).unwrap();
+        
+        let bob = Node::builder()
+            .with_transport(TransportType::Tcp, Box::new(MockTransport::default()))
+            .build()
+            .unwrap();
+        let info = bob.handle_announce(&message).unwrap().unwrap();
+        assert_eq!(info.address, external);
+        assert_eq!(bob.peers()[0].address, external);
+    }
+    
+    #[tokio::test]
+    async fn forwarded_broadcast_skips_origin() {
+        let transport = MockTransport::default();
+        let sent = transport.sent.clone();
+        let mut node = Node::builder()
+            .with_transport(TransportType::Tcp, Box::new(transport))
+            .with_discovery(Box::new(StaticDiscovery::new(vec![
+                peer_info(1, 7001),
+                peer_info(2, 7002),
+                peer_info(3, 7003),
+            ])))
+            .build()
+            .unwrap();
+        node.discover_peers().await.unwrap();
+        
+        let message = Message::new_broadcast(peer_id(2), b"gossip".to_vec());
+        let report = node.forward_broadcast(&message).await.unwrap();
+        
+        assert_eq!(report.delivered.len(), 2);
+        assert!(report.delivered.contains(&peer_id(1)));
+        assert!(report.delivered.contains(&peer_id(3)));
+        
+        let targets: Vec<u16> = sent.lock().unwrap().iter().map(|(endpoint, _)| endpoint.port).collect();
+        assert_eq!(targets.len(), 2);
+        assert!(!targets.contains(&7002));
+        
+        // Пересылаются только сообщения с данными
+        let ping = Message::new(peer_id(2), None, MessageType::Ping, Vec::new());
+        assert!(node.forward_broadcast(&ping).await.is_err());
+    }
+    
+    #[tokio::test]
+    async fn challenge_proves_key_ownership() {
+        let network = MockNetwork::default();
+        let mut alice = node_on(&network, 1, 7501);
+        let mut bob = node_on(&network, 2, 7502);
+        introduce(&alice, &bob);
+        introduce(&bob, &alice);
+        alice.connect().await.unwrap();
+        bob.connect().await.unwrap();
+        
+        // Пир не считается подключенным до проверки
+        assert!(!alice.is_verified(&peer_id(2)));
+        assert!(!alice.shared.peers.lock().unwrap()[&peer_id(2)].is_connected());
+        
+        // Боб отвечает на вызов сам
+        alice.challenge_peer(&peer_id(2), Duration::from_secs(2)).await.unwrap();
+        assert!(alice.is_verified(&peer_id(2)));
+        assert!(alice.shared.peers.lock().unwrap()[&peer_id(2)].is_connected());
+    }
+    
+    #[tokio::test]
+    async fn spoofed_identity_is_rejected() {
+        let network = MockNetwork::default();
+        let mut alice = node_on(&network, 1, 7511);
+        // Мэллори выдает себя за узел 9, но владеет другим ключом
+        let mut mallory = Node::builder()
+            .with_peer_id(peer_id(9))
+            .with_keypair(keypair(3))
+            .with_port(7512)
+            .with_transport(TransportType::Tcp, Box::new(MockTransport::on(&network)))
+            .build()
+            .unwrap();
+        introduce(&alice, &mallory);
+        introduce(&mallory, &alice);
+        alice.connect().await.unwrap();
+        mallory.connect().await.unwrap();
+        
+        let result = alice.challenge_peer(&peer_id(9), Duration::from_secs(2)).await;
+        assert!(matches!(result, Err(Error::Network(_))));
+        assert!(!alice.is_verified(&peer_id(9)));
+        assert!(alice.peers().is_empty());
+    }
+    
+    #[tokio::test]
+    async fn full_peer_list_evicts_stalest_peer() {
+        let node = Node::builder()
+            .with_transport(TransportType::Tcp, Box::new(MockTransport::default()))
+            .with_max_peers(3)
+            .build()
+            .unwrap();
+        {
+            let mut peers = node.shared.peers.lock().unwrap();
+            for id in 1..=3 {
+                assert!(node.shared.insert_peer(&mut peers, Peer::new(peer_info(id, 7000 + id as u16))));
+            }
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        let mut peers = node.shared.peers.lock().unwrap();
+        peers.get_mut(&peer_id(1)).unwrap().update_last_seen();
+        peers.get_mut(&peer_id(3)).unwrap().update_last_seen();
+        
+        // Вытесняется пир, дольше всех не выходивший на связь
+        assert!(node.shared.insert_peer(&mut peers, Peer::new(peer_info(4, 7004))));
+        assert_eq!(peers.len(), 3);
+        assert!(!peers.contains_key(&peer_id(2)));
+        
+        // Подключенные пиры не вытесняются, даже если давно не отвечали
+        for id in [1, 3, 4] {
+            peers.get_mut(&peer_id(id)).unwrap().set_status(peer::PeerStatus::Connected);
+        }
+        assert!(!node.shared.insert_peer(&mut peers, Peer::new(peer_info(5, 7005))));
+        assert_eq!(peers.len(), 3);
+        assert!([1, 3, 4].iter().all(|&id| peers.contains_key(&peer_id(id))));
+    }
+    
+    #[tokio::test]
+    async fn discovered_peer_past_cap_evicts_stalest_unconnected_peer() {
+        let mut node = Node::builder()
+            .with_transport(TransportType::Tcp, Box::new(MockTransport::default()))
+            .with_discovery(Box::new(StaticDiscovery::new(vec![peer_info(4, 7004)])))
+            .with_max_peers(3)
+            .build()
+            .unwrap();
+        {
+            let mut peers = node.shared.peers.lock().unwrap();
+            for id in 1..=3 {
+                assert!(node.shared.insert_peer(&mut peers, Peer::new(peer_info(id, 7000 + id as u16))));
+            }
+            // Самый давний пир подключен и поэтому не вытесняется
+            peers.get_mut(&peer_id(1)).unwrap().set_status(peer::PeerStatus::Connected);
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        node.shared.peers.lock().unwrap().get_mut(&peer_id(3)).unwrap().update_last_seen();
+        
+        let discovered = node.discover_peers().await.unwrap();
+        assert_eq!(discovered.len(), 1);
+        
+        let peers = node.shared.peers.lock().unwrap();
+        assert_eq!(peers.len(), 3);
+        assert!([1, 3, 4].iter().all(|&id| peers.contains_key(&peer_id(id))));
+    }
+    
+    #[test]
+    fn builder_installs_dht_and_mdns() {
+        let node = Node::builder()
+            .with_transport(TransportType::Tcp, Box::new(MockTransport::default()))
+            .with_dht()
+            .with_mdns()
+            .build()
+            .unwrap();
+        assert!(node.dht.is_some());
+        let names: Vec<&str> = node.discoveries.iter().map(|discovery| discovery.name()).collect();
+        assert_eq!(names, vec!["mDNS"]);
+        
+        let plain = Node::builder()
+            .with_transport(TransportType::Tcp, Box::new(MockTransport::default()))
+            .build()
+            .unwrap();
+        assert!(plain.dht.is_none());
+        assert!(plain.discoveries.is_empty());
+    }
+    
+    /// Представление сообщения в том виде, в котором его доставляет транспорт
+    fn wire(message: &Message) -> Vec<u8> {
+        message.to_bytes(SerializationFormat::default()).unwrap()
+    }
+    
+    #[tokio::test]
+    async fn observer_receives_but_never_sends() {
+        let network = MockNetwork::default();
+        let transport = MockTransport::on(&network);
+        let inject = transport.incoming_tx.clone();
+        let mut observer = Node::builder()
+            .with_port(7601)
+            .with_mode(NodeMode::Observer)
+            .with_transport(TransportType::Tcp, Box::new(transport))
+            .build()
+            .unwrap();
+        let alice = node_on(&network, 1, 7602);
+        introduce(&observer, &alice);
+        observer.connect().await.unwrap();
+        
+        // Наблюдатель не принимает входящих соединений
+        assert!(network.lock().unwrap().is_empty());
+        
+        // Сообщения, пришедшие по собственному соединению, доставляются
+        let mut incoming = observer.incoming();
+        let message = Message::new_data(peer_id(1), observer.peer_id().clone(), b"hello".to_vec());
+        inject.send((wire(&message), "127.0.0.1:7602".parse().unwrap())).unwrap();
+        let received = tokio::time::timeout(Duration::from_secs(1), incoming.next()).await.unwrap().unwrap();
+        assert_eq!(received.data, b"hello");
+        
+        assert!(matches!(observer.send_to(&peer_id(1), b"hi").await, Err(Error::Network(_))));
+        assert!(matches!(observer.broadcast(b"hi").await, Err(Error::Network(_))));
+        assert!(matches!(observer.announce_presence().await, Err(Error::Network(_))));
+    }
+    
+    #[tokio::test]
+    async fn broadcast_reaches_incoming_subscriber() {
+        let mut node = Node::builder()
+            .with_transport(TransportType::Tcp, Box::new(MockTransport::default()))
+            .with_discovery(Box::new(StaticDiscovery::new(vec![peer_info(1, 7001)])))
+            .build()
+            .unwrap();
+        node.discover_peers().await.unwrap();
+        let mut incoming = node.incoming();
+        
+        let report = node.broadcast(b"news").await.unwrap();
+        assert_eq!(report.delivered, vec![peer_id(1)]);
+        
+        let message = tokio::time::timeout(Duration::from_secs(1), incoming.next()).await.unwrap().unwrap();
+        assert_eq!(message.data, b"news");
+        assert_eq!(message.from, *node.peer_id());
+        assert!(message.to.is_none());
+    }
+    
+    #[tokio::test]
+    async fn sent_message_arrives_in_peer_incoming() {
+        let network = MockNetwork::default();
+        let mut alice = node_on(&network, 1, 7621);
+        let mut bob = node_on(&network, 2, 7622);
+        introduce(&alice, &bob);
+        introduce(&bob, &alice);
+        alice.connect().await.unwrap();
+        bob.connect().await.unwrap();
+        
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        let silent_for = bob.shared.peers.lock().unwrap()[&peer_id(1)].time_since_last_seen();
+        let mut incoming = bob.incoming();
+        alice.send_to(&peer_id(2), b"hello").await.unwrap();
+        
+        let message = tokio::time::timeout(Duration::from_secs(1), incoming.next()).await.unwrap().unwrap();
+        assert_eq!(message.from, peer_id(1));
+        assert_eq!(message.data, b"hello");
+        assert_eq!(message.source, Some(Endpoint::tcp("127.0.0.1", 7621)));
+        
+        // Получение сообщения обновляет время последнего контакта с отправителем
+        assert!(bob.shared.peers.lock().unwrap()[&peer_id(1)].time_since_last_seen() < silent_for);
+    }
+    
+    #[tokio::test]
+    async fn request_receives_correlated_response() {
+        let network = MockNetwork::default();
+        let mut alice = node_on(&network, 1, 7631);
+        let mut bob = node_on(&network, 2, 7632);
+        let mut carol = node_on(&network, 3, 7633);
+        introduce(&alice, &bob);
+        introduce(&alice, &carol);
+        introduce(&bob, &alice);
+        for node in [&mut alice, &mut bob, &mut carol] {
+            node.connect().await.unwrap();
+        }
+        
+        let mut incoming = bob.incoming();
+        tokio::spawn(async move {
+            while let Some(request) = incoming.next().await {
+                if request.message_type == MessageType::Request {
+                    let mut reply = b"re: ".to_vec();
+                    reply.extend_from_slice(&request.data);
+                    bob.respond(&request, &reply).await.unwrap();
+                }
+            }
+        });
+        
+        let response = alice.request(&peer_id(2), b"ping?", Duration::from_secs(1)).await.unwrap();
+        assert_eq!(response, b"re: ping?");
+        
+        // Carol не отвечает, поэтому запрос завершается по таймауту
+        let result = alice.request(&peer_id(3), b"ping?", Duration::from_millis(50)).await;
+        assert!(matches!(result, Err(Error::Network(_))));
+        assert!(alice.shared.pending_requests.lock().unwrap().is_empty());
+    }
+    
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Quote {
+        symbol: String,
+        price: u64,
+        tags: Vec<String>,
+    }
+    
+    #[tokio::test]
+    async fn typed_broadcast_round_trips_custom_struct() {
+        let network = MockNetwork::default();
+        let mut alice = node_on(&network, 1, 7641);
+        let mut bob = node_on(&network, 2, 7642);
+        introduce(&alice, &bob);
+        alice.connect().await.unwrap();
+        bob.connect().await.unwrap();
+        let mut incoming = bob.incoming();
+        
+        let quote = Quote { symbol: "NOXY".to_string(), price: 4200, tags: vec!["spot".to_string()] };
+        let report = alice.broadcast_typed(&quote).await.unwrap();
+        assert_eq!(report.delivered, vec![peer_id(2)]);
+        
+        let message = tokio::time::timeout(Duration::from_secs(1), incoming.next()).await.unwrap().unwrap();
+        assert_eq!(message.decode::<Quote>().unwrap(), quote);
+        assert!(matches!(message.decode::<Vec<Quote>>(), Err(Error::Serialization(_))));
+    }
+    
+    /// Передавать публикации, полученные узлом, в его `PubSub`
+    fn run_pubsub(node: Arc<Node>, pubsub: Arc<pubsub::PubSub>) {
+        let mut incoming = node.incoming();
+        tokio::spawn(async move {
+            while let Some(message) = incoming.next().await {
+                if message.message_type == MessageType::Publish {
+                    pubsub.handle_message(&node, &message).await.unwrap();
+                }
+            }
+        });
+    }
+    
+    #[tokio::test]
+    async fn non_subscriber_relays_between_subscribers() {
+        // Цепочка alice - bob - carol: alice и carol не знают друг о друге
+        let network = MockNetwork::default();
+        let mut alice = node_on(&network, 1, 7651);
+        let mut bob = node_on(&network, 2, 7652);
+        let mut carol = node_on(&network, 3, 7653);
+        introduce(&alice, &bob);
+        introduce(&bob, &alice);
+        introduce(&bob, &carol);
+        introduce(&carol, &bob);
+        for node in [&mut alice, &mut bob, &mut carol] {
+            node.connect().await.unwrap();
+        }
+        
+        let nodes = [Arc::new(alice), Arc::new(bob), Arc::new(carol)];
+        let pubsubs = [(); 3].map(|_| Arc::new(pubsub::PubSub::new()));
+        pubsubs[0].subscribe("blocks");
+        pubsubs[2].subscribe("blocks");
+        let mut received = Box::pin(pubsubs[2].messages("blocks"));
+        for (node, pubsub) in nodes.iter().zip(&pubsubs) {
+            run_pubsub(Arc::clone(node), Arc::clone(pubsub));
+        }
+        
+        let report = pubsubs[0].publish(&nodes[0], "blocks", b"block #1").await.unwrap();
+        assert_eq!(report.delivered, vec![peer_id(2)]);
+        
+        let data = tokio::time::timeout(Duration::from_secs(1), received.next()).await.unwrap().unwrap();
+        assert_eq!(data, b"block #1");
+        assert!(!pubsubs[1].is_subscribed("blocks"));
+    }
+    
+    /// Пересылать полученные от других узлов данные дальше, считая обработанные сообщения
+    fn run_gossip(mut node: Node, processed: Arc<AtomicUsize>) {
+        let mut incoming = node.incoming();
+        tokio::spawn(async move {
+            while let Some(message) = incoming.next().await {
+                if message.message_type == MessageType::Data && message.from != *node.peer_id() {
+                    processed.fetch_add(1, Ordering::SeqCst);
+                    node.forward_broadcast(&message).await.unwrap();
+                }
+            }
+        });
+    }
+    
+    #[tokio::test]
+    async fn triangle_processes_broadcast_once_per_node() {
+        let network = MockNetwork::default();
+        let mut nodes = vec![
+            node_on(&network, 1, 7661),
+            node_on(&network, 2, 7662),
+            node_on(&network, 3, 7663),
+        ];
+        for (i, node) in nodes.iter().enumerate() {
+            for (j, other) in nodes.iter().enumerate() {
+                if i != j {
+                    introduce(node, other);
+                }
+            }
+        }
+        for node in nodes.iter_mut() {
+            node.connect().await.unwrap();
+        }
+        
+        let mut alice = nodes.remove(0);
+        let counters: Vec<Arc<AtomicUsize>> = nodes.into_iter()
+            .map(|node| {
+                let processed = Arc::new(AtomicUsize::new(0));
+                run_gossip(node, Arc::clone(&processed));
+                processed
+            })
+            .collect();
+        
+        // Bob и Carol пересылают сообщение друг другу, повторные копии отбрасываются
+        alice.broadcast(b"flood").await.unwrap();
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        for processed in &counters {
+            assert_eq!(processed.load(Ordering::SeqCst), 1);
+        }
+    }
+    
+    #[tokio::test]
+    async fn ttl_bounds_broadcast_to_two_hops() {
+        // Цепочка alice -> bob -> carol -> dave
+        let network = MockNetwork::default();
+        let mut nodes: Vec<Node> = (1..=4).map(|id| node_on(&network, id, 7670 + id as u16)).collect();
+        for pair in nodes.windows(2) {
+            introduce(&pair[0], &pair[1]);
+        }
+        for node in nodes.iter_mut() {
+            node.connect().await.unwrap();
+        }
+        
+        let alice = nodes.remove(0);
+        let counters: Vec<Arc<AtomicUsize>> = nodes.into_iter()
+            .map(|node| {
+                let processed = Arc::new(AtomicUsize::new(0));
+                run_gossip(node, Arc::clone(&processed));
+                processed
+            })
+            .collect();
+        
+        let message = Message::new_broadcast(alice.peer_id().clone(), b"limited".to_vec()).with_ttl(2);
+        alice.broadcast_own(&message).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        
+        // Сообщение проходит два перехода (bob, carol), но не три (dave)
+        let processed: Vec<usize> = counters.iter().map(|counter| counter.load(Ordering::SeqCst)).collect();
+        assert_eq!(processed, vec![1, 1, 0]);
+    }
+    
+    #[tokio::test]
+    async fn discovered_peer_emits_event() {
+        let mut node = Node::builder()
+            .with_transport(TransportType::Tcp, Box::new(MockTransport::default()))
+            .with_discovery(Box::new(StaticDiscovery::new(vec![peer_info(1, 7001)])))
+            .build()
+            .unwrap();
+        let mut events = Box::pin(node.events());
+        
+        node.discover_peers().await.unwrap();
+        let event = tokio::time::timeout(Duration::from_secs(1), events.next()).await.unwrap().unwrap();
+        assert!(matches!(event, PeerEvent::Discovered(info) if info.id == peer_id(1)));
+        
+        // Успешная отправка пиру, не подтвердившему владение ключом, не означает подключения
+        node.send_to(&peer_id(1), b"hello").await.unwrap();
+        assert!(tokio::time::timeout(Duration::from_millis(100), events.next()).await.is_err());
+        
+        // После проверки ключа первая успешная отправка означает установленную связь
+        node.shared.verified_peers.lock().unwrap().insert(peer_id(1));
+        node.send_to(&peer_id(1), b"hello").await.unwrap();
+        let event = tokio::time::timeout(Duration::from_secs(1), events.next()).await.unwrap().unwrap();
+        assert!(matches!(event, PeerEvent::Connected(info) if info.id == peer_id(1)));
+    }
+    
+    /// Транспорт, который можно «отключить»: пока `down`, отправка и подключение не удаются
+    #[derive(Clone, Default)]
+    struct FlakyTransport {
+        down: Arc<AtomicBool>,
+        connects: Arc<AtomicUsize>,
+    }
+    
+    #[async_trait]
+    impl Transport for FlakyTransport {
+        fn transport_type(&self) -> TransportType {
+            TransportType::Tcp
+        }
+        
+        async fn listen(&mut self, _address: &str, _port: u16) -> Result<()> {
+            Ok(())
+        }
+        
+        async fn connect(&mut self, endpoint: &Endpoint) -> Result<()> {
+            self.connects.fetch_add(1, Ordering::SeqCst);
+            self.send_to(endpoint, &[]).await
+        }
+        
+        async fn send_to(&self, endpoint: &Endpoint, _data: &[u8]) -> Result<()> {
+            if self.down.load(Ordering::SeqCst) {
+                return Err(Error::Transport(format!("{} недоступен", endpoint)));
+            }
+            Ok(())
+        }
+        
+        fn incoming(&self) -> broadcast::Receiver<(Vec<u8>, SocketAddr)> {
+            broadcast::channel(1).1
+        }
+        
+        async fn close(&mut self) -> Result<()> {
+            Ok(())
+        }
+    }
+    
+    #[tokio::test]
+    async fn dropped_peer_is_retried_with_backoff() {
+        let transport = FlakyTransport::default();
+        let (down, connects) = (transport.down.clone(), transport.connects.clone());
+        let mut node = Node::builder()
+            .with_transport(TransportType::Tcp, Box::new(transport))
+            .with_discovery(Box::new(StaticDiscovery::new(vec![peer_info(1, 7001)])))
+            .build()
+            .unwrap();
+        node.discover_peers().await.unwrap();
+        // Пир уже подтвердил владение ключом
+        node.shared.verified_peers.lock().unwrap().insert(peer_id(1));
+        node.send_to(&peer_id(1), b"hello").await.unwrap();
+        
+        // Обрыв связи с подключенным пиром планирует переподключение
+        down.store(true, Ordering::SeqCst);
+        assert!(node.send_to(&peer_id(1), b"hello").await.is_err());
+        assert!(node.shared.reconnect_at.lock().unwrap().contains_key(&peer_id(1)));
+        assert!(node.reconnect_due().await.is_empty());
+        
+        // Неудачная попытка удваивает задержку
+        node.shared.reconnect_at.lock().unwrap().insert(peer_id(1), Instant::now());
+        assert!(node.reconnect_due().await.is_empty());
+        let delay = node.shared.reconnect_at.lock().unwrap()[&peer_id(1)] - Instant::now();
+        assert!(delay > Duration::from_millis(1500));
+        
+        down.store(false, Ordering::SeqCst);
+        node.shared.reconnect_at.lock().unwrap().insert(peer_id(1), Instant::now());
+        assert_eq!(node.reconnect_due().await, vec![peer_id(1)]);
+        assert_eq!(connects.load(Ordering::SeqCst), 2);
+        assert!(node.shared.peers.lock().unwrap()[&peer_id(1)].is_connected());
     }
-} 
\ No newline at end of file
+    
+    #[tokio::test]
+    async fn banned_peer_is_dropped_and_hidden() {
+        let network = MockNetwork::default();
+        let mut alice = node_on(&network, 1, 7681);
+        let mut bob = node_on(&network, 2, 7682);
+        let mut carol = node_on(&network, 3, 7683);
+        introduce(&alice, &bob);
+        introduce(&carol, &bob);
+        introduce(&bob, &alice);
+        for node in [&mut alice, &mut bob, &mut carol] {
+            node.connect().await.unwrap();
+        }
+        
+        bob.ban_peer(&peer_id(1), None);
+        assert!(bob.peers().is_empty());
+        assert!(matches!(bob.send_to(&peer_id(1), b"hi").await, Err(Error::Network(_))));
+        
+        // Сообщение заблокированной Alice отбрасывается, сообщение Carol доставляется
+        let mut incoming = bob.incoming();
+        alice.send_to(&peer_id(2), b"from alice").await.unwrap();
+        carol.send_to(&peer_id(2), b"from carol").await.unwrap();
+        let message = tokio::time::timeout(Duration::from_secs(1), incoming.next()).await.unwrap().unwrap();
+        assert_eq!(message.from, peer_id(3));
+        
+        // Временная блокировка снимается сама
+        bob.ban_peer(&peer_id(3), Some(Duration::from_millis(50)));
+        assert!(bob.is_banned(&peer_id(3)));
+        tokio::time::sleep(Duration::from_millis(80)).await;
+        assert!(!bob.is_banned(&peer_id(3)));
+    }
+    
+    /// Собрать сообщения, пришедшие до паузы в `idle`
+    async fn drain(incoming: &mut IncomingMessages, idle: Duration) -> Vec<Message> {
+        let mut messages = Vec::new();
+        while let Ok(Some(message)) = tokio::time::timeout(idle, incoming.next()).await {
+            messages.push(message);
+        }
+        messages
+    }
+    
+    #[tokio::test]
+    async fn rate_limit_drops_burst_and_recovers() {
+        let network = MockNetwork::default();
+        let mut alice = node_on(&network, 1, 7691);
+        let mut bob = Node::builder()
+            .with_peer_id(peer_id(2))
+            .with_port(7692)
+            .with_transport(TransportType::Tcp, Box::new(MockTransport::on(&network)))
+            .with_rate_limit(10, 5)
+            .build()
+            .unwrap();
+        introduce(&alice, &bob);
+        alice.connect().await.unwrap();
+        bob.connect().await.unwrap();
+        let mut incoming = bob.incoming();
+        
+        // Из всплеска принимается только емкость корзины
+        for i in 0..10u8 {
+            alice.send_to(&peer_id(2), &[i]).await.unwrap();
+        }
+        let received = drain(&mut incoming, Duration::from_millis(50)).await;
+        assert_eq!(received.len(), 5);
+        assert_eq!(bob.rate_limited_messages(), 5);
+        
+        // За 300 мс корзина пополняется на три токена
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        for i in 0..2u8 {
+            alice.send_to(&peer_id(2), &[i]).await.unwrap();
+        }
+        let received = drain(&mut incoming, Duration::from_millis(50)).await;
+        assert_eq!(received.len(), 2);
+        assert_eq!(bob.rate_limited_messages(), 5);
+    }
+    
+    #[tokio::test]
+    async fn ping_measures_loopback_rtt() {
+        let network = MockNetwork::default();
+        let mut alice = node_on(&network, 1, 7701);
+        let mut bob = node_on(&network, 2, 7702);
+        introduce(&alice, &bob);
+        introduce(&bob, &alice);
+        alice.connect().await.unwrap();
+        bob.connect().await.unwrap();
+        let mut incoming = bob.incoming();
+        
+        // Боб отвечает сам, приложение его сообщения не читает
+        assert_eq!(alice.shared.peers.lock().unwrap()[&peer_id(2)].rtt(), None);
+        let rtt = alice.ping(&peer_id(2)).await.unwrap();
+        assert!(rtt < Duration::from_secs(1));
+        assert_eq!(alice.shared.peers.lock().unwrap()[&peer_id(2)].rtt(), Some(rtt));
+        
+        // `Ping` подписчикам не передается
+        assert!(tokio::time::timeout(Duration::from_millis(50), incoming.next()).await.is_err());
+    }
+    
+    #[tokio::test]
+    async fn stats_count_sent_and_received_messages() {
+        let network = MockNetwork::default();
+        let mut alice = node_on(&network, 1, 7711);
+        let mut bob = node_on(&network, 2, 7712);
+        introduce(&alice, &bob);
+        // Пир уже подтвердил владение ключом
+        alice.shared.verified_peers.lock().unwrap().insert(peer_id(2));
+        alice.connect().await.unwrap();
+        bob.connect().await.unwrap();
+        
+        let mut incoming = bob.incoming();
+        for data in [&b"one"[..], b"two", b"three"] {
+            alice.send_to(&peer_id(2), data).await.unwrap();
+            tokio::time::timeout(Duration::from_secs(1), incoming.next()).await.unwrap().unwrap();
+        }
+        
+        let sent = alice.stats();
+        assert_eq!(sent.messages_sent, 3);
+        assert_eq!(sent.messages_received, 0);
+        assert_eq!(sent.peer_count, 1);
+        assert_eq!(sent.connected_peers, 1);
+        
+        let received = bob.stats();
+        assert_eq!(received.messages_received, 3);
+        assert_eq!(received.bytes_received, sent.bytes_sent);
+        assert_eq!(received.messages_sent, 0);
+    }
+    
+    /// Создать узел с подписью сообщений, слушающий 127.0.0.1:`port`
+    fn signed_node_on(network: &MockNetwork, port: u16, peer_id: Option<PeerId>) -> Node {
+        let mut builder = Node::builder()
+            .with_keypair(Ed25519KeyPair::generate().unwrap())
+            .with_port(port)
+            .with_transport(TransportType::Tcp, Box::new(MockTransport::on(network)))
+            .with_signed_messages();
+        if let Some(peer_id) = peer_id {
+            builder = builder.with_peer_id(peer_id);
+        }
+        builder.build().unwrap()
+    }
+    
+    /// Дождаться следующего сообщения с данными, пропуская служебные
+    async fn next_data(incoming: &mut IncomingMessages) -> Message {
+        loop {
+            let message = tokio::time::timeout(Duration::from_secs(1), incoming.next()).await.unwrap().unwrap();
+            if message.message_type == MessageType::Data {
+                return message;
+            }
+        }
+    }
+    
+    #[tokio::test]
+    async fn signed_messages_reject_forged_sender() {
+        let network = MockNetwork::default();
+        let mut alice = signed_node_on(&network, 7721, None);
+        let mut bob = signed_node_on(&network, 7722, None);
+        // Mallory выдает себя за Alice, но подписывает своим ключом
+        let mut mallory = signed_node_on(&network, 7723, Some(alice.peer_id().clone()));
+        introduce(&alice, &bob);
+        introduce(&mallory, &bob);
+        for node in [&mut alice, &mut bob, &mut mallory] {
+            node.connect().await.unwrap();
+        }
+        
+        let mut incoming = bob.incoming();
+        
+        // До объявления ключ Alice неизвестен, и ее сообщения не принимаются
+        alice.send_to(bob.peer_id(), b"too early").await.unwrap();
+        alice.announce_presence().await.unwrap();
+        alice.send_to(bob.peer_id(), b"hello").await.unwrap();
+        let message = next_data(&mut incoming).await;
+        assert_eq!(message.data, b"hello");
+        assert!(message.signature.is_some());
+        
+        mallory.announce_presence().await.unwrap();
+        mallory.send_to(bob.peer_id(), b"forged").await.unwrap();
+        alice.send_to(bob.peer_id(), b"after").await.unwrap();
+        let message = next_data(&mut incoming).await;
+        assert_eq!(message.from, *alice.peer_id());
+        assert_eq!(message.data, b"after");
+    }
+    
+    #[tokio::test]
+    async fn signed_messages_reject_impostor_announcing_first() {
+        let network = MockNetwork::default();
+        let mut alice = signed_node_on(&network, 7731, None);
+        let mut bob = signed_node_on(&network, 7732, None);
+        let mut mallory = signed_node_on(&network, 7733, Some(alice.peer_id().clone()));
+        introduce(&alice, &bob);
+        introduce(&mallory, &bob);
+        for node in [&mut alice, &mut bob, &mut mallory] {
+            node.connect().await.unwrap();
+        }
+        
+        let mut incoming = bob.incoming();
+        
+        // Mallory объявляется раньше Alice, но ее ключ не принадлежит идентификатору Alice
+        mallory.announce_presence().await.unwrap();
+        mallory.send_to(bob.peer_id(), b"forged").await.unwrap();
+        
+        alice.announce_presence().await.unwrap();
+        alice.send_to(bob.peer_id(), b"hello").await.unwrap();
+        let message = next_data(&mut incoming).await;
+        assert_eq!(message.data, b"hello");
+        assert_eq!(message.from, *alice.peer_id());
+    }
+    
+    #[tokio::test]
+    async fn compressed_payload_is_smaller_on_the_wire() {
+        let network = MockNetwork::default();
+        let transport = MockTransport::on(&network);
+        let sent = transport.sent.clone();
+        let mut alice = Node::builder()
+            .with_peer_id(peer_id(1))
+            .with_port(7731)
+            .with_transport(TransportType::Tcp, Box::new(transport))
+            .with_compression(512)
+            .build()
+            .unwrap();
+        let mut bob = node_on(&network, 2, 7732);
+        introduce(&alice, &bob);
+        alice.connect().await.unwrap();
+        bob.connect().await.unwrap();
+        
+        let mut incoming = bob.incoming();
+        let payload = b"noxy block ".repeat(10_000);
+        alice.send_to(&peer_id(2), &payload).await.unwrap();
+        
+        let message = tokio::time::timeout(Duration::from_secs(1), incoming.next()).await.unwrap().unwrap();
+        assert!(!message.compressed);
+        assert_eq!(message.data, payload);
+        assert!(sent.lock().unwrap()[0].1.len() < payload.len() / 10);
+    }
+    
+    #[tokio::test]
+    async fn nodes_with_different_formats_understand_each_other() {
+        let network = MockNetwork::default();
+        let node = |id: u8, port: u16, format: SerializationFormat| {
+            Node::builder()
+                .with_peer_id(peer_id(id))
+                .with_port(port)
+                .with_transport(TransportType::Tcp, Box::new(MockTransport::on(&network)))
+                .with_serialization(format)
+                .build()
+                .unwrap()
+        };
+        let mut alice = node(1, 7741, SerializationFormat::Json);
+        let mut bob = node(2, 7742, SerializationFormat::Cbor);
+        introduce(&alice, &bob);
+        introduce(&bob, &alice);
+        alice.connect().await.unwrap();
+        bob.connect().await.unwrap();
+        
+        let mut alice_incoming = alice.incoming();
+        let mut bob_incoming = bob.incoming();
+        alice.send_to(&peer_id(2), b"json").await.unwrap();
+        bob.send_to(&peer_id(1), b"cbor").await.unwrap();
+        
+        let message = tokio::time::timeout(Duration::from_secs(1), bob_incoming.next()).await.unwrap().unwrap();
+        assert_eq!(message.data, b"json");
+        let message = tokio::time::timeout(Duration::from_secs(1), alice_incoming.next()).await.unwrap().unwrap();
+        assert_eq!(message.data, b"cbor");
+    }
+    
+    #[tokio::test]
+    async fn chunked_transfer_fits_frame_limit() {
+        const FRAME_LIMIT: usize = 64 * 1024;
+        
+        let network = MockNetwork::default();
+        let transport = MockTransport::on(&network);
+        let sent = transport.sent.clone();
+        let mut alice = Node::builder()
+            .with_peer_id(peer_id(1))
+            .with_port(7751)
+            .with_transport(TransportType::Tcp, Box::new(transport))
+            .build()
+            .unwrap();
+        let mut bob = node_on(&network, 2, 7752);
+        introduce(&alice, &bob);
+        alice.connect().await.unwrap();
+        bob.connect().await.unwrap();
+        
+        let mut incoming = bob.incoming();
+        let payload: Vec<u8> = (0..1024 * 1024u32).map(|i| (i % 251) as u8).collect();
+        alice.send_chunked(&peer_id(2), &payload).await.unwrap();
+        
+        let message = tokio::time::timeout(Duration::from_secs(5), incoming.next()).await.unwrap().unwrap();
+        assert_eq!(message.message_type, MessageType::Data);
+        assert_eq!(message.data, payload);
+        
+        let frames = sent.lock().unwrap();
+        assert!(frames.len() > payload.len() / FRAME_LIMIT);
+        assert!(frames.iter().all(|(_, frame)| frame.len() <= FRAME_LIMIT));
+    }
+    
+    #[test]
+    fn builder_rejects_peer_id_of_wrong_length() {
+        let built = Node::builder()
+            .with_peer_id(PeerId::new(vec![1; 16]))
+            .with_dht()
+            .build();
+        assert!(built.is_err());
+    }
+    
+    #[test]
+    fn distinct_custom_transports_are_both_retained() {
+        let node = Node::builder()
+            .with_transport(TransportType::custom("quic"), Box::new(MockTransport::default()))
+            .with_transport(TransportType::custom("udp"), Box::new(MockTransport::default()))
+            .build()
+            .unwrap();
+        assert_eq!(node.shared.transports.len(), 2);
+        assert!(node.shared.transports.contains_key(&TransportType::custom("quic")));
+        assert!(node.shared.transports.contains_key(&TransportType::custom("udp")));
+        
+        // Повторная регистрация того же имени заменяет транспорт
+        let node = Node::builder()
+            .with_transport(TransportType::custom("quic"), Box::new(MockTransport::default()))
+            .with_transport(TransportType::custom("QUIC"), Box::new(MockTransport::default()))
+            .build()
+            .unwrap();
+        assert_eq!(node.shared.transports.len(), 1);
+    }
+}
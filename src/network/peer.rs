@@ -1,5 +1,6 @@
 use std::time::{Duration, Instant};
-use crate::types::PeerInfo;
+use crate::types::{PeerId, PeerInfo};
+use super::rtt::RttEstimator;
 
 /// Статус подключения к пиру
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -14,6 +15,17 @@ pub enum PeerStatus {
     Unknown,
 }
 
+/// Событие изменения состояния пира
+#[derive(Debug, Clone)]
+pub enum PeerEvent {
+    /// С пиром установлена связь
+    Connected(PeerInfo),
+    /// Связь с пиром потеряна
+    Disconnected(PeerId),
+    /// Обнаружен новый пир
+    Discovered(PeerInfo),
+}
+
 /// Представление пира в сети
 pub struct Peer {
     /// Информация о пире
@@ -26,6 +38,10 @@ pub struct Peer {
     first_seen: Instant,
     /// Счетчик неудачных попыток подключения
     failed_attempts: u32,
+    /// Оценка времени приема-передачи
+    rtt: RttEstimator,
+    /// Последнее измеренное время приема-передачи
+    last_rtt: Option<Duration>,
 }
 
 impl Peer {
@@ -38,14 +54,28 @@ impl Peer {
             last_seen: now,
             first_seen: now,
             failed_attempts: 0,
+            rtt: RttEstimator::new(),
+            last_rtt: None,
         }
     }
     
+    /// Создать нового пира с заданным таймаутом по умолчанию
+    pub fn with_default_timeout(info: PeerInfo, default_timeout: Duration) -> Self {
+        let mut peer = Self::new(info);
+        peer.rtt = RttEstimator::with_default_timeout(default_timeout);
+        peer
+    }
+    
     /// Получить информацию о пире
     pub fn info(&self) -> &PeerInfo {
         &self.info
     }
     
+    /// Обновить информацию о пире
+    pub fn update_info(&mut self, info: PeerInfo) {
+        self.info = info;
+    }
+    
     /// Получить текущий статус пира
     pub fn status(&self) -> PeerStatus {
         self.status
@@ -69,6 +99,11 @@ impl Peer {
         self.last_seen.elapsed()
     }
     
+    /// Получить время с момента первого контакта
+    pub fn time_since_first_seen(&self) -> Duration {
+        self.first_seen.elapsed()
+    }
+    
     /// Увеличить счетчик неудачных попыток
     pub fn increment_failed_attempts(&mut self) {
         self.failed_attempts += 1;
@@ -79,8 +114,35 @@ impl Peer {
         self.failed_attempts
     }
     
+    /// Подключен ли пир
+    pub fn is_connected(&self) -> bool {
+        self.status == PeerStatus::Connected
+    }
+    
     /// Проверить, устарел ли пир (давно не было контакта)
     pub fn is_stale(&self, timeout: Duration) -> bool {
         self.time_since_last_seen() > timeout
     }
+    
+    /// Учесть измеренное время приема-передачи
+    pub fn record_rtt(&mut self, rtt: Duration) {
+        self.rtt.record(rtt);
+        self.last_rtt = Some(rtt);
+        self.update_last_seen();
+    }
+    
+    /// Получить последнее измеренное время приема-передачи
+    pub fn rtt(&self) -> Option<Duration> {
+        self.last_rtt
+    }
+    
+    /// Получить оценку времени приема-передачи
+    pub fn rtt_estimator(&self) -> &RttEstimator {
+        &self.rtt
+    }
+    
+    /// Получить адаптивный таймаут для запросов к пиру
+    pub fn timeout(&self) -> Duration {
+        self.rtt.timeout()
+    }
 } 
\ No newline at end of file
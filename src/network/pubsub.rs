@@ -0,0 +1,150 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use futures::stream::{Stream, StreamExt};
+use serde::{Serialize, Deserialize};
+use tokio::sync::broadcast;
+use tokio_stream::wrappers::BroadcastStream;
+
+use crate::error::{Error, Result};
+use super::message::{Message, MessageType};
+use super::seen::SeenCache;
+use super::{BroadcastReport, Node};
+
+/// Количество запоминаемых идентификаторов сообщений по умолчанию
+pub const DEFAULT_SEEN_CAPACITY: usize = 4096;
+
+/// Емкость канала сообщений одной темы
+const TOPIC_CHANNEL_CAPACITY: usize = 256;
+
+/// Заголовок и данные сообщения темы
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct TopicFrame {
+    /// Название темы
+    topic: String,
+    /// Данные сообщения
+    data: Vec<u8>,
+}
+
+/// Публикация-подписка по темам поверх рассылки узла
+///
+/// Опубликованные сообщения рассылаются всем пирам, а каждый узел пересылает
+/// впервые увиденные сообщения дальше, даже если сам не подписан на тему.
+/// Повторы отбрасываются по идентификатору сообщения.
+///
+/// Входящие сообщения типа `MessageType::Publish` нужно передавать в
+/// [`PubSub::handle_message`] из цикла обработки событий узла.
+pub struct PubSub {
+    /// Каналы сообщений для тем, на которые подписан узел
+    topics: Mutex<HashMap<String, broadcast::Sender<Vec<u8>>>>,
+    /// Недавно виденные сообщения
+    seen: Mutex<SeenCache>,
+}
+
+impl PubSub {
+    /// Создать слой публикации-подписки
+    pub fn new() -> Self {
+        Self::with_seen_capacity(DEFAULT_SEEN_CAPACITY)
+    }
+    
+    /// Создать слой публикации-подписки с заданным размером кэша виденных сообщений
+    pub fn with_seen_capacity(capacity: usize) -> Self {
+        Self {
+            topics: Mutex::new(HashMap::new()),
+            seen: Mutex::new(SeenCache::new(capacity)),
+        }
+    }
+    
+    /// Подписаться на тему
+    pub fn subscribe(&self, topic: &str) {
+        self.topic_sender(topic);
+    }
+    
+    /// Отписаться от темы
+    ///
+    /// Открытые потоки `messages` для темы завершаются.
+    pub fn unsubscribe(&self, topic: &str) {
+        self.topics.lock().expect("Не удалось получить блокировку topics").remove(topic);
+    }
+    
+    /// Подписан ли узел на тему
+    pub fn is_subscribed(&self, topic: &str) -> bool {
+        self.topics.lock().expect("Не удалось получить блокировку topics").contains_key(topic)
+    }
+    
+    /// Получить поток сообщений темы
+    ///
+    /// Если подписки на тему еще нет, она создается.
+    pub fn messages(&self, topic: &str) -> impl Stream<Item = Vec<u8>> {
+        let rx = self.topic_sender(topic).subscribe();
+        let topic = topic.to_string();
+        
+        BroadcastStream::new(rx).filter_map(move |item| {
+            let data = match item {
+                Ok(data) => Some(data),
+                Err(e) => {
+                    tracing::warn!("Пропущены сообщения темы {}: {}", topic, e);
+                    None
+                }
+            };
+            async move { data }
+        })
+    }
+    
+    /// Опубликовать сообщение в теме
+    ///
+    /// Собственные сообщения узла в его потоки `messages` не попадают.
+    pub async fn publish(&self, node: &Node, topic: &str, data: &[u8]) -> Result<BroadcastReport> {
+        let frame = TopicFrame {
+            topic: topic.to_string(),
+            data: data.to_vec(),
+        };
+        let payload = bincode::serialize(&frame)
+            .map_err(|e| Error::Serialization(format!("Не удалось сериализовать сообщение темы: {}", e)))?;
+        
+        let message = Message::new(node.shared.peer_id.clone(), None, MessageType::Publish, payload);
+        
+        // Отмечаем сообщение, чтобы не обработать его, когда оно вернется от пиров
+        self.seen.lock().expect("Не удалось получить блокировку seen").insert(message.id);
+        
+        node.broadcast_own(&message).await
+    }
+    
+    /// Обработать входящее сообщение темы
+    ///
+    /// Впервые увиденное сообщение доставляется подписчикам темы и
+    /// пересылается остальным пирам. Возвращает `false` для повторов.
+    pub async fn handle_message(&self, node: &Node, message: &Message) -> Result<bool> {
+        if message.message_type != MessageType::Publish {
+            return Err(Error::Network("Сообщение не относится к публикации-подписке".to_string()));
+        }
+        
+        if !self.seen.lock().expect("Не удалось получить блокировку seen").insert(message.id) {
+            return Ok(false);
+        }
+        
+        let frame: TopicFrame = bincode::deserialize(&message.data)
+            .map_err(|e| Error::Serialization(format!("Некорректное сообщение темы: {}", e)))?;
+        
+        if let Some(sender) = self.topics.lock().expect("Не удалось получить блокировку topics").get(&frame.topic) {
+            // Отсутствие открытых потоков не является ошибкой
+            let _ = sender.send(frame.data);
+        }
+        
+        node.relay(message).await?;
+        Ok(true)
+    }
+    
+    /// Получить канал темы, создав его при необходимости
+    fn topic_sender(&self, topic: &str) -> broadcast::Sender<Vec<u8>> {
+        self.topics.lock().expect("Не удалось получить блокировку topics")
+            .entry(topic.to_string())
+            .or_insert_with(|| broadcast::channel(TOPIC_CHANNEL_CAPACITY).0)
+            .clone()
+    }
+}
+
+impl Default for PubSub {
+    fn default() -> Self {
+        Self::new()
+    }
+}
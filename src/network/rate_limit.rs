@@ -0,0 +1,104 @@
+use std::collections::HashMap;
+use std::time::Instant;
+
+use crate::types::PeerId;
+
+/// Количество отслеживаемых пиров, после которого удаляются полные корзины
+const PRUNE_THRESHOLD: usize = 1024;
+
+/// Ограничение частоты входящих сообщений от одного пира
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+    /// Средняя допустимая частота сообщений в секунду
+    pub messages_per_sec: u32,
+    /// Максимальное количество сообщений, принимаемых подряд без ожидания
+    pub burst: u32,
+}
+
+/// Корзина токенов одного пира
+#[derive(Debug)]
+struct TokenBucket {
+    /// Доступные токены
+    tokens: f64,
+    /// Время последнего пополнения
+    updated: Instant,
+}
+
+/// Ограничитель частоты сообщений по алгоритму корзины токенов
+///
+/// Каждому пиру соответствует своя корзина емкостью `burst`, которая
+/// пополняется со скоростью `messages_per_sec` токенов в секунду.
+/// Сообщение принимается, если в корзине есть хотя бы один токен.
+#[derive(Debug)]
+pub struct RateLimiter {
+    /// Параметры ограничения
+    limit: RateLimit,
+    /// Корзины по идентификатору пира
+    buckets: HashMap<PeerId, TokenBucket>,
+    /// Количество отброшенных сообщений
+    dropped: u64,
+}
+
+impl RateLimiter {
+    /// Создать ограничитель с заданными параметрами
+    pub fn new(limit: RateLimit) -> Self {
+        Self {
+            limit,
+            buckets: HashMap::new(),
+            dropped: 0,
+        }
+    }
+    
+    /// Параметры ограничения
+    pub fn limit(&self) -> RateLimit {
+        self.limit
+    }
+    
+    /// Проверить, укладывается ли очередное сообщение от пира в его бюджет
+    ///
+    /// При превышении бюджета сообщение учитывается как отброшенное.
+    pub fn allow(&mut self, peer_id: &PeerId) -> bool {
+        let now = Instant::now();
+        let capacity = self.limit.burst.max(1) as f64;
+        let rate = self.limit.messages_per_sec as f64;
+        
+        if self.buckets.len() >= PRUNE_THRESHOLD && !self.buckets.contains_key(peer_id) {
+            self.prune(now);
+        }
+        
+        let bucket = self.buckets.entry(peer_id.clone()).or_insert(TokenBucket {
+            tokens: capacity,
+            updated: now,
+        });
+        
+        let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * rate).min(capacity);
+        bucket.updated = now;
+        
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            true
+        } else {
+            self.dropped += 1;
+            false
+        }
+    }
+    
+    /// Количество отброшенных сообщений
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+    
+    /// Удалить корзины, которые к моменту `now` успели заполниться
+    ///
+    /// Такие корзины ничем не отличаются от новых, поэтому их можно не хранить.
+    fn prune(&mut self, now: Instant) {
+        let capacity = self.limit.burst.max(1) as f64;
+        let rate = self.limit.messages_per_sec as f64;
+        
+        self.buckets.retain(|_, bucket| {
+            let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+            bucket.tokens + elapsed * rate < capacity
+        });
+    }
+}
@@ -0,0 +1,129 @@
+ use std::time::Duration;
+
+/// Таймаут по умолчанию, пока нет ни одного измерения RTT
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Минимально допустимый адаптивный таймаут
+pub const MIN_TIMEOUT: Duration = Duration::from_millis(200);
+
+/// Максимально допустимый адаптивный таймаут
+pub const MAX_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Оценщик времени приема-передачи (RTT) для вычисления адаптивного таймаута
+///
+/// Использует экспоненциально взвешенное скользящее среднее RTT и его
+/// отклонения по аналогии с вычислением RTO в TCP (RFC 6298).
+#[derive(Debug, Clone, Copy)]
+pub struct RttEstimator {
+    /// Сглаженное значение RTT
+    srtt: Option<Duration>,
+    /// Сглаженное отклонение RTT
+    rttvar: Duration,
+    /// Таймаут до появления первого измерения
+    default_timeout: Duration,
+    /// Нижняя граница таймаута
+    min_timeout: Duration,
+    /// Верхняя граница таймаута
+    max_timeout: Duration,
+}
+
+impl RttEstimator {
+    /// Создать новый оценщик с таймаутом по умолчанию
+    pub fn new() -> Self {
+        Self::with_default_timeout(DEFAULT_TIMEOUT)
+    }
+    
+    /// Создать новый оценщик с заданным таймаутом по умолчанию
+    pub fn with_default_timeout(default_timeout: Duration) -> Self {
+        Self {
+            srtt: None,
+            rttvar: Duration::ZERO,
+            default_timeout,
+            min_timeout: MIN_TIMEOUT,
+            max_timeout: MAX_TIMEOUT,
+        }
+    }
+    
+    /// Установить границы адаптивного таймаута
+    pub fn with_bounds(mut self, min_timeout: Duration, max_timeout: Duration) -> Self {
+        self.min_timeout = min_timeout;
+        self.max_timeout = max_timeout.max(min_timeout);
+        self
+    }
+    
+    /// Учесть новое измерение RTT
+    pub fn record(&mut self, rtt: Duration) {
+        match self.srtt {
+            None => {
+                self.srtt = Some(rtt);
+                self.rttvar = rtt / 2;
+            }
+            Some(srtt) => {
+                let deviation = srtt.abs_diff(rtt);
+                // RTTVAR = 3/4 * RTTVAR + 1/4 * |SRTT - RTT|
+                self.rttvar = self.rttvar * 3 / 4 + deviation / 4;
+                // SRTT = 7/8 * SRTT + 1/8 * RTT
+                self.srtt = Some(srtt * 7 / 8 + rtt / 8);
+            }
+        }
+    }
+    
+    /// Получить сглаженное значение RTT (если есть измерения)
+    pub fn smoothed_rtt(&self) -> Option<Duration> {
+        self.srtt
+    }
+    
+    /// Получить текущий адаптивный таймаут
+    pub fn timeout(&self) -> Duration {
+        match self.srtt {
+            Some(srtt) => (srtt + self.rttvar * 4).clamp(self.min_timeout, self.max_timeout),
+            None => self.default_timeout,
+        }
+    }
+    
+    /// Сбросить накопленные измерения
+    pub fn reset(&mut self) {
+        self.srtt = None;
+        self.rttvar = Duration::ZERO;
+    }
+}
+
+impl Default for RttEstimator {
+    fn default() -> Self {
+        Self::new()
+    }
+} 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn timeout_adapts_to_recorded_rtt() {
+        let mut estimator = RttEstimator::new();
+        assert_eq!(estimator.timeout(), DEFAULT_TIMEOUT);
+        
+        for _ in 0..20 {
+            estimator.record(Duration::from_millis(50));
+        }
+        let fast = estimator.timeout();
+        assert!(fast < DEFAULT_TIMEOUT);
+        assert!(fast >= MIN_TIMEOUT);
+        
+        estimator.record(Duration::from_secs(3));
+        let slow = estimator.timeout();
+        assert!(slow > fast);
+        assert!(slow <= MAX_TIMEOUT);
+    }
+    
+    #[test]
+    fn reset_restores_default_timeout() {
+        let mut estimator = RttEstimator::with_default_timeout(Duration::from_secs(2));
+        estimator.record(Duration::from_millis(10));
+        assert!(estimator.smoothed_rtt().is_some());
+        
+        estimator.reset();
+        assert_eq!(estimator.smoothed_rtt(), None);
+        assert_eq!(estimator.timeout(), Duration::from_secs(2));
+    }
+}
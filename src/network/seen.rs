@@ -0,0 +1,60 @@
+use std::collections::{HashSet, VecDeque};
+
+/// Ограниченное множество недавно виденных идентификаторов сообщений
+///
+/// При переполнении вытесняется идентификатор, добавленный раньше всех.
+#[derive(Debug)]
+pub struct SeenCache {
+    /// Идентификаторы для быстрой проверки
+    ids: HashSet<[u8; 16]>,
+    /// Порядок добавления идентификаторов, от старых к новым
+    order: VecDeque<[u8; 16]>,
+    /// Максимальное количество хранимых идентификаторов
+    capacity: usize,
+}
+
+impl SeenCache {
+    /// Создать множество заданной емкости
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        
+        Self {
+            ids: HashSet::with_capacity(capacity),
+            order: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+    
+    /// Отметить идентификатор как виденный
+    ///
+    /// Возвращает `true`, если идентификатор встретился впервые.
+    pub fn insert(&mut self, id: [u8; 16]) -> bool {
+        if !self.ids.insert(id) {
+            return false;
+        }
+        
+        if self.order.len() == self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.ids.remove(&oldest);
+            }
+        }
+        self.order.push_back(id);
+        
+        true
+    }
+    
+    /// Виден ли идентификатор
+    pub fn contains(&self, id: &[u8; 16]) -> bool {
+        self.ids.contains(id)
+    }
+    
+    /// Количество хранимых идентификаторов
+    pub fn len(&self) -> usize {
+        self.order.len()
+    }
+    
+    /// Пусто ли множество
+    pub fn is_empty(&self) -> bool {
+        self.order.is_empty()
+    }
+}
@@ -0,0 +1,58 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// Снимок показателей работы узла
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NodeStats {
+    /// Количество успешно отправленных сообщений
+    pub messages_sent: u64,
+    /// Количество принятых сообщений
+    pub messages_received: u64,
+    /// Количество отправленных байт
+    pub bytes_sent: u64,
+    /// Количество принятых байт
+    pub bytes_received: u64,
+    /// Количество известных пиров
+    pub peer_count: usize,
+    /// Количество подключенных пиров
+    pub connected_peers: usize,
+    /// Время с момента создания узла
+    pub uptime: Duration,
+}
+
+/// Счетчики сообщений узла
+///
+/// Обновляются на путях отправки и приема без блокировок.
+#[derive(Debug, Default)]
+pub(crate) struct StatsCounters {
+    /// Количество успешно отправленных сообщений
+    messages_sent: AtomicU64,
+    /// Количество принятых сообщений
+    messages_received: AtomicU64,
+    /// Количество отправленных байт
+    bytes_sent: AtomicU64,
+    /// Количество принятых байт
+    bytes_received: AtomicU64,
+}
+
+impl StatsCounters {
+    /// Учесть отправленное сообщение
+    pub(crate) fn record_sent(&self, bytes: usize) {
+        self.messages_sent.fetch_add(1, Ordering::Relaxed);
+        self.bytes_sent.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+    
+    /// Учесть принятое сообщение
+    pub(crate) fn record_received(&self, bytes: usize) {
+        self.messages_received.fetch_add(1, Ordering::Relaxed);
+        self.bytes_received.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+    
+    /// Заполнить счетчики сообщений в снимке
+    pub(crate) fn fill(&self, stats: &mut NodeStats) {
+        stats.messages_sent = self.messages_sent.load(Ordering::Relaxed);
+        stats.messages_received = self.messages_received.load(Ordering::Relaxed);
+        stats.bytes_sent = self.bytes_sent.load(Ordering::Relaxed);
+        stats.bytes_received = self.bytes_received.load(Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,131 @@
+use async_trait::async_trait;
+use futures::stream::StreamExt;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::crypto::Cipher;
+use crate::error::Result;
+use super::{EntryStream, Storage};
+
+/// Хранилище, шифрующее значения поверх другого хранилища
+///
+/// Значения шифруются при записи и расшифровываются при чтении, ключи
+/// хранятся открыто, поэтому поиск по префиксу и проверка наличия ключа
+/// передаются вложенному хранилищу без изменений.
+pub struct EncryptedStorage<S: Storage> {
+    /// Вложенное хранилище
+    inner: S,
+    /// Шифр для значений
+    cipher: Arc<dyn Cipher + Send + Sync>,
+}
+
+impl<S: Storage> EncryptedStorage<S> {
+    /// Создать шифрующее хранилище поверх `inner`
+    pub fn new(inner: S, cipher: impl Cipher + Send + Sync + 'static) -> Self {
+        Self {
+            inner,
+            cipher: Arc::new(cipher),
+        }
+    }
+    
+    /// Получить вложенное хранилище
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+    
+    /// Извлечь вложенное хранилище
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+#[async_trait]
+impl<S: Storage> Storage for EncryptedStorage<S> {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+    
+    async fn put(&mut self, key: &[u8], value: &[u8]) -> Result<()> {
+        let encrypted = self.cipher.encrypt(value)?;
+        self.inner.put(key, &encrypted).await
+    }
+    
+    async fn put_with_ttl(&mut self, key: &[u8], value: &[u8], ttl: Duration) -> Result<()> {
+        let encrypted = self.cipher.encrypt(value)?;
+        self.inner.put_with_ttl(key, &encrypted, ttl).await
+    }
+    
+    async fn put_batch(&mut self, entries: &[(Vec<u8>, Vec<u8>)]) -> Result<()> {
+        // Шифруем всю пачку заранее, чтобы сохранить атомарность вложенного хранилища
+        let encrypted = entries.iter()
+            .map(|(key, value)| Ok((key.clone(), self.cipher.encrypt(value)?)))
+            .collect::<Result<Vec<_>>>()?;
+        
+        self.inner.put_batch(&encrypted).await
+    }
+    
+    async fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        match self.inner.get(key).await? {
+            Some(encrypted) => Ok(Some(self.cipher.decrypt(&encrypted)?)),
+            None => Ok(None),
+        }
+    }
+    
+    async fn get_many(&self, keys: &[Vec<u8>]) -> Result<Vec<Option<Vec<u8>>>> {
+        self.inner.get_many(keys).await?
+            .into_iter()
+            .map(|value| value.map(|encrypted| self.cipher.decrypt(&encrypted)).transpose())
+            .collect()
+    }
+    
+    async fn delete(&mut self, key: &[u8]) -> Result<()> {
+        self.inner.delete(key).await
+    }
+    
+    async fn has(&self, key: &[u8]) -> Result<bool> {
+        self.inner.has(key).await
+    }
+    
+    async fn keys_with_prefix(&self, prefix: &[u8]) -> Result<Vec<Vec<u8>>> {
+        self.inner.keys_with_prefix(prefix).await
+    }
+    
+    async fn scan_prefix(&self, prefix: &[u8]) -> Result<EntryStream> {
+        let cipher = Arc::clone(&self.cipher);
+        let entries = self.inner.scan_prefix(prefix).await?
+            .map(move |entry| entry.and_then(|(key, encrypted)| Ok((key, cipher.decrypt(&encrypted)?))));
+        
+        Ok(Box::new(entries))
+    }
+    
+    async fn close(&mut self) -> Result<()> {
+        self.inner.close().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::cipher::{ChaCha20Poly1305Cipher, KEY_LEN};
+    use crate::error::Error;
+    use crate::storage::memory::MemoryStorage;
+    
+    #[tokio::test]
+    async fn values_are_encrypted_at_rest() {
+        let inner = MemoryStorage::new("test");
+        let mut storage = EncryptedStorage::new(inner.clone(), ChaCha20Poly1305Cipher::new(&[1u8; KEY_LEN]).unwrap());
+        storage.put(b"wallet", b"private key").await.unwrap();
+        
+        // Во вложенном хранилище лежит шифротекст, ключ остается открытым
+        let stored = inner.get(b"wallet").await.unwrap().unwrap();
+        assert_ne!(stored, b"private key");
+        assert!(!stored.windows(b"private key".len()).any(|w| w == b"private key"));
+        assert!(storage.has(b"wallet").await.unwrap());
+        assert_eq!(storage.keys_with_prefix(b"wal").await.unwrap(), vec![b"wallet".to_vec()]);
+        assert_eq!(storage.get(b"wallet").await.unwrap(), Some(b"private key".to_vec()));
+        
+        // С другим ключом шифрования значение не расшифровывается
+        let wrong = EncryptedStorage::new(inner, ChaCha20Poly1305Cipher::new(&[2u8; KEY_LEN]).unwrap());
+        assert!(matches!(wrong.get(b"wallet").await, Err(Error::Crypto(_))));
+    }
+}
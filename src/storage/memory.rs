@@ -1,16 +1,42 @@
 use async_trait::async_trait;
-use std::collections::HashMap;
+use std::collections::BTreeMap;
+use std::ops::Bound;
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 use crate::error::{Error, Result};
-use super::Storage;
+use super::{EntryStream, Storage};
+
+/// Значение хранилища в памяти
+#[derive(Debug, Clone)]
+struct Entry {
+    /// Сохраненное значение
+    value: Vec<u8>,
+    /// Момент, после которого значение считается отсутствующим
+    expires_at: Option<Instant>,
+}
+
+impl Entry {
+    /// Создать бессрочное значение
+    fn permanent(value: Vec<u8>) -> Self {
+        Self { value, expires_at: None }
+    }
+    
+    /// Действует ли значение в момент `now`
+    fn is_live(&self, now: Instant) -> bool {
+        self.expires_at.map(|expires_at| expires_at > now).unwrap_or(true)
+    }
+}
 
 /// Реализация хранилища в памяти
+///
+/// Значения с истекшим сроком жизни не возвращаются при чтении и удаляются
+/// при обращении к ним или при вызове `purge_expired`.
 pub struct MemoryStorage {
     /// Имя хранилища
     name: String,
-    /// Данные хранилища
-    data: Arc<Mutex<HashMap<Vec<u8>, Vec<u8>>>>,
+    /// Данные хранилища, упорядоченные по ключу
+    data: Arc<Mutex<BTreeMap<Vec<u8>, Entry>>>,
 }
 
 impl MemoryStorage {
@@ -18,9 +44,22 @@ impl MemoryStorage {
     pub fn new(name: impl Into<String>) -> Self {
         Self {
             name: name.into(),
-            data: Arc::new(Mutex::new(HashMap::new())),
+            data: Arc::new(Mutex::new(BTreeMap::new())),
         }
     }
+    
+    /// Удалить все значения с истекшим сроком жизни
+    ///
+    /// Возвращает количество удаленных значений.
+    pub fn purge_expired(&self) -> Result<usize> {
+        let mut data = self.data.lock()
+            .map_err(|_| Error::Storage("Не удалось получить блокировку хранилища".to_string()))?;
+        
+        let now = Instant::now();
+        let before = data.len();
+        data.retain(|_, entry| entry.is_live(now));
+        Ok(before - data.len())
+    }
 }
 
 #[async_trait]
@@ -33,15 +72,53 @@ impl Storage for MemoryStorage {
         let mut data = self.data.lock()
             .map_err(|_| Error::Storage("Не удалось получить блокировку хранилища".to_string()))?;
         
-        data.insert(key.to_vec(), value.to_vec());
+        data.insert(key.to_vec(), Entry::permanent(value.to_vec()));
+        Ok(())
+    }
+    
+    async fn put_with_ttl(&mut self, key: &[u8], value: &[u8], ttl: Duration) -> Result<()> {
+        let mut data = self.data.lock()
+            .map_err(|_| Error::Storage("Не удалось получить блокировку хранилища".to_string()))?;
+        
+        data.insert(key.to_vec(), Entry {
+            value: value.to_vec(),
+            expires_at: Some(Instant::now() + ttl),
+        });
+        Ok(())
+    }
+    
+    async fn put_batch(&mut self, entries: &[(Vec<u8>, Vec<u8>)]) -> Result<()> {
+        // Все значения записываются под одной блокировкой, поэтому
+        // читатели видят пачку либо целиком, либо не видят вовсе
+        let mut data = self.data.lock()
+            .map_err(|_| Error::Storage("Не удалось получить блокировку хранилища".to_string()))?;
+        
+        data.extend(entries.iter().map(|(key, value)| (key.clone(), Entry::permanent(value.clone()))));
         Ok(())
     }
     
     async fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        let mut data = self.data.lock()
+            .map_err(|_| Error::Storage("Не удалось получить блокировку хранилища".to_string()))?;
+        
+        match data.get(key) {
+            Some(entry) if entry.is_live(Instant::now()) => Ok(Some(entry.value.clone())),
+            Some(_) => {
+                data.remove(key);
+                Ok(None)
+            }
+            None => Ok(None),
+        }
+    }
+    
+    async fn get_many(&self, keys: &[Vec<u8>]) -> Result<Vec<Option<Vec<u8>>>> {
         let data = self.data.lock()
             .map_err(|_| Error::Storage("Не удалось получить блокировку хранилища".to_string()))?;
         
-        Ok(data.get(key).cloned())
+        let now = Instant::now();
+        Ok(keys.iter()
+            .map(|key| data.get(key).filter(|entry| entry.is_live(now)).map(|entry| entry.value.clone()))
+            .collect())
     }
     
     async fn delete(&mut self, key: &[u8]) -> Result<()> {
@@ -53,19 +130,27 @@ impl Storage for MemoryStorage {
     }
     
     async fn has(&self, key: &[u8]) -> Result<bool> {
-        let data = self.data.lock()
+        let mut data = self.data.lock()
             .map_err(|_| Error::Storage("Не удалось получить блокировку хранилища".to_string()))?;
         
-        Ok(data.contains_key(key))
+        match data.get(key) {
+            Some(entry) if entry.is_live(Instant::now()) => Ok(true),
+            Some(_) => {
+                data.remove(key);
+                Ok(false)
+            }
+            None => Ok(false),
+        }
     }
     
     async fn keys_with_prefix(&self, prefix: &[u8]) -> Result<Vec<Vec<u8>>> {
         let data = self.data.lock()
             .map_err(|_| Error::Storage("Не удалось получить блокировку хранилища".to_string()))?;
         
+        let now = Instant::now();
         let mut keys = Vec::new();
-        for key in data.keys() {
-            if key.starts_with(prefix) {
+        for (key, entry) in data.iter() {
+            if key.starts_with(prefix) && entry.is_live(now) {
                 keys.push(key.clone());
             }
         }
@@ -73,6 +158,49 @@ impl Storage for MemoryStorage {
         Ok(keys)
     }
     
+    async fn scan_prefix(&self, prefix: &[u8]) -> Result<EntryStream> {
+        let data = Arc::clone(&self.data);
+        let prefix = prefix.to_vec();
+        let mut cursor = Bound::Included(prefix.clone());
+        let mut finished = false;
+        
+        // Каждая пара читается под отдельной блокировкой, начиная с ключа,
+        // следующего за последним выданным
+        let entries = std::iter::from_fn(move || {
+            if finished {
+                return None;
+            }
+            
+            let data = match data.lock() {
+                Ok(data) => data,
+                Err(_) => {
+                    finished = true;
+                    return Some(Err(Error::Storage("Не удалось получить блокировку хранилища".to_string())));
+                }
+            };
+            
+            // Значения с истекшим сроком жизни пропускаем
+            let now = Instant::now();
+            let next = data.range((cursor.clone(), Bound::Unbounded))
+                .take_while(|(key, _)| key.starts_with(&prefix))
+                .find(|(_, entry)| entry.is_live(now))
+                .map(|(key, entry)| (key.clone(), entry.value.clone()));
+            
+            match next {
+                Some((key, value)) => {
+                    cursor = Bound::Excluded(key.clone());
+                    Some(Ok((key, value)))
+                }
+                None => {
+                    finished = true;
+                    None
+                }
+            }
+        });
+        
+        Ok(Box::new(futures::stream::iter(entries)))
+    }
+    
     async fn close(&mut self) -> Result<()> {
         // Для хранилища в памяти не требуется никаких действий
         Ok(())
@@ -86,4 +214,83 @@ impl Clone for MemoryStorage {
             data: Arc::clone(&self.data),
         }
     }
-} 
\ No newline at end of file
+} 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::stream::StreamExt;
+    
+    #[tokio::test]
+    async fn get_many_preserves_order_and_reports_missing() {
+        let mut storage = MemoryStorage::new("test");
+        storage.put(b"a", b"1").await.unwrap();
+        storage.put(b"c", b"3").await.unwrap();
+        
+        let keys = vec![b"c".to_vec(), b"b".to_vec(), b"a".to_vec()];
+        let values = storage.get_many(&keys).await.unwrap();
+        assert_eq!(values, vec![Some(b"3".to_vec()), None, Some(b"1".to_vec())]);
+        
+        assert!(storage.get_many(&[]).await.unwrap().is_empty());
+    }
+    
+    #[tokio::test]
+    async fn put_batch_applies_all_entries() {
+        let mut storage = MemoryStorage::new("test");
+        storage.put(b"a", b"old").await.unwrap();
+        
+        storage.put_batch(&[
+            (b"a".to_vec(), b"1".to_vec()),
+            (b"b".to_vec(), b"2".to_vec()),
+            (b"c".to_vec(), b"3".to_vec()),
+        ]).await.unwrap();
+        
+        let keys = vec![b"a".to_vec(), b"b".to_vec(), b"c".to_vec()];
+        let values = storage.get_many(&keys).await.unwrap();
+        assert_eq!(values, vec![Some(b"1".to_vec()), Some(b"2".to_vec()), Some(b"3".to_vec())]);
+    }
+    
+    #[tokio::test]
+    async fn scan_prefix_streams_matching_entries() {
+        let mut storage = MemoryStorage::new("test");
+        for i in 0..1000u32 {
+            storage.put(format!("block:{:04}", i).as_bytes(), &i.to_be_bytes()).await.unwrap();
+        }
+        storage.put(b"last_height", b"999").await.unwrap();
+        storage.put(b"blocks", b"-").await.unwrap();
+        
+        let mut stream = storage.scan_prefix(b"block:").await.unwrap();
+        let mut count = 0u32;
+        while let Some(entry) = stream.next().await {
+            let (key, value) = entry.unwrap();
+            assert_eq!(key, format!("block:{:04}", count).into_bytes());
+            assert_eq!(value, count.to_be_bytes());
+            count += 1;
+        }
+        assert_eq!(count, 1000);
+    }
+    
+    #[tokio::test]
+    async fn value_with_short_ttl_expires() {
+        let mut storage = MemoryStorage::new("test");
+        storage.put_with_ttl(b"cache", b"value", Duration::from_millis(30)).await.unwrap();
+        storage.put(b"permanent", b"value").await.unwrap();
+        assert_eq!(storage.get(b"cache").await.unwrap(), Some(b"value".to_vec()));
+        assert!(storage.has(b"cache").await.unwrap());
+        
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(storage.get(b"cache").await.unwrap(), None);
+        assert!(!storage.has(b"cache").await.unwrap());
+        assert_eq!(storage.keys_with_prefix(b"").await.unwrap(), vec![b"permanent".to_vec()]);
+    }
+    
+    #[tokio::test]
+    async fn put_replaces_value_with_ttl() {
+        let mut storage = MemoryStorage::new("test");
+        storage.put_with_ttl(b"cache", b"old", Duration::from_millis(30)).await.unwrap();
+        storage.put(b"cache", b"new").await.unwrap();
+        
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(storage.get(b"cache").await.unwrap(), Some(b"new".to_vec()));
+    }
+}
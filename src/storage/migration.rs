@@ -0,0 +1,96 @@
+ use async_trait::async_trait;
+
+use crate::error::{Error, Result};
+use super::Storage;
+
+/// Ключ, под которым хранится текущая версия схемы данных
+pub const SCHEMA_VERSION_KEY: &[u8] = b"schema_version";
+
+/// Трейт миграции формата хранимых данных
+#[async_trait]
+pub trait Migration: Send + Sync {
+    /// Версия схемы, к которой приводит миграция
+    fn version(&self) -> u32;
+    
+    /// Краткое описание миграции
+    fn description(&self) -> &str;
+    
+    /// Применить миграцию к хранилищу
+    async fn apply(&self, storage: &mut dyn Storage) -> Result<()>;
+}
+
+/// Исполнитель миграций
+///
+/// Хранит версию схемы под ключом [`SCHEMA_VERSION_KEY`] и применяет
+/// по порядку все миграции с версией выше записанной.
+#[derive(Default)]
+pub struct MigrationRunner {
+    /// Зарегистрированные миграции
+    migrations: Vec<Box<dyn Migration>>,
+}
+
+impl MigrationRunner {
+    /// Создать исполнитель без миграций
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Добавить миграцию
+    pub fn with_migration(mut self, migration: Box<dyn Migration>) -> Self {
+        self.add_migration(migration);
+        self
+    }
+    
+    /// Добавить миграцию
+    ///
+    /// Миграция с уже зарегистрированной версией заменяет прежнюю.
+    pub fn add_migration(&mut self, migration: Box<dyn Migration>) {
+        self.migrations.retain(|m| m.version() != migration.version());
+        self.migrations.push(migration);
+        self.migrations.sort_by_key(|m| m.version());
+    }
+    
+    /// Последняя известная версия схемы
+    pub fn latest_version(&self) -> u32 {
+        self.migrations.last().map(|m| m.version()).unwrap_or(0)
+    }
+    
+    /// Прочитать текущую версию схемы из хранилища
+    pub async fn current_version(storage: &dyn Storage) -> Result<u32> {
+        match storage.get(SCHEMA_VERSION_KEY).await? {
+            Some(data) => bincode::deserialize(&data)
+                .map_err(|e| Error::Serialization(format!("Не удалось десериализовать версию схемы: {}", e))),
+            None => Ok(0),
+        }
+    }
+    
+    /// Применить все ожидающие миграции
+    ///
+    /// Версия записывается после каждой успешной миграции, поэтому при ошибке
+    /// повторный запуск продолжит с неудавшейся миграции. Возвращает итоговую версию.
+    pub async fn run(&self, storage: &mut dyn Storage) -> Result<u32> {
+        let mut version = Self::current_version(storage).await?;
+        
+        if version > self.latest_version() {
+            return Err(Error::Storage(format!(
+                "Версия схемы хранилища {} новее поддерживаемой {}", version, self.latest_version()
+            )));
+        }
+        
+        for migration in &self.migrations {
+            if migration.version() <= version {
+                continue;
+            }
+            
+            tracing::info!("Применение миграции {}: {}", migration.version(), migration.description());
+            migration.apply(storage).await?;
+            
+            version = migration.version();
+            let data = bincode::serialize(&version)
+                .map_err(|e| Error::Serialization(format!("Не удалось сериализовать версию схемы: {}", e)))?;
+            storage.put(SCHEMA_VERSION_KEY, &data).await?;
+        }
+        
+        Ok(version)
+    }
+}
\ No newline at end of file
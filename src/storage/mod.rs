@@ -1,5 +1,28 @@
 use async_trait::async_trait;
-use crate::error::Result;
+use futures::stream::Stream;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use crate::error::{Error, Result};
+
+/// Префикс служебных ключей, под которыми `put_with_ttl` по умолчанию хранит срок жизни
+pub const EXPIRY_PREFIX: &[u8] = b"__expiry/";
+
+/// Получить служебный ключ со сроком жизни значения `key`
+pub fn expiry_key(key: &[u8]) -> Vec<u8> {
+    let mut expiry = EXPIRY_PREFIX.to_vec();
+    expiry.extend_from_slice(key);
+    expiry
+}
+
+/// Текущее время в миллисекундах с начала эпохи Unix
+fn unix_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Поток пар ключ-значение хранилища
+pub type EntryStream = Box<dyn Stream<Item = Result<(Vec<u8>, Vec<u8>)>> + Send + Unpin>;
 
 /// Трейт для хранилища данных
 #[async_trait]
@@ -10,9 +33,80 @@ pub trait Storage: Send + Sync {
     /// Сохранить значение по ключу
     async fn put(&mut self, key: &[u8], value: &[u8]) -> Result<()>;
     
+    /// Сохранить значение с ограниченным сроком жизни
+    ///
+    /// По истечении `ttl` значение считается отсутствующим: `get` возвращает
+    /// `None`, а `has` — `false`. Реализация по умолчанию сохраняет рядом со
+    /// значением момент истечения под ключом `expiry_key(key)`. Хранилища,
+    /// которые ее используют, проверяют `is_expired` в `get` и `has`, вызывают
+    /// `clear_expiry` в `put` и `delete` и не возвращают ключи с префиксом
+    /// `EXPIRY_PREFIX` из `keys_with_prefix` и `scan_prefix`.
+    async fn put_with_ttl(&mut self, key: &[u8], value: &[u8], ttl: Duration) -> Result<()> {
+        let expires_at = unix_millis().saturating_add(ttl.as_millis() as u64);
+        self.put_batch(&[
+            (key.to_vec(), value.to_vec()),
+            (expiry_key(key), expires_at.to_be_bytes().to_vec()),
+        ]).await
+    }
+    
+    /// Истек ли срок жизни значения, сохраненного `put_with_ttl` по умолчанию
+    ///
+    /// Для значений без срока жизни и для служебных ключей возвращает `false`.
+    async fn is_expired(&self, key: &[u8]) -> Result<bool> {
+        if key.starts_with(EXPIRY_PREFIX) {
+            return Ok(false);
+        }
+        
+        match self.get(&expiry_key(key)).await? {
+            Some(data) => {
+                let bytes: [u8; 8] = data.as_slice().try_into()
+                    .map_err(|_| Error::Storage("Неверный формат срока жизни значения".to_string()))?;
+                Ok(u64::from_be_bytes(bytes) <= unix_millis())
+            }
+            None => Ok(false),
+        }
+    }
+    
+    /// Удалить срок жизни, сохраненный `put_with_ttl` по умолчанию
+    ///
+    /// Перезаписанное через `put` или удаленное значение не должно
+    /// унаследовать прежний срок жизни.
+    async fn clear_expiry(&mut self, key: &[u8]) -> Result<()> {
+        if key.starts_with(EXPIRY_PREFIX) {
+            return Ok(());
+        }
+        
+        self.delete(&expiry_key(key)).await
+    }
+    
+    /// Сохранить несколько значений
+    ///
+    /// Реализация по умолчанию сохраняет значения по очереди и не атомарна:
+    /// при ошибке часть значений может остаться записанной. Хранилища,
+    /// поддерживающие транзакции, переопределяют метод так, чтобы пачка
+    /// применялась целиком или не применялась вовсе.
+    async fn put_batch(&mut self, entries: &[(Vec<u8>, Vec<u8>)]) -> Result<()> {
+        for (key, value) in entries {
+            self.put(key, value).await?;
+        }
+        Ok(())
+    }
+    
     /// Получить значение по ключу
     async fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>>;
     
+    /// Получить значения для нескольких ключей
+    ///
+    /// Результаты возвращаются в порядке запрошенных ключей,
+    /// для отсутствующих ключей возвращается `None`.
+    async fn get_many(&self, keys: &[Vec<u8>]) -> Result<Vec<Option<Vec<u8>>>> {
+        let mut values = Vec::with_capacity(keys.len());
+        for key in keys {
+            values.push(self.get(key).await?);
+        }
+        Ok(values)
+    }
+    
     /// Удалить значение по ключу
     async fn delete(&mut self, key: &[u8]) -> Result<()>;
     
@@ -22,8 +116,111 @@ pub trait Storage: Send + Sync {
     /// Получить все ключи с определенным префиксом
     async fn keys_with_prefix(&self, prefix: &[u8]) -> Result<Vec<Vec<u8>>>;
     
+    /// Получить поток пар ключ-значение с определенным префиксом
+    ///
+    /// В отличие от `keys_with_prefix`, пары читаются по мере потребления
+    /// потока и не собираются в память целиком.
+    async fn scan_prefix(&self, prefix: &[u8]) -> Result<EntryStream>;
+    
     /// Закрыть хранилище
     async fn close(&mut self) -> Result<()>;
 }
 
-pub mod memory; 
\ No newline at end of file
+pub mod encrypted;
+pub mod memory;
+pub mod migration; 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::memory::MemoryStorage;
+    use futures::future;
+    use futures::stream::StreamExt;
+    
+    /// Хранилище, использующее реализацию `put_with_ttl` по умолчанию
+    struct PlainStorage(MemoryStorage);
+    
+    #[async_trait]
+    impl Storage for PlainStorage {
+        fn name(&self) -> &str {
+            self.0.name()
+        }
+        
+        async fn put(&mut self, key: &[u8], value: &[u8]) -> Result<()> {
+            self.clear_expiry(key).await?;
+            self.0.put(key, value).await
+        }
+        
+        async fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>> {
+            if self.is_expired(key).await? {
+                return Ok(None);
+            }
+            self.0.get(key).await
+        }
+        
+        async fn delete(&mut self, key: &[u8]) -> Result<()> {
+            self.clear_expiry(key).await?;
+            self.0.delete(key).await
+        }
+        
+        async fn has(&self, key: &[u8]) -> Result<bool> {
+            Ok(!self.is_expired(key).await? && self.0.has(key).await?)
+        }
+        
+        async fn keys_with_prefix(&self, prefix: &[u8]) -> Result<Vec<Vec<u8>>> {
+            let mut keys = Vec::new();
+            for key in self.0.keys_with_prefix(prefix).await? {
+                if !key.starts_with(EXPIRY_PREFIX) && !self.is_expired(&key).await? {
+                    keys.push(key);
+                }
+            }
+            Ok(keys)
+        }
+        
+        async fn scan_prefix(&self, prefix: &[u8]) -> Result<EntryStream> {
+            let entries = self.0.scan_prefix(prefix).await?;
+            Ok(Box::new(entries.filter(|entry| {
+                future::ready(!matches!(entry, Ok((key, _)) if key.starts_with(EXPIRY_PREFIX)))
+            })))
+        }
+        
+        async fn close(&mut self) -> Result<()> {
+            self.0.close().await
+        }
+    }
+    
+    #[tokio::test]
+    async fn default_put_with_ttl_expires_value() {
+        let mut storage = PlainStorage(MemoryStorage::new("test"));
+        storage.put_with_ttl(b"cache", b"value", Duration::from_millis(30)).await.unwrap();
+        storage.put(b"permanent", b"value").await.unwrap();
+        assert_eq!(storage.get(b"cache").await.unwrap(), Some(b"value".to_vec()));
+        assert!(storage.has(b"cache").await.unwrap());
+        assert!(storage.has(&expiry_key(b"cache")).await.unwrap());
+        assert_eq!(storage.keys_with_prefix(b"").await.unwrap(), vec![b"cache".to_vec(), b"permanent".to_vec()]);
+        
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(storage.get(b"cache").await.unwrap(), None);
+        assert!(!storage.has(b"cache").await.unwrap());
+        assert!(storage.has(b"permanent").await.unwrap());
+    }
+    
+    #[tokio::test]
+    async fn default_put_and_delete_clear_ttl() {
+        let mut storage = PlainStorage(MemoryStorage::new("test"));
+        storage.put_with_ttl(b"cache", b"old", Duration::from_millis(30)).await.unwrap();
+        storage.put(b"cache", b"new").await.unwrap();
+        assert!(!storage.has(&expiry_key(b"cache")).await.unwrap());
+        
+        storage.put_with_ttl(b"session", b"value", Duration::from_secs(60)).await.unwrap();
+        storage.delete(b"session").await.unwrap();
+        assert!(!storage.has(&expiry_key(b"session")).await.unwrap());
+        
+        let entries: Vec<_> = storage.scan_prefix(b"").await.unwrap().collect().await;
+        assert_eq!(entries.len(), 1);
+        
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(storage.get(b"cache").await.unwrap(), Some(b"new".to_vec()));
+        assert_eq!(storage.keys_with_prefix(b"").await.unwrap(), vec![b"cache".to_vec()]);
+    }
+}
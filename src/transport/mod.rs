@@ -1,8 +1,11 @@
 use async_trait::async_trait;
 use std::net::SocketAddr;
-use tokio::sync::mpsc;
+use tokio::sync::broadcast;
 use crate::error::Result;
-use crate::types::TransportType;
+use crate::types::{Endpoint, TransportType};
+
+/// Емкость канала входящих сообщений транспорта по умолчанию
+pub const INCOMING_CHANNEL_CAPACITY: usize = 1024;
 
 /// Трейт для транспортных протоколов
 #[async_trait]
@@ -14,17 +17,23 @@ pub trait Transport: Send + Sync {
     async fn listen(&mut self, address: &str, port: u16) -> Result<()>;
     
     /// Подключиться к удаленному узлу
-    async fn connect(&mut self, address: &str) -> Result<()>;
+    async fn connect(&mut self, endpoint: &Endpoint) -> Result<()>;
     
     /// Отправить данные на указанный адрес
-    async fn send_to(&self, address: &str, data: &[u8]) -> Result<()>;
+    async fn send_to(&self, endpoint: &Endpoint, data: &[u8]) -> Result<()>;
     
-    /// Получить канал для входящих сообщений
-    fn incoming(&self) -> mpsc::Receiver<(Vec<u8>, SocketAddr)>;
+    /// Подписаться на входящие сообщения
+    ///
+    /// Каждый вызов создает нового подписчика, получающего сообщения,
+    /// поступившие после подписки.
+    fn incoming(&self) -> broadcast::Receiver<(Vec<u8>, SocketAddr)>;
     
     /// Закрыть все соединения
     async fn close(&mut self) -> Result<()>;
 }
 
+pub mod noise;
 pub mod tcp;
+#[cfg(feature = "rustls")]
+pub mod tls;
 pub mod websocket; 
\ No newline at end of file
@@ -0,0 +1,510 @@
+//! Аутентифицированное шифрование поверх любого транспорта (Noise XX)
+//!
+//! Каждое новое соединение начинается с рукопожатия Noise XX на статических
+//! ключах X25519 узлов, после чего все данные шифруются. Статический ключ
+//! удаленной стороны, полученный при рукопожатии, доступен через
+//! [`NoiseTransport::peer_static_key`] и может быть привязан к `PeerId`.
+
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{broadcast, watch, RwLock};
+use tokio::task::JoinHandle;
+use zeroize::Zeroizing;
+
+use crate::crypto::x25519::X25519KeyPair;
+use crate::crypto::Key;
+use crate::error::{Error, Result};
+use crate::types::{Endpoint, TransportType};
+use super::{Transport, INCOMING_CHANNEL_CAPACITY};
+
+/// Параметры протокола Noise
+const NOISE_PARAMS: &str = "Noise_XX_25519_ChaChaPoly_BLAKE2s";
+
+/// Максимальный размер одного сообщения Noise
+const MAX_NOISE_MESSAGE_LEN: usize = 65535;
+
+/// Размер тега аутентификации
+const TAG_LEN: usize = 16;
+
+/// Максимальный размер открытого текста в одном сообщении Noise
+const MAX_CHUNK_LEN: usize = MAX_NOISE_MESSAGE_LEN - TAG_LEN;
+
+/// Таймаут рукопожатия по умолчанию
+const DEFAULT_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Метка кадра рукопожатия
+const FRAME_HANDSHAKE: u8 = 0;
+
+/// Метка кадра с зашифрованными данными
+const FRAME_DATA: u8 = 1;
+
+/// Состояние сеанса с удаленным узлом
+enum SessionState {
+    /// Рукопожатие не завершено
+    Handshaking(Box<snow::HandshakeState>),
+    /// Рукопожатие завершено, данные шифруются
+    Established(snow::TransportState),
+    /// Рукопожатие завершилось ошибкой
+    Failed,
+}
+
+/// Сеанс Noise с удаленным узлом
+struct Session {
+    /// Текущее состояние
+    state: SessionState,
+    /// Признак завершения рукопожатия для ожидающих отправителей
+    ready: watch::Sender<bool>,
+}
+
+impl Session {
+    fn new(state: SessionState) -> Self {
+        let (ready, _) = watch::channel(false);
+        Self { state, ready }
+    }
+}
+
+/// Состояние, разделяемое с задачей приема
+struct Shared<T> {
+    /// Нижележащий транспорт
+    inner: RwLock<T>,
+    /// Статический приватный ключ узла
+    static_key: Zeroizing<Vec<u8>>,
+    /// Сеансы по адресу удаленного узла
+    sessions: Mutex<HashMap<String, Arc<tokio::sync::Mutex<Session>>>>,
+    /// Статические ключи удаленных узлов, полученные при рукопожатии
+    remote_keys: Mutex<HashMap<String, Vec<u8>>>,
+    /// Канал для рассылки расшифрованных сообщений подписчикам
+    incoming_tx: broadcast::Sender<(Vec<u8>, SocketAddr)>,
+}
+
+/// Транспорт, шифрующий данные нижележащего транспорта по протоколу Noise XX
+pub struct NoiseTransport<T: Transport> {
+    /// Разделяемое состояние
+    shared: Arc<Shared<T>>,
+    /// Тип нижележащего транспорта
+    transport_type: TransportType,
+    /// Задача приема и расшифровки входящих кадров
+    receive_task: Mutex<Option<JoinHandle<()>>>,
+    /// Таймаут рукопожатия
+    handshake_timeout: Duration,
+}
+
+impl<T: Transport + 'static> NoiseTransport<T> {
+    /// Обернуть транспорт, используя статическую пару ключей X25519 узла
+    pub fn new(inner: T, keypair: &X25519KeyPair) -> Result<Self> {
+        let static_key = keypair.private_bytes()
+            .ok_or_else(|| Error::Crypto("Для Noise требуется приватный ключ X25519".to_string()))?;
+        let (incoming_tx, _) = broadcast::channel(INCOMING_CHANNEL_CAPACITY);
+        let transport_type = inner.transport_type();
+        
+        Ok(Self {
+            transport_type,
+            shared: Arc::new(Shared {
+                inner: RwLock::new(inner),
+                static_key,
+                sessions: Mutex::new(HashMap::new()),
+                remote_keys: Mutex::new(HashMap::new()),
+                incoming_tx,
+            }),
+            receive_task: Mutex::new(None),
+            handshake_timeout: DEFAULT_HANDSHAKE_TIMEOUT,
+        })
+    }
+    
+    /// Установить таймаут рукопожатия
+    pub fn with_handshake_timeout(mut self, timeout: Duration) -> Self {
+        self.handshake_timeout = timeout;
+        self
+    }
+    
+    /// Получить статический ключ узла, приславшего данные с указанного адреса
+    pub fn peer_static_key(&self, addr: &SocketAddr) -> Option<Vec<u8>> {
+        self.shared.remote_keys.lock().unwrap().get(&addr.to_string()).cloned()
+    }
+    
+    /// Получить статический ключ узла по адресу, на который выполнялась отправка
+    pub fn endpoint_static_key(&self, endpoint: &Endpoint) -> Option<Vec<u8>> {
+        self.shared.remote_keys.lock().unwrap().get(&endpoint.socket_addr()).cloned()
+    }
+    
+    /// Запустить задачу приема, если она еще не запущена
+    async fn ensure_receive_task(&self) {
+        if self.receive_task.lock().unwrap().is_some() {
+            return;
+        }
+        
+        let rx = self.shared.inner.read().await.incoming();
+        let mut task = self.receive_task.lock().unwrap();
+        if task.is_none() {
+            *task = Some(tokio::spawn(Self::receive_loop(Arc::clone(&self.shared), rx)));
+        }
+    }
+    
+    /// Создать объект рукопожатия
+    fn handshake_state(static_key: &[u8], initiator: bool) -> Result<snow::HandshakeState> {
+        let params: snow::params::NoiseParams = NOISE_PARAMS.parse()
+            .map_err(|e| Error::Crypto(format!("Некорректные параметры Noise: {:?}", e)))?;
+        let builder = snow::Builder::new(params).local_private_key(static_key);
+        
+        let state = if initiator {
+            builder.build_initiator()
+        } else {
+            builder.build_responder()
+        };
+        
+        state.map_err(|e| Error::Crypto(format!("Не удалось создать рукопожатие Noise: {}", e)))
+    }
+    
+    /// Преобразовать адрес отправителя в адрес конечной точки для ответа
+    fn reply_endpoint(transport: TransportType, addr: &SocketAddr) -> Endpoint {
+        Endpoint::new(transport, addr.ip().to_string(), addr.port())
+    }
+    
+    /// Цикл приема кадров нижележащего транспорта
+    async fn receive_loop(shared: Arc<Shared<T>>, mut rx: broadcast::Receiver<(Vec<u8>, SocketAddr)>) {
+        loop {
+            match rx.recv().await {
+                Ok((frame, addr)) => {
+                    if let Err(e) = Self::handle_frame(&shared, &frame, addr).await {
+                        tracing::debug!("Отброшен кадр Noise от {}: {}", addr, e);
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    tracing::warn!("Пропущено {} входящих кадров Noise", skipped);
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    }
+    
+    /// Обработать входящий кадр
+    async fn handle_frame(shared: &Arc<Shared<T>>, frame: &[u8], addr: SocketAddr) -> Result<()> {
+        let (&kind, payload) = frame.split_first()
+            .ok_or_else(|| Error::Crypto("Пустой кадр Noise".to_string()))?;
+        let key = addr.to_string();
+        
+        match kind {
+            FRAME_HANDSHAKE => Self::handle_handshake(shared, &key, payload, addr).await,
+            FRAME_DATA => {
+                let session = shared.sessions.lock().unwrap().get(&key).cloned()
+                    .ok_or_else(|| Error::Crypto("Данные до завершения рукопожатия".to_string()))?;
+                let mut session = session.lock().await;
+                
+                let transport = match &mut session.state {
+                    SessionState::Established(transport) => transport,
+                    _ => return Err(Error::Crypto("Данные до завершения рукопожатия".to_string())),
+                };
+                
+                let data = Self::decrypt(transport, payload)?;
+                let _ = shared.incoming_tx.send((data, addr));
+                Ok(())
+            }
+            other => Err(Error::Crypto(format!("Неизвестный тип кадра Noise: {}", other))),
+        }
+    }
+    
+    /// Обработать сообщение рукопожатия
+    async fn handle_handshake(shared: &Arc<Shared<T>>, key: &str, payload: &[u8], addr: SocketAddr) -> Result<()> {
+        let existing = shared.sessions.lock().unwrap().get(key).cloned();
+        let in_progress = match &existing {
+            Some(session) => matches!(session.lock().await.state, SessionState::Handshaking(_)),
+            None => false,
+        };
+        
+        // Новое рукопожатие от узла без сеанса или с уже установленным сеансом
+        // (например, после перезапуска) начинаем как отвечающая сторона
+        let session = match existing {
+            Some(session) if in_progress => session,
+            _ => {
+                let state = Self::handshake_state(&shared.static_key, false)?;
+                let session = Arc::new(tokio::sync::Mutex::new(Session::new(
+                    SessionState::Handshaking(Box::new(state)),
+                )));
+                shared.sessions.lock().unwrap().insert(key.to_string(), Arc::clone(&session));
+                session
+            }
+        };
+        
+        let mut session = session.lock().await;
+        let result = Self::advance_handshake(shared, &mut session, payload, addr).await;
+        
+        if result.is_err() {
+            // Закрываем канал готовности, чтобы ожидающие отправители получили ошибку
+            session.state = SessionState::Failed;
+            session.ready = watch::channel(false).0;
+            shared.sessions.lock().unwrap().remove(key);
+        }
+        
+        result
+    }
+    
+    /// Продвинуть рукопожатие на одно входящее сообщение
+    async fn advance_handshake(
+        shared: &Arc<Shared<T>>,
+        session: &mut Session,
+        payload: &[u8],
+        addr: SocketAddr,
+    ) -> Result<()> {
+        let state = match &mut session.state {
+            SessionState::Handshaking(state) => state,
+            _ => return Err(Error::Crypto("Сеанс не ожидает рукопожатия".to_string())),
+        };
+        
+        let mut buf = vec![0u8; MAX_NOISE_MESSAGE_LEN];
+        state.read_message(payload, &mut buf)
+            .map_err(|e| Error::Crypto(format!("Ошибка рукопожатия Noise: {}", e)))?;
+        
+        // Отвечаем следующим сообщением, если рукопожатие еще не завершено
+        if !state.is_handshake_finished() {
+            let len = state.write_message(&[], &mut buf)
+                .map_err(|e| Error::Crypto(format!("Ошибка рукопожатия Noise: {}", e)))?;
+            
+            let mut frame = Vec::with_capacity(1 + len);
+            frame.push(FRAME_HANDSHAKE);
+            frame.extend_from_slice(&buf[..len]);
+            
+            let inner = shared.inner.read().await;
+            let endpoint = Self::reply_endpoint(inner.transport_type(), &addr);
+            inner.send_to(&endpoint, &frame).await?;
+        }
+        
+        if state.is_handshake_finished() {
+            let remote_key = state.get_remote_static()
+                .map(|k| k.to_vec())
+                .ok_or_else(|| Error::Crypto("Удаленная сторона не передала статический ключ".to_string()))?;
+            
+            let state = match std::mem::replace(&mut session.state, SessionState::Failed) {
+                SessionState::Handshaking(state) => state,
+                _ => unreachable!(),
+            };
+            let transport = state.into_transport_mode()
+                .map_err(|e| Error::Crypto(format!("Ошибка завершения рукопожатия Noise: {}", e)))?;
+            
+            session.state = SessionState::Established(transport);
+            shared.remote_keys.lock().unwrap().insert(addr.to_string(), remote_key);
+            session.ready.send_replace(true);
+        }
+        
+        Ok(())
+    }
+    
+    /// Получить установленный сеанс, при необходимости выполнив рукопожатие
+    async fn establish(&self, endpoint: &Endpoint) -> Result<Arc<tokio::sync::Mutex<Session>>> {
+        self.ensure_receive_task().await;
+        
+        let key = endpoint.socket_addr();
+        let existing = self.shared.sessions.lock().unwrap().get(&key).cloned();
+        
+        let session = match existing {
+            Some(session) => session,
+            None => {
+                let mut state = Self::handshake_state(&self.shared.static_key, true)?;
+                let mut buf = vec![0u8; MAX_NOISE_MESSAGE_LEN];
+                let len = state.write_message(&[], &mut buf)
+                    .map_err(|e| Error::Crypto(format!("Ошибка рукопожатия Noise: {}", e)))?;
+                
+                // Сеанс регистрируется до отправки, чтобы ответ не опередил его
+                let session = Arc::new(tokio::sync::Mutex::new(Session::new(
+                    SessionState::Handshaking(Box::new(state)),
+                )));
+                self.shared.sessions.lock().unwrap().insert(key.clone(), Arc::clone(&session));
+                
+                let mut frame = Vec::with_capacity(1 + len);
+                frame.push(FRAME_HANDSHAKE);
+                frame.extend_from_slice(&buf[..len]);
+                
+                if let Err(e) = self.shared.inner.read().await.send_to(endpoint, &frame).await {
+                    self.shared.sessions.lock().unwrap().remove(&key);
+                    return Err(e);
+                }
+                
+                session
+            }
+        };
+        
+        let mut ready = session.lock().await.ready.subscribe();
+        let wait = ready.wait_for(|ready| *ready);
+        let outcome = tokio::time::timeout(self.handshake_timeout, wait).await
+            .map(|result| result.is_ok());
+        
+        match outcome {
+            Ok(true) => Ok(session),
+            Ok(false) => Err(Error::Crypto(format!("Рукопожатие Noise с {} не удалось", endpoint))),
+            Err(_) => {
+                self.shared.sessions.lock().unwrap().remove(&key);
+                Err(Error::Timeout(format!("Рукопожатие Noise с {}", endpoint)))
+            }
+        }
+    }
+    
+    /// Зашифровать данные, разбивая их на сообщения Noise
+    ///
+    /// Каждое сообщение предваряется длиной (2 байта, big-endian).
+    fn encrypt(transport: &mut snow::TransportState, data: &[u8]) -> Result<Vec<u8>> {
+        let mut out = Vec::with_capacity(1 + data.len() + (data.len() / MAX_CHUNK_LEN + 1) * (TAG_LEN + 2));
+        out.push(FRAME_DATA);
+        
+        let mut buf = vec![0u8; MAX_NOISE_MESSAGE_LEN];
+        // Пустые данные передаются одним пустым сообщением
+        let chunks: Vec<&[u8]> = if data.is_empty() { vec![data] } else { data.chunks(MAX_CHUNK_LEN).collect() };
+        
+        for chunk in chunks {
+            let len = transport.write_message(chunk, &mut buf)
+                .map_err(|e| Error::Crypto(format!("Ошибка шифрования Noise: {}", e)))?;
+            out.extend_from_slice(&(len as u16).to_be_bytes());
+            out.extend_from_slice(&buf[..len]);
+        }
+        
+        Ok(out)
+    }
+    
+    /// Расшифровать последовательность сообщений Noise
+    fn decrypt(transport: &mut snow::TransportState, mut payload: &[u8]) -> Result<Vec<u8>> {
+        let mut data = Vec::with_capacity(payload.len());
+        let mut buf = vec![0u8; MAX_NOISE_MESSAGE_LEN];
+        
+        while !payload.is_empty() {
+            if payload.len() < 2 {
+                return Err(Error::Crypto("Усеченный кадр Noise".to_string()));
+            }
+            let len = u16::from_be_bytes([payload[0], payload[1]]) as usize;
+            let message = payload.get(2..2 + len)
+                .ok_or_else(|| Error::Crypto("Усеченный кадр Noise".to_string()))?;
+            
+            let n = transport.read_message(message, &mut buf)
+                .map_err(|e| Error::Crypto(format!("Ошибка расшифровки Noise: {}", e)))?;
+            data.extend_from_slice(&buf[..n]);
+            payload = &payload[2 + len..];
+        }
+        
+        Ok(data)
+    }
+}
+
+#[async_trait]
+impl<T: Transport + 'static> Transport for NoiseTransport<T> {
+    fn transport_type(&self) -> TransportType {
+        self.transport_type.clone()
+    }
+    
+    async fn listen(&mut self, address: &str, port: u16) -> Result<()> {
+        self.shared.inner.write().await.listen(address, port).await?;
+        self.ensure_receive_task().await;
+        Ok(())
+    }
+    
+    async fn connect(&mut self, endpoint: &Endpoint) -> Result<()> {
+        self.shared.inner.write().await.connect(endpoint).await?;
+        self.establish(endpoint).await?;
+        Ok(())
+    }
+    
+    async fn send_to(&self, endpoint: &Endpoint, data: &[u8]) -> Result<()> {
+        let session = self.establish(endpoint).await?;
+        
+        // Блокировка сеанса удерживается до конца отправки, чтобы порядок
+        // сообщений совпадал с порядком nonce
+        let mut session = session.lock().await;
+        let transport = match &mut session.state {
+            SessionState::Established(transport) => transport,
+            _ => return Err(Error::Crypto(format!("Сеанс Noise с {} не установлен", endpoint))),
+        };
+        
+        let frame = Self::encrypt(transport, data)?;
+        self.shared.inner.read().await.send_to(endpoint, &frame).await
+    }
+    
+    fn incoming(&self) -> broadcast::Receiver<(Vec<u8>, SocketAddr)> {
+        self.shared.incoming_tx.subscribe()
+    }
+    
+    async fn close(&mut self) -> Result<()> {
+        // Отменяем задачу приема
+        if let Some(task) = self.receive_task.lock().unwrap().take() {
+            task.abort();
+        }
+        
+        // Удаляем все сеансы
+        self.shared.sessions.lock().unwrap().clear();
+        
+        self.shared.inner.write().await.close().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::tcp::TcpTransport;
+    
+    /// Найти свободный локальный порт
+    fn free_port() -> u16 {
+        std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port()
+    }
+    
+    /// Запустить незашифрованный TCP посредник, пересылающий кадры между
+    /// клиентом и `target` и сохраняющий все увиденные байты
+    async fn spawn_eavesdropper(port: u16, target: Endpoint) -> Arc<Mutex<Vec<u8>>> {
+        let captured = Arc::new(Mutex::new(Vec::new()));
+        let mut relay = TcpTransport::new();
+        relay.listen("127.0.0.1", port).await.unwrap();
+        let mut incoming = relay.incoming();
+        
+        let seen = Arc::clone(&captured);
+        tokio::spawn(async move {
+            let mut client = None;
+            while let Ok((data, from)) = incoming.recv().await {
+                seen.lock().unwrap().extend_from_slice(&data);
+                if from.to_string() == target.socket_addr() {
+                    if let Some(client) = &client {
+                        let _ = relay.send_to(client, &data).await;
+                    }
+                } else {
+                    client = Some(Endpoint::tcp(from.ip().to_string(), from.port()));
+                    let _ = relay.send_to(&target, &data).await;
+                }
+            }
+        });
+        
+        captured
+    }
+    
+    #[tokio::test]
+    async fn handshake_encrypts_traffic_and_reveals_static_keys() {
+        let alice_keys = X25519KeyPair::generate().unwrap();
+        let bob_keys = X25519KeyPair::generate().unwrap();
+        
+        let bob_port = free_port();
+        let mut bob = NoiseTransport::new(TcpTransport::new(), &bob_keys).unwrap();
+        bob.listen("127.0.0.1", bob_port).await.unwrap();
+        let mut bob_incoming = bob.incoming();
+        
+        // Алиса подключается к Бобу через посредника, видящего весь трафик
+        let relay_port = free_port();
+        let captured = spawn_eavesdropper(relay_port, Endpoint::tcp("127.0.0.1", bob_port)).await;
+        
+        let mut alice = NoiseTransport::new(TcpTransport::new(), &alice_keys).unwrap();
+        
+        let secret = b"the eavesdropper must not read this";
+        let relay = Endpoint::tcp("127.0.0.1", relay_port);
+        alice.send_to(&relay, secret).await.unwrap();
+        
+        let (data, from) = tokio::time::timeout(Duration::from_secs(5), bob_incoming.recv())
+            .await.unwrap().unwrap();
+        assert_eq!(data, secret);
+        
+        // Обе стороны получили статический ключ собеседника
+        assert_eq!(bob.peer_static_key(&from), Some(alice_keys.public_bytes()));
+        assert_eq!(alice.endpoint_static_key(&relay), Some(bob_keys.public_bytes()));
+        
+        // Посредник видел трафик, но не открытый текст
+        let captured = captured.lock().unwrap().clone();
+        assert!(!captured.is_empty());
+        assert!(!captured.windows(secret.len()).any(|window| window == secret));
+        
+        alice.close().await.unwrap();
+        bob.close().await.unwrap();
+    }
+}
@@ -2,43 +2,50 @@ use async_trait::async_trait;
 use std::collections::HashMap;
 use std::net::SocketAddr;
 use std::sync::{Arc, Mutex};
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+use tokio::net::tcp::OwnedWriteHalf;
 use tokio::net::{TcpListener, TcpStream};
-use tokio::sync::mpsc;
+use tokio::sync::broadcast;
 use tokio::task::JoinHandle;
 
 use crate::error::{Error, Result};
-use crate::types::TransportType;
-use super::Transport;
+use crate::types::{Endpoint, TransportType};
+use super::{Transport, INCOMING_CHANNEL_CAPACITY};
+
+/// Длина заголовка кадра (префикс длины)
+const FRAME_HEADER_LEN: usize = 4;
+
+/// Максимальный размер кадра по умолчанию (16 MB)
+pub const DEFAULT_MAX_FRAME_SIZE: usize = 16 * 1024 * 1024;
 
 /// Реализация транспорта на основе TCP
 pub struct TcpTransport {
-    /// Канал для отправки входящих сообщений
-    incoming_tx: mpsc::Sender<(Vec<u8>, SocketAddr)>,
-    /// Канал для получения входящих сообщений
-    incoming_rx: mpsc::Receiver<(Vec<u8>, SocketAddr)>,
-    /// Активные соединения
-    connections: Arc<Mutex<HashMap<String, TcpStream>>>,
+    /// Канал для рассылки входящих сообщений подписчикам
+    incoming_tx: broadcast::Sender<(Vec<u8>, SocketAddr)>,
+    /// Пишущие половины активных соединений, по адресу удаленного узла
+    connections: Arc<Mutex<HashMap<String, OwnedWriteHalf>>>,
     /// Задача для прослушивания входящих соединений
     listener_task: Option<JoinHandle<()>>,
     /// Адрес для прослушивания
     listen_addr: Option<SocketAddr>,
     /// Размер буфера для чтения
     read_buffer_size: usize,
+    /// Максимальный размер кадра
+    max_frame_size: usize,
 }
 
 impl TcpTransport {
     /// Создать новый TCP транспорт
     pub fn new() -> Self {
-        let (incoming_tx, incoming_rx) = mpsc::channel(100);
+        let (incoming_tx, _) = broadcast::channel(INCOMING_CHANNEL_CAPACITY);
         
         Self {
             incoming_tx,
-            incoming_rx,
             connections: Arc::new(Mutex::new(HashMap::new())),
             listener_task: None,
             listen_addr: None,
             read_buffer_size: 4096, // 4 KB
+            max_frame_size: DEFAULT_MAX_FRAME_SIZE,
         }
     }
     
@@ -48,15 +55,63 @@ impl TcpTransport {
         self
     }
     
-    /// Обработать входящее соединение
-    async fn handle_connection(
+    /// Установить максимальный размер кадра
+    ///
+    /// Соединения, присылающие кадры большего размера, закрываются.
+    pub fn with_max_frame_size(mut self, size: usize) -> Self {
+        self.max_frame_size = size;
+        self
+    }
+    
+    /// Добавить к данным префикс длины (4 байта, big-endian)
+    pub(super) fn encode_frame(data: &[u8], max_frame_size: usize) -> Result<Vec<u8>> {
+        if data.len() > max_frame_size || data.len() > u32::MAX as usize {
+            return Err(Error::Transport(format!(
+                "Размер сообщения {} превышает максимальный размер кадра {}", data.len(), max_frame_size
+            )));
+        }
+        
+        let mut frame = Vec::with_capacity(FRAME_HEADER_LEN + data.len());
+        frame.extend_from_slice(&(data.len() as u32).to_be_bytes());
+        frame.extend_from_slice(data);
+        Ok(frame)
+    }
+    
+    /// Разделить соединение на половины
+    ///
+    /// Читающая половина обслуживается в отдельной задаче, пишущая возвращается
+    /// для сохранения в таблице соединений.
+    fn split_stream(
         stream: TcpStream,
         addr: SocketAddr,
-        tx: mpsc::Sender<(Vec<u8>, SocketAddr)>,
+        tx: broadcast::Sender<(Vec<u8>, SocketAddr)>,
+        buffer_size: usize,
+        max_frame_size: usize,
+    ) -> OwnedWriteHalf {
+        let (read_half, write_half) = stream.into_split();
+        
+        tokio::spawn(async move {
+            Self::handle_connection(read_half, addr, tx, buffer_size, max_frame_size).await;
+        });
+        
+        write_half
+    }
+    
+    /// Обработать входящие данные соединения
+    ///
+    /// Принимает любой читаемый поток, чтобы кадрирование можно было
+    /// переиспользовать поверх TLS.
+    pub(super) async fn handle_connection<R: AsyncRead + Unpin>(
+        stream: R,
+        addr: SocketAddr,
+        tx: broadcast::Sender<(Vec<u8>, SocketAddr)>,
         buffer_size: usize,
+        max_frame_size: usize,
     ) {
         let mut stream = stream;
         let mut buffer = vec![0u8; buffer_size];
+        // Накопленные байты, еще не составившие полный кадр
+        let mut pending: Vec<u8> = Vec::new();
         
         // Читаем данные из соединения
         loop {
@@ -66,10 +121,26 @@ impl TcpTransport {
                     break;
                 }
                 Ok(n) => {
-                    // Отправляем данные в канал
-                    if let Err(_) = tx.send((buffer[..n].to_vec(), addr)).await {
-                        // Канал закрыт, выходим из цикла
-                        break;
+                    pending.extend_from_slice(&buffer[..n]);
+                    
+                    // Извлекаем все полные кадры
+                    while pending.len() >= FRAME_HEADER_LEN {
+                        let frame_len = u32::from_be_bytes([pending[0], pending[1], pending[2], pending[3]]) as usize;
+                        
+                        if frame_len > max_frame_size {
+                            // Кадр превышает допустимый размер, закрываем соединение
+                            return;
+                        }
+                        
+                        if pending.len() < FRAME_HEADER_LEN + frame_len {
+                            break;
+                        }
+                        
+                        let frame = pending[FRAME_HEADER_LEN..FRAME_HEADER_LEN + frame_len].to_vec();
+                        pending.drain(..FRAME_HEADER_LEN + frame_len);
+                        
+                        // Отправляем кадр подписчикам; при их отсутствии кадр отбрасывается
+                        let _ = tx.send((frame, addr));
                     }
                 }
                 Err(_) => {
@@ -99,22 +170,16 @@ impl Transport for TcpTransport {
         let connections = Arc::clone(&self.connections);
         let tx = self.incoming_tx.clone();
         let buffer_size = self.read_buffer_size;
+        let max_frame_size = self.max_frame_size;
         
         // Запускаем задачу для прослушивания
         let task = tokio::spawn(async move {
             loop {
                 match listener.accept().await {
                     Ok((stream, addr)) => {
-                        // Сохраняем соединение
-                        let addr_str = addr.to_string();
-                        let mut stream_for_map = stream.try_clone().unwrap();
-                        connections.lock().unwrap().insert(addr_str, stream_for_map);
-                        
-                        // Запускаем обработку соединения
-                        let tx_clone = tx.clone();
-                        tokio::spawn(async move {
-                            Self::handle_connection(stream, addr, tx_clone, buffer_size).await;
-                        });
+                        // Сохраняем пишущую половину и запускаем чтение
+                        let writer = Self::split_stream(stream, addr, tx.clone(), buffer_size, max_frame_size);
+                        connections.lock().unwrap().insert(addr.to_string(), writer);
                     }
                     Err(_) => {
                         // Ошибка при принятии соединения
@@ -130,40 +195,70 @@ impl Transport for TcpTransport {
         Ok(())
     }
     
-    async fn connect(&mut self, address: &str) -> Result<()> {
+    async fn connect(&mut self, endpoint: &Endpoint) -> Result<()> {
+        let address = endpoint.socket_addr();
+        
         // Подключаемся к удаленному адресу
-        let stream = TcpStream::connect(address).await
+        let stream = TcpStream::connect(&address).await
             .map_err(|e| Error::Transport(format!("Не удалось подключиться к {}: {}", address, e)))?;
+        let peer_addr = stream.peer_addr()
+            .map_err(|e| Error::Transport(format!("Не удалось получить адрес {}: {}", address, e)))?;
         
         // Сохраняем соединение
-        let mut connections = self.connections.lock().unwrap();
-        connections.insert(address.to_string(), stream);
+        let writer = Self::split_stream(
+            stream,
+            peer_addr,
+            self.incoming_tx.clone(),
+            self.read_buffer_size,
+            self.max_frame_size,
+        );
+        self.connections.lock().unwrap().insert(address, writer);
         
         Ok(())
     }
     
-    async fn send_to(&self, address: &str, data: &[u8]) -> Result<()> {
-        // Проверяем, есть ли соединение
-        let mut connections = self.connections.lock().unwrap();
-        let stream = if let Some(stream) = connections.get_mut(address) {
-            stream
-        } else {
-            // Если нет соединения, пытаемся подключиться
-            let stream = TcpStream::connect(address).await
-                .map_err(|e| Error::Transport(format!("Не удалось подключиться к {}: {}", address, e)))?;
-            connections.insert(address.to_string(), stream);
-            connections.get_mut(address).unwrap()
+    async fn send_to(&self, endpoint: &Endpoint, data: &[u8]) -> Result<()> {
+        if endpoint.transport != TransportType::Tcp {
+            return Err(Error::Transport(format!("Адрес не является TCP адресом: {}", endpoint)));
+        }
+        
+        let address = endpoint.socket_addr();
+        
+        // Забираем существующее соединение, не удерживая блокировку во время ввода-вывода
+        let existing = self.connections.lock().unwrap().remove(&address);
+        let mut writer = match existing {
+            Some(writer) => writer,
+            None => {
+                // Если нет соединения, подключаемся
+                let stream = TcpStream::connect(&address).await
+                    .map_err(|e| Error::Transport(format!("Не удалось подключиться к {}: {}", address, e)))?;
+                let peer_addr = stream.peer_addr()
+                    .map_err(|e| Error::Transport(format!("Не удалось получить адрес {}: {}", address, e)))?;
+                
+                // Ответы узла читаем так же, как и у входящих соединений
+                Self::split_stream(
+                    stream,
+                    peer_addr,
+                    self.incoming_tx.clone(),
+                    self.read_buffer_size,
+                    self.max_frame_size,
+                )
+            }
         };
         
-        // Отправляем данные
-        stream.write_all(data).await
+        // Отправляем кадр с префиксом длины
+        let frame = Self::encode_frame(data, self.max_frame_size)?;
+        writer.write_all(&frame).await
             .map_err(|e| Error::Transport(format!("Ошибка отправки данных: {}", e)))?;
         
+        // Возвращаем соединение для повторного использования
+        self.connections.lock().unwrap().insert(address, writer);
+        
         Ok(())
     }
     
-    fn incoming(&self) -> mpsc::Receiver<(Vec<u8>, SocketAddr)> {
-        self.incoming_rx.clone()
+    fn incoming(&self) -> broadcast::Receiver<(Vec<u8>, SocketAddr)> {
+        self.incoming_tx.subscribe()
     }
     
     async fn close(&mut self) -> Result<()> {
@@ -184,4 +279,83 @@ impl Default for TcpTransport {
     fn default() -> Self {
         Self::new()
     }
-} 
\ No newline at end of file
+} 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    
+    #[tokio::test]
+    async fn back_to_back_messages_arrive_as_separate_frames() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (tx, mut rx) = broadcast::channel(10);
+        tokio::spawn(async move {
+            let (stream, peer) = listener.accept().await.unwrap();
+            let (reader, _writer) = stream.into_split();
+            // Маленький буфер чтения разрезает кадры на части
+            TcpTransport::handle_connection(reader, peer, tx, 7, DEFAULT_MAX_FRAME_SIZE).await;
+        });
+        
+        let messages = [&b"first"[..], b"second", b"third"];
+        let mut bytes = Vec::new();
+        for message in messages {
+            bytes.extend(TcpTransport::encode_frame(message, DEFAULT_MAX_FRAME_SIZE).unwrap());
+        }
+        let mut client = TcpStream::connect(addr).await.unwrap();
+        client.write_all(&bytes).await.unwrap();
+        
+        for expected in messages {
+            let (frame, _) = rx.recv().await.unwrap();
+            assert_eq!(frame, expected);
+        }
+    }
+    
+    /// Найти свободный локальный порт
+    fn free_port() -> u16 {
+        std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port()
+    }
+    
+    #[tokio::test]
+    async fn listening_transport_delivers_client_bytes() {
+        let port = free_port();
+        let mut transport = TcpTransport::new();
+        transport.listen("127.0.0.1", port).await.unwrap();
+        let mut incoming = transport.incoming();
+        
+        let mut client = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+        let frame = TcpTransport::encode_frame(b"hello", DEFAULT_MAX_FRAME_SIZE).unwrap();
+        client.write_all(&frame).await.unwrap();
+        
+        let (data, from) = tokio::time::timeout(Duration::from_secs(2), incoming.recv()).await.unwrap().unwrap();
+        assert_eq!(data, b"hello");
+        assert_eq!(from, client.local_addr().unwrap());
+        
+        transport.close().await.unwrap();
+    }
+    
+    #[tokio::test]
+    async fn accepted_connection_echoes_data_back() {
+        let port = free_port();
+        let mut server = TcpTransport::new();
+        server.listen("127.0.0.1", port).await.unwrap();
+        let mut server_incoming = server.incoming();
+        
+        let client = TcpTransport::new();
+        let mut client_incoming = client.incoming();
+        client.send_to(&Endpoint::tcp("127.0.0.1", port), b"ping").await.unwrap();
+        
+        let (data, from) = tokio::time::timeout(Duration::from_secs(2), server_incoming.recv()).await.unwrap().unwrap();
+        assert_eq!(data, b"ping");
+        
+        // Ответ уходит по принятому соединению
+        let reply_to = Endpoint::tcp(from.ip().to_string(), from.port());
+        server.send_to(&reply_to, &data).await.unwrap();
+        
+        let (echo, _) = tokio::time::timeout(Duration::from_secs(2), client_incoming.recv()).await.unwrap().unwrap();
+        assert_eq!(echo, b"ping");
+        
+        server.close().await.unwrap();
+    }
+}
@@ -0,0 +1,420 @@
+//! TCP транспорт, защищенный TLS
+//!
+//! Транспорт использует то же кадрирование с префиксом длины, что и
+//! [`TcpTransport`](super::tcp::TcpTransport), но поверх TLS соединения.
+//!
+//! Конфигурация сервера и клиента передается в конструктор:
+//!
+//! ```ignore
+//! use noxy::transport::tls::{self, TlsTcpTransport};
+//! use noxy::transport::tls::rustls::{Certificate, PrivateKey, RootCertStore};
+//!
+//! // Сертификат и ключ сервера в формате DER
+//! let server_config = tls::server_config(vec![Certificate(cert_der)], PrivateKey(key_der))?;
+//!
+//! // Проверка сертификатов пиров по набору доверенных корней
+//! let mut roots = RootCertStore::empty();
+//! roots.add(&Certificate(ca_der))?;
+//! let client_config = tls::client_config(roots);
+//!
+//! let transport = TlsTcpTransport::new(server_config, client_config);
+//! ```
+//!
+//! Готовый `rustls::ServerConfig` (например, с проверкой клиентских
+//! сертификатов) можно передать напрямую, обернув его в `Arc`.
+//!
+//! Клиент без проверки сертификатов (`insecure_client_config`) доступен
+//! только с функцией `insecure-tls`.
+
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+#[cfg(feature = "insecure-tls")]
+use std::time::SystemTime;
+use tokio::io::{AsyncWriteExt, WriteHalf};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+#[cfg(feature = "insecure-tls")]
+use tokio_rustls::rustls::client::{ServerCertVerified, ServerCertVerifier};
+use tokio_rustls::rustls::{
+    Certificate, ClientConfig, PrivateKey, RootCertStore, ServerConfig, ServerName,
+};
+use tokio_rustls::{TlsAcceptor, TlsConnector, TlsStream};
+
+use crate::error::{Error, Result};
+use crate::types::{Endpoint, TransportType};
+use super::tcp::{TcpTransport, DEFAULT_MAX_FRAME_SIZE};
+use super::{Transport, INCOMING_CHANNEL_CAPACITY};
+
+pub use tokio_rustls::rustls;
+
+/// Пишущая половина TLS соединения
+type TlsWriter = WriteHalf<TlsStream<TcpStream>>;
+
+/// Создать конфигурацию сервера из цепочки сертификатов и закрытого ключа
+pub fn server_config(cert_chain: Vec<Certificate>, key: PrivateKey) -> Result<Arc<ServerConfig>> {
+    let config = ServerConfig::builder()
+        .with_safe_defaults()
+        .with_no_client_auth()
+        .with_single_cert(cert_chain, key)
+        .map_err(|e| Error::Crypto(format!("Некорректный сертификат или ключ: {}", e)))?;
+    
+    Ok(Arc::new(config))
+}
+
+/// Создать конфигурацию клиента, проверяющую сертификаты по набору доверенных корней
+pub fn client_config(roots: RootCertStore) -> Arc<ClientConfig> {
+    let config = ClientConfig::builder()
+        .with_safe_defaults()
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    
+    Arc::new(config)
+}
+
+/// Создать конфигурацию клиента, принимающую любой сертификат
+///
+/// Соединение остается зашифрованным, но подлинность пира не проверяется.
+/// Предназначено только для отладки и доступно с функцией `insecure-tls`.
+#[cfg(feature = "insecure-tls")]
+pub fn insecure_client_config() -> Arc<ClientConfig> {
+    let config = ClientConfig::builder()
+        .with_safe_defaults()
+        .with_custom_certificate_verifier(Arc::new(AcceptAnyCertificate))
+        .with_no_client_auth();
+    
+    Arc::new(config)
+}
+
+/// Проверка сертификата, принимающая любой сертификат сервера
+#[cfg(feature = "insecure-tls")]
+struct AcceptAnyCertificate;
+
+#[cfg(feature = "insecure-tls")]
+impl ServerCertVerifier for AcceptAnyCertificate {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &Certificate,
+        _intermediates: &[Certificate],
+        _server_name: &ServerName,
+        _scts: &mut dyn Iterator<Item = &[u8]>,
+        _ocsp_response: &[u8],
+        _now: SystemTime,
+    ) -> std::result::Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+}
+
+/// Реализация транспорта на основе TCP с шифрованием TLS
+pub struct TlsTcpTransport {
+    /// Канал для рассылки входящих сообщений подписчикам
+    incoming_tx: broadcast::Sender<(Vec<u8>, SocketAddr)>,
+    /// Пишущие половины активных соединений, по адресу удаленного узла
+    connections: Arc<Mutex<HashMap<String, TlsWriter>>>,
+    /// Задача для прослушивания входящих соединений
+    listener_task: Option<JoinHandle<()>>,
+    /// Адрес для прослушивания
+    listen_addr: Option<SocketAddr>,
+    /// Конфигурация TLS для входящих соединений
+    acceptor: TlsAcceptor,
+    /// Конфигурация TLS для исходящих соединений
+    connector: TlsConnector,
+    /// Размер буфера для чтения
+    read_buffer_size: usize,
+    /// Максимальный размер кадра
+    max_frame_size: usize,
+}
+
+impl TlsTcpTransport {
+    /// Создать новый TLS транспорт
+    ///
+    /// `server_config` используется для входящих соединений, `client_config` —
+    /// для проверки пиров при исходящих.
+    pub fn new(server_config: Arc<ServerConfig>, client_config: Arc<ClientConfig>) -> Self {
+        let (incoming_tx, _) = broadcast::channel(INCOMING_CHANNEL_CAPACITY);
+        
+        Self {
+            incoming_tx,
+            connections: Arc::new(Mutex::new(HashMap::new())),
+            listener_task: None,
+            listen_addr: None,
+            acceptor: TlsAcceptor::from(server_config),
+            connector: TlsConnector::from(client_config),
+            read_buffer_size: 4096, // 4 KB
+            max_frame_size: DEFAULT_MAX_FRAME_SIZE,
+        }
+    }
+    
+    /// Установить размер буфера для чтения
+    pub fn with_read_buffer_size(mut self, size: usize) -> Self {
+        self.read_buffer_size = size;
+        self
+    }
+    
+    /// Установить максимальный размер кадра
+    ///
+    /// Соединения, присылающие кадры большего размера, закрываются.
+    pub fn with_max_frame_size(mut self, size: usize) -> Self {
+        self.max_frame_size = size;
+        self
+    }
+    
+    /// Разделить TLS соединение на половины
+    ///
+    /// Читающая половина обслуживается в отдельной задаче, пишущая возвращается
+    /// для сохранения в таблице соединений.
+    fn split_stream(
+        stream: TlsStream<TcpStream>,
+        addr: SocketAddr,
+        tx: broadcast::Sender<(Vec<u8>, SocketAddr)>,
+        buffer_size: usize,
+        max_frame_size: usize,
+    ) -> TlsWriter {
+        let (read_half, write_half) = tokio::io::split(stream);
+        
+        tokio::spawn(async move {
+            TcpTransport::handle_connection(read_half, addr, tx, buffer_size, max_frame_size).await;
+        });
+        
+        write_half
+    }
+    
+    /// Установить TLS соединение с удаленным узлом
+    async fn dial(&self, endpoint: &Endpoint) -> Result<TlsWriter> {
+        let address = endpoint.socket_addr();
+        
+        let server_name = ServerName::try_from(endpoint.host.as_str())
+            .map_err(|e| Error::Transport(format!("Некорректное имя сервера {}: {}", endpoint.host, e)))?;
+        
+        let stream = TcpStream::connect(&address).await
+            .map_err(|e| Error::Transport(format!("Не удалось подключиться к {}: {}", address, e)))?;
+        let peer_addr = stream.peer_addr()
+            .map_err(|e| Error::Transport(format!("Не удалось получить адрес {}: {}", address, e)))?;
+        
+        let stream = self.connector.connect(server_name, stream).await
+            .map_err(|e| Error::Transport(format!("Ошибка TLS рукопожатия с {}: {}", address, e)))?;
+        
+        Ok(Self::split_stream(
+            stream.into(),
+            peer_addr,
+            self.incoming_tx.clone(),
+            self.read_buffer_size,
+            self.max_frame_size,
+        ))
+    }
+}
+
+#[async_trait]
+impl Transport for TlsTcpTransport {
+    fn transport_type(&self) -> TransportType {
+        TransportType::Tcp
+    }
+    
+    async fn listen(&mut self, address: &str, port: u16) -> Result<()> {
+        // Создаем адрес для прослушивания
+        let addr = format!("{}:{}", address, port).parse::<SocketAddr>()
+            .map_err(|e| Error::Transport(format!("Неверный адрес: {}", e)))?;
+        
+        // Создаем TCP слушателя
+        let listener = TcpListener::bind(&addr).await
+            .map_err(|e| Error::Transport(format!("Не удалось привязаться к адресу {}: {}", addr, e)))?;
+        
+        let connections = Arc::clone(&self.connections);
+        let tx = self.incoming_tx.clone();
+        let acceptor = self.acceptor.clone();
+        let buffer_size = self.read_buffer_size;
+        let max_frame_size = self.max_frame_size;
+        
+        // Запускаем задачу для прослушивания
+        let task = tokio::spawn(async move {
+            loop {
+                match listener.accept().await {
+                    Ok((stream, addr)) => {
+                        let connections = Arc::clone(&connections);
+                        let tx = tx.clone();
+                        let acceptor = acceptor.clone();
+                        
+                        // Рукопожатие выполняем отдельно, чтобы медленный клиент не задерживал остальных
+                        tokio::spawn(async move {
+                            let stream = match acceptor.accept(stream).await {
+                                Ok(stream) => stream,
+                                Err(e) => {
+                                    tracing::debug!("Ошибка TLS рукопожатия с {}: {}", addr, e);
+                                    return;
+                                }
+                            };
+                            
+                            let writer = Self::split_stream(stream.into(), addr, tx, buffer_size, max_frame_size);
+                            connections.lock().unwrap().insert(addr.to_string(), writer);
+                        });
+                    }
+                    Err(_) => {
+                        // Ошибка при принятии соединения
+                        continue;
+                    }
+                }
+            }
+        });
+        
+        self.listener_task = Some(task);
+        self.listen_addr = Some(addr);
+        
+        Ok(())
+    }
+    
+    async fn connect(&mut self, endpoint: &Endpoint) -> Result<()> {
+        let writer = self.dial(endpoint).await?;
+        
+        // Сохраняем соединение
+        self.connections.lock().unwrap().insert(endpoint.socket_addr(), writer);
+        
+        Ok(())
+    }
+    
+    async fn send_to(&self, endpoint: &Endpoint, data: &[u8]) -> Result<()> {
+        if endpoint.transport != TransportType::Tcp {
+            return Err(Error::Transport(format!("Адрес не является TCP адресом: {}", endpoint)));
+        }
+        
+        let address = endpoint.socket_addr();
+        
+        // Забираем существующее соединение, не удерживая блокировку во время ввода-вывода
+        let existing = self.connections.lock().unwrap().remove(&address);
+        let mut writer = match existing {
+            Some(writer) => writer,
+            None => self.dial(endpoint).await?,
+        };
+        
+        // Отправляем кадр с префиксом длины
+        let frame = TcpTransport::encode_frame(data, self.max_frame_size)?;
+        writer.write_all(&frame).await
+            .map_err(|e| Error::Transport(format!("Ошибка отправки данных: {}", e)))?;
+        // TLS буферизует записи, поэтому кадр нужно вытолкнуть явно
+        writer.flush().await
+            .map_err(|e| Error::Transport(format!("Ошибка отправки данных: {}", e)))?;
+        
+        // Возвращаем соединение для повторного использования
+        self.connections.lock().unwrap().insert(address, writer);
+        
+        Ok(())
+    }
+    
+    fn incoming(&self) -> broadcast::Receiver<(Vec<u8>, SocketAddr)> {
+        self.incoming_tx.subscribe()
+    }
+    
+    async fn close(&mut self) -> Result<()> {
+        // Отменяем задачу прослушивания
+        if let Some(task) = self.listener_task.take() {
+            task.abort();
+        }
+        
+        // Закрываем все соединения
+        let mut connections = self.connections.lock().unwrap();
+        connections.clear();
+        
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    
+    /// Самоподписанный сертификат для localhost (P-256, DER)
+    const CERT_DER: &str = concat!(
+        "3082019230820138a0030201020214564118aaf6c82bd52f2710a6bc5d1fae07",
+        "c2f2ab300a06082a8648ce3d04030230143112301006035504030c096c6f6361",
+        "6c686f73743020170d3236313031363039313032315a180f3231323630393232",
+        "3039313032315a30143112301006035504030c096c6f63616c686f7374305930",
+        "1306072a8648ce3d020106082a8648ce3d0301070342000414d87769ea25433f",
+        "c86f1b7dd8dccc33566b5d4f9b0c26221b26ba1d02cf9d0ce60d49a6402abe82",
+        "3bd814211838414439312b8d96af4d925f63f8fc9cbd68bea3663064301d0603",
+        "551d0e0416041484ff69a39419fc42c4a8438cf2d1eb66985e37a7301f060355",
+        "1d2304183016801484ff69a39419fc42c4a8438cf2d1eb66985e37a730140603",
+        "551d11040d300b82096c6f63616c686f7374300c0603551d130101ff04023000",
+        "300a06082a8648ce3d040302034800304502205a101db9b825e44cf0c6456aec",
+        "61c38f378fe87c91aa577859a14dc265017299022100c737e4f1aa1a7ef50434",
+        "bebdae8fc9d0033581bb0a92a8a2a9096f51dac48ad3",
+    );
+    
+    /// Закрытый ключ сертификата в формате PKCS#8 (DER)
+    const KEY_DER: &str = concat!(
+        "308187020100301306072a8648ce3d020106082a8648ce3d030107046d306b02",
+        "0101042023fcc4f0005180300e309b5980094f8faaad0027cd9e33f8af43f320",
+        "54c52bd0a1440342000414d87769ea25433fc86f1b7dd8dccc33566b5d4f9b0c",
+        "26221b26ba1d02cf9d0ce60d49a6402abe823bd814211838414439312b8d96af",
+        "4d925f63f8fc9cbd68be",
+    );
+    
+    fn certificate() -> Certificate {
+        Certificate(hex::decode(CERT_DER).unwrap())
+    }
+    
+    fn server() -> Arc<ServerConfig> {
+        server_config(vec![certificate()], PrivateKey(hex::decode(KEY_DER).unwrap())).unwrap()
+    }
+    
+    /// Клиент, доверяющий тестовому сертификату
+    fn trusting_client() -> Arc<ClientConfig> {
+        let mut roots = RootCertStore::empty();
+        roots.add(&certificate()).unwrap();
+        client_config(roots)
+    }
+    
+    /// Найти свободный локальный порт
+    fn free_port() -> u16 {
+        std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port()
+    }
+    
+    #[tokio::test]
+    async fn self_signed_loopback_round_trip() {
+        let port = free_port();
+        let mut server_transport = TlsTcpTransport::new(server(), trusting_client());
+        server_transport.listen("127.0.0.1", port).await.unwrap();
+        let mut incoming = server_transport.incoming();
+        
+        let client = TlsTcpTransport::new(server(), trusting_client());
+        let endpoint = Endpoint::tcp("localhost", port);
+        
+        client.send_to(&endpoint, b"first").await.unwrap();
+        client.send_to(&endpoint, b"second").await.unwrap();
+        
+        for expected in [&b"first"[..], b"second"] {
+            let (data, _) = tokio::time::timeout(Duration::from_secs(2), incoming.recv()).await.unwrap().unwrap();
+            assert_eq!(data, expected);
+        }
+        
+        server_transport.close().await.unwrap();
+    }
+    
+    #[tokio::test]
+    async fn untrusted_certificate_is_rejected() {
+        let port = free_port();
+        let mut server_transport = TlsTcpTransport::new(server(), trusting_client());
+        server_transport.listen("127.0.0.1", port).await.unwrap();
+        
+        let client = TlsTcpTransport::new(server(), client_config(RootCertStore::empty()));
+        let result = client.send_to(&Endpoint::tcp("localhost", port), b"secret").await;
+        assert!(matches!(result, Err(Error::Transport(_))));
+        
+        server_transport.close().await.unwrap();
+    }
+    
+    #[cfg(feature = "insecure-tls")]
+    #[tokio::test]
+    async fn insecure_client_skips_verification() {
+        let port = free_port();
+        let mut server_transport = TlsTcpTransport::new(server(), trusting_client());
+        server_transport.listen("127.0.0.1", port).await.unwrap();
+        
+        // Без проверки подлинности соединение устанавливается
+        let insecure = TlsTcpTransport::new(server(), insecure_client_config());
+        insecure.send_to(&Endpoint::tcp("localhost", port), b"secret").await.unwrap();
+        
+        server_transport.close().await.unwrap();
+    }
+}
@@ -0,0 +1,248 @@
+use async_trait::async_trait;
+use futures::stream::{SplitSink, SplitStream};
+use futures::{SinkExt, StreamExt};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+use tokio_tungstenite::tungstenite::Message as WsMessage;
+use tokio_tungstenite::WebSocketStream;
+
+use crate::error::{Error, Result};
+use crate::types::{Endpoint, TransportType};
+use super::{Transport, INCOMING_CHANNEL_CAPACITY};
+
+/// Пишущая половина WebSocket соединения
+type WsSink = SplitSink<WebSocketStream<TcpStream>, WsMessage>;
+
+/// Читающая половина WebSocket соединения
+type WsSource = SplitStream<WebSocketStream<TcpStream>>;
+
+/// Реализация транспорта на основе WebSocket
+///
+/// Данные передаются бинарными WebSocket сообщениями, поэтому дополнительное
+/// кадрирование не требуется.
+pub struct WebSocketTransport {
+    /// Канал для рассылки входящих сообщений подписчикам
+    incoming_tx: broadcast::Sender<(Vec<u8>, SocketAddr)>,
+    /// Пишущие половины активных соединений, по адресу удаленного узла
+    connections: Arc<Mutex<HashMap<String, WsSink>>>,
+    /// Задача для прослушивания входящих соединений
+    listener_task: Option<JoinHandle<()>>,
+    /// Адрес для прослушивания
+    listen_addr: Option<SocketAddr>,
+}
+
+impl WebSocketTransport {
+    /// Создать новый WebSocket транспорт
+    pub fn new() -> Self {
+        let (incoming_tx, _) = broadcast::channel(INCOMING_CHANNEL_CAPACITY);
+        
+        Self {
+            incoming_tx,
+            connections: Arc::new(Mutex::new(HashMap::new())),
+            listener_task: None,
+            listen_addr: None,
+        }
+    }
+    
+    /// Установить WebSocket соединение с удаленным узлом
+    ///
+    /// Читающая половина обслуживается в отдельной задаче, пишущая возвращается
+    /// для сохранения в таблице соединений.
+    async fn dial(
+        endpoint: &Endpoint,
+        tx: broadcast::Sender<(Vec<u8>, SocketAddr)>,
+    ) -> Result<WsSink> {
+        let address = endpoint.socket_addr();
+        let url = endpoint.to_string();
+        
+        let stream = TcpStream::connect(&address).await
+            .map_err(|e| Error::Transport(format!("Не удалось подключиться к {}: {}", address, e)))?;
+        let peer_addr = stream.peer_addr()
+            .map_err(|e| Error::Transport(format!("Не удалось получить адрес {}: {}", address, e)))?;
+        
+        let (ws_stream, _) = tokio_tungstenite::client_async(url.as_str(), stream).await
+            .map_err(|e| Error::Transport(format!("Ошибка WebSocket рукопожатия с {}: {}", url, e)))?;
+        
+        let (sink, source) = ws_stream.split();
+        tokio::spawn(Self::handle_connection(source, peer_addr, tx));
+        
+        Ok(sink)
+    }
+    
+    /// Обработать входящие данные соединения
+    async fn handle_connection(
+        mut source: WsSource,
+        addr: SocketAddr,
+        tx: broadcast::Sender<(Vec<u8>, SocketAddr)>,
+    ) {
+        while let Some(message) = source.next().await {
+            match message {
+                Ok(WsMessage::Binary(data)) => {
+                    // Отправляем данные подписчикам; при их отсутствии данные отбрасываются
+                    let _ = tx.send((data, addr));
+                }
+                Ok(WsMessage::Close(_)) => break,
+                Ok(_) => {
+                    // Текстовые и служебные сообщения игнорируются
+                }
+                Err(_) => {
+                    // Ошибка чтения, выходим из цикла
+                    break;
+                }
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Transport for WebSocketTransport {
+    fn transport_type(&self) -> TransportType {
+        TransportType::WebSocket
+    }
+    
+    async fn listen(&mut self, address: &str, port: u16) -> Result<()> {
+        // Создаем адрес для прослушивания
+        let addr = format!("{}:{}", address, port).parse::<SocketAddr>()
+            .map_err(|e| Error::Transport(format!("Неверный адрес: {}", e)))?;
+        
+        // Создаем TCP слушателя
+        let listener = TcpListener::bind(&addr).await
+            .map_err(|e| Error::Transport(format!("Не удалось привязаться к адресу {}: {}", addr, e)))?;
+        
+        let connections = Arc::clone(&self.connections);
+        let tx = self.incoming_tx.clone();
+        
+        // Запускаем задачу для прослушивания
+        let task = tokio::spawn(async move {
+            loop {
+                match listener.accept().await {
+                    Ok((stream, addr)) => {
+                        let connections = Arc::clone(&connections);
+                        let tx = tx.clone();
+                        
+                        // Рукопожатие выполняем отдельно, чтобы медленный клиент не задерживал остальных
+                        tokio::spawn(async move {
+                            let ws_stream = match tokio_tungstenite::accept_async(stream).await {
+                                Ok(ws_stream) => ws_stream,
+                                Err(e) => {
+                                    tracing::debug!("Ошибка WebSocket рукопожатия с {}: {}", addr, e);
+                                    return;
+                                }
+                            };
+                            
+                            let (sink, source) = ws_stream.split();
+                            connections.lock().unwrap().insert(addr.to_string(), sink);
+                            Self::handle_connection(source, addr, tx).await;
+                        });
+                    }
+                    Err(_) => {
+                        // Ошибка при принятии соединения
+                        continue;
+                    }
+                }
+            }
+        });
+        
+        self.listener_task = Some(task);
+        self.listen_addr = Some(addr);
+        
+        Ok(())
+    }
+    
+    async fn connect(&mut self, endpoint: &Endpoint) -> Result<()> {
+        if endpoint.transport != TransportType::WebSocket {
+            return Err(Error::Transport(format!("Адрес не является WebSocket адресом: {}", endpoint)));
+        }
+        
+        let sink = Self::dial(endpoint, self.incoming_tx.clone()).await?;
+        
+        // Сохраняем соединение
+        self.connections.lock().unwrap().insert(endpoint.socket_addr(), sink);
+        
+        Ok(())
+    }
+    
+    async fn send_to(&self, endpoint: &Endpoint, data: &[u8]) -> Result<()> {
+        if endpoint.transport != TransportType::WebSocket {
+            return Err(Error::Transport(format!("Адрес не является WebSocket адресом: {}", endpoint)));
+        }
+        
+        let address = endpoint.socket_addr();
+        
+        // Забираем существующее соединение, не удерживая блокировку во время ввода-вывода
+        let existing = self.connections.lock().unwrap().remove(&address);
+        let mut sink = match existing {
+            Some(sink) => sink,
+            None => {
+                // Если нет соединения, подключаемся
+                Self::dial(endpoint, self.incoming_tx.clone()).await?
+            }
+        };
+        
+        // Отправляем данные бинарным сообщением
+        sink.send(WsMessage::Binary(data.to_vec())).await
+            .map_err(|e| Error::Transport(format!("Ошибка отправки данных: {}", e)))?;
+        
+        // Возвращаем соединение для повторного использования
+        self.connections.lock().unwrap().insert(address, sink);
+        
+        Ok(())
+    }
+    
+    fn incoming(&self) -> broadcast::Receiver<(Vec<u8>, SocketAddr)> {
+        self.incoming_tx.subscribe()
+    }
+    
+    async fn close(&mut self) -> Result<()> {
+        // Отменяем задачу прослушивания
+        if let Some(task) = self.listener_task.take() {
+            task.abort();
+        }
+        
+        // Закрываем все соединения
+        let mut connections = self.connections.lock().unwrap();
+        connections.clear();
+        
+        Ok(())
+    }
+}
+
+impl Default for WebSocketTransport {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    
+    #[tokio::test]
+    async fn binary_message_round_trip_over_loopback() {
+        let port = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        let mut server = WebSocketTransport::new();
+        server.listen("127.0.0.1", port).await.unwrap();
+        let mut server_incoming = server.incoming();
+        
+        let client = WebSocketTransport::new();
+        let mut client_incoming = client.incoming();
+        let payload = vec![0u8, 159, 146, 150, 255];
+        client.send_to(&Endpoint::websocket("127.0.0.1", port, None), &payload).await.unwrap();
+        
+        let (data, from) = tokio::time::timeout(Duration::from_secs(2), server_incoming.recv()).await.unwrap().unwrap();
+        assert_eq!(data, payload);
+        
+        let reply_to = Endpoint::websocket(from.ip().to_string(), from.port(), None);
+        server.send_to(&reply_to, &data).await.unwrap();
+        let (echo, _) = tokio::time::timeout(Duration::from_secs(2), client_incoming.recv()).await.unwrap().unwrap();
+        assert_eq!(echo, payload);
+        
+        assert!(client.send_to(&Endpoint::tcp("127.0.0.1", port), &payload).await.is_err());
+        server.close().await.unwrap();
+    }
+}
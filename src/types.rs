@@ -1,16 +1,93 @@
 use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::str::FromStr;
 use serde::{Serialize, Deserialize};
 
+use crate::error::{Error, Result};
+
+/// Код хеш-функции SHA-256 в формате multihash
+const MULTIHASH_SHA256: u8 = 0x12;
+
+/// Длина дайджеста SHA-256
+const SHA256_DIGEST_LEN: usize = 32;
+
+/// Стандартная длина идентификатора узла в байтах
+///
+/// Совпадает с длиной дайджеста SHA-256, из которого выводится идентификатор,
+/// и с размером пространства ключей Kademlia (256 бит).
+pub const PEER_ID_LEN: usize = SHA256_DIGEST_LEN;
+
 /// Идентификатор узла в сети
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct PeerId(Vec<u8>);
 
 impl PeerId {
     /// Создать новый PeerId из байтов
+    ///
+    /// Длина не проверяется: идентификатор нестандартной длины не может
+    /// участвовать в DHT. Для данных из сети и от пользователя используйте
+    /// `try_new`.
     pub fn new(bytes: Vec<u8>) -> Self {
         Self(bytes)
     }
 
+    /// Создать PeerId из байтов, проверив длину
+    ///
+    /// Возвращает `Error::Crypto`, если длина отличается от `PEER_ID_LEN`.
+    pub fn try_new(bytes: Vec<u8>) -> Result<Self> {
+        if bytes.len() != PEER_ID_LEN {
+            return Err(Error::Crypto(format!(
+                "Неверная длина идентификатора узла: {} байт, ожидается {}", bytes.len(), PEER_ID_LEN
+            )));
+        }
+        Ok(Self(bytes))
+    }
+
+    /// Вывести идентификатор из публичного ключа узла (SHA-256 ключа)
+    ///
+    /// Идентификатор хранит дайджест SHA-256, а в текстовой форме
+    /// `to_base58` дополняется заголовком multihash.
+    pub fn from_public_key(public_key: &[u8]) -> Self {
+        Self(crate::crypto::sha256(public_key))
+    }
+
+    /// Закодировать идентификатор как multihash SHA-256 в base58btc
+    ///
+    /// Формат совместим с текстовыми идентификаторами узлов libp2p
+    /// (строка вида `Qm...`).
+    pub fn to_base58(&self) -> String {
+        let mut multihash = vec![MULTIHASH_SHA256];
+        // Длина дайджеста записывается как беззнаковый varint
+        let mut len = self.0.len();
+        while len >= 0x80 {
+            multihash.push((len as u8 & 0x7f) | 0x80);
+            len >>= 7;
+        }
+        multihash.push(len as u8);
+        multihash.extend_from_slice(&self.0);
+
+        bs58::encode(multihash).into_string()
+    }
+
+    /// Разобрать идентификатор из multihash SHA-256 в base58btc
+    ///
+    /// Возвращает `Error::Crypto`, если строка не является base58, хеш-функция
+    /// отлична от SHA-256 или длина дайджеста не совпадает с заголовком.
+    pub fn from_base58(s: &str) -> Result<Self> {
+        let multihash = bs58::decode(s)
+            .into_vec()
+            .map_err(|e| Error::Crypto(format!("Неверная строка base58: {}", e)))?;
+
+        match multihash.as_slice() {
+            [MULTIHASH_SHA256, len, digest @ ..] if *len as usize == SHA256_DIGEST_LEN && digest.len() == SHA256_DIGEST_LEN => {
+                Ok(Self(digest.to_vec()))
+            }
+            [MULTIHASH_SHA256, ..] => Err(Error::Crypto("Неверная длина дайджеста multihash".to_string())),
+            [code, ..] => Err(Error::Crypto(format!("Неподдерживаемая хеш-функция multihash: 0x{:02x}", code))),
+            [] => Err(Error::Crypto("Пустой multihash".to_string())),
+        }
+    }
+
     /// Получить байтовое представление
     pub fn as_bytes(&self) -> &[u8] {
         &self.0
@@ -24,18 +101,45 @@ impl fmt::Display for PeerId {
 }
 
 /// Адрес узла в сети
+///
+/// Задается в формате multiaddr, например `/ip4/127.0.0.1/tcp/8000` или
+/// `/dns4/example.com/tcp/9000/ws`.
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct PeerAddress {
-    /// IP адрес и порт
+    /// Адрес в каноническом формате multiaddr
     pub address: String,
+    /// Разобранный адрес конечной точки
+    pub endpoint: Endpoint,
     /// Идентификатор узла
     pub peer_id: PeerId,
 }
 
 impl PeerAddress {
-    /// Создать новый адрес узла
-    pub fn new(address: String, peer_id: PeerId) -> Self {
-        Self { address, peer_id }
+    /// Создать адрес узла из строки multiaddr
+    ///
+    /// Строка `address` приводится к каноническому виду. Некорректный
+    /// или неподдерживаемый адрес возвращает `Error::Transport`.
+    pub fn new(address: &str, peer_id: PeerId) -> Result<Self> {
+        let endpoint = Endpoint::from_multiaddr(address)?;
+        Ok(Self {
+            address: endpoint.multiaddr(),
+            endpoint,
+            peer_id,
+        })
+    }
+
+    /// Транспортный протокол адреса
+    pub fn transport_type(&self) -> TransportType {
+        self.endpoint.transport.clone()
+    }
+
+    /// Адрес сокета узла
+    ///
+    /// Доступен только для адресов `/ip4` и `/ip6`: DNS-имя сначала нужно разрешить.
+    pub fn socket_addr(&self) -> Result<SocketAddr> {
+        let ip = self.endpoint.host.parse::<IpAddr>()
+            .map_err(|_| Error::Transport(format!("Адрес {} требует разрешения имени", self.address)))?;
+        Ok(SocketAddr::new(ip, self.endpoint.port))
     }
 }
 
@@ -51,7 +155,7 @@ pub struct PeerInfo {
     /// Идентификатор узла
     pub id: PeerId,
     /// Адрес узла
-    pub address: Option<String>,
+    pub address: Option<Endpoint>,
     /// Поддерживаемые протоколы
     pub protocols: Vec<String>,
     /// Версия клиента
@@ -59,12 +163,362 @@ pub struct PeerInfo {
 }
 
 /// Тип протокола транспортного уровня
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum TransportType {
     /// TCP протокол
     Tcp,
     /// WebSocket протокол
     WebSocket,
     /// Пользовательский транспорт
-    Custom,
-} 
\ No newline at end of file
+    ///
+    /// Имя служит схемой адреса и отличает разные пользовательские
+    /// транспорты одного узла друг от друга.
+    Custom(String),
+}
+
+impl TransportType {
+    /// Создать пользовательский транспорт с заданным именем
+    ///
+    /// Имя приводится к нижнему регистру, как и схема адреса.
+    pub fn custom(name: impl Into<String>) -> Self {
+        TransportType::Custom(name.into().to_ascii_lowercase())
+    }
+
+    /// Схема адреса для данного транспорта
+    pub fn scheme(&self) -> &str {
+        match self {
+            TransportType::Tcp => "tcp",
+            TransportType::WebSocket => "ws",
+            TransportType::Custom(name) => name,
+        }
+    }
+
+    /// Определить транспорт по схеме адреса
+    ///
+    /// Схемы, кроме `tcp` и `ws`, соответствуют пользовательским транспортам.
+    pub fn from_scheme(scheme: &str) -> Option<Self> {
+        let valid = !scheme.is_empty()
+            && scheme.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+        if !valid {
+            return None;
+        }
+
+        match scheme.to_ascii_lowercase().as_str() {
+            "tcp" => Some(TransportType::Tcp),
+            "ws" => Some(TransportType::WebSocket),
+            _ => Some(TransportType::custom(scheme)),
+        }
+    }
+}
+
+/// Транспортно-независимый адрес конечной точки
+///
+/// Текстовая форма: `<схема>://<хост>:<порт>[/путь]`, например
+/// `tcp://127.0.0.1:8000` или `ws://example.com:9000/p2p`.
+/// Строка без схемы (`127.0.0.1:8000`) считается TCP адресом.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Endpoint {
+    /// Транспортный протокол
+    pub transport: TransportType,
+    /// Имя хоста или IP адрес
+    pub host: String,
+    /// Порт
+    pub port: u16,
+    /// Путь (используется для WebSocket)
+    pub path: Option<String>,
+}
+
+impl Endpoint {
+    /// Создать новый адрес конечной точки
+    pub fn new(transport: TransportType, host: impl Into<String>, port: u16) -> Self {
+        Self {
+            transport,
+            host: host.into(),
+            port,
+            path: None,
+        }
+    }
+
+    /// Создать TCP адрес
+    pub fn tcp(host: impl Into<String>, port: u16) -> Self {
+        Self::new(TransportType::Tcp, host, port)
+    }
+
+    /// Создать WebSocket адрес
+    pub fn websocket(host: impl Into<String>, port: u16, path: Option<String>) -> Self {
+        Self::new(TransportType::WebSocket, host, port).with_path(path)
+    }
+
+    /// Установить путь
+    pub fn with_path(mut self, path: Option<String>) -> Self {
+        self.path = path.filter(|p| !p.is_empty() && p != "/");
+        self
+    }
+
+    /// Получить адрес в виде `хост:порт` для сокетов
+    pub fn socket_addr(&self) -> String {
+        if self.host.contains(':') {
+            format!("[{}]:{}", self.host, self.port)
+        } else {
+            format!("{}:{}", self.host, self.port)
+        }
+    }
+
+    /// Разобрать адрес в формате multiaddr
+    ///
+    /// Поддерживается подмножество `/ip4|ip6|dns|dns4|dns6/<хост>/tcp/<порт>`
+    /// с необязательным суффиксом `/ws` для WebSocket.
+    pub fn from_multiaddr(s: &str) -> Result<Self> {
+        let invalid = |reason: &str| Error::Transport(format!("Некорректный multiaddr {}: {}", s, reason));
+
+        let parts: Vec<&str> = s.trim()
+            .strip_prefix('/')
+            .ok_or_else(|| invalid("адрес должен начинаться с /"))?
+            .split('/')
+            .collect();
+
+        let (protocol, host, transport, port, rest) = match parts.as_slice() {
+            [protocol, host, transport, port, rest @ ..] => (*protocol, *host, *transport, *port, rest),
+            _ => return Err(invalid("ожидается /<протокол>/<хост>/tcp/<порт>")),
+        };
+
+        match protocol {
+            "ip4" => {
+                host.parse::<Ipv4Addr>().map_err(|_| invalid("неверный IPv4 адрес"))?;
+            }
+            "ip6" => {
+                host.parse::<Ipv6Addr>().map_err(|_| invalid("неверный IPv6 адрес"))?;
+            }
+            "dns" | "dns4" | "dns6" => {
+                if host.is_empty() || host.contains(|c: char| c.is_whitespace() || c == ':') {
+                    return Err(invalid("неверное имя хоста"));
+                }
+            }
+            _ => return Err(invalid(&format!("неподдерживаемый протокол {}", protocol))),
+        }
+
+        if transport != "tcp" {
+            return Err(invalid(&format!("неподдерживаемый транспорт {}", transport)));
+        }
+        let port = port.parse::<u16>().map_err(|_| invalid("неверный порт"))?;
+
+        let transport = match rest {
+            [] => TransportType::Tcp,
+            ["ws"] => TransportType::WebSocket,
+            _ => return Err(invalid(&format!("неподдерживаемый суффикс /{}", rest.join("/")))),
+        };
+
+        Ok(Self::new(transport, host, port))
+    }
+
+    /// Представить адрес в формате multiaddr
+    ///
+    /// Путь WebSocket в multiaddr не передается.
+    fn multiaddr(&self) -> String {
+        let protocol = match self.host.parse::<IpAddr>() {
+            Ok(IpAddr::V4(_)) => "ip4",
+            Ok(IpAddr::V6(_)) => "ip6",
+            Err(_) => "dns",
+        };
+        let suffix = match self.transport {
+            TransportType::WebSocket => "/ws",
+            _ => "",
+        };
+        format!("/{}/{}/tcp/{}{}", protocol, self.host, self.port, suffix)
+    }
+
+    /// Разобрать пару `хост:порт`
+    fn parse_host_port(input: &str) -> Result<(String, u16)> {
+        let invalid = || Error::Transport(format!("Некорректный адрес: {}", input));
+
+        let (host, port) = if let Some(rest) = input.strip_prefix('[') {
+            // IPv6 адрес в квадратных скобках
+            let (host, rest) = rest.split_once(']').ok_or_else(invalid)?;
+            let port = rest.strip_prefix(':').ok_or_else(invalid)?;
+            (host, port)
+        } else {
+            input.rsplit_once(':').ok_or_else(invalid)?
+        };
+
+        if host.is_empty() || host.contains(|c: char| c.is_whitespace() || c == '/') {
+            return Err(invalid());
+        }
+
+        let port = port.parse::<u16>().map_err(|_| invalid())?;
+
+        Ok((host.to_string(), port))
+    }
+}
+
+impl fmt::Display for Endpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}://{}", self.transport.scheme(), self.socket_addr())?;
+        if let Some(path) = &self.path {
+            if !path.starts_with('/') {
+                write!(f, "/")?;
+            }
+            write!(f, "{}", path)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Endpoint {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+
+        let (transport, rest) = match s.split_once("://") {
+            Some((scheme, rest)) => {
+                let transport = TransportType::from_scheme(scheme)
+                    .ok_or_else(|| Error::Transport(format!("Неизвестная схема адреса: {}", scheme)))?;
+                (transport, rest)
+            }
+            None => (TransportType::Tcp, s),
+        };
+
+        let (authority, path) = match rest.find('/') {
+            Some(idx) => (&rest[..idx], Some(rest[idx..].to_string())),
+            None => (rest, None),
+        };
+
+        if path.is_some() && transport != TransportType::WebSocket {
+            return Err(Error::Transport(format!("Путь допустим только для WebSocket адресов: {}", s)));
+        }
+
+        let (host, port) = Self::parse_host_port(authority)?;
+
+        Ok(Self::new(transport, host, port).with_path(path))
+    }
+}
+
+impl TryFrom<&str> for Endpoint {
+    type Error = Error;
+
+    fn try_from(value: &str) -> Result<Self> {
+        value.parse()
+    }
+}
+
+impl TryFrom<String> for Endpoint {
+    type Error = Error;
+
+    fn try_from(value: String) -> Result<Self> {
+        value.parse()
+    }
+}
+
+impl From<Endpoint> for String {
+    fn from(endpoint: Endpoint) -> Self {
+        endpoint.to_string()
+    }
+} 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_tcp_and_websocket_endpoints() {
+        let tcp: Endpoint = "tcp://127.0.0.1:8000".parse().unwrap();
+        assert_eq!(tcp, Endpoint::tcp("127.0.0.1", 8000));
+
+        let ws: Endpoint = "ws://example.com:9000/p2p".parse().unwrap();
+        assert_eq!(ws, Endpoint::websocket("example.com", 9000, Some("/p2p".to_string())));
+
+        let ipv6: Endpoint = "tcp://[::1]:7000".parse().unwrap();
+        assert_eq!(ipv6.host, "::1");
+        assert_eq!(ipv6.port, 7000);
+    }
+
+    #[test]
+    fn address_without_scheme_is_tcp() {
+        let endpoint: Endpoint = "10.0.0.1:30303".parse().unwrap();
+        assert_eq!(endpoint.transport, TransportType::Tcp);
+        assert_eq!(endpoint.socket_addr(), "10.0.0.1:30303");
+    }
+
+    #[test]
+    fn endpoint_round_trips_through_display() {
+        for text in ["tcp://127.0.0.1:8000", "ws://example.com:9000/p2p", "tcp://[::1]:7000"] {
+            let endpoint: Endpoint = text.parse().unwrap();
+            assert_eq!(endpoint.to_string(), text);
+            assert_eq!(endpoint.to_string().parse::<Endpoint>().unwrap(), endpoint);
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_endpoints() {
+        for text in ["", "tcp://127.0.0.1", "tcp://:8000", "tcp://127.0.0.1:99999", "f t p://127.0.0.1:21", "ws://[::1:80"] {
+            assert!(text.parse::<Endpoint>().is_err(), "{} должен быть отклонен", text);
+        }
+    }
+
+    #[test]
+    fn peer_id_round_trips_through_base58() {
+        let id = PeerId::from_public_key(&[1u8; 32]);
+        let text = id.to_base58();
+        assert_eq!(text, "QmW4nsXQFRqVVuFzkHfFaxxUkM9soTNQQEsD7qXpFLLJGa");
+        assert_eq!(PeerId::from_base58(&text).unwrap(), id);
+    }
+
+    #[test]
+    fn malformed_base58_is_rejected() {
+        let digest = [7u8; 32];
+        let encode = |bytes: &[u8]| bs58::encode(bytes).into_string();
+
+        assert!(PeerId::from_base58("Qm0OIl").is_err());
+        assert!(PeerId::from_base58("").is_err());
+        // Другая хеш-функция и неверная длина дайджеста
+        assert!(PeerId::from_base58(&encode(&[[0x13, 0x20].as_slice(), &digest].concat())).is_err());
+        assert!(PeerId::from_base58(&encode(&[[0x12, 0x20].as_slice(), &digest[..31]].concat())).is_err());
+    }
+
+    #[test]
+    fn peer_id_of_wrong_length_is_rejected() {
+        assert!(matches!(PeerId::try_new(Vec::new()), Err(Error::Crypto(_))));
+        assert!(PeerId::try_new(vec![1; PEER_ID_LEN - 1]).is_err());
+        assert!(PeerId::try_new(vec![1; PEER_ID_LEN + 1]).is_err());
+        assert_eq!(PeerId::try_new(vec![1; PEER_ID_LEN]).unwrap(), PeerId::new(vec![1; PEER_ID_LEN]));
+    }
+
+    #[test]
+    fn parses_tcp_and_websocket_multiaddrs() {
+        let tcp = PeerAddress::new("/ip4/127.0.0.1/tcp/8000", PeerId::new(vec![1; 32])).unwrap();
+        assert_eq!(tcp.endpoint, Endpoint::tcp("127.0.0.1", 8000));
+        assert_eq!(tcp.transport_type(), TransportType::Tcp);
+        assert_eq!(tcp.socket_addr().unwrap(), "127.0.0.1:8000".parse::<SocketAddr>().unwrap());
+
+        let ws = PeerAddress::new("/dns4/example.com/tcp/9000/ws", PeerId::new(vec![1; 32])).unwrap();
+        assert_eq!(ws.transport_type(), TransportType::WebSocket);
+        assert_eq!(ws.address, "/dns/example.com/tcp/9000/ws");
+        assert!(ws.socket_addr().is_err());
+
+        let ipv6 = Endpoint::from_multiaddr("/ip6/::1/tcp/7000").unwrap();
+        assert_eq!(ipv6.host, "::1");
+        assert_eq!(ipv6.port, 7000);
+    }
+
+    #[test]
+    fn rejects_malformed_multiaddrs() {
+        for text in [
+            "", "ip4/127.0.0.1/tcp/8000", "/ip4/127.0.0.1/tcp", "/ip4/300.0.0.1/tcp/8000",
+            "/ip4/127.0.0.1/udp/8000", "/ip4/127.0.0.1/tcp/99999", "/ip4/127.0.0.1/tcp/8000/wss",
+        ] {
+            assert!(matches!(Endpoint::from_multiaddr(text), Err(Error::Transport(_))), "{} должен быть отклонен", text);
+        }
+    }
+
+    #[test]
+    fn custom_transport_names_come_from_scheme() {
+        assert_eq!(TransportType::from_scheme("QUIC"), Some(TransportType::custom("quic")));
+        assert_ne!(TransportType::custom("quic"), TransportType::custom("udp"));
+        assert_eq!(TransportType::custom("quic").scheme(), "quic");
+        assert_eq!(TransportType::from_scheme("q u i c"), None);
+
+        let endpoint: Endpoint = "quic://127.0.0.1:4433".parse().unwrap();
+        assert_eq!(endpoint.transport, TransportType::custom("quic"));
+        assert_eq!(endpoint.to_string(), "quic://127.0.0.1:4433");
+    }
+}